@@ -1,26 +1,287 @@
 use crate::error::{Result, TimeLockerError};
-use crate::progress::{ProgressEmitter, ProgressPhase, ProgressTracker};
+use crate::progress::{PacedReader, ProgressEmitter, ProgressPhase, ProgressTracker, ReportingReader};
+use serde::{Deserialize, Serialize};
 use sevenz_rust2::encoder_options::{AesEncoderOptions, Lzma2Options};
-use sevenz_rust2::{decompress_with_extract_fn_and_password, decompress_with_password, ArchiveEntry, ArchiveWriter, Password};
+use sevenz_rust2::{decompress_with_extract_fn_and_password, decompress_with_password, ArchiveEntry, ArchiveReader, ArchiveWriter, EncoderConfiguration, EncoderMethod, Password};
 use std::fs::{create_dir_all, File, FileTimes};
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::WebviewWindow;
 use walkdir::WalkDir;
 
+/// Default LZMA2 compression level
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// How archive contents are compressed (they are always AES-encrypted)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionMethod {
+    /// LZMA2 at the configured level
+    #[default]
+    Lzma2,
+    /// No compression, for data that doesn't compress (media, archives)
+    Store,
+}
+
+/// Filter applied to archive contents before compression
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionFilter {
+    /// No filter
+    #[default]
+    None,
+    /// x86 branch-conversion (BCJ) filter, which helps LZMA2 compress machine code
+    BcjX86,
+    /// `BcjX86` if any file being archived is an executable or library (.exe, .dll, .so)
+    Auto,
+}
+
+/// File extensions `CompressionFilter::Auto` treats as executables
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "dll", "so"];
+
+/// Whether `path` looks like an executable or shared library (including `libfoo.so.1`)
+fn is_executable_file(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    name.contains(".so.")
+        || path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| EXECUTABLE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// File extensions of formats that are already compressed, which LZMA2 can't shrink
+const PRECOMPRESSED_EXTENSIONS: &[&str] = &[
+    // Images
+    "jpg", "jpeg", "png", "gif", "webp", "heic", "avif",
+    // Audio and video
+    "mp3", "aac", "m4a", "ogg", "opus", "flac", "mp4", "m4v", "mov", "mkv", "webm", "avi",
+    // Archives and compressed documents
+    "zip", "7z", "rar", "gz", "tgz", "bz2", "xz", "zst", "docx", "xlsx", "pptx", "epub",
+];
+
+/// Whether `path` looks like already-compressed data (by extension)
+fn is_precompressed_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| PRECOMPRESSED_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Cipher 7z encrypts archive contents (and headers) with
+pub const ARCHIVE_CIPHER: &str = "AES-256-CBC";
+
+/// How 7z derives the cipher key from the archive password (with a random salt)
+pub const ARCHIVE_KDF: &str = "SHA-256";
+
+/// The 7z key derivation iterates SHA-256 2^n times; `n` for new archives
+pub fn archive_kdf_cycles_power() -> u8 {
+    AesEncoderOptions::new("".into()).num_cycles_power
+}
+
+/// Compression used when creating an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionSettings {
+    /// LZMA2 level, 0 (fastest) to 9 (smallest); ignored by `Store`
+    pub level: u32,
+    pub method: CompressionMethod,
+    /// Filter ahead of LZMA2; ignored by `Store`
+    pub filter: CompressionFilter,
+    /// Compress every file with `method`, including already-compressed ones
+    /// (JPEG, MP4, zip...) that are otherwise only stored
+    pub uniform: bool,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_COMPRESSION_LEVEL,
+            method: CompressionMethod::Lzma2,
+            filter: CompressionFilter::None,
+            uniform: false,
+        }
+    }
+}
+
+impl CompressionSettings {
+    /// Decide the filter actually used for `sources`
+    ///
+    /// `Auto` becomes `BcjX86` or `None` depending on whether any source file
+    /// is an executable, and `Store` never filters.
+    pub fn resolve_filter(mut self, sources: &[&Path]) -> Self {
+        self.filter = match (self.method, self.filter) {
+            (CompressionMethod::Store, _) => CompressionFilter::None,
+            (_, CompressionFilter::Auto) => {
+                let has_executable = sources.iter().any(|source| {
+                    WalkDir::new(source)
+                        .follow_links(false)
+                        .into_iter()
+                        .filter_map(|e| e.ok())
+                        .any(|e| e.file_type().is_file() && is_executable_file(e.path()))
+                });
+                if has_executable {
+                    CompressionFilter::BcjX86
+                } else {
+                    CompressionFilter::None
+                }
+            }
+            (_, filter) => filter,
+        };
+        self
+    }
+
+    /// Name of the filter for metadata, if one is applied (call `resolve_filter` first)
+    pub fn filter_name(&self) -> Option<&'static str> {
+        match (self.method, self.filter) {
+            (CompressionMethod::Lzma2, CompressionFilter::BcjX86) => Some("bcj_x86"),
+            _ => None,
+        }
+    }
+
+    /// Coder pipeline for the archive contents: AES encryption, then compression,
+    /// then the optional filter (which is the first to see the data)
+    fn content_methods(&self, password: &str) -> Vec<EncoderConfiguration> {
+        let aes = AesEncoderOptions::new(password.into()).into();
+        match self.method {
+            // Multi-threaded for faster compression of large files (4 threads, 1MB chunks)
+            CompressionMethod::Lzma2 => {
+                let mut methods = vec![
+                    aes,
+                    Lzma2Options::from_level_mt(self.level.min(9), 4, 1 << 20).into(),
+                ];
+                if self.filter == CompressionFilter::BcjX86 {
+                    methods.push(EncoderConfiguration::new(EncoderMethod::BCJ_X86_FILTER));
+                }
+                methods
+            }
+            CompressionMethod::Store => vec![aes],
+        }
+    }
+}
+
+/// Picks each file's coder pipeline as it's added to an archive
+///
+/// Already-compressed files are only encrypted, unless the settings ask for
+/// uniform compression, and the time that saves is logged at the end.
+struct EntryMethods {
+    compressed: Vec<EncoderConfiguration>,
+    /// Pipeline for already-compressed files, `None` when every file gets `compressed`
+    stored: Option<Vec<EncoderConfiguration>>,
+    storing: bool,
+    stored_files: u32,
+    stored_bytes: u64,
+    stored_time: Duration,
+    compressed_bytes: u64,
+    compressed_time: Duration,
+}
+
+impl EntryMethods {
+    /// Configure `writer` for `compression` (already resolved for the sources)
+    fn new<W: Write + Seek>(writer: &mut ArchiveWriter<W>, compression: &CompressionSettings, password: &str) -> Self {
+        let compressed = compression.content_methods(password);
+        let stored = (compression.method != CompressionMethod::Store && !compression.uniform).then(|| {
+            CompressionSettings { method: CompressionMethod::Store, ..*compression }.content_methods(password)
+        });
+        writer.set_content_methods(compressed.clone());
+        Self {
+            compressed,
+            stored,
+            storing: false,
+            stored_files: 0,
+            stored_bytes: 0,
+            stored_time: Duration::ZERO,
+            compressed_bytes: 0,
+            compressed_time: Duration::ZERO,
+        }
+    }
+
+    /// Add the file at `path` with `push`, storing it if it's already compressed
+    fn push<W: Write + Seek>(
+        &mut self,
+        writer: &mut ArchiveWriter<W>,
+        path: &Path,
+        push: impl FnOnce(&mut ArchiveWriter<W>) -> Result<()>,
+    ) -> Result<()> {
+        let store = self.stored.is_some() && is_precompressed_file(path);
+        if store != self.storing {
+            let methods = match &self.stored {
+                Some(stored) if store => stored.clone(),
+                _ => self.compressed.clone(),
+            };
+            writer.set_content_methods(methods);
+            self.storing = store;
+        }
+
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let started = Instant::now();
+        push(writer)?;
+        if store {
+            self.stored_files += 1;
+            self.stored_bytes += size;
+            self.stored_time += started.elapsed();
+        } else {
+            self.compressed_bytes += size;
+            self.compressed_time += started.elapsed();
+        }
+        Ok(())
+    }
+
+    /// Log how much storing already-compressed files saved, estimated from
+    /// how fast the other files compressed
+    fn log_savings(&self, context: &str) {
+        if self.stored_files == 0 {
+            return;
+        }
+        let saved = if self.compressed_bytes > 0 {
+            let rate = self.compressed_time.as_secs_f64() / self.compressed_bytes as f64;
+            format!(
+                "about {:.1}s saved",
+                (rate * self.stored_bytes as f64 - self.stored_time.as_secs_f64()).max(0.0)
+            )
+        } else {
+            "no compressed files to estimate the time saved from".to_string()
+        };
+        eprintln!(
+            "[{}] Stored {} already-compressed file(s) ({} bytes) in {:.1}s without compression; {}",
+            context,
+            self.stored_files,
+            self.stored_bytes,
+            self.stored_time.as_secs_f64(),
+            saved
+        );
+    }
+}
+
 /// Create a password-protected 7z archive with encrypted headers (filenames hidden)
 ///
 /// # Arguments
 /// * `source_path` - Path to file or directory to archive
 /// * `password` - Password for 7z encryption
+/// * `compression` - Compression method and level
 ///
 /// # Returns
 /// Path to the created 7z file
-pub fn create_encrypted_archive(source_path: &Path, password: &str) -> Result<PathBuf> {
-    if !source_path.exists() {
-        return Err(TimeLockerError::FileNotFound(source_path.display().to_string()));
-    }
+pub fn create_encrypted_archive(
+    source_path: &Path,
+    password: &str,
+    compression: &CompressionSettings,
+) -> Result<PathBuf> {
+    create_encrypted_archive_with_rate_limit(source_path, password, compression, None, None)
+}
+
+/// Same as `create_encrypted_archive`, reading sources no faster than
+/// `rate_limit` bytes per second (`None` = unlimited) and reporting to
+/// `progress`, if given
+pub fn create_encrypted_archive_with_rate_limit(
+    source_path: &Path,
+    password: &str,
+    compression: &CompressionSettings,
+    rate_limit: Option<u64>,
+    progress: Option<&ProgressEmitter>,
+) -> Result<PathBuf> {
+    check_lock_source(source_path)?;
+    let source_path = named_source(source_path)?;
 
     // Create output path with .7z extension
     let archive_path = source_path.with_extension("7z");
@@ -29,36 +290,206 @@ pub fn create_encrypted_archive(source_path: &Path, password: &str) -> Result<Pa
     eprintln!("[create_encrypted_archive] Source: {:?}", source_path);
     eprintln!("[create_encrypted_archive] Password length: {}", password.len());
 
-    // Use ArchiveWriter for header encryption support
-    let mut writer = ArchiveWriter::create(&archive_path)
+    // Entries are named by our own walk rather than the 7z library's, so
+    // their names are guaranteed to be relative to the source
+    create_encrypted_archive_multi_with_progress(
+        &[&source_path],
+        &archive_path,
+        password,
+        compression,
+        rate_limit,
+        progress,
+    )?;
+
+    Ok(archive_path)
+}
+
+/// Create a password-protected 7z archive from several independent paths
+///
+/// Each source becomes a top-level entry named after it: a file keeps its
+/// name, a directory keeps its name with its contents below it. Sources must
+/// have distinct names, since they would collide when extracted.
+///
+/// # Arguments
+/// * `sources` - Files and/or directories to archive
+/// * `archive_path` - Where to write the 7z file
+/// * `password` - Password for 7z encryption
+/// * `compression` - Compression method and level
+/// * `rate_limit` - Maximum read throughput in bytes per second (`None` = unlimited)
+pub fn create_encrypted_archive_multi(
+    sources: &[&Path],
+    archive_path: &Path,
+    password: &str,
+    compression: &CompressionSettings,
+    rate_limit: Option<u64>,
+) -> Result<()> {
+    create_encrypted_archive_multi_with_progress(sources, archive_path, password, compression, rate_limit, None)
+}
+
+/// Same as `create_encrypted_archive_multi`, reporting to `progress` as the
+/// sources are read: totals up front, throttled updates within each file,
+/// and completion at the end
+pub fn create_encrypted_archive_multi_with_progress(
+    sources: &[&Path],
+    archive_path: &Path,
+    password: &str,
+    compression: &CompressionSettings,
+    rate_limit: Option<u64>,
+    progress: Option<&ProgressEmitter>,
+) -> Result<()> {
+    let sources = sources
+        .iter()
+        .map(|source| {
+            check_lock_source(source)?;
+            named_source(source)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut names = std::collections::HashSet::new();
+    for source in &sources {
+        let name = top_level_name(source)?;
+        if !names.insert(name.clone()) {
+            return Err(TimeLockerError::Archive(format!(
+                "Two sources are named '{}'; their entries would collide",
+                name
+            )));
+        }
+    }
+
+    eprintln!("[create_encrypted_archive_multi] Creating 7z archive at: {:?}", archive_path);
+    eprintln!("[create_encrypted_archive_multi] Sources: {:?}", sources);
+
+    let mut writer = ArchiveWriter::create(archive_path)
         .map_err(|e| TimeLockerError::Archive(format!("Failed to create archive writer: {}", e)))?;
 
     // Enable header encryption (hides filenames until password is entered)
     writer.set_encrypt_header(true);
 
-    // Configure compression pipeline: AES encryption + LZMA2
-    // Use level 1 in debug (fast), level 6 in release (better compression)
-    // Multi-threaded for faster compression of large files
-    #[cfg(debug_assertions)]
-    let lzma2_opts = Lzma2Options::from_level_mt(1, 4, 1 << 20); // level 1, 4 threads, 1MB chunks
-    #[cfg(not(debug_assertions))]
-    let lzma2_opts = Lzma2Options::from_level_mt(6, 4, 1 << 20); // level 6, 4 threads, 1MB chunks
+    // Configure compression pipeline: AES encryption + compression (+ filter)
+    let source_refs: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+    let mut methods = EntryMethods::new(&mut writer, &compression.resolve_filter(&source_refs), password);
+
+    let pacer = ProgressTracker::new().with_rate_limit(rate_limit);
+    // Progress is counted apart from the pacer, whose count only drives pacing
+    let untracked = ProgressTracker::new();
+    let tracker = progress.map(ProgressEmitter::tracker).unwrap_or(&untracked);
+    if let Some(emitter) = progress {
+        emitter.emit_progress_forced(None, ProgressPhase::Scanning);
+        let (mut total_bytes, mut total_files) = (0u64, 0u32);
+        for source in &sources {
+            let (bytes, files) = crate::progress::calculate_total_size(source)?;
+            total_bytes += bytes;
+            total_files += files;
+        }
+        tracker.set_total(total_bytes, total_files);
+        emitter.emit_progress_forced(None, ProgressPhase::Compressing);
+    }
 
-    writer.set_content_methods(vec![
-        AesEncoderOptions::new(password.into()).into(),
-        lzma2_opts.into(),
-    ]);
+    let result = sources.iter().try_for_each(|source| -> Result<()> {
+        let top = top_level_name(source)?;
+        for entry in WalkDir::new(source).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let name = match entry_name(path, source)? {
+                relative if relative.is_empty() => top.clone(),
+                relative => format!("{}/{}", top, relative),
+            };
+
+            if path.is_dir() {
+                writer
+                    .push_archive_entry(ArchiveEntry::from_path(path, name), None::<std::io::Empty>)
+                    .map_err(|e| TimeLockerError::Archive(format!("Failed to add directory entry: {}", e)))?;
+            } else if should_archive(path) {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let paced = PacedReader::new(BufReader::new(File::open(path)?), &pacer);
+                let reader = ReportingReader::new(paced, tracker, || {
+                    if let Some(emitter) = progress.filter(|_| tracker.should_emit()) {
+                        emitter.emit_progress_forced(Some(file_name.clone()), ProgressPhase::Compressing);
+                    }
+                });
+                methods.push(&mut writer, path, |writer| {
+                    writer
+                        .push_archive_entry(ArchiveEntry::from_path(path, name), Some(reader))
+                        .map(|_| ())
+                        .map_err(|e| TimeLockerError::Archive(format!("Failed to add '{}': {}", path.display(), e)))
+                })?;
+                tracker.increment_files();
+            }
+        }
+        Ok(())
+    });
 
-    // Add source to archive
-    writer.push_source_path(source_path, |_| true)
-        .map_err(|e| TimeLockerError::Archive(format!("Failed to add files: {}", e)))?;
+    if let Some(emitter) = progress.filter(|_| result.is_ok()) {
+        emitter.emit_progress_forced(None, ProgressPhase::Finalizing);
+    }
+    if let Err(e) = result.and_then(|()| {
+        writer
+            .finish()
+            .map(|_| ())
+            .map_err(|e| TimeLockerError::Archive(format!("Failed to finalize archive: {}", e)))
+    }) {
+        let _ = std::fs::remove_file(archive_path);
+        return Err(e);
+    }
 
-    writer.finish()
-        .map_err(|e| TimeLockerError::Archive(format!("Failed to finalize archive: {}", e)))?;
+    if let Some(emitter) = progress {
+        emitter.emit_complete();
+    }
+    methods.log_savings("create_encrypted_archive_multi");
+    eprintln!("[create_encrypted_archive_multi] Archive created successfully (headers encrypted)");
 
-    eprintln!("[create_encrypted_archive] Archive created successfully (headers encrypted)");
+    Ok(())
+}
 
-    Ok(archive_path)
+/// Entry name a source gets at the top of a multi-source archive
+fn top_level_name(source: &Path) -> Result<String> {
+    source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| TimeLockerError::Archive(format!("Cannot lock {}: it has no file name", source.display())))
+}
+
+/// Resolve a source path that ends in `.` or `..` to the directory it names
+///
+/// Entry names are derived from the source's final component, which such
+/// paths don't have. Other paths are returned unchanged, so a symlinked
+/// source keeps its own name.
+fn named_source(source: &Path) -> Result<PathBuf> {
+    if source.file_name().is_some() {
+        return Ok(source.to_path_buf());
+    }
+    let resolved = std::fs::canonicalize(source)?;
+    if resolved.file_name().is_none() {
+        return Err(TimeLockerError::Archive(format!(
+            "Cannot lock {}: it has no file name",
+            source.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Archive entry name for `path`, relative to the `base` being archived
+///
+/// Always a `/`-separated relative path (empty for `base` itself); anything
+/// that would carry absolute or `..` components into the archive is an error
+/// rather than a leak of the host path.
+fn entry_name(path: &Path, base: &Path) -> Result<String> {
+    let relative = path.strip_prefix(base).map_err(|_| {
+        TimeLockerError::Archive(format!("{} is outside {}", path.display(), base.display()))
+    })?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            std::path::Component::CurDir => {}
+            _ => {
+                return Err(TimeLockerError::Archive(format!(
+                    "Refusing to archive {} under a non-relative entry name",
+                    path.display()
+                )))
+            }
+        }
+    }
+    Ok(parts.join("/"))
 }
 
 /// Create a password-protected 7z archive with progress tracking
@@ -69,6 +500,7 @@ pub fn create_encrypted_archive(source_path: &Path, password: &str) -> Result<Pa
 /// # Arguments
 /// * `source_path` - Path to file or directory to archive
 /// * `password` - Password for 7z encryption
+/// * `compression` - Compression method and level
 /// * `window` - Tauri window handle for emitting progress events
 /// * `tracker` - Optional shared progress tracker for cancellation support
 ///
@@ -77,14 +509,13 @@ pub fn create_encrypted_archive(source_path: &Path, password: &str) -> Result<Pa
 pub fn create_encrypted_archive_with_progress(
     source_path: &Path,
     password: &str,
+    compression: &CompressionSettings,
     window: WebviewWindow,
     tracker: Option<Arc<ProgressTracker>>,
 ) -> Result<PathBuf> {
-    if !source_path.exists() {
-        return Err(TimeLockerError::FileNotFound(
-            source_path.display().to_string(),
-        ));
-    }
+    check_lock_source(source_path)?;
+    let source_path = named_source(source_path)?;
+    let source_path = source_path.as_path();
 
     // Create output path with .7z extension
     let archive_path = source_path.with_extension("7z");
@@ -128,23 +559,16 @@ pub fn create_encrypted_archive_with_progress(
     // Enable header encryption (hides filenames)
     writer.set_encrypt_header(true);
 
-    // Configure compression pipeline: AES encryption + LZMA2
-    // Use level 1 in debug (fast), level 6 in release (better compression)
-    // Multi-threaded for faster compression of large files
-    #[cfg(debug_assertions)]
-    let lzma2_opts = Lzma2Options::from_level_mt(1, 4, 1 << 20); // level 1, 4 threads, 1MB chunks
-    #[cfg(not(debug_assertions))]
-    let lzma2_opts = Lzma2Options::from_level_mt(6, 4, 1 << 20); // level 6, 4 threads, 1MB chunks
-
-    writer.set_content_methods(vec![
-        AesEncoderOptions::new(password.into()).into(),
-        lzma2_opts.into(),
-    ]);
+    // Configure compression pipeline: AES encryption + compression (+ filter);
+    // already-compressed files are only encrypted
+    let mut methods = EntryMethods::new(&mut writer, &compression.resolve_filter(&[source_path]), password);
 
     // Add files to the archive
     if source_path.is_file() {
         // Single file
-        add_file_to_archive(&mut writer, source_path, source_path, &emitter, &tracker)?;
+        methods.push(&mut writer, source_path, |writer| {
+            add_file_to_archive(writer, source_path, source_path, &emitter, &tracker)
+        })?;
     } else if source_path.is_dir() {
         // Directory - walk and add all files
         for entry in WalkDir::new(source_path)
@@ -161,15 +585,16 @@ pub fn create_encrypted_archive_with_progress(
 
             let path = entry.path();
 
-            if path.is_file() {
-                add_file_to_archive(&mut writer, path, source_path, &emitter, &tracker)?;
-            } else if path.is_dir() && path != source_path {
+            if !path.is_dir() {
+                // Regular files only; special and unreadable files are skipped
+                if should_archive(path) {
+                    methods.push(&mut writer, path, |writer| {
+                        add_file_to_archive(writer, path, source_path, &emitter, &tracker)
+                    })?;
+                }
+            } else if path != source_path {
                 // Add directory entry (empty, just for structure)
-                let relative_path = path
-                    .strip_prefix(source_path)
-                    .unwrap_or(path)
-                    .to_string_lossy()
-                    .replace("\\", "/");
+                let relative_path = entry_name(path, source_path)?;
 
                 let entry = ArchiveEntry::from_path(path, relative_path);
                 writer
@@ -191,11 +616,96 @@ pub fn create_encrypted_archive_with_progress(
     // Emit completion
     emitter.emit_complete();
 
+    methods.log_savings("create_encrypted_archive_with_progress");
     eprintln!("[create_encrypted_archive_with_progress] Archive created successfully");
 
     Ok(archive_path)
 }
 
+/// Check that a lock target is a readable regular file or directory
+///
+/// Named pipes, sockets and device files are rejected up front: reading them
+/// can block forever or produce endless data.
+pub fn check_lock_source(source_path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(source_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            TimeLockerError::FileNotFound(source_path.display().to_string())
+        }
+        _ => TimeLockerError::Archive(format!("Cannot read {}: {}", source_path.display(), e)),
+    })?;
+
+    if metadata.is_file() {
+        File::open(source_path).map_err(|e| {
+            TimeLockerError::Archive(format!("Cannot read {}: {}", source_path.display(), e))
+        })?;
+    } else if metadata.is_dir() {
+        std::fs::read_dir(source_path).map_err(|e| {
+            TimeLockerError::Archive(format!("Cannot read {}: {}", source_path.display(), e))
+        })?;
+    } else {
+        return Err(TimeLockerError::Archive(format!(
+            "Unsupported file type: {} (only regular files and directories can be locked)",
+            source_path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check that a lock source doesn't overlap the vault the locked file goes to
+///
+/// Locking the vault itself, or a directory containing it, would archive the
+/// vault's other locked files and move the result back inside the source.
+/// A source inside the vault is fine: that is how unlocked folders are relocked.
+/// Paths are canonicalized first; a vault that doesn't exist yet can't overlap.
+pub fn check_source_outside_vault(source_path: &Path, vault: &Path) -> Result<()> {
+    let (Ok(source), Ok(vault)) = (std::fs::canonicalize(source_path), std::fs::canonicalize(vault)) else {
+        return Ok(());
+    };
+
+    if vault.starts_with(&source) {
+        let relation = if vault == source { "is" } else { "contains" };
+        return Err(TimeLockerError::Archive(format!(
+            "Cannot lock {}: it {} the vault {}",
+            source_path.display(),
+            relation,
+            vault.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Whether a path inside a locked directory should go into the archive
+///
+/// Directories and readable regular files are archived; special files and
+/// files we can't open are skipped with a warning.
+fn should_archive(path: &Path) -> bool {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            eprintln!("[should_archive] Skipping {}: {}", path.display(), e);
+            return false;
+        }
+    };
+
+    if metadata.is_dir() {
+        return true;
+    }
+    if !metadata.is_file() {
+        eprintln!("[should_archive] Skipping unsupported file type: {}", path.display());
+        return false;
+    }
+
+    match File::open(path) {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("[should_archive] Skipping unreadable file {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
 /// Helper function to add a single file to the archive with progress tracking
 fn add_file_to_archive<W: std::io::Write + std::io::Seek>(
     writer: &mut ArchiveWriter<W>,
@@ -207,18 +717,10 @@ fn add_file_to_archive<W: std::io::Write + std::io::Seek>(
     // Calculate relative path for archive entry name
     let relative_path = if file_path == base_path {
         // Single file - use just the filename
-        file_path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string()
+        top_level_name(file_path)?
     } else {
         // Directory member - use relative path
-        file_path
-            .strip_prefix(base_path)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .replace("\\", "/")
+        entry_name(file_path, base_path)?
     };
 
     let file_name = file_path
@@ -230,41 +732,21 @@ fn add_file_to_archive<W: std::io::Write + std::io::Seek>(
     // Create archive entry
     let entry = ArchiveEntry::from_path(file_path, relative_path);
 
-    // Open file and add to archive
-    let file = File::open(file_path)?;
-    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
-    let reader = BufReader::new(file);
-
     // Emit initial progress for this file
     emitter.emit_progress_forced(Some(file_name.clone()), ProgressPhase::Compressing);
     eprintln!("[add_file_to_archive] Compressing: {}", file_name);
 
-    // Clone file_name for the closure
-    let file_name_for_closure = file_name.clone();
-
-    // Get current bytes as base (bytes completed from previous files)
-    // We'll use add_bytes for this file's progress
-    let bytes_before_this_file = tracker.get_bytes_written();
-
-    // Run compression with output-based progress callback
-    // The callback receives total compressed bytes written so far
+    // Count bytes into the tracker as the compressor reads them, so progress
+    // moves through a large file instead of jumping when it's done
+    let reader = ReportingReader::new(BufReader::new(File::open(file_path)?), tracker, || {
+        if tracker.should_emit() {
+            emitter.emit_progress_forced(Some(file_name.clone()), ProgressPhase::Compressing);
+        }
+    });
     writer
-        .push_archive_entry_with_progress(entry, Some(reader), Some(|compressed_bytes_written: usize| {
-            // Use compressed bytes directly as progress indicator
-            // This ensures progress keeps moving during finalization
-            // Cap at file_size so we don't exceed 100% for this file
-            let progress_bytes = (compressed_bytes_written as u64).min(file_size);
-
-            // Update tracker with cumulative bytes (previous files + current file's progress)
-            tracker.set_bytes_written(bytes_before_this_file + progress_bytes);
-            // Emit progress (throttled)
-            emitter.emit_progress(Some(file_name_for_closure.clone()), ProgressPhase::Compressing);
-        }))
+        .push_archive_entry(entry, Some(reader))
         .map_err(|e| TimeLockerError::Archive(format!("Failed to add file '{}': {}", file_name, e)))?;
 
-    // Mark this file as fully complete (use actual file size, not estimate)
-    tracker.set_bytes_written(bytes_before_this_file + file_size);
-
     // Increment file counter after completion
     tracker.increment_files();
 
@@ -304,17 +786,31 @@ pub fn extract_encrypted_archive_with_progress(
     // Emit start of extraction
     emitter.emit_progress_forced(None, ProgressPhase::Extracting);
 
+    // Everything this extraction creates, so a cancelled run can be rolled back
+    let mut created_dirs: Vec<PathBuf> = Vec::new();
+    let mut created_files: Vec<PathBuf> = Vec::new();
+
     // Create destination directory
+    created_dirs.extend(missing_dirs(dest));
     create_dir_all(dest)?;
 
-    // Get archive size for progress estimation
-    let archive_size = std::fs::metadata(archive_path)
-        .map(|m| m.len())
-        .unwrap_or(0);
-
-    // For extraction, we estimate based on archive size
-    // (actual uncompressed size is not easily available without reading the archive)
-    tracker.set_total(archive_size, 1);
+    // Totals come from the archive header, so progress follows the
+    // uncompressed bytes actually written. If the header can't be read the
+    // extraction below reports why; until then, estimate from the archive size.
+    let (total_bytes, total_files) = File::open(archive_path)
+        .map_err(TimeLockerError::from)
+        .and_then(|f| list_archive_entries(BufReader::new(f), password))
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|e| !e.is_directory)
+                .fold((0u64, 0u32), |(bytes, files), e| (bytes + e.size, files + 1))
+        })
+        .unwrap_or_else(|_| {
+            let archive_size = std::fs::metadata(archive_path).map(|m| m.len()).unwrap_or(0);
+            (archive_size, 1)
+        });
+    tracker.set_total(total_bytes, total_files);
 
     // Check for cancellation
     if tracker.is_cancelled() {
@@ -325,30 +821,40 @@ pub fn extract_encrypted_archive_with_progress(
     let file = File::open(archive_path)?;
     let reader = BufReader::new(file);
 
-    // Extract using custom callback with progress tracking
-    decompress_with_extract_fn_and_password(
+    let cancelled = || -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Interrupted, "Operation cancelled")
+    };
+
+    // Extract entry by entry, so progress follows the bytes written and a
+    // cancellation takes effect between chunks, not just between files
+    let result = decompress_with_extract_fn_and_password(
         reader,
         dest,
         Password::from(password),
         |entry: &ArchiveEntry, reader: &mut dyn Read, dest_path: &PathBuf| {
             // Check for cancellation
             if tracker.is_cancelled() {
-                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "Operation cancelled").into());
+                return Err(cancelled().into());
             }
 
             if entry.is_directory() {
                 if !dest_path.exists() {
+                    created_dirs.extend(missing_dirs(dest_path));
                     std::fs::create_dir_all(dest_path)?;
                 }
             } else {
                 // Create parent directories
                 if let Some(parent) = dest_path.parent() {
                     if !parent.exists() {
+                        created_dirs.extend(missing_dirs(parent));
                         std::fs::create_dir_all(parent)?;
                     }
                 }
 
                 // Create file and write with progress tracking
+                if !dest_path.exists() {
+                    created_files.push(dest_path.clone());
+                }
                 let file = File::create(dest_path)?;
 
                 if entry.size() > 0 {
@@ -358,6 +864,10 @@ pub fn extract_encrypted_archive_with_progress(
                         .map(|n| n.to_string_lossy().to_string());
 
                     loop {
+                        if tracker.is_cancelled() {
+                            return Err(cancelled().into());
+                        }
+
                         let n = reader.read(&mut buf)?;
                         if n == 0 {
                             break;
@@ -366,6 +876,7 @@ pub fn extract_encrypted_archive_with_progress(
 
                         // Update progress
                         tracker.add_bytes(n as u64);
+                        tracker.pace();
                         emitter.emit_progress(file_name.clone(), ProgressPhase::Extracting);
                     }
 
@@ -386,7 +897,14 @@ pub fn extract_encrypted_archive_with_progress(
 
             Ok(true)
         },
-    ).map_err(|e| {
+    );
+
+    if tracker.is_cancelled() {
+        remove_partial_output(&created_files, &created_dirs);
+        return Err(TimeLockerError::Archive("Operation cancelled".to_string()));
+    }
+
+    result.map_err(|e| {
         eprintln!(
             "[extract_encrypted_archive_with_progress] Extraction failed: {}",
             e
@@ -409,6 +927,105 @@ pub fn extract_encrypted_archive_with_progress(
     Ok(())
 }
 
+/// `path` and its ancestors that don't exist yet, i.e. what `create_dir_all(path)` would create
+fn missing_dirs(path: &Path) -> Vec<PathBuf> {
+    path.ancestors()
+        .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Remove what a cancelled extraction wrote: its files, then the directories
+/// it created (deepest first, and only once they are empty)
+fn remove_partial_output(created_files: &[PathBuf], created_dirs: &[PathBuf]) {
+    for path in created_files {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("[remove_partial_output] Failed to remove {:?}: {}", path, e);
+        }
+    }
+
+    let mut dirs = created_dirs.to_vec();
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+    for dir in dirs {
+        // Directories that still hold something are left alone
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    eprintln!(
+        "[remove_partial_output] Removed {} partially extracted files",
+        created_files.len()
+    );
+}
+
+/// Suffix of the staging directory an extraction is written to before it is moved into place
+pub const PARTIAL_DIR_SUFFIX: &str = ".partial";
+
+/// Empty file left in a directory a staged extraction created, so tools that
+/// remove unlock output can tell it from a folder the user made
+pub const UNLOCK_MARKER: &str = ".timelocker-unlocked";
+
+/// Whether `dir` was created by a staged extraction (it holds an `UNLOCK_MARKER`)
+pub fn is_unlock_output(dir: &Path) -> bool {
+    dir.is_dir() && dir.join(UNLOCK_MARKER).is_file()
+}
+
+/// Staging directory for an extraction into `dest`: a hidden sibling, `.<name>.partial`
+pub fn partial_dir(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    dest.with_file_name(format!(".{}{}", name, PARTIAL_DIR_SUFFIX))
+}
+
+/// Run `extract` into a staging directory, then rename it to `dest` once it succeeds
+///
+/// A failed or cancelled extraction only ever leaves the staging directory
+/// behind, and that is removed, so `dest` never exists half-populated. If
+/// `dest` already holds files (an earlier unlock), there is nothing to keep
+/// consistent and `extract` writes into it directly; only a staged
+/// extraction marks its output with `UNLOCK_MARKER`.
+pub fn extract_atomically(dest: &Path, extract: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    if is_populated_dir(dest) {
+        return extract(dest);
+    }
+    extract_staged(dest, extract)
+}
+
+/// Like `extract_atomically`, but never writes into a destination that already holds files
+///
+/// # Errors
+/// - `Archive` if `dest` is a non-empty directory
+pub fn extract_staged(dest: &Path, extract: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    if is_populated_dir(dest) {
+        return Err(TimeLockerError::Archive(format!(
+            "{} already contains files; staged extraction needs a missing or empty directory",
+            dest.display()
+        )));
+    }
+
+    let staging = partial_dir(dest);
+    // Left over from a run that was killed before it could clean up
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    create_dir_all(&staging)?;
+
+    let result = extract(&staging).and_then(|()| {
+        File::create(staging.join(UNLOCK_MARKER))?;
+        if dest.exists() {
+            std::fs::remove_dir(dest)?;
+        }
+        std::fs::rename(&staging, dest)?;
+        Ok(())
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn is_populated_dir(path: &Path) -> bool {
+    std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some())
+}
+
 /// Extract a password-protected 7z archive
 ///
 /// # Arguments
@@ -442,11 +1059,417 @@ pub fn extract_encrypted_archive(archive_path: &Path, password: &str, dest: &Pat
     Ok(())
 }
 
+/// Summary of a single archive entry (for listing contents without extracting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntryInfo {
+    /// Path of the entry inside the archive
+    pub name: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Whether the entry is a directory
+    pub is_directory: bool,
+    /// CRC-32 of the entry's content, when the archive records one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc: Option<u32>,
+}
+
+/// List the entries of a password-protected 7z archive by reading its header
+///
+/// # Arguments
+/// * `reader` - Reader positioned over the 7z data
+/// * `password` - Password for the (encrypted) header
+pub fn list_archive_entries<R: Read + Seek>(reader: R, password: &str) -> Result<Vec<ArchiveEntryInfo>> {
+    let archive = ArchiveReader::new(reader, Password::from(password))
+        .map_err(|e| TimeLockerError::Archive(format!("Failed to read archive header: {}", e)))?;
+
+    Ok(archive
+        .archive()
+        .files
+        .iter()
+        .map(|entry| ArchiveEntryInfo {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            is_directory: entry.is_directory(),
+            crc: entry.has_crc.then_some(entry.crc as u32),
+        })
+        .collect())
+}
+
+/// Check whether a password opens a 7z archive, without writing anything to disk
+///
+/// Opens the (encrypted) archive header and decodes the first byte of the first
+/// non-empty entry, which fails quickly when the password is wrong.
+///
+/// # Arguments
+/// * `reader` - Reader positioned over the 7z data
+/// * `password` - Password to test
+///
+/// # Errors
+/// - `Archive` if the archive can't be read for another reason than the
+///   password (truncated, malformed, I/O failure), so a damaged file isn't
+///   reported as a wrong password
+pub fn verify_archive_password<R: Read + Seek>(reader: R, password: &str) -> Result<bool> {
+    let mut archive = match ArchiveReader::new(reader, Password::from(password)) {
+        Ok(archive) => archive,
+        Err(e) if is_wrong_password(&e) => {
+            eprintln!("[verify_archive_password] Failed to open archive header: {}", e);
+            return Ok(false);
+        }
+        Err(e) => {
+            return Err(TimeLockerError::Archive(format!("Failed to read archive header: {}", e)));
+        }
+    };
+
+    let result = archive.for_each_entries(|entry, entry_reader| {
+        if entry.is_directory() || entry.size() == 0 {
+            return Ok(true);
+        }
+        let mut buf = [0u8; 1];
+        entry_reader.read_exact(&mut buf)?;
+        // Stop after the first entry with content
+        Ok(false)
+    });
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if is_wrong_password(&e) => {
+            eprintln!("[verify_archive_password] Failed to decode entry: {}", e);
+            Ok(false)
+        }
+        Err(e) => Err(TimeLockerError::Archive(format!("Failed to decode entry: {}", e))),
+    }
+}
+
+/// Whether a 7z error means the password is wrong rather than the archive being damaged
+///
+/// Decrypting with the wrong key yields garbage, which the 7z reader reports
+/// as a possibly bad password or as data failing its CRC.
+fn is_wrong_password(error: &sevenz_rust2::Error) -> bool {
+    matches!(
+        error,
+        sevenz_rust2::Error::PasswordRequired
+            | sevenz_rust2::Error::MaybeBadPassword(_)
+            | sevenz_rust2::Error::ChecksumVerificationFailed
+    )
+}
+
+/// Read a single file entry of a password-protected 7z archive into memory
+///
+/// Refuses directories and entries larger than `max_bytes`, so a preview never
+/// pulls a large file into memory. Nothing is written to disk.
+///
+/// # Arguments
+/// * `reader` - Reader positioned over the 7z data
+/// * `password` - Password for decryption
+/// * `entry_name` - Path of the entry inside the archive
+/// * `max_bytes` - Largest entry size accepted
+pub fn read_archive_entry<R: Read + Seek>(
+    reader: R,
+    password: &str,
+    entry_name: &str,
+    max_bytes: u64,
+) -> Result<Vec<u8>> {
+    let mut archive = ArchiveReader::new(reader, Password::from(password))
+        .map_err(|e| TimeLockerError::Archive(format!("Failed to read archive header: {}", e)))?;
+
+    let entry = archive
+        .archive()
+        .files
+        .iter()
+        .find(|entry| entry.name() == entry_name)
+        .ok_or_else(|| TimeLockerError::FileNotFound(format!("{} (in archive)", entry_name)))?;
+    if entry.is_directory() {
+        return Err(TimeLockerError::Archive(format!("{} is a directory", entry_name)));
+    }
+    if entry.size() > max_bytes {
+        return Err(TimeLockerError::Archive(format!(
+            "{} is {} bytes, over the {} byte limit",
+            entry_name,
+            entry.size(),
+            max_bytes
+        )));
+    }
+
+    let mut contents = Vec::new();
+    archive
+        .for_each_entries(|entry, entry_reader| {
+            if entry.name() != entry_name {
+                return Ok(true);
+            }
+            // Cap the read too, in case the header size is wrong
+            entry_reader.take(max_bytes).read_to_end(&mut contents)?;
+            Ok(false)
+        })
+        .map_err(|e| TimeLockerError::Archive(format!("Failed to read {}: {}", entry_name, e)))?;
+
+    Ok(contents)
+}
+
+/// What to do when an extracted file would replace an existing one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// Refuse to extract if any output file already exists (nothing is written)
+    #[default]
+    Error,
+    /// Leave existing files untouched
+    Skip,
+    /// Replace existing files
+    Overwrite,
+    /// Write alongside existing files as `name (1).ext`
+    Rename,
+}
+
+/// Options controlling how entries are written by `extract_encrypted_archive_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Skip entries whose output file already exists with the expected size,
+    /// so an interrupted extraction can be continued instead of restarted.
+    /// Incomplete files are rewritten regardless of `overwrite`.
+    pub resume: bool,
+    /// Conflict handling for files that already exist
+    pub overwrite: OverwritePolicy,
+    /// Maximum write throughput in bytes per second (`None` = unlimited)
+    pub rate_limit: Option<u64>,
+    /// Where to report progress as entries are written
+    pub progress: Option<Arc<ProgressEmitter>>,
+}
+
+/// Extract a password-protected 7z archive entry by entry
+///
+/// Unlike `extract_encrypted_archive`, each entry is checked against `options`
+/// before it is written.
+///
+/// # Arguments
+/// * `archive_path` - Path to 7z file
+/// * `password` - Password for decryption
+/// * `dest` - Destination directory
+/// * `options` - Extraction behaviour (resume, overwrite policy)
+pub fn extract_encrypted_archive_with_options(
+    archive_path: &Path,
+    password: &str,
+    dest: &Path,
+    options: &ExtractOptions,
+) -> Result<()> {
+    eprintln!("[extract_encrypted_archive_with_options] Extracting: {:?}", archive_path);
+    eprintln!("[extract_encrypted_archive_with_options] Destination: {:?}", dest);
+    eprintln!("[extract_encrypted_archive_with_options] Options: {:?}", options);
+
+    // Check for conflicts up front so a refused extraction writes nothing
+    if options.overwrite == OverwritePolicy::Error && !options.resume {
+        let entries = list_archive_entries(BufReader::new(File::open(archive_path)?), password)?;
+        if let Some(conflict) = entries
+            .iter()
+            .filter(|e| !e.is_directory)
+            .map(|e| dest.join(&e.name))
+            .find(|p| p.exists())
+        {
+            return Err(TimeLockerError::Archive(format!(
+                "Output file already exists: {} (choose an overwrite policy to continue)",
+                conflict.display()
+            )));
+        }
+    }
+
+    create_dir_all(dest)?;
+
+    let file = File::open(archive_path)?;
+    let reader = BufReader::new(file);
+
+    let mut skipped = 0u32;
+    let pacer = ProgressTracker::new().with_rate_limit(options.rate_limit);
+    let progress = options.progress.as_deref();
+    if let Some(emitter) = progress {
+        let entries = list_archive_entries(BufReader::new(File::open(archive_path)?), password)?;
+        let files: Vec<_> = entries.iter().filter(|e| !e.is_directory).collect();
+        emitter
+            .tracker()
+            .set_total(files.iter().map(|e| e.size).sum(), files.len() as u32);
+        emitter.emit_progress_forced(None, ProgressPhase::Extracting);
+    }
+
+    decompress_with_extract_fn_and_password(
+        reader,
+        dest,
+        Password::from(password),
+        |entry: &ArchiveEntry, reader: &mut dyn Read, dest_path: &PathBuf| {
+            if entry.is_directory() {
+                if !dest_path.exists() {
+                    std::fs::create_dir_all(dest_path)?;
+                }
+                return Ok(true);
+            }
+
+            if dest_path.exists() {
+                let skip = if options.resume {
+                    is_entry_complete(entry, dest_path)
+                } else {
+                    match options.overwrite {
+                        OverwritePolicy::Error => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::AlreadyExists,
+                                format!("Output file already exists: {}", dest_path.display()),
+                            ).into());
+                        }
+                        OverwritePolicy::Skip => true,
+                        OverwritePolicy::Overwrite => false,
+                        OverwritePolicy::Rename => {
+                            write_entry(entry, reader, &next_available_path(dest_path), &pacer, progress)?;
+                            return Ok(true);
+                        }
+                    }
+                };
+
+                if skip {
+                    // Still drain the entry so the (solid) stream stays in sync
+                    std::io::copy(reader, &mut std::io::sink())?;
+                    skipped += 1;
+                    if let Some(emitter) = progress {
+                        emitter.tracker().add_bytes(entry.size());
+                        emitter.tracker().increment_files();
+                    }
+                    return Ok(true);
+                }
+            }
+
+            write_entry(entry, reader, dest_path, &pacer, progress)?;
+            Ok(true)
+        },
+    ).map_err(|e| {
+        eprintln!("[extract_encrypted_archive_with_options] Extraction failed: {}", e);
+        let err_str = e.to_string();
+        if err_str.contains("password") || err_str.contains("Password") || err_str.contains("decrypt") {
+            TimeLockerError::Decryption("Invalid password".to_string())
+        } else {
+            TimeLockerError::Archive(format!("Extraction failed: {}", e))
+        }
+    })?;
+
+    if skipped > 0 {
+        eprintln!("[extract_encrypted_archive_with_options] Skipped {} existing entries", skipped);
+    }
+    if let Some(emitter) = progress {
+        emitter.emit_complete();
+    }
+
+    eprintln!("[extract_encrypted_archive_with_options] Extraction complete");
+    Ok(())
+}
+
+/// Check whether an entry was already fully written by a previous extraction.
+///
+/// The size must match, and when the archive records a CRC for the entry the
+/// existing file's CRC-32 must match too, so a same-size file with different
+/// content is rewritten rather than kept.
+fn is_entry_complete(entry: &ArchiveEntry, dest_path: &Path) -> bool {
+    let same_size = std::fs::metadata(dest_path)
+        .map(|m| m.is_file() && m.len() == entry.size())
+        .unwrap_or(false);
+    if !same_size {
+        return false;
+    }
+    if !entry.has_crc {
+        return true;
+    }
+    file_crc32(dest_path)
+        .map(|crc| crc == entry.crc as u32)
+        .unwrap_or(false)
+}
+
+/// CRC-32 of a file's content, read in chunks
+pub(crate) fn file_crc32(path: &Path) -> Result<u32> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Find a free sibling path of the form `name (1).ext`, `name (2).ext`, ...
+pub(crate) fn next_available_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+    (1u32..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded counter always finds a free name")
+}
+
+/// Write a single file entry to disk, creating parent directories and restoring file times
+///
+/// Writes are paced to `pacer`'s rate limit, if it has one, and reported to `progress`.
+fn write_entry(
+    entry: &ArchiveEntry,
+    reader: &mut dyn Read,
+    dest_path: &Path,
+    pacer: &ProgressTracker,
+    progress: Option<&ProgressEmitter>,
+) -> std::io::Result<()> {
+    if let Some(parent) = dest_path.parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let untracked = ProgressTracker::new();
+    let tracker = progress.map(ProgressEmitter::tracker).unwrap_or(&untracked);
+
+    let mut writer = BufWriter::new(File::create(dest_path)?);
+    if entry.size() > 0 {
+        let mut reader = ReportingReader::new(PacedReader::new(reader, pacer), tracker, || {
+            if let Some(emitter) = progress.filter(|_| tracker.should_emit()) {
+                emitter.emit_progress_forced(Some(entry.name().to_string()), ProgressPhase::Extracting);
+            }
+        });
+        std::io::copy(&mut reader, &mut writer)?;
+    }
+    writer.flush()?;
+    tracker.increment_files();
+
+    let file_times = FileTimes::new()
+        .set_accessed(entry.access_date().into())
+        .set_modified(entry.last_modified_date().into());
+    let _ = writer.get_mut().set_times(file_times);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
 
+    #[test]
+    fn test_remove_partial_output() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_partial_output_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(&temp_dir)?;
+        fs::write(temp_dir.join("existing.txt"), b"keep me")?;
+
+        // A cancelled extraction into a fresh nested destination
+        let dest = temp_dir.join("out");
+        let nested = dest.join("a").join("b");
+        let created_dirs = missing_dirs(&nested);
+        assert_eq!(created_dirs, vec![nested.clone(), dest.join("a"), dest.clone()]);
+        create_dir_all(&nested)?;
+        let partial = nested.join("partial.bin");
+        fs::write(&partial, b"half")?;
+
+        remove_partial_output(&[partial], &created_dirs);
+        assert!(!dest.exists());
+        assert!(temp_dir.join("existing.txt").exists());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn test_create_and_extract_7z() -> Result<()> {
         let temp_dir = std::env::temp_dir().join("test_7z_timelocker");
@@ -459,7 +1482,7 @@ mod tests {
 
         // Create encrypted 7z
         let password = "test_password_123";
-        let archive_path = create_encrypted_archive(&test_file, password)?;
+        let archive_path = create_encrypted_archive(&test_file, password, &CompressionSettings::default())?;
         assert!(archive_path.exists());
         assert!(archive_path.extension().unwrap() == "7z");
 
@@ -480,6 +1503,138 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_special_and_unreadable_files() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = std::env::temp_dir().join("test_7z_special_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let source_dir = temp_dir.join("data");
+        create_dir_all(&source_dir)?;
+        fs::write(source_dir.join("a.txt"), b"regular file")?;
+
+        let fifo = source_dir.join("pipe");
+        let status = std::process::Command::new("mkfifo").arg(&fifo).status()?;
+        assert!(status.success());
+
+        let denied = source_dir.join("denied.txt");
+        fs::write(&denied, b"no access")?;
+        fs::set_permissions(&denied, fs::Permissions::from_mode(0o000))?;
+        // Root can open anything, so only check the denied file when it really is
+        let denied_readable = File::open(&denied).is_ok();
+
+        // Direct targets fail with a clear error instead of hanging
+        let err = check_lock_source(&fifo).unwrap_err();
+        assert!(err.to_string().contains("Unsupported file type"), "{}", err);
+        if !denied_readable {
+            let err = create_encrypted_archive(&denied, "pw", &CompressionSettings::default()).unwrap_err();
+            assert!(err.to_string().contains("Cannot read"), "{}", err);
+        }
+
+        // In directory mode they are skipped
+        let archive_path = create_encrypted_archive(&source_dir, "pw", &CompressionSettings::default())?;
+        let names: Vec<String> = list_archive_entries(File::open(&archive_path)?, "pw")?
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert!(names.iter().any(|n| n.ends_with("a.txt")), "{:?}", names);
+        assert!(!names.iter().any(|n| n.ends_with("pipe")), "{:?}", names);
+        if !denied_readable {
+            assert!(!names.iter().any(|n| n.ends_with("denied.txt")), "{:?}", names);
+        }
+
+        fs::set_permissions(&denied, fs::Permissions::from_mode(0o644))?;
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_names_never_contain_host_path() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_entry_names_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let source_dir = temp_dir.join("data");
+        create_dir_all(source_dir.join("sub"))?;
+        fs::write(source_dir.join("sub").join("a.txt"), b"nested")?;
+        let single = temp_dir.join("single.txt");
+        fs::write(&single, b"single")?;
+
+        // The same temp paths spelled relative to the working directory
+        let cwd = std::env::current_dir()?;
+        let to_root: PathBuf = cwd.components().skip(1).map(|_| "..").collect();
+        let relative = |path: &Path| to_root.join(path.strip_prefix("/").unwrap_or(path));
+
+        let sources = [
+            single.clone(),
+            relative(&single),
+            source_dir.clone(),
+            relative(&source_dir),
+            source_dir.join("sub").join(".."),
+            source_dir.join("."),
+        ];
+        let host = temp_dir.to_string_lossy().trim_start_matches('/').to_string();
+        for source in &sources {
+            let archive_path = temp_dir.join("names.7z");
+            create_encrypted_archive_multi(&[source.as_path()], &archive_path, "pw", &CompressionSettings::default(), None)?;
+            let names: Vec<String> = list_archive_entries(File::open(&archive_path)?, "pw")?
+                .into_iter()
+                .map(|e| e.name)
+                .collect();
+            assert!(!names.is_empty(), "{:?}", source);
+            for name in &names {
+                assert!(!name.starts_with('/') && !name.contains(':'), "{:?}: {}", source, name);
+                assert!(!name.split('/').any(|part| part == ".." || part == "."), "{:?}: {}", source, name);
+                assert!(!name.contains(&host), "{:?}: {}", source, name);
+            }
+            let expected = if source.is_file() { "single.txt" } else { "data/sub/a.txt" };
+            assert!(names.iter().any(|n| n == expected), "{:?}: {:?}", source, names);
+            fs::remove_file(&archive_path)?;
+        }
+
+        assert_eq!(entry_name(&source_dir.join("sub/a.txt"), &source_dir)?, "sub/a.txt");
+        assert!(entry_name(&single, &source_dir).is_err());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_skips_complete_entries() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_resume_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let source_dir = temp_dir.join("data");
+        create_dir_all(&source_dir)?;
+        fs::write(source_dir.join("a.txt"), b"first file")?;
+        fs::write(source_dir.join("b.txt"), b"second file")?;
+
+        let password = "resume_password";
+        let archive_path = create_encrypted_archive(&source_dir, password, &CompressionSettings::default())?;
+
+        let extract_dir = temp_dir.join("extracted");
+        extract_encrypted_archive(&archive_path, password, &extract_dir)?;
+
+        // Simulate an interrupted run: one file missing, one truncated
+        let extracted_a = WalkDir::new(&extract_dir).into_iter().filter_map(|e| e.ok())
+            .find(|e| e.file_name() == "a.txt").unwrap().into_path();
+        let extracted_b = extracted_a.with_file_name("b.txt");
+        fs::remove_file(&extracted_a)?;
+        fs::write(&extracted_b, b"sec")?;
+
+        let options = ExtractOptions { resume: true, ..Default::default() };
+        extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &options)?;
+
+        assert_eq!(fs::read(&extracted_a)?, b"first file");
+        assert_eq!(fs::read(&extracted_b)?, b"second file");
+
+        // A file of the right size but with different content is rewritten
+        fs::write(&extracted_b, b"SECOND FILE")?;
+        extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &options)?;
+        assert_eq!(fs::read(&extracted_b)?, b"second file");
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn test_wrong_password_fails() -> Result<()> {
         let temp_dir = std::env::temp_dir().join("test_7z_wrong_pwd_timelocker");
@@ -492,7 +1647,7 @@ mod tests {
 
         // Create encrypted 7z
         let correct_password = "correct_password";
-        let archive_path = create_encrypted_archive(&test_file, correct_password)?;
+        let archive_path = create_encrypted_archive(&test_file, correct_password, &CompressionSettings::default())?;
 
         // Try to extract with wrong password - should fail
         let extract_dir = temp_dir.join("extracted_wrong");
@@ -508,6 +1663,215 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_overwrite_policies() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_overwrite_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(&temp_dir)?;
+
+        let test_file = temp_dir.join("report.txt");
+        fs::write(&test_file, b"archived")?;
+        let password = "overwrite_password";
+        let archive_path = create_encrypted_archive(&test_file, password, &CompressionSettings::default())?;
+
+        let extract_dir = temp_dir.join("out");
+        create_dir_all(&extract_dir)?;
+        let existing = extract_dir.join("report.txt");
+        fs::write(&existing, b"existing")?;
+
+        let with_policy = |overwrite| ExtractOptions { overwrite, ..Default::default() };
+
+        // Default refuses and leaves the existing file alone
+        assert!(extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &ExtractOptions::default()).is_err());
+        assert_eq!(fs::read(&existing)?, b"existing");
+
+        extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &with_policy(OverwritePolicy::Skip))?;
+        assert_eq!(fs::read(&existing)?, b"existing");
+
+        extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &with_policy(OverwritePolicy::Rename))?;
+        assert_eq!(fs::read(&existing)?, b"existing");
+        assert_eq!(fs::read(extract_dir.join("report (1).txt"))?, b"archived");
+
+        extract_encrypted_archive_with_options(&archive_path, password, &extract_dir, &with_policy(OverwritePolicy::Overwrite))?;
+        assert_eq!(fs::read(&existing)?, b"archived");
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_atomically() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_atomic_extract_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(&temp_dir)?;
+        let dest = temp_dir.join("unlocked_photos");
+        let staging = partial_dir(&dest);
+        assert_eq!(staging, temp_dir.join(".unlocked_photos.partial"));
+
+        // A failure halfway leaves neither the destination nor the staging directory
+        let failed = extract_atomically(&dest, |dir| {
+            fs::write(dir.join("first.jpg"), b"written")?;
+            Err(TimeLockerError::Archive("Operation cancelled".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(!dest.exists());
+        assert!(!staging.exists());
+
+        // Success moves the staged files into place
+        extract_atomically(&dest, |dir| Ok(fs::write(dir.join("first.jpg"), b"written")?))?;
+        assert_eq!(fs::read(dest.join("first.jpg"))?, b"written");
+        assert!(!staging.exists());
+        assert!(is_unlock_output(&dest));
+
+        // Writing into a folder that already has files doesn't claim it
+        let existing = temp_dir.join("my-folder");
+        create_dir_all(&existing)?;
+        fs::write(existing.join("notes.txt"), b"mine")?;
+        extract_atomically(&existing, |dir| Ok(fs::write(dir.join("first.jpg"), b"written")?))?;
+        assert!(!is_unlock_output(&existing));
+
+        // Staged-only extraction refuses the now populated destination
+        let refused = extract_staged(&dest, |_| panic!("must not extract"));
+        assert!(matches!(refused, Err(TimeLockerError::Archive(_))));
+        assert!(!staging.exists());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_must_not_contain_vault() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_vault_overlap_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let vault = temp_dir.join("home/vault");
+        create_dir_all(vault.join("unlocked_photos"))?;
+
+        assert!(check_source_outside_vault(&vault, &vault).is_err());
+        // Via a different spelling of the same directory
+        assert!(check_source_outside_vault(&vault.join("unlocked_photos/.."), &vault).is_err());
+        assert!(check_source_outside_vault(&temp_dir.join("home"), &vault).is_err());
+
+        // Sources inside the vault, or beside it, are fine
+        assert!(check_source_outside_vault(&vault.join("unlocked_photos"), &vault).is_ok());
+        fs::write(temp_dir.join("home/letter.txt"), b"hi")?;
+        assert!(check_source_outside_vault(&temp_dir.join("home/letter.txt"), &vault).is_ok());
+        assert!(check_source_outside_vault(&temp_dir.join("home"), &temp_dir.join("missing")).is_ok());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_filter_detects_executables() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_auto_filter_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(temp_dir.join("build/lib"))?;
+        fs::write(temp_dir.join("notes.txt"), b"plain text")?;
+        fs::write(temp_dir.join("build/lib/libfoo.so.1"), b"not really a library")?;
+
+        let auto = CompressionSettings { filter: CompressionFilter::Auto, ..Default::default() };
+        let notes = temp_dir.join("notes.txt");
+        assert_eq!(auto.resolve_filter(&[temp_dir.as_path()]).filter_name(), Some("bcj_x86"));
+        assert_eq!(auto.resolve_filter(&[notes.as_path()]).filter_name(), None);
+
+        // Store never filters
+        let store = CompressionSettings { method: CompressionMethod::Store, ..auto };
+        assert_eq!(store.resolve_filter(&[temp_dir.as_path()]).filter_name(), None);
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_precompressed_files_are_stored() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_precompressed_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let source = temp_dir.join("photos");
+        create_dir_all(&source)?;
+        // Compressible bytes behind a .jpg name: storing it shows up in the archive size
+        let content = b"not really a JPEG ".repeat(20_000);
+        fs::write(source.join("photo.JPG"), &content)?;
+        fs::write(source.join("notes.txt"), &content)?;
+
+        assert!(is_precompressed_file(Path::new("clip.mp4")));
+        assert!(!is_precompressed_file(Path::new("notes.txt")));
+
+        let mixed_path = temp_dir.join("mixed.7z");
+        let uniform_path = temp_dir.join("uniform.7z");
+        let uniform = CompressionSettings { uniform: true, ..Default::default() };
+        create_encrypted_archive_multi(&[source.as_path()], &mixed_path, "pw", &CompressionSettings::default(), None)?;
+        create_encrypted_archive_multi(&[source.as_path()], &uniform_path, "pw", &uniform, None)?;
+
+        let mixed_len = fs::metadata(&mixed_path)?.len();
+        let uniform_len = fs::metadata(&uniform_path)?.len();
+        assert!(mixed_len > content.len() as u64, "photo.JPG should be stored, archive is {} bytes", mixed_len);
+        assert!(uniform_len < content.len() as u64 / 10, "uniform archive is {} bytes", uniform_len);
+
+        let out = temp_dir.join("out");
+        extract_encrypted_archive(&mixed_path, "pw", &out)?;
+        assert_eq!(fs::read(out.join("photos/photo.JPG"))?, content);
+        assert_eq!(fs::read(out.join("photos/notes.txt"))?, content);
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    /// The test binary itself is x86 machine code, so BCJ should shrink it
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_bcj_filter_shrinks_executable() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_bcj_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(&temp_dir)?;
+
+        let program = fs::read(std::env::current_exe()?)?;
+        let program = &program[..program.len().min(4 << 20)];
+        let exe = temp_dir.join("program.exe");
+        fs::write(&exe, program)?;
+
+        let plain = CompressionSettings::default();
+        let bcj = CompressionSettings { filter: CompressionFilter::BcjX86, ..plain };
+        let plain_path = temp_dir.join("plain.7z");
+        let bcj_path = temp_dir.join("bcj.7z");
+        create_encrypted_archive_multi(&[exe.as_path()], &plain_path, "pw", &plain, None)?;
+        create_encrypted_archive_multi(&[exe.as_path()], &bcj_path, "pw", &bcj, None)?;
+
+        let plain_len = fs::metadata(&plain_path)?.len();
+        let bcj_len = fs::metadata(&bcj_path)?.len();
+        assert!(bcj_len < plain_len, "BCJ {} bytes vs plain {} bytes", bcj_len, plain_len);
+
+        // Filtered archives still extract to the original bytes
+        let out = temp_dir.join("out");
+        extract_encrypted_archive(&bcj_path, "pw", &out)?;
+        assert_eq!(fs::read(out.join("program.exe"))?, program);
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_archive_password() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_7z_verify_pwd_timelocker");
+        let _ = fs::remove_dir_all(&temp_dir);
+        create_dir_all(&temp_dir)?;
+
+        let test_file = temp_dir.join("check.txt");
+        fs::write(&test_file, b"Password check content")?;
+
+        let archive_path = create_encrypted_archive(&test_file, "right_password", &CompressionSettings::default())?;
+
+        assert!(verify_archive_password(File::open(&archive_path)?, "right_password")?);
+        assert!(!verify_archive_password(File::open(&archive_path)?, "wrong_password")?);
+
+        // A truncated archive is an error, not a wrong password
+        let bytes = fs::read(&archive_path)?;
+        let truncated = std::io::Cursor::new(bytes[..bytes.len() / 2].to_vec());
+        assert!(verify_archive_password(truncated, "right_password").is_err());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
     #[test]
     fn test_header_encryption() -> Result<()> {
         let temp_dir = std::env::temp_dir().join("test_7z_header_enc");
@@ -520,7 +1884,7 @@ mod tests {
 
         // Create encrypted archive
         let password = "test_password";
-        let archive_path = create_encrypted_archive(&test_file, password)?;
+        let archive_path = create_encrypted_archive(&test_file, password, &CompressionSettings::default())?;
 
         // Read raw bytes and check for filename
         let data = fs::read(&archive_path)?;
@@ -557,7 +1921,7 @@ mod tests {
 
         // Create encrypted archive
         let password = "test_password";
-        let archive_path = create_encrypted_archive(&test_file, password)?;
+        let archive_path = create_encrypted_archive(&test_file, password, &CompressionSettings::default())?;
 
         eprintln!("Archive created: {} bytes", fs::metadata(&archive_path)?.len());
 
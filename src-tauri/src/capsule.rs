@@ -0,0 +1,191 @@
+//! Time capsule export
+//!
+//! Bundles a .7z.tlock file into a self-explanatory folder for someone with no
+//! prior setup (gifts, inheritance): the locked file, a plaintext instructions
+//! file, and optionally a copy of this program's binary to unlock it with.
+//!
+//! ```text
+//! <name>-capsule/
+//! +-- <name>.7z.tlock
+//! +-- README-UNLOCK.txt
+//! +-- timelocker[.exe]        (optional)
+//! ```
+
+use crate::error::{Result, TimeLockerError};
+use crate::tlock_format::{TlockArchive, TlockMetadata};
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the instructions file inside a capsule
+pub const INSTRUCTIONS_FILE: &str = "README-UNLOCK.txt";
+
+/// Name of the bundled binary inside a capsule
+#[cfg(target_os = "windows")]
+pub const BINARY_NAME: &str = "timelocker.exe";
+#[cfg(not(target_os = "windows"))]
+pub const BINARY_NAME: &str = "timelocker";
+
+/// Export a time capsule folder for a .7z.tlock file
+///
+/// # Arguments
+/// * `tlock_path` - Path to the .7z.tlock file
+/// * `dest_dir` - Directory to create the capsule folder in
+/// * `include_binary` - Copy the running executable into the capsule
+///
+/// # Returns
+/// Path to the created capsule folder
+pub fn export_capsule(tlock_path: &Path, dest_dir: &Path, include_binary: bool) -> Result<PathBuf> {
+    let archive = TlockArchive::read_metadata(tlock_path)?;
+    let metadata = archive
+        .get_metadata()
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+
+    let tlock_name = tlock_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| TimeLockerError::Parse("Invalid tlock file name".to_string()))?
+        .to_string();
+
+    let capsule_dir = dest_dir.join(format!("{}-capsule", metadata.original_file));
+    if capsule_dir.exists() {
+        return Err(TimeLockerError::Archive(format!(
+            "Capsule folder already exists: {}",
+            capsule_dir.display()
+        )));
+    }
+    fs::create_dir_all(&capsule_dir)?;
+
+    eprintln!("[export_capsule] Creating capsule at: {:?}", capsule_dir);
+
+    fs::copy(tlock_path, capsule_dir.join(&tlock_name))?;
+
+    let binary_name = if include_binary {
+        let exe = std::env::current_exe()?;
+        fs::copy(&exe, capsule_dir.join(BINARY_NAME))?;
+        Some(BINARY_NAME)
+    } else {
+        None
+    };
+
+    let instructions = capsule_instructions(metadata, &tlock_name, binary_name);
+    fs::write(capsule_dir.join(INSTRUCTIONS_FILE), instructions)?;
+
+    eprintln!("[export_capsule] Capsule complete");
+    Ok(capsule_dir)
+}
+
+/// Build the plaintext instructions for a capsule
+pub fn capsule_instructions(
+    metadata: &TlockMetadata,
+    tlock_name: &str,
+    binary_name: Option<&str>,
+) -> String {
+    let unlocks_local = metadata.unlocks.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z");
+    let unlocks_utc = metadata.unlocks.format("%Y-%m-%d %H:%M:%S UTC");
+
+    let program = match binary_name {
+        Some(name) if cfg!(target_os = "windows") => format!(".\\{}", name),
+        Some(name) => format!("./{}", name),
+        None => "timelocker".to_string(),
+    };
+
+    let mut text = String::new();
+    text.push_str("TIME CAPSULE\n");
+    text.push_str("============\n\n");
+    text.push_str(&format!(
+        "This folder contains \"{}\", sealed with Time Locker.\n\n",
+        metadata.original_file
+    ));
+    text.push_str("It cannot be opened by anyone - including the person who sealed it -\n");
+    text.push_str("before the unlock date below. The lock is enforced by the public drand\n");
+    text.push_str("randomness network (https://drand.love), not by a password.\n\n");
+    text.push_str(&format!("Unlocks: {}\n", unlocks_local));
+    text.push_str(&format!("         ({})\n\n", unlocks_utc));
+    text.push_str("HOW TO OPEN\n");
+    text.push_str("-----------\n");
+    text.push_str("After the unlock date, with an internet connection, open a terminal in\n");
+    text.push_str("this folder and run:\n\n");
+    text.push_str(&format!("    {} unlock {} --output unlocked\n\n", program, tlock_name));
+    text.push_str("The contents will be written to the \"unlocked\" folder.\n");
+    text.push_str(&format!("To check the status at any time, run: {} info {}\n", program, tlock_name));
+
+    if binary_name.is_none() {
+        text.push_str("\nTime Locker can be downloaded from:\n");
+        text.push_str("https://github.com/justmaier/time-locker/releases\n");
+    }
+
+    text
+}
+
+/// Build a short message to send along with a .7z.tlock file
+///
+/// Unlike the capsule instructions this assumes the recipient gets the file
+/// on its own. Only public metadata is used; nothing in it helps unlock early.
+pub fn share_instructions(metadata: &TlockMetadata, tlock_name: &str) -> String {
+    let unlocks_local = metadata.unlocks.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z");
+    let unlocks_utc = metadata.unlocks.format("%Y-%m-%d %H:%M:%S UTC");
+
+    let mut text = String::new();
+    text.push_str(&format!(
+        "\"{}\" contains \"{}\", time-locked with Time Locker.\n\n",
+        tlock_name, metadata.original_file
+    ));
+    text.push_str(&format!("Unlocks: {} ({})\n", unlocks_local, unlocks_utc));
+    if let Some(round) = metadata.unlock_round() {
+        text.push_str(&format!("drand round: {}\n", round));
+    }
+    text.push_str("\nAfter the unlock date, run:\n\n");
+    text.push_str(&format!("    timelocker unlock {} --output unlocked\n\n", tlock_name));
+    text.push_str("Unlocking needs an internet connection to fetch the drand signature for\n");
+    text.push_str("that round (https://drand.love). Time Locker can be downloaded from:\n");
+    text.push_str("https://github.com/justmaier/time-locker/releases\n");
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_capsule_instructions() {
+        let metadata = TlockMetadata::new(
+            "letter.txt".to_string(),
+            "2030-01-01".to_string(),
+            Utc::now() + Duration::days(30),
+            None,
+            None,
+        );
+
+        let text = capsule_instructions(&metadata, "letter.7z.tlock", None);
+        assert!(text.contains("letter.txt"));
+        assert!(text.contains("timelocker unlock letter.7z.tlock --output unlocked"));
+        assert!(text.contains(&metadata.unlocks.format("%Y-%m-%d").to_string()));
+        assert!(text.contains("releases"));
+
+        let bundled = capsule_instructions(&metadata, "letter.7z.tlock", Some(BINARY_NAME));
+        assert!(bundled.contains(&format!("{} unlock letter.7z.tlock", BINARY_NAME)));
+        assert!(!bundled.contains("releases"));
+    }
+
+    #[test]
+    fn test_share_instructions() {
+        let metadata = TlockMetadata::new(
+            "letter.txt".to_string(),
+            "2030-01-01".to_string(),
+            Utc::now() + Duration::days(30),
+            Some(12345),
+            Some("not a real ciphertext".to_string()),
+        );
+
+        let text = share_instructions(&metadata, "letter.7z.tlock");
+        assert!(text.contains("letter.txt"));
+        assert!(text.contains("UTC"));
+        assert!(text.contains("drand round: 12345"));
+        assert!(text.contains("timelocker unlock letter.7z.tlock --output unlocked"));
+        assert!(text.contains("internet"));
+        assert!(!text.contains("not a real ciphertext"));
+    }
+}
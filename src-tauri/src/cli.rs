@@ -1,34 +1,139 @@
 // cli.rs - Command Line Interface for Time Locker
 
+use crate::archive::{
+    check_source_outside_vault, extract_atomically, extract_staged, CompressionFilter,
+    CompressionMethod, CompressionSettings, ExtractOptions, OverwritePolicy,
+};
+use crate::capsule;
+use crate::clock;
+use crate::commands::TlockMetadataResponse;
 use crate::crypto;
+use crate::download;
 use crate::error::{Result, TimeLockerError};
-use crate::tlock_format::{self, TlockArchive, TlockMetadata};
+use crate::job::LockJob;
+use crate::progress::{self, ProgressEmitter, ProgressPayload, ProgressSink, ProgressTracker};
+use crate::server;
+use crate::tlock_format::{
+    self, ContentSummary, CreateOptions, CryptoParameters, ExtractionPlan, PathHint, TlockArchive,
+    TlockMetadata, VaultMarker,
+};
 use chrono::{DateTime, Local, TimeZone, Utc};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Set under `--json`: progress messages then go to stderr, keeping stdout parseable
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for progress messages of commands that also report JSON
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if JSON_OUTPUT.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// `print!` counterpart of `status!`
+macro_rules! status_inline {
+    ($($arg:tt)*) => {
+        if JSON_OUTPUT.load(Ordering::Relaxed) {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
 
 /// Time Locker - Secure time-locked file encryption
 #[derive(Parser, Debug)]
 #[command(name = "timelocker")]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    /// Print machine-readable JSON ({ "ok", "data", "errors" }) instead of text
+    ///
+    /// A command that fails prints the envelope with an `error` message to
+    /// stderr instead. Commands without a JSON form refuse the flag.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// drand relay to fetch rounds from instead of the public ones (repeatable)
+    #[arg(long = "drand-endpoint", global = true, value_name = "URL")]
+    pub drand_endpoints: Vec<String>,
+
+    #[command(flatten)]
+    pub beacon: BeaconArgs,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+/// Experimental: lock and unlock against a drand chain other than Quicknet
+///
+/// Hidden from help; all four chain parameters must be given together.
+#[derive(Args, Debug)]
+pub struct BeaconArgs {
+    /// Chain hash of the beacon (hex)
+    #[arg(long, global = true, hide = true, requires_all = ["public_key", "genesis_time", "period"])]
+    pub chain_hash: Option<String>,
+
+    /// Public key of the beacon (hex, compressed G2)
+    #[arg(long, global = true, hide = true, requires = "chain_hash")]
+    pub public_key: Option<String>,
+
+    /// Unix timestamp of the beacon's first round
+    #[arg(long, global = true, hide = true, requires = "chain_hash")]
+    pub genesis_time: Option<u64>,
+
+    /// Seconds between rounds
+    #[arg(long, global = true, hide = true, requires = "chain_hash")]
+    pub period: Option<u64>,
+
+    /// Relay serving the beacon (repeatable; defaults to the Quicknet relays)
+    #[arg(long = "beacon-endpoint", global = true, hide = true, requires = "chain_hash")]
+    pub beacon_endpoints: Vec<String>,
+}
+
+impl BeaconArgs {
+    /// Beacon described by the flags, None when they weren't given
+    fn to_config(&self) -> Option<crypto::BeaconConfig> {
+        let chain_hash = self.chain_hash.clone()?;
+        let endpoints = if self.beacon_endpoints.is_empty() {
+            crypto::BeaconConfig::quicknet().endpoints
+        } else {
+            self.beacon_endpoints.clone()
+        };
+        Some(crypto::BeaconConfig {
+            chain_hash: chain_hash.to_lowercase(),
+            public_key: self.public_key.clone()?.to_lowercase(),
+            genesis_time: self.genesis_time?,
+            period: self.period?,
+            endpoints,
+        })
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Lock a file or directory with time-based encryption
     Lock {
-        /// Path to file or directory to lock
-        source: PathBuf,
+        /// Files or directories to lock; several are locked together into one file
+        #[arg(required = true)]
+        sources: Vec<PathBuf>,
 
-        /// Date/time when the file can be unlocked (RFC3339 or "YYYY-MM-DD" or "YYYY-MM-DD HH:MM")
-        #[arg(long, short = 'u')]
-        unlock_at: String,
+        /// Date/time when the file can be unlocked (RFC3339 or "YYYY-MM-DD" or "YYYY-MM-DD HH:MM").
+        /// Repeat it to release the same content on a schedule: each date gets its own
+        /// independently locked copy, named after the date
+        #[arg(long, short = 'u', required = true)]
+        unlock_at: Vec<String>,
 
         /// Vault directory to store the locked file
         #[arg(long, short = 'v')]
@@ -37,21 +142,161 @@ pub enum Commands {
         /// Delete the original file after locking
         #[arg(long, short = 'd')]
         delete_original: bool,
+
+        /// Note to yourself about why this was locked; stored UNENCRYPTED and
+        /// readable by anyone with the file, so never put secrets here
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Custom metadata field (repeatable), e.g. --meta case=1234; stored
+        /// UNENCRYPTED like --note
+        #[arg(long = "meta", value_name = "KEY=VALUE")]
+        meta: Vec<String>,
+
+        /// Date/time the file is meant to be opened by (same formats as --unlock-at).
+        /// Advisory only: unlocking after it needs --force, but nothing cryptographic stops it
+        #[arg(long, value_name = "DATE")]
+        expires_at: Option<String>,
+
+        /// Also let this age recipient (age1...) unlock the file at ANY time.
+        /// This bypasses the time lock for whoever holds the matching key
+        #[arg(long, value_name = "AGE_RECIPIENT")]
+        recipient: Option<String>,
+
+        /// Store an encrypted entry index for fast content listing
+        #[arg(long)]
+        index: bool,
+
+        /// Record where the source was locked from, for `info` and `unlock --restore-path`:
+        /// the name of its folder, or with ROOT, its folder relative to ROOT. Stored
+        /// UNENCRYPTED like --note
+        #[arg(long, value_name = "ROOT", num_args = 0..=1)]
+        record_path: Option<Option<PathBuf>>,
+
+        /// Write an older format version for builds that only read that version
+        #[arg(long, alias = "force-version", value_name = "VERSION")]
+        format_version: Option<u8>,
+
+        /// LZMA2 compression level, 0 (fastest) to 9 (smallest). Defaults to the
+        /// vault's setting (see init-vault), else 6
+        #[arg(long, value_name = "LEVEL", value_parser = clap::value_parser!(u32).range(0..=9))]
+        compression_level: Option<u32>,
+
+        /// Compression method; `store` skips compression for data that doesn't shrink.
+        /// Defaults to the vault's setting, else lzma2
+        #[arg(long, value_enum, value_name = "METHOD")]
+        compression_method: Option<CompressionMethod>,
+
+        /// Filter ahead of LZMA2; `bcj-x86` shrinks x86 executables, `auto` uses it
+        /// when any source is a .exe, .dll or .so file. Defaults to the vault's setting, else none
+        #[arg(long, value_enum, value_name = "FILTER")]
+        compression_filter: Option<CompressionFilter>,
+
+        /// Compress already-compressed files (JPEG, MP4, zip...) too, instead of only
+        /// encrypting them
+        #[arg(long)]
+        uniform_compression: bool,
+
+        /// Record completed sources in a JSON job file; sources already done are skipped
+        #[arg(long, value_name = "PATH")]
+        job_file: Option<PathBuf>,
+
+        /// Read source files no faster than this many MB/s, to spare slow or shared disks
+        #[arg(long, value_name = "MB/s", value_parser = parse_throttle)]
+        throttle: Option<u64>,
+
+        /// Archive password to use instead of a generated one. Whoever knows it can
+        /// open the file at ANY time, so a weak one defeats the time lock
+        #[arg(long)]
+        password: Option<String>,
+
+        /// Don't warn when --password looks weak
+        #[arg(long, requires = "password")]
+        force_weak_password: bool,
+
+        /// Validate and show the unlock round, size and output path without locking anything
+        #[arg(long, conflicts_with = "job_file")]
+        dry_run: bool,
     },
 
     /// Unlock a time-locked file
     Unlock {
-        /// Path to the .7z.tlock file to unlock
+        /// Path or http(s) URL of the .7z.tlock file to unlock
         file: PathBuf,
 
         /// Output directory for extracted files
         #[arg(long, short = 'o')]
         output: Option<PathBuf>,
+
+        /// Name of the output directory when --output isn't given; {name} (the
+        /// original file name), {date} and {timestamp} are filled in, so e.g.
+        /// "{name} unlocked {timestamp}" never collides with an earlier unlock. It needs
+        /// {name} and some fixed text
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            default_value = tlock_format::DEFAULT_OUTPUT_NAME_TEMPLATE,
+            conflicts_with = "output"
+        )]
+        output_name: String,
+
+        /// Recreate the original directory structure under the output directory
+        #[arg(long)]
+        restore_path: bool,
+
+        /// Resume an interrupted extraction, skipping files that are already complete
+        #[arg(long)]
+        resume: bool,
+
+        /// What to do with files that already exist in the output directory
+        #[arg(long, value_enum, default_value_t = OverwritePolicy::Error)]
+        overwrite_policy: OverwritePolicy,
+
+        /// Show what would be extracted without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Delete the .7z.tlock file after the extracted content has been verified.
+        /// Only with the error or overwrite policy, and not with --resume
+        #[arg(long, conflicts_with = "resume")]
+        consume: bool,
+
+        /// Unlock offline with a drand signature fetched elsewhere (hex, for the
+        /// file's round); no network is used and the local clock is not checked
+        #[arg(long, value_name = "HEX")]
+        signature_hex: Option<String>,
+
+        /// age identity file; if the file was locked with --recipient for its
+        /// public key, it opens before the unlock time
+        #[arg(long, value_name = "PATH")]
+        identity: Option<PathBuf>,
+
+        /// Unlock even though the file's advisory expiry time has passed
+        #[arg(long)]
+        force: bool,
+
+        /// Write extracted files no faster than this many MB/s
+        #[arg(long, value_name = "MB/s", value_parser = parse_throttle)]
+        throttle: Option<u64>,
+
+        /// Always extract into a hidden temp directory and rename it into place on
+        /// success; fails instead of writing into an output directory that has files
+        #[arg(long, conflicts_with = "resume")]
+        extract_to_temp_and_move: bool,
+
+        /// Don't extract: write the decrypted-key 7z archive as `<name>.7z` (into the
+        /// output directory, if given) and print its password for any 7z tool.
+        /// Whoever has both can open it at any time; the .7z.tlock file is kept
+        #[arg(
+            long,
+            conflicts_with_all = ["restore_path", "resume", "dry_run", "consume", "extract_to_temp_and_move"]
+        )]
+        as_archive: bool,
     },
 
     /// Display metadata from a .7z.tlock file
     Info {
-        /// Path to the .7z.tlock file
+        /// Path or http(s) URL of the .7z.tlock file
         file: PathBuf,
     },
 
@@ -64,24 +309,519 @@ pub enum Commands {
 
     /// Migrate old .key.md format to new .7z.tlock format
     Migrate {
-        /// Path to the .key.md file
+        /// Path to the .key.md file, or with --all the directory to migrate
         keyfile: PathBuf,
 
+        /// Migrate every .key.md file in the directory and below; a file that fails
+        /// doesn't stop the rest, and ones already migrated are skipped
+        #[arg(long)]
+        all: bool,
+
         /// Delete old files after successful migration
         #[arg(long, short = 'd')]
         delete_old: bool,
+
+        /// Keep old files renamed to *.migrated instead (remove them later with cleanup-migrated)
+        #[arg(long, conflicts_with = "delete_old")]
+        mark_migrated: bool,
+    },
+
+    /// Delete the old files that `migrate --mark-migrated` kept as *.migrated
+    CleanupMigrated {
+        /// Vault directory to clean (defaults to current directory)
+        #[arg(long, short = 'v')]
+        vault: Option<PathBuf>,
+    },
+
+    /// List what an unlockable .7z.tlock file contains, without extracting it
+    Contents {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+    },
+
+    /// Export the encrypted 7z payload for use with an external 7z tool
+    ExportPayload {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+
+        /// Where to write the .7z archive (defaults to <file>.7z next to the input)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// Export a "time capsule" folder with unlock instructions for the recipient
+    Capsule {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+
+        /// Directory to create the capsule folder in (defaults to current directory)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+
+        /// Include a copy of this program so the recipient needs no setup
+        #[arg(long)]
+        include_binary: bool,
+    },
+
+    /// Change the unlock time of a locked file without touching its contents
+    ///
+    /// Needs the archive password: pass it with --password, or the file must
+    /// already be unlockable so the password can be recovered.
+    RelockTime {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+
+        /// New unlock date/time (RFC3339 or "YYYY-MM-DD" or "YYYY-MM-DD HH:MM")
+        #[arg(long, short = 'u')]
+        unlock_at: String,
+
+        /// Archive password, for files locked with a user-chosen password
+        #[arg(long)]
+        password: Option<String>,
+    },
+
+    /// Re-archive a locked file's contents with other compression settings
+    ///
+    /// Needs the archive password: pass it with --password, or the file must
+    /// already be unlockable so the password can be recovered. The unlock time
+    /// and the rest of the metadata stay the same.
+    Recompress {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+
+        /// LZMA2 compression level, 0 (fastest) to 9 (smallest)
+        #[arg(long, short = 'l', value_parser = clap::value_parser!(u32).range(0..=9))]
+        level: u32,
+
+        /// Compression method; `store` skips compression
+        #[arg(long, value_enum, default_value_t = CompressionMethod::Lzma2)]
+        method: CompressionMethod,
+
+        /// Filter ahead of LZMA2 (see `lock --compression-filter`)
+        #[arg(long, value_enum, default_value_t = CompressionFilter::None)]
+        filter: CompressionFilter,
+
+        /// Archive password, for files locked with a user-chosen password
+        #[arg(long)]
+        password: Option<String>,
+    },
+
+    /// Re-encrypt a locked file under a fresh random password, e.g. after
+    /// revealing the old one with export-payload
+    ///
+    /// Needs the archive password: pass it with --password, or the file must
+    /// already be unlockable. The contents are re-archived and the new password
+    /// is time-locked to the same unlock time.
+    RotatePassword {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+
+        /// Current archive password, for files locked with a user-chosen password
+        #[arg(long)]
+        password: Option<String>,
+    },
+
+    /// Print a SHA-256 fingerprint of the whole file, to check copies are identical
+    ///
+    /// Covers the metadata as well as the payload, so editing the note changes
+    /// it. For deduplication and verification, not security.
+    Fingerprint {
+        /// Path to the .7z.tlock file
+        file: PathBuf,
+    },
+
+    /// Verify all locked files in a vault and move corrupt ones into its _corrupt/ folder
+    Quarantine {
+        /// Vault directory to check (defaults to current directory)
+        #[arg(long, short = 'v')]
+        vault: Option<PathBuf>,
+
+        /// Only report corrupt files, don't move them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Cross-check every locked file in a vault: unlock time vs. drand round, header, payload, checksum
+    ///
+    /// Read-only. Exits with a failure status if any file is flagged.
+    Audit {
+        /// Vault directory to audit (defaults to current directory)
+        #[arg(long, short = 'v')]
+        vault: Option<PathBuf>,
+    },
+
+    /// Mark a directory as a vault by writing a .timelocker-vault file into it
+    ///
+    /// Vault discovery prefers marked directories, and files locked into the
+    /// vault use its compression settings unless lock is given others.
+    InitVault {
+        /// Directory to mark, created if missing (defaults to current directory)
+        dir: Option<PathBuf>,
+
+        /// Default LZMA2 compression level for files locked into this vault
+        #[arg(long, value_name = "LEVEL", value_parser = clap::value_parser!(u32).range(0..=9))]
+        compression_level: Option<u32>,
+
+        /// Default compression method for files locked into this vault
+        #[arg(long, value_enum, value_name = "METHOD")]
+        compression_method: Option<CompressionMethod>,
+
+        /// Keep this directory and everything below it out of vault discovery instead
+        #[arg(long, conflicts_with_all = ["compression_level", "compression_method"])]
+        exclude: bool,
+
+        /// Replace an existing marker
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check .7z.tlock files for corruption without unlocking them
+    ///
+    /// Exits with a failure status if any file fails.
+    Verify {
+        /// A .7z.tlock file, or a directory of them
+        file_or_dir: PathBuf,
+
+        /// Also check files in subdirectories
+        #[arg(long, short = 'r')]
+        recursive: bool,
+    },
+
+    /// Rewrite file headers in the canonical layout (zeroed reserved bytes)
+    ///
+    /// Metadata and payload are kept as they are; each rewritten file is
+    /// verified before it replaces the original.
+    NormalizeHeader {
+        /// A .7z.tlock file, or a directory of them
+        file_or_dir: PathBuf,
+
+        /// Also rewrite files in subdirectories
+        #[arg(long, short = 'r')]
+        recursive: bool,
+    },
+
+    /// Rebuild a .7z.tlock file whose header was damaged, from the payload still inside it
+    ///
+    /// A last resort, e.g. after a transfer cut off the start of the file. The
+    /// original name, unlock time and encrypted key can't be read back from a
+    /// damaged file: pass them (e.g. from the plain-JSON metadata of an older
+    /// copy) or answer the prompts. Writes <name>.recovered.7z.tlock; the damaged file is kept.
+    Recover {
+        /// Path to the damaged .7z.tlock file
+        file: PathBuf,
+
+        /// Name of the file or folder that was locked
+        #[arg(long)]
+        original_file: Option<String>,
+
+        /// Unlock date/time the file was locked until (same formats as `lock`)
+        #[arg(long, short = 'u')]
+        unlock_at: Option<String>,
+
+        /// Time-locked archive password: the file's "encrypted_key" metadata field
+        #[arg(long)]
+        encrypted_key: Option<String>,
+
+        /// Don't ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+
+    /// Serve a local JSON API (lock/unlock/info/list) on 127.0.0.1 for other apps
+    ///
+    /// Prints an access token at startup; every request must send it as
+    /// `Authorization: Bearer <token>`.
+    Serve {
+        /// Port to listen on (0 picks a free port)
+        #[arg(long, short = 'p', default_value_t = 0)]
+        port: u16,
+    },
+
+    /// Show the app version, the file format versions it supports and the beacon it locks against
+    About,
+
+    /// Check that the drand relays answer, serve the expected chain and agree with the local clock
+    ///
+    /// Exits with a failure status if no relay is healthy.
+    Doctor,
+
+    /// Time fetching the latest round from each drand relay, fastest first
+    ///
+    /// Exits with a failure status if no relay answered.
+    BenchmarkEndpoints {
+        /// Fetches per relay; the median is reported
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+        samples: u32,
     },
 }
 
+impl Commands {
+    /// Whether the command has a `--json` form; the others only print text
+    fn supports_json(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Migrate { .. }
+                | Commands::ExportPayload { .. }
+                | Commands::Capsule { .. }
+                | Commands::RelockTime { .. }
+                | Commands::Recompress { .. }
+                | Commands::RotatePassword { .. }
+                | Commands::Serve { .. }
+        )
+    }
+}
+
+// ============================================================================
+// JSON output
+// ============================================================================
+
+/// Envelope shared by every command's `--json` output
+#[derive(Debug, Serialize)]
+pub(crate) struct JsonEnvelope<T: Serialize> {
+    pub(crate) ok: bool,
+    pub(crate) data: Option<T>,
+    pub(crate) errors: Vec<String>,
+}
+
+impl<T: Serialize> JsonEnvelope<T> {
+    pub(crate) fn success(data: T) -> Self {
+        Self {
+            ok: true,
+            data: Some(data),
+            errors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn failure(error: &TimeLockerError) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            errors: vec![error.to_string()],
+        }
+    }
+}
+
+/// Print a successful result as a JSON envelope
+fn print_json<T: Serialize>(data: T) -> Result<()> {
+    print_json_envelope(&JsonEnvelope::success(data))
+}
+
+/// `--json` output of a command that failed: the envelope plus the message as `error`
+#[derive(Debug, Serialize)]
+struct JsonError {
+    #[serde(flatten)]
+    envelope: JsonEnvelope<()>,
+    error: String,
+}
+
+/// Print a command failure to stderr as `{ "ok": false, "data": null, "errors": [..], "error": ".." }`
+///
+/// Scripts can read `error` alone or treat it like any other envelope; stdout
+/// stays empty, so only successful output is ever parsed from it.
+fn print_json_error(error: &TimeLockerError) {
+    let output = JsonError {
+        envelope: JsonEnvelope::failure(error),
+        error: error.to_string(),
+    };
+    match serde_json::to_string_pretty(&output) {
+        Ok(json) => eprintln!("{}", json),
+        Err(_) => eprintln!("{{\"error\": \"Failed to serialize output\"}}"),
+    }
+}
+
+fn print_json_envelope<T: Serialize>(envelope: &JsonEnvelope<T>) -> Result<()> {
+    let json = serde_json::to_string_pretty(envelope)
+        .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize output: {}", e)))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// `info` output
+#[derive(Debug, Serialize)]
+pub(crate) struct InfoReport {
+    file: String,
+    original_file: String,
+    original_files: Option<Vec<String>>,
+    is_directory: bool,
+    original_path: Option<String>,
+    note: Option<String>,
+    created: DateTime<Utc>,
+    unlocks: DateTime<Utc>,
+    expires: Option<DateTime<Utc>>,
+    expired: bool,
+    /// Whether an age recipient can unlock the file before `unlocks`
+    recipient_can_unlock_early: bool,
+    duration: String,
+    unlockable: bool,
+    seconds_remaining: i64,
+    drand_round: Option<u64>,
+    /// Seconds until the drand round is published and the key can be decrypted
+    seconds_until_decryptable: i64,
+    /// Size of the contents, as recorded when the file was locked
+    original_size: Option<u64>,
+    /// File count and size from the archive itself, once it's unlockable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contents: Option<ContentSummary>,
+    /// How the contents were protected, if recorded when the file was locked
+    crypto_parameters: Option<CryptoParameters>,
+    /// Beacon the key is locked against, if its parameters were recorded
+    beacon_parameters: Option<crypto::BeaconParameters>,
+}
+
+impl InfoReport {
+    pub(crate) fn new(file: &Path, metadata: &TlockMetadata) -> Self {
+        Self {
+            file: file.display().to_string(),
+            original_file: metadata.original_file.clone(),
+            original_files: metadata.original_files.clone(),
+            is_directory: metadata.is_directory,
+            original_path: metadata.original_path.clone(),
+            note: metadata.note.clone(),
+            created: metadata.created,
+            unlocks: metadata.unlocks,
+            expires: metadata.expires,
+            expired: metadata.is_expired(),
+            recipient_can_unlock_early: metadata.recipient_encrypted_key.is_some(),
+            duration: metadata.duration.clone(),
+            unlockable: metadata.is_unlockable(),
+            seconds_remaining: metadata.time_until_unlock().num_seconds().max(0),
+            drand_round: metadata.unlock_round(),
+            seconds_until_decryptable: metadata.time_until_cryptographic_unlock().num_seconds().max(0),
+            original_size: metadata.original_size,
+            contents: None,
+            crypto_parameters: metadata.crypto_parameters.clone(),
+            beacon_parameters: metadata.beacon_parameters.clone(),
+        }
+    }
+}
+
+/// One row of `list` output: the file path plus the same fields the GUI gets
+/// from `read_tlock_metadata`
+#[derive(Debug, Serialize)]
+pub(crate) struct ListEntry {
+    file: String,
+    #[serde(flatten)]
+    metadata: TlockMetadataResponse,
+}
+
+impl ListEntry {
+    /// Build the `list` rows for every readable archive
+    pub(crate) fn from_archives(archives: &[TlockArchive]) -> Vec<Self> {
+        archives
+            .iter()
+            .filter_map(|archive| {
+                archive.get_metadata().map(|metadata| ListEntry {
+                    file: archive.path.display().to_string(),
+                    metadata: TlockMetadataResponse::from(metadata),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `about` output
+#[derive(Debug, Serialize)]
+struct AboutReport {
+    version: &'static str,
+    /// Oldest .7z.tlock format version this build reads and writes
+    min_format_version: u8,
+    /// Newest .7z.tlock format version; written by default
+    max_format_version: u8,
+    beacon_chain_hash: String,
+    /// "command line" or the `TIMELOCKER_BEACON` config file, None for Quicknet
+    beacon_config: Option<String>,
+    features: Vec<&'static str>,
+}
+
+/// `fingerprint` output
+#[derive(Debug, Serialize)]
+struct FingerprintReport {
+    file: String,
+    /// SHA-256 of the whole file, hex
+    fingerprint: String,
+}
+
+/// `lock` output
+#[derive(Debug, Serialize)]
+struct LockReport {
+    /// The created (or, with a job file, previously created) .7z.tlock file
+    file: String,
+    /// What would be locked, for `--dry-run` (`file` is then not created)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<LockPlan>,
+}
+
+/// `lock --dry-run` summary
+#[derive(Debug, Serialize)]
+struct LockPlan {
+    unlock_at: String,
+    drand_round: u64,
+    total_bytes: u64,
+    file_count: u32,
+}
+
+/// `unlock` output
+#[derive(Debug, Serialize)]
+struct UnlockReport {
+    file: String,
+    output: String,
+    /// What would be extracted, for `--dry-run`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<ExtractionPlan>,
+    /// Password of the written archive, for `--as-archive`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_password: Option<String>,
+}
+
+/// One row of `verify` output
+#[derive(Debug, Serialize)]
+struct VerifyResult {
+    file: String,
+    passed: bool,
+    error: Option<String>,
+}
+
+/// One row of `normalize-header` output
+#[derive(Debug, Serialize)]
+struct NormalizeResult {
+    file: String,
+    rewritten: bool,
+    error: Option<String>,
+}
+
 /// Run the CLI application
 pub fn run() -> ExitCode {
     let cli = Cli::parse();
+    JSON_OUTPUT.store(cli.json, Ordering::Relaxed);
+
+    let report_error = |e: &TimeLockerError| {
+        if cli.json {
+            print_json_error(e);
+        } else {
+            eprintln!("Error: {}", e);
+        }
+    };
+
+    if let Err(e) = crypto::set_drand_endpoints(&cli.drand_endpoints) {
+        report_error(&e);
+        return ExitCode::FAILURE;
+    }
+    if let Some(beacon) = cli.beacon.to_config() {
+        if let Err(e) = crypto::set_beacon_override(beacon) {
+            report_error(&e);
+            return ExitCode::FAILURE;
+        }
+        eprintln!(
+            "Warning: using an experimental beacon; files locked with it only unlock \
+             while its relays serve that chain"
+        );
+    }
 
     match cli.command {
-        Some(cmd) => match execute_command(cmd) {
-            Ok(_) => ExitCode::SUCCESS,
+        Some(cmd) => match execute_command(cmd, cli.json) {
+            Ok(code) => code,
             Err(e) => {
-                eprintln!("Error: {}", e);
+                report_error(&e);
                 ExitCode::FAILURE
             }
         },
@@ -93,72 +833,467 @@ pub fn run() -> ExitCode {
 }
 
 /// Execute a CLI command
-fn execute_command(cmd: Commands) -> Result<()> {
-    match cmd {
+fn execute_command(cmd: Commands, json: bool) -> Result<ExitCode> {
+    if json && !cmd.supports_json() {
+        return Err(TimeLockerError::Parse(
+            "This command has no --json output; run it without --json".to_string(),
+        ));
+    }
+
+    let result = match cmd {
         Commands::Lock {
-            source,
+            sources,
             unlock_at,
             vault,
             delete_original,
-        } => cmd_lock(&source, &unlock_at, vault.as_deref(), delete_original),
+            note,
+            meta,
+            expires_at,
+            recipient,
+            index,
+            record_path,
+            format_version,
+            compression_level,
+            compression_method,
+            compression_filter,
+            uniform_compression,
+            job_file,
+            throttle,
+            password,
+            force_weak_password,
+            dry_run,
+        } => {
+            if let Some(ref password) = password {
+                warn_weak_password(password, force_weak_password);
+            }
+            let extra = tlock_format::parse_extra_fields(&meta)?;
+            // Flags override the vault's defaults one by one
+            let mut compression = vault
+                .as_deref()
+                .and_then(tlock_format::vault_compression)
+                .unwrap_or_default();
+            compression.level = compression_level.unwrap_or(compression.level);
+            compression.method = compression_method.unwrap_or(compression.method);
+            compression.filter = compression_filter.unwrap_or(compression.filter);
+            compression.uniform |= uniform_compression;
+            let options = CreateOptions {
+                entry_index: index,
+                format_version,
+                path_hint: record_path.map(|root| match root {
+                    Some(root) => PathHint::RelativeTo(root),
+                    None => PathHint::Parent,
+                }),
+                compression,
+                rate_limit: throttle,
+                ..Default::default()
+            };
+            let version = options.resolve_version()?;
+            if version < tlock_format::CHECKSUM_VERSION {
+                status!(
+                    "Note: format version {} stores no payload checksum, so corruption is only \
+                     noticed when the file is unlocked",
+                    version
+                );
+            }
+            if unlock_at.len() > 1 {
+                if job_file.is_some() {
+                    return Err(TimeLockerError::Parse(
+                        "--job-file takes one --unlock-at per run".to_string(),
+                    ));
+                }
+                let reports = cmd_lock_schedule(
+                    &sources,
+                    &unlock_at,
+                    vault.as_deref(),
+                    delete_original,
+                    note,
+                    extra,
+                    expires_at.as_deref(),
+                    recipient.as_deref(),
+                    password.as_deref(),
+                    &options,
+                    dry_run,
+                )?;
+                return if json {
+                    print_json(reports).map(|_| ExitCode::SUCCESS)
+                } else {
+                    Ok(ExitCode::SUCCESS)
+                };
+            }
+            let unlock_at = &unlock_at[0];
+            let report = match job_file {
+                Some(_) if sources.len() > 1 => Err(TimeLockerError::Parse(
+                    "--job-file takes one source per run".to_string(),
+                )),
+                Some(job_path) => cmd_lock_job(
+                    &job_path,
+                    &sources[0],
+                    unlock_at,
+                    vault.as_deref(),
+                    delete_original,
+                    note,
+                    extra,
+                    expires_at.as_deref(),
+                    recipient.as_deref(),
+                    password.as_deref(),
+                    &options,
+                )
+                .map(|tlock_path| LockReport {
+                    file: tlock_path.display().to_string(),
+                    plan: None,
+                }),
+                None => cmd_lock(
+                    &sources,
+                    unlock_at,
+                    vault.as_deref(),
+                    delete_original,
+                    note,
+                    extra,
+                    expires_at.as_deref(),
+                    recipient.as_deref(),
+                    password.as_deref(),
+                    &options,
+                    dry_run,
+                ),
+            }?;
+            if json {
+                print_json(report)
+            } else {
+                Ok(())
+            }
+        }
+
+        Commands::Unlock {
+            file,
+            output,
+            output_name,
+            restore_path,
+            resume,
+            overwrite_policy,
+            dry_run,
+            consume,
+            signature_hex,
+            identity,
+            force,
+            throttle,
+            extract_to_temp_and_move,
+            as_archive,
+        } => {
+            // Skipped or renamed files mean the output isn't this file's content
+            if consume && !matches!(overwrite_policy, OverwritePolicy::Error | OverwritePolicy::Overwrite) {
+                return Err(TimeLockerError::Parse(
+                    "--consume needs --overwrite-policy error or overwrite".to_string(),
+                ));
+            }
+            let options = ExtractOptions {
+                resume,
+                overwrite: overwrite_policy,
+                rate_limit: throttle,
+                ..Default::default()
+            };
+            let signature = signature_hex.as_deref().map(crypto::parse_signature_hex).transpose()?;
+            let report = match file.to_str().filter(|s| download::is_url(s)) {
+                Some(_) if signature.is_some() => Err(TimeLockerError::Parse(
+                    "--signature-hex is for offline unlocking and can't be used with a URL".to_string(),
+                )),
+                Some(_) if as_archive => Err(TimeLockerError::Parse(
+                    "--as-archive needs a local file; download it first".to_string(),
+                )),
+                Some(_) if consume => Err(TimeLockerError::Parse(
+                    "--consume needs a local file; a downloaded copy is never kept".to_string(),
+                )),
+                Some(url) => cmd_unlock_url(
+                    url,
+                    output.as_deref(),
+                    &output_name,
+                    restore_path,
+                    &options,
+                    extract_to_temp_and_move,
+                    dry_run,
+                    identity.as_deref(),
+                    force,
+                ),
+                None => cmd_unlock(
+                    &file,
+                    output.as_deref(),
+                    &output_name,
+                    restore_path,
+                    &options,
+                    extract_to_temp_and_move,
+                    dry_run,
+                    consume,
+                    signature.as_deref(),
+                    identity.as_deref(),
+                    force,
+                    as_archive,
+                ),
+            }?;
+            if json {
+                print_json(report)
+            } else {
+                Ok(())
+            }
+        }
+
+        Commands::Info { file } => cmd_info(&file, json),
+
+        Commands::List { vault } => cmd_list(vault.as_deref(), json),
+
+        Commands::Migrate {
+            keyfile,
+            all,
+            delete_old,
+            mark_migrated,
+        } => {
+            if all {
+                let complete = cmd_migrate_all(&keyfile, delete_old, mark_migrated)?;
+                return Ok(if complete { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+            }
+            cmd_migrate(&keyfile, delete_old, mark_migrated)
+        }
+
+        Commands::CleanupMigrated { vault } => cmd_cleanup_migrated(vault.as_deref(), json),
+
+        Commands::Contents { file } => cmd_contents(&file, json),
+
+        Commands::ExportPayload { file, output } => cmd_export_payload(&file, output.as_deref()),
+
+        Commands::Capsule {
+            file,
+            output,
+            include_binary,
+        } => cmd_capsule(&file, output.as_deref(), include_binary),
+
+        Commands::RelockTime {
+            file,
+            unlock_at,
+            password,
+        } => cmd_relock_time(&file, &unlock_at, password),
+
+        Commands::Recompress {
+            file,
+            level,
+            method,
+            filter,
+            password,
+        } => cmd_recompress(
+            &file,
+            CompressionSettings { level, method, filter, uniform: false },
+            password,
+        ),
+
+        Commands::RotatePassword { file, password } => cmd_rotate_password(&file, password),
+
+        Commands::Fingerprint { file } => cmd_fingerprint(&file, json),
+
+        Commands::Quarantine { vault, dry_run } => cmd_quarantine(vault.as_deref(), dry_run, json),
+
+        Commands::Audit { vault } => {
+            let clean = cmd_audit(vault.as_deref(), json)?;
+            return Ok(if clean { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+
+        Commands::InitVault {
+            dir,
+            compression_level,
+            compression_method,
+            exclude,
+            force,
+        } => {
+            let compression = (compression_level.is_some() || compression_method.is_some()).then(|| {
+                let defaults = CompressionSettings::default();
+                CompressionSettings {
+                    level: compression_level.unwrap_or(defaults.level),
+                    method: compression_method.unwrap_or(defaults.method),
+                    ..defaults
+                }
+            });
+            let marker = VaultMarker { exclude, compression };
+            cmd_init_vault(dir.as_deref(), &marker, force, json)
+        }
+
+        Commands::Verify {
+            file_or_dir,
+            recursive,
+        } => {
+            let passed = cmd_verify(&file_or_dir, recursive, json)?;
+            return Ok(if passed { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+
+        Commands::NormalizeHeader {
+            file_or_dir,
+            recursive,
+        } => {
+            let succeeded = cmd_normalize_header(&file_or_dir, recursive, json)?;
+            return Ok(if succeeded { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+
+        Commands::Recover {
+            file,
+            original_file,
+            unlock_at,
+            encrypted_key,
+            yes,
+        } => cmd_recover(&file, original_file, unlock_at, encrypted_key, yes, json),
+
+        Commands::Serve { port } => server::serve(port),
+
+        Commands::About => cmd_about(json),
+
+        Commands::Doctor => {
+            let healthy = cmd_doctor(json)?;
+            return Ok(if healthy { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+
+        Commands::BenchmarkEndpoints { samples } => {
+            let answered = cmd_benchmark_endpoints(samples, json)?;
+            return Ok(if answered { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+    };
 
-        Commands::Unlock { file, output } => cmd_unlock(&file, output.as_deref()),
+    result.map(|_| ExitCode::SUCCESS)
+}
+
+/// Redraws one status line with the percentage and ETA of a running lock or unlock
+struct TerminalProgress;
+
+impl ProgressSink for TerminalProgress {
+    fn send(&self, payload: &ProgressPayload) -> bool {
+        let percent = match payload.percentage {
+            Some(percent) => format!("{:5.1}%", percent),
+            None => "    ?%".to_string(),
+        };
+        status_inline!("\r  {}  {}", percent, format_eta(payload.eta_seconds));
+        let _ = io::stdout().flush();
+        true
+    }
+}
 
-        Commands::Info { file } => cmd_info(&file),
+/// A progress emitter printing to the terminal, refreshed at the tracker's throttle interval
+fn terminal_progress() -> Arc<ProgressEmitter> {
+    Arc::new(ProgressEmitter::with_sink(Arc::new(ProgressTracker::new()), TerminalProgress))
+}
 
-        Commands::List { vault } => cmd_list(vault.as_deref()),
+/// `ETA 00:02:13`, or `ETA --:--:--` until there's an estimate
+fn format_eta(eta_seconds: Option<f64>) -> String {
+    match eta_seconds {
+        Some(seconds) => {
+            let seconds = seconds.round() as u64;
+            format!(
+                "ETA {:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            )
+        }
+        None => "ETA --:--:--".to_string(),
+    }
+}
 
-        Commands::Migrate { keyfile, delete_old } => cmd_migrate(&keyfile, delete_old),
+/// Warn (without refusing) when a user-chosen lock password looks guessable
+fn warn_weak_password(password: &str, force: bool) {
+    if force {
+        return;
+    }
+    if let Some(warning) = crypto::weak_password_warning(password) {
+        status!("Warning: {}", warning);
+        status!("Use a longer password, or pass --force-weak-password to hide this warning");
     }
 }
 
 /// Lock command implementation
+#[allow(clippy::too_many_arguments)]
 fn cmd_lock(
-    source: &Path,
+    sources: &[PathBuf],
     unlock_at: &str,
     vault: Option<&Path>,
     delete_original: bool,
-) -> Result<()> {
-    // Validate source exists
-    if !source.exists() {
-        return Err(TimeLockerError::FileNotFound(source.display().to_string()));
+    note: Option<String>,
+    extra: BTreeMap<String, String>,
+    expires_at: Option<&str>,
+    recipient: Option<&str>,
+    password: Option<&str>,
+    options: &CreateOptions,
+    dry_run: bool,
+) -> Result<LockReport> {
+    // Validate sources exist
+    for source in sources {
+        if !source.exists() {
+            return Err(TimeLockerError::FileNotFound(source.display().to_string()));
+        }
+    }
+    let source = sources[0].as_path();
+    let all_sources: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+    if let Some(vault_dir) = vault {
+        for source in &all_sources {
+            check_source_outside_vault(source, vault_dir)?;
+        }
     }
 
-    // Parse unlock time
-    let unlock_datetime = parse_datetime(unlock_at)?;
+    // Reject an unsupported --format-version before doing any work
+    options.resolve_version()?;
+
+    let beacon = crypto::BeaconConfig::active()?;
+    let unlock_datetime = resolve_lock_time(unlock_at, &beacon)?;
 
-    if unlock_datetime <= Utc::now() {
+    let expires = expires_at.map(parse_datetime).transpose()?;
+    if expires.is_some_and(|expires| expires <= unlock_datetime) {
         return Err(TimeLockerError::Parse(
-            "Unlock time must be in the future".to_string(),
+            "Expiry time must be after the unlock time".to_string(),
         ));
     }
 
-    println!("Locking: {}", source.display());
-    println!(
+    for source in &all_sources {
+        status!("Locking: {}", source.display());
+    }
+    status!(
         "Unlock at: {}",
         unlock_datetime
             .with_timezone(&Local)
             .format("%Y-%m-%d %H:%M:%S %Z")
     );
+    if let Some(expires) = expires {
+        status!(
+            "Expires (advisory): {}",
+            expires.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z")
+        );
+    }
 
-    // Generate password
-    print!("Generating secure password... ");
-    io::stdout().flush()?;
-    let password = crypto::generate_password(32);
-    println!("done");
+    if dry_run {
+        return lock_dry_run(&all_sources, vault, delete_original, unlock_datetime, &beacon);
+    }
+
+    let password = match password {
+        Some(password) => password.to_string(),
+        None => {
+            status_inline!("Generating secure password... ");
+            io::stdout().flush()?;
+            let password = crypto::generate_password(32);
+            status!("done");
+            password
+        }
+    };
 
     // Encrypt the password with time-lock
-    print!("Encrypting password with time-lock... ");
+    status_inline!("Encrypting password with time-lock... ");
     io::stdout().flush()?;
-    let encrypted_password = crypto::encrypt_with_tlock(&password, unlock_datetime)?;
-    println!("done");
+    let encrypted_password = crypto::encrypt_with_tlock(&password, unlock_datetime, &beacon)?;
+    status!("done");
+
+    let recipient_encrypted_key = match recipient {
+        Some(recipient) => {
+            status_inline!("Encrypting password to recipient... ");
+            io::stdout().flush()?;
+            let encrypted = crypto::encrypt_to_recipient(&password, recipient)?;
+            status!("done");
+            status!("Warning: the recipient can unlock this file at any time, before the unlock date");
+            Some(encrypted)
+        }
+        None => None,
+    };
 
     // Create metadata
-    let original_filename = source
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let original_filename = tlock_format::multi_source_summary(&all_sources);
 
     let duration_str = unlock_datetime.format("%Y-%m-%d").to_string();
     let mut metadata = TlockMetadata::new(
@@ -169,25 +1304,44 @@ fn cmd_lock(
         Some(encrypted_password),
     );
     metadata.is_directory = source.is_dir();
+    metadata.original_path = options
+        .path_hint
+        .as_ref()
+        .map(|hint| tlock_format::original_path_hint(source, hint))
+        .transpose()?
+        .flatten();
+    metadata.set_beacon(&beacon);
+    metadata.note = tlock_format::normalize_note(note);
+    metadata.extra = extra;
+    metadata.expires = expires;
+    metadata.recipient_encrypted_key = recipient_encrypted_key;
+    // Fail on oversized custom fields before spending time on the archive
+    metadata.to_json()?;
 
     // Create .7z.tlock file
-    print!("Creating encrypted archive... ");
-    io::stdout().flush()?;
-    let tlock_path = TlockArchive::create(source, metadata, &password)?;
-    println!("done");
+    status!("Creating encrypted archive...");
+    let options = &CreateOptions {
+        progress: Some(terminal_progress()),
+        ..options.clone()
+    };
+    let tlock_path = if all_sources.len() > 1 {
+        TlockArchive::create_multi_with_options(&all_sources, metadata, &password, options)?
+    } else {
+        TlockArchive::create_with_options(source, metadata, &password, options)?
+    };
+    status!("\r  done                    ");
 
     // Move to vault if specified
     let final_path = if let Some(vault_dir) = vault {
-        if vault_dir.exists() && vault_dir.is_dir() {
-            let filename = tlock_path.file_name().unwrap();
-            let dest_path = vault_dir.join(filename);
-            print!("Moving to vault... ");
+        if vault_dir.is_dir() {
+            let dest_path = vault_path(vault_dir, &tlock_path);
+            status_inline!("Moving to vault... ");
             io::stdout().flush()?;
             fs::rename(&tlock_path, &dest_path)?;
-            println!("done");
+            status!("done");
             dest_path
         } else {
-            println!("Warning: Vault directory does not exist, keeping in place");
+            status!("Warning: Vault directory does not exist, keeping in place");
             tlock_path
         }
     } else {
@@ -196,61 +1350,438 @@ fn cmd_lock(
 
     // Delete original if requested
     if delete_original {
-        print!("Verifying archive... ");
+        status_inline!("Verifying archive... ");
         io::stdout().flush()?;
         if TlockArchive::validate(&final_path)? {
-            println!("done");
-            print!("Deleting original... ");
+            status!("done");
+            status_inline!("Deleting original... ");
             io::stdout().flush()?;
-            if source.is_dir() {
-                fs::remove_dir_all(source)?;
-            } else {
-                fs::remove_file(source)?;
+            for source in &all_sources {
+                if source.is_dir() {
+                    fs::remove_dir_all(source)?;
+                } else {
+                    fs::remove_file(source)?;
+                }
             }
-            println!("done");
+            status!("done");
         } else {
-            println!("failed");
-            println!("Warning: Archive verification failed, original not deleted");
+            status!("failed");
+            status!("Warning: Archive verification failed, original not deleted");
         }
     }
 
-    println!();
-    println!("Success! Created: {}", final_path.display());
-    println!(
+    status!();
+    status!("Success! Created: {}", final_path.display());
+    status!(
         "File will be unlockable after: {}",
         unlock_datetime
             .with_timezone(&Local)
             .format("%Y-%m-%d %H:%M:%S")
     );
 
-    Ok(())
+    Ok(LockReport {
+        file: final_path.display().to_string(),
+        plan: None,
+    })
 }
 
-/// Unlock command implementation
-fn cmd_unlock(file: &Path, output: Option<&Path>) -> Result<()> {
-    // Validate file exists
+/// Parse and check an `--unlock-at` value for locking
+///
+/// Moves it later when it's too close to now for the first unlock attempt to succeed.
+fn resolve_lock_time(unlock_at: &str, beacon: &crypto::BeaconConfig) -> Result<DateTime<Utc>> {
+    let unlock_datetime = parse_datetime(unlock_at)?;
+
+    if unlock_datetime <= clock::now() {
+        return Err(TimeLockerError::Parse(
+            "Unlock time must be in the future".to_string(),
+        ));
+    }
+
+    // Keep a few beacon periods of headroom so the first unlock attempt succeeds
+    let (unlock_datetime, adjusted) =
+        crypto::enforce_min_lead(unlock_datetime, clock::now(), crypto::min_unlock_lead());
+    if adjusted {
+        status!(
+            "Note: unlock time moved to {} (minimum lead time is {}s)",
+            unlock_datetime.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            crypto::min_unlock_lead().num_seconds()
+        );
+    }
+
+    crypto::validate_unlock_time(unlock_datetime, beacon)?;
+    Ok(unlock_datetime)
+}
+
+/// Lock the same sources once per `--unlock-at`, each copy into its own dated file
+///
+/// Every copy is independently recoverable. With a generated password each
+/// copy is archived under its own password: sharing one would let whoever
+/// opens the earliest copy open all the later ones too. A `--password` is
+/// shared by every copy anyway, so then the sources are compressed only once
+/// and every copy carries that payload (see `TlockArchive::create_many`).
+#[allow(clippy::too_many_arguments)]
+fn cmd_lock_schedule(
+    sources: &[PathBuf],
+    unlock_ats: &[String],
+    vault: Option<&Path>,
+    delete_original: bool,
+    note: Option<String>,
+    extra: BTreeMap<String, String>,
+    expires_at: Option<&str>,
+    recipient: Option<&str>,
+    password: Option<&str>,
+    options: &CreateOptions,
+    dry_run: bool,
+) -> Result<Vec<LockReport>> {
+    // Check every date before locking anything
+    let beacon = crypto::BeaconConfig::active()?;
+    let unlock_times = unlock_ats
+        .iter()
+        .map(|unlock_at| resolve_lock_time(unlock_at, &beacon))
+        .collect::<Result<Vec<_>>>()?;
+    let label = |unlock_time: &DateTime<Utc>, with_time: bool| {
+        let format = if with_time { "%Y-%m-%d_%H%M%S" } else { "%Y-%m-%d" };
+        unlock_time.with_timezone(&Local).format(format).to_string()
+    };
+    let distinct = |with_time: bool| {
+        unlock_times
+            .iter()
+            .map(|t| label(t, with_time))
+            .collect::<HashSet<_>>()
+            .len()
+    };
+    // Dates alone name the copies unless two of them fall on the same day
+    let with_time = distinct(false) < unlock_times.len();
+    if distinct(true) < unlock_times.len() {
+        return Err(TimeLockerError::Parse("The same --unlock-at is given twice".to_string()));
+    }
+
+    let dated = |path: &Path, unlock_time: &DateTime<Utc>| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let stem = name.strip_suffix(".7z.tlock").unwrap_or(&name);
+        path.with_file_name(format!("{}_{}.7z.tlock", stem, label(unlock_time, with_time)))
+    };
+
+    let reports = match password {
+        Some(password) if !dry_run => lock_shared_payload(
+            sources,
+            &unlock_times,
+            &dated,
+            vault,
+            note,
+            extra,
+            expires_at,
+            recipient,
+            password,
+            options,
+        )?,
+        _ => {
+            let mut reports = Vec::new();
+            for (unlock_at, unlock_time) in unlock_ats.iter().zip(&unlock_times) {
+                status!();
+                let mut report = cmd_lock(
+                    sources,
+                    unlock_at,
+                    vault,
+                    false,
+                    note.clone(),
+                    extra.clone(),
+                    expires_at,
+                    recipient,
+                    password,
+                    options,
+                    dry_run,
+                )?;
+                let undated = PathBuf::from(&report.file);
+                let dated_path = dated(&undated, unlock_time);
+                if !dry_run {
+                    fs::rename(&undated, &dated_path)?;
+                }
+                report.file = dated_path.display().to_string();
+                status!("Copy for {}: {}", unlock_at, report.file);
+                reports.push(report);
+            }
+            reports
+        }
+    };
+
+    if delete_original && !dry_run {
+        status!();
+        status_inline!("Verifying archives... ");
+        io::stdout().flush()?;
+        for report in &reports {
+            if !TlockArchive::validate(Path::new(&report.file))? {
+                status!("failed");
+                status!("Warning: Archive verification failed, original not deleted");
+                return Ok(reports);
+            }
+        }
+        status!("done");
+        status_inline!("Deleting original... ");
+        io::stdout().flush()?;
+        for source in sources {
+            if source.is_dir() {
+                fs::remove_dir_all(source)?;
+            } else {
+                fs::remove_file(source)?;
+            }
+        }
+        status!("done");
+    }
+
+    status!();
+    status!("Created {} independently locked copies", reports.len());
+    Ok(reports)
+}
+
+/// The `--password` end of `cmd_lock_schedule`: one payload, one copy per unlock time
+#[allow(clippy::too_many_arguments)]
+fn lock_shared_payload(
+    sources: &[PathBuf],
+    unlock_times: &[DateTime<Utc>],
+    dated: &dyn Fn(&Path, &DateTime<Utc>) -> PathBuf,
+    vault: Option<&Path>,
+    note: Option<String>,
+    extra: BTreeMap<String, String>,
+    expires_at: Option<&str>,
+    recipient: Option<&str>,
+    password: &str,
+    options: &CreateOptions,
+) -> Result<Vec<LockReport>> {
+    for source in sources {
+        if !source.exists() {
+            return Err(TimeLockerError::FileNotFound(source.display().to_string()));
+        }
+    }
+    let all_sources: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+    if let Some(vault_dir) = vault {
+        for source in &all_sources {
+            check_source_outside_vault(source, vault_dir)?;
+        }
+    }
+    options.resolve_version()?;
+
+    let expires = expires_at.map(parse_datetime).transpose()?;
+    if expires.is_some_and(|expires| unlock_times.iter().any(|unlocks| expires <= *unlocks)) {
+        return Err(TimeLockerError::Parse(
+            "Expiry time must be after the unlock time".to_string(),
+        ));
+    }
+
+    let mut undated = TlockArchive::output_path(&all_sources)?;
+    match vault {
+        Some(vault_dir) if vault_dir.is_dir() => undated = vault_path(vault_dir, &undated),
+        Some(_) => status!("Warning: Vault directory does not exist, keeping in place"),
+        None => {}
+    }
+    let outputs: Vec<(PathBuf, DateTime<Utc>)> = unlock_times
+        .iter()
+        .map(|unlocks| (dated(&undated, unlocks), *unlocks))
+        .collect();
+
+    let recipient_encrypted_key = match recipient {
+        Some(recipient) => {
+            status!("Warning: the recipient can unlock these files at any time, before the unlock dates");
+            Some(crypto::encrypt_to_recipient(password, recipient)?)
+        }
+        None => None,
+    };
+
+    let beacon = crypto::BeaconConfig::active()?;
+    let source = all_sources[0];
+    let mut metadata = TlockMetadata::new(
+        tlock_format::multi_source_summary(&all_sources),
+        String::new(),
+        unlock_times[0],
+        None,
+        None,
+    );
+    metadata.is_directory = source.is_dir();
+    metadata.original_path = options
+        .path_hint
+        .as_ref()
+        .map(|hint| tlock_format::original_path_hint(source, hint))
+        .transpose()?
+        .flatten();
+    metadata.set_beacon(&beacon);
+    metadata.note = tlock_format::normalize_note(note);
+    metadata.extra = extra;
+    metadata.expires = expires;
+    metadata.recipient_encrypted_key = recipient_encrypted_key;
+    metadata.to_json()?;
+
+    for source in &all_sources {
+        status!("Locking: {}", source.display());
+    }
+    status_inline!("Creating {} copies from one encrypted archive... ", outputs.len());
+    io::stdout().flush()?;
+    let created = TlockArchive::create_many(&all_sources, metadata, password, &outputs, options)?;
+    status!("done");
+
+    Ok(created
+        .into_iter()
+        .zip(unlock_times)
+        .map(|(path, unlocks)| {
+            status!(
+                "Copy for {}: {}",
+                unlocks.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                path.display()
+            );
+            LockReport {
+                file: path.display().to_string(),
+                plan: None,
+            }
+        })
+        .collect())
+}
+
+/// Where a locked file ends up inside `vault_dir`
+fn vault_path(vault_dir: &Path, tlock_path: &Path) -> PathBuf {
+    vault_dir.join(tlock_path.file_name().unwrap_or_default())
+}
+
+/// The `--dry-run` end of `cmd_lock`: summarize what would be locked and where
+fn lock_dry_run(
+    sources: &[&Path],
+    vault: Option<&Path>,
+    delete_original: bool,
+    unlock_datetime: DateTime<Utc>,
+    beacon: &crypto::BeaconConfig,
+) -> Result<LockReport> {
+    let (mut total_bytes, mut file_count) = (0u64, 0u32);
+    for source in sources {
+        let (bytes, files) = progress::calculate_total_size(source)?;
+        total_bytes += bytes;
+        file_count += files;
+    }
+
+    let tlock_path = TlockArchive::output_path(sources)?;
+    let output = match vault {
+        Some(vault_dir) if vault_dir.is_dir() => vault_path(vault_dir, &tlock_path),
+        _ => tlock_path,
+    };
+    let drand_round = crypto::datetime_to_round(unlock_datetime, beacon);
+
+    status!();
+    status!("Dry run - nothing will be written");
+    status!("Drand round: {}", drand_round);
+    status!("Size: {} bytes in {} file(s)", total_bytes, file_count);
+    status!("Output: {}", output.display());
+    if delete_original {
+        status!("The original(s) would be deleted once the locked file is verified");
+    }
+
+    Ok(LockReport {
+        file: output.display().to_string(),
+        plan: Some(LockPlan {
+            unlock_at: unlock_datetime.to_rfc3339(),
+            drand_round,
+            total_bytes,
+            file_count,
+        }),
+    })
+}
+
+/// Lock command with a resumable job file
+///
+/// The job file can be shared by several `lock` runs (e.g. a script looping
+/// over files); re-running the script after an interruption skips the sources
+/// that were already locked.
+#[allow(clippy::too_many_arguments)]
+fn cmd_lock_job(
+    job_path: &Path,
+    source: &Path,
+    unlock_at: &str,
+    vault: Option<&Path>,
+    delete_original: bool,
+    note: Option<String>,
+    extra: BTreeMap<String, String>,
+    expires_at: Option<&str>,
+    recipient: Option<&str>,
+    password: Option<&str>,
+    options: &CreateOptions,
+) -> Result<PathBuf> {
+    let mut job = LockJob::load_or_new(job_path, unlock_at)?;
+    job.add_sources(&[source.to_path_buf()]);
+
+    let index = job.position(source).expect("source was just added");
+
+    if !job.pending().contains(&index) {
+        let done_path = job.entries[index].tlock_path.clone().unwrap_or_default();
+        status!("Already locked (job file): {}", done_path.display());
+        return Ok(done_path);
+    }
+
+    // Save before locking so the job file lists everything that was started
+    job.save(job_path)?;
+
+    let final_path = PathBuf::from(
+        cmd_lock(
+            &[source.to_path_buf()],
+            unlock_at,
+            vault,
+            delete_original,
+            note,
+            extra,
+            expires_at,
+            recipient,
+            password,
+            options,
+            false,
+        )?
+        .file,
+    );
+
+    job.mark_done(index, final_path.clone());
+    job.save(job_path)?;
+
+    let remaining = job.pending().len();
+    if remaining > 0 {
+        status!("Job file: {} source(s) still pending", remaining);
+    }
+
+    Ok(final_path)
+}
+
+/// Unlock command implementation
+#[allow(clippy::too_many_arguments)]
+fn cmd_unlock(
+    file: &Path,
+    output: Option<&Path>,
+    output_name: &str,
+    restore_path: bool,
+    options: &ExtractOptions,
+    staged_only: bool,
+    dry_run: bool,
+    consume: bool,
+    signature: Option<&[u8]>,
+    identity: Option<&Path>,
+    force: bool,
+    as_archive: bool,
+) -> Result<UnlockReport> {
+    // Validate file exists
     if !file.exists() {
         return Err(TimeLockerError::FileNotFound(file.display().to_string()));
     }
 
     // Read metadata
-    print!("Reading metadata... ");
+    status_inline!("Reading metadata... ");
     io::stdout().flush()?;
     let archive = TlockArchive::read_metadata(file)?;
     let metadata = archive
         .get_metadata()
         .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
-    println!("done");
+    status!("done");
 
-    println!("Original file: {}", metadata.original_file);
-    println!(
+    status!("Original file: {}", metadata.original_file);
+    status!(
         "Locked at: {}",
         metadata
             .created
             .with_timezone(&Local)
             .format("%Y-%m-%d %H:%M:%S")
     );
-    println!(
+    status!(
         "Unlock time: {}",
         metadata
             .unlocks
@@ -258,70 +1789,395 @@ fn cmd_unlock(file: &Path, output: Option<&Path>) -> Result<()> {
             .format("%Y-%m-%d %H:%M:%S")
     );
 
-    // Check if unlockable
-    if !metadata.is_unlockable() {
+    // A recipient key opens the file whatever the time; otherwise fall back to the time lock
+    let recipient_password = match (identity, metadata.recipient_encrypted_key.as_ref()) {
+        (Some(identity), Some(recipient_key)) => {
+            status_inline!("Trying recipient key... ");
+            io::stdout().flush()?;
+            let identity = crypto::read_identity_file(identity)?;
+            match crypto::decrypt_with_identity(recipient_key, &identity) {
+                Ok(password) => {
+                    status!("done");
+                    Some(password)
+                }
+                Err(e) => {
+                    status!("failed ({})", e);
+                    None
+                }
+            }
+        }
+        (Some(_), None) => {
+            status!("Note: this file has no recipient key, ignoring --identity");
+            None
+        }
+        _ => None,
+    };
+
+    // Check if unlockable; a supplied signature proves the round is out whatever the clock says
+    if recipient_password.is_none() && signature.is_none() && !metadata.is_unlockable() {
         let remaining = metadata.time_until_unlock();
         let hours = remaining.num_hours();
         let minutes = remaining.num_minutes() % 60;
         let seconds = remaining.num_seconds() % 60;
 
-        println!();
-        println!("Time lock still active!");
-        println!("Remaining: {}h {}m {}s", hours, minutes, seconds);
+        status!();
+        status!("Time lock still active!");
+        status!("Remaining: {}h {}m {}s", hours, minutes, seconds);
         return Err(TimeLockerError::TimeLockActive);
     }
 
-    println!("Time lock expired - proceeding with unlock");
+    if recipient_password.is_none() {
+        status!("Time lock expired - proceeding with unlock");
+    }
 
-    // Get encrypted password from metadata
-    let encrypted_password = metadata
-        .encrypted_key
-        .as_ref()
-        .ok_or_else(|| TimeLockerError::MissingField("encrypted_key".to_string()))?;
+    // The expiry is only advisory, so --force overrides it
+    if let Some(expires) = metadata.expires.filter(|_| metadata.is_expired()) {
+        let expires = expires.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+        if !force {
+            status!();
+            status!("This file was meant to be opened before {}.", expires);
+            return Err(TimeLockerError::Parse(
+                "File expired; pass --force to unlock it anyway".to_string(),
+            ));
+        }
+        status!("Warning: this file was meant to be opened before {} (unlocking anyway)", expires);
+    }
 
-    // Decrypt password
-    print!("Decrypting password... ");
-    io::stdout().flush()?;
-    let password = crypto::decrypt_with_tlock(encrypted_password, metadata.unlocks)?;
-    println!("done");
+    let password = match recipient_password {
+        Some(password) => password,
+        None => {
+            // Get encrypted password from metadata
+            let encrypted_password = metadata
+                .encrypted_key
+                .as_ref()
+                .ok_or_else(|| TimeLockerError::MissingField("encrypted_key".to_string()))?;
+
+            // Decrypt password
+            status_inline!("Decrypting password... ");
+            io::stdout().flush()?;
+            let password = match signature {
+                Some(signature) => {
+                    crypto::decrypt_with_tlock_offline(encrypted_password, signature, &metadata.beacon()?)?
+                }
+                None => crypto::decrypt_with_tlock(encrypted_password, metadata.unlocks, &metadata.beacon()?)?,
+            };
+            status!("done");
+            password
+        }
+    };
+
+    if as_archive {
+        return unlock_as_archive(file, output, password);
+    }
 
     // Determine output directory
     let output_dir = match output {
         Some(p) => p.to_path_buf(),
         None => {
             let parent = file.parent().unwrap_or_else(|| Path::new("."));
-            parent.join(format!("unlocked_{}", metadata.original_file))
+            parent.join(render_output_name(output_name, &metadata.original_file)?)
         }
     };
+    let output_dir = if restore_path {
+        metadata.restore_dir(&output_dir)
+    } else {
+        output_dir
+    };
 
     // Extract the archive
-    print!("Extracting files... ");
+    if dry_run {
+        status_inline!("Reading archive contents... ");
+        io::stdout().flush()?;
+        let plan = TlockArchive::plan_extraction(file, &password, &output_dir)?;
+        status!("done");
+        print_extraction_plan(&plan);
+        return Ok(UnlockReport {
+            file: file.display().to_string(),
+            output: output_dir.display().to_string(),
+            plan: Some(plan),
+            archive_password: None,
+        });
+    }
+
+    if options.resume {
+        status!("Resuming extraction...");
+    } else {
+        status!("Extracting files...");
+    }
+    let options = &ExtractOptions {
+        progress: Some(terminal_progress()),
+        ..options.clone()
+    };
+    if options.resume {
+        // Resuming continues whatever an earlier run left in the output directory
+        TlockArchive::extract_with_options(file, &password, &output_dir, options)?;
+    } else if staged_only {
+        extract_staged(&output_dir, |dir| {
+            TlockArchive::extract_with_options(file, &password, dir, options)
+        })?;
+    } else {
+        extract_atomically(&output_dir, |dir| {
+            TlockArchive::extract_with_options(file, &password, dir, options)
+        })?;
+    }
+    status!("\r  done                    ");
+
+    if consume {
+        status_inline!("Verifying extracted files... ");
+        io::stdout().flush()?;
+        if TlockArchive::verify_extraction(file, &password, &output_dir)? {
+            status!("done");
+            status_inline!("Deleting {}... ", file.display());
+            io::stdout().flush()?;
+            fs::remove_file(file)?;
+            status!("done");
+        } else {
+            status!("failed");
+            status!("Warning: Extracted files could not be verified, locked file not deleted");
+        }
+    }
+
+    status!();
+    status!("Success! Extracted to: {}", output_dir.display());
+
+    Ok(UnlockReport {
+        file: file.display().to_string(),
+        output: output_dir.display().to_string(),
+        plan: None,
+        archive_password: None,
+    })
+}
+
+/// The `--as-archive` end of `cmd_unlock`: write the 7z payload out instead of extracting it
+///
+/// The password is checked against the payload first, so what gets printed
+/// is known to open the archive.
+fn unlock_as_archive(file: &Path, output: Option<&Path>, password: String) -> Result<UnlockReport> {
+    // "name.7z.tlock" -> "name.7z"
+    let archive_path = file.with_extension("");
+    let dest = match output {
+        Some(dir) => dir.join(archive_path.file_name().unwrap_or_default()),
+        None => archive_path,
+    };
+    if dest.exists() {
+        return Err(TimeLockerError::Archive(format!(
+            "Destination already exists: {}",
+            dest.display()
+        )));
+    }
+
+    status_inline!("Checking password... ");
     io::stdout().flush()?;
-    TlockArchive::extract(file, &password, &output_dir)?;
-    println!("done");
+    if !crate::archive::verify_archive_password(TlockArchive::open_payload(file)?, &password)? {
+        status!("failed");
+        return Err(TimeLockerError::Decryption("Incorrect password".to_string()));
+    }
+    status!("done");
 
-    println!();
-    println!("Success! Extracted to: {}", output_dir.display());
+    status_inline!("Writing archive... ");
+    io::stdout().flush()?;
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let size = TlockArchive::export_payload(file, &dest)?;
+    status!("done ({} bytes)", size);
+
+    status!();
+    status!("Archive: {}", dest.display());
+    status!("Password: {}", password);
+    status!("Open it with any 7z tool using the password above. The archive is no longer");
+    status!("time-locked: anyone with both the file and the password can open it.");
+
+    Ok(UnlockReport {
+        file: file.display().to_string(),
+        output: dest.display().to_string(),
+        plan: None,
+        archive_password: Some(password),
+    })
+}
 
-    Ok(())
+/// Print the result of a dry-run extraction
+fn print_extraction_plan(plan: &ExtractionPlan) {
+    status!();
+    status!("Dry run - nothing will be written");
+    status!("Output directory: {}", plan.output_dir.display());
+    status!();
+    status!("{:>12}  {}", "Size", "Name");
+    status!("{}", "-".repeat(60));
+
+    for entry in &plan.entries {
+        let name = if entry.is_directory {
+            format!("{}/", entry.name)
+        } else {
+            entry.name.clone()
+        };
+        let marker = if plan.conflicts.contains(&entry.name) { "  (exists)" } else { "" };
+        status!("{:>12}  {}{}", entry.size, name, marker);
+    }
+
+    status!("{}", "-".repeat(60));
+    status!("{:>12}  {} entries", plan.total_size, plan.entries.len());
+
+    if !plan.conflicts.is_empty() {
+        status!();
+        status!(
+            "{} file(s) already exist in the output directory; use --overwrite-policy to choose how to handle them",
+            plan.conflicts.len()
+        );
+    }
 }
 
-/// Info command implementation
-fn cmd_info(file: &Path) -> Result<()> {
-    if !file.exists() {
-        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+/// Unlock a remote .7z.tlock file
+///
+/// Checks the remote metadata first so a still-locked file is rejected before
+/// anything large is downloaded, then downloads resumably and unlocks the copy.
+#[allow(clippy::too_many_arguments)]
+fn cmd_unlock_url(
+    url: &str,
+    output: Option<&Path>,
+    output_name: &str,
+    restore_path: bool,
+    options: &ExtractOptions,
+    staged_only: bool,
+    dry_run: bool,
+    identity: Option<&Path>,
+    force: bool,
+) -> Result<UnlockReport> {
+    status_inline!("Fetching remote metadata... ");
+    io::stdout().flush()?;
+    let metadata = download::fetch_metadata(url)?;
+    status!("done");
+
+    // With a recipient key the time lock may not apply; cmd_unlock decides once downloaded
+    let may_open_early = identity.is_some() && metadata.recipient_encrypted_key.is_some();
+    if !metadata.is_unlockable() && !may_open_early {
+        status!(
+            "Time lock still active until {}",
+            metadata
+                .unlocks
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        );
+        return Err(TimeLockerError::TimeLockActive);
     }
 
-    let archive = TlockArchive::read_metadata(file)?;
-    let metadata = archive
-        .get_metadata()
-        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+    // Don't download a file that will be refused once it's here
+    if metadata.is_expired() && !force {
+        return Err(TimeLockerError::Parse(
+            "File expired; pass --force to unlock it anyway".to_string(),
+        ));
+    }
+
+    let partial = download::partial_path(url);
+    let tracker = ProgressTracker::new();
+    status!("Downloading: {}", url);
+    download::download_resumable(url, &partial, &tracker, |t| {
+        if t.should_emit() {
+            match t.percentage() {
+                Some(pct) => status_inline!("\r  {:.1}% ({} bytes)", pct, t.get_bytes_written()),
+                None => status_inline!("\r  {} bytes", t.get_bytes_written()),
+            }
+            let _ = io::stdout().flush();
+        }
+    })?;
+    status!("\r  done ({} bytes)          ", tracker.get_bytes_written());
+
+    // Without an explicit output, extract next to where the command was run
+    // rather than into the temp directory holding the download
+    let output_dir = match output {
+        Some(p) => p.to_path_buf(),
+        None => std::env::current_dir()?.join(render_output_name(output_name, &metadata.original_file)?),
+    };
+
+    let mut report = cmd_unlock(
+        &partial,
+        Some(&output_dir),
+        output_name,
+        restore_path,
+        options,
+        staged_only,
+        dry_run,
+        false,
+        None,
+        identity,
+        force,
+        false,
+    )?;
+    report.file = url.to_string();
+
+    if let Err(e) = fs::remove_file(&partial) {
+        eprintln!("Warning: Failed to remove downloaded file: {}", e);
+    }
+
+    Ok(report)
+}
+
+/// Name of the directory to unlock `original_file` into, from an `--output-name` template
+pub(crate) fn render_output_name(template: &str, original_file: &str) -> Result<String> {
+    tlock_format::render_output_name(template, original_file, clock::now().with_timezone(&Local))
+}
+
+/// Info command implementation
+fn cmd_info(file: &Path, json: bool) -> Result<()> {
+    let (metadata, contents) = match file.to_str().filter(|s| download::is_url(s)) {
+        // Remote file: only the header and metadata are fetched
+        Some(url) => (download::fetch_metadata(url)?, None),
+        None => {
+            if !file.exists() {
+                return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+            }
+
+            let metadata = TlockArchive::read_metadata(file)?
+                .metadata
+                .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+            // The entry table is exact but encrypted; until the file unlocks
+            // only the size recorded at lock time (if any) is known
+            let contents = if metadata.is_unlockable() {
+                decrypt_archive_password(&metadata)
+                    .and_then(|password| TlockArchive::content_summary(file, &password))
+                    .ok()
+            } else {
+                None
+            };
+            (metadata, contents)
+        }
+    };
+
+    if json {
+        let mut report = InfoReport::new(file, &metadata);
+        report.contents = contents;
+        return print_json(report);
+    }
 
     println!("Time-Locked File Information");
     println!("============================");
     println!("File: {}", file.display());
     println!("Original name: {}", metadata.original_file);
-    println!("Type: {}", if metadata.is_directory { "Directory" } else { "File" });
+    match metadata.original_files {
+        Some(ref names) => println!("Type: {} items ({})", names.len(), names.join(", ")),
+        None => println!("Type: {}", if metadata.is_directory { "Directory" } else { "File" }),
+    }
+    if let Some(ref original_path) = metadata.original_path {
+        println!("Original location: {}", original_path);
+    }
+    match (contents, metadata.original_size) {
+        (Some(contents), _) => println!(
+            "Contents: {} files, {}",
+            contents.file_count,
+            format_megabytes(contents.total_size)
+        ),
+        (None, Some(size)) => println!("Size: {} (recorded when locked)", format_megabytes(size)),
+        (None, None) => {}
+    }
+    if let Some(ref note) = metadata.note {
+        println!("Note: {}", note);
+    }
+    if let Some(ref filter) = metadata.compression_filter {
+        println!("Compression filter: {}", filter);
+    }
+    for (key, value) in &metadata.extra {
+        println!("Meta {}: {}", key, value);
+    }
     println!();
     println!(
         "Created: {}",
@@ -337,10 +2193,50 @@ fn cmd_info(file: &Path) -> Result<()> {
             .with_timezone(&Local)
             .format("%Y-%m-%d %H:%M:%S %Z")
     );
+    if let Some(expires) = metadata.expires {
+        println!(
+            "Expires: {} (advisory)",
+            expires.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z")
+        );
+    }
     println!("Duration: {}", metadata.duration);
+    if metadata.recipient_encrypted_key.is_some() {
+        println!("Recipient key: the holder of an age key can unlock this file at any time");
+    }
     println!();
 
-    if metadata.is_unlockable() {
+    if let Some(ref params) = metadata.crypto_parameters {
+        println!("Crypto parameters");
+        println!("  Cipher: {}", params.cipher);
+        println!(
+            "  Key derivation: {} with a random salt, 2^{} iterations",
+            params.kdf, params.kdf_cycles_power
+        );
+        println!(
+            "  Header encryption: {}",
+            if params.header_encryption { "yes (entry names are hidden)" } else { "no" }
+        );
+        match params.compression_level {
+            Some(level) => println!("  Compression: {} level {}", params.compression, level),
+            None => println!("  Compression: {}", params.compression),
+        }
+        if params.precompressed_stored {
+            println!("  Already-compressed files: stored without compression");
+        }
+        println!("  Time lock: {}", params.time_lock);
+        println!();
+    }
+
+    let until_decryptable = metadata.time_until_cryptographic_unlock();
+    if metadata.is_unlockable() && until_decryptable > chrono::Duration::zero() {
+        // Nominally due, but the beacon for the key's round isn't out yet
+        println!("Status: WAITING FOR BEACON");
+        println!(
+            "Unlockable in ~{} seconds (drand round {})",
+            until_decryptable.num_seconds().max(1),
+            metadata.unlock_round().unwrap_or_default()
+        );
+    } else if metadata.is_unlockable() {
         println!("Status: UNLOCKABLE");
         println!("The time lock has expired. This file can now be unlocked.");
     } else {
@@ -353,24 +2249,40 @@ fn cmd_info(file: &Path) -> Result<()> {
         println!("Time remaining: {}d {}h {}m", days, hours, minutes);
     }
 
-    if let Some(ref drand_round) = metadata.drand_round {
+    if metadata.is_expired() {
+        println!();
+        println!("Warning: this file has expired; it was meant to be opened before the time above.");
+        println!("Unlocking it needs --force.");
+    }
+
+    if let Some(drand_round) = metadata.unlock_round() {
         println!();
         println!("Drand round: {}", drand_round);
     }
+    if let Some(params) = &metadata.beacon_parameters {
+        println!("Beacon chain: {}", params.chain_hash);
+        println!("Beacon public key: {}", params.public_key);
+        println!("Beacon genesis: {} (period {}s)", params.genesis_time, params.period);
+    }
 
     Ok(())
 }
 
 /// List command implementation
-fn cmd_list(vault: Option<&Path>) -> Result<()> {
+fn cmd_list(vault: Option<&Path>, json: bool) -> Result<()> {
     let scan_dir = vault
         .map(|p| p.to_path_buf())
         .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
+    if json {
+        let archives = tlock_format::scan_tlock_files(&scan_dir, true)?;
+        return print_json(ListEntry::from_archives(&archives));
+    }
+
     println!("Scanning: {}", scan_dir.display());
     println!();
 
-    let archives = tlock_format::scan_tlock_files(&scan_dir)?;
+    let archives = tlock_format::scan_tlock_files(&scan_dir, true)?;
 
     if archives.is_empty() {
         println!("No .7z.tlock files found.");
@@ -417,7 +2329,7 @@ fn cmd_list(vault: Option<&Path>) -> Result<()> {
 }
 
 /// Migrate command implementation
-fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
+fn cmd_migrate(keyfile: &Path, delete_old: bool, mark_migrated: bool) -> Result<()> {
     if !keyfile.exists() {
         return Err(TimeLockerError::FileNotFound(keyfile.display().to_string()));
     }
@@ -426,17 +2338,16 @@ fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
 
     // Read old format key file
     let content = fs::read_to_string(keyfile)?;
-    let old_keyfile = crate::keyfile::KeyFile::parse(&content)?;
+    let mut old_keyfile = crate::keyfile::KeyFile::parse(&content)?;
+    old_keyfile.file_path = Some(keyfile.to_path_buf());
 
-    let archive_path_str = old_keyfile
-        .metadata
-        .archive_path
-        .as_ref()
+    let archive_path = old_keyfile
+        .resolved_archive_path()
         .ok_or_else(|| TimeLockerError::MissingField("archive_path".to_string()))?;
-    let archive_path = Path::new(archive_path_str);
+    let archive_path = archive_path.as_path();
 
     if !archive_path.exists() {
-        return Err(TimeLockerError::FileNotFound(archive_path_str.clone()));
+        return Err(TimeLockerError::FileNotFound(archive_path.display().to_string()));
     }
 
     println!("Archive: {}", archive_path.display());
@@ -451,13 +2362,14 @@ fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
     );
 
     // Create new tlock metadata from old format
-    let metadata = TlockMetadata::new(
+    let mut metadata = TlockMetadata::new(
         old_keyfile.metadata.original_file.clone(),
         old_keyfile.metadata.duration.clone(),
         old_keyfile.metadata.unlocks,
         None,
         Some(old_keyfile.encrypted_body.clone()),
     );
+    metadata.is_directory = old_keyfile.archive_is_directory(archive_path);
 
     // Read the old 7z archive
     print!("Reading archive... ");
@@ -475,30 +2387,54 @@ fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
     let metadata_json = serde_json::to_vec(&metadata)
         .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize metadata: {}", e)))?;
 
-    // Write the tlock file: header + metadata + payload
-    let mut output = fs::File::create(&tlock_path)?;
+    // Write the tlock file: header + metadata + payload, removing it again if any write fails
+    let write_tlock = || -> Result<()> {
+        let mut output = fs::File::create(&tlock_path)?;
 
-    // Write header
-    output.write_all(tlock_format::TLOCK_MAGIC)?;
-    output.write_all(&[tlock_format::TLOCK_VERSION])?;
-    output.write_all(&(metadata_json.len() as u32).to_le_bytes())?;
-    output.write_all(&[0u8; 12])?; // Reserved bytes
+        // Write a version 1 header (no payload checksum), like the GUI migration
+        output.write_all(tlock_format::TLOCK_MAGIC)?;
+        output.write_all(&[tlock_format::MIN_TLOCK_VERSION])?;
+        output.write_all(&(metadata_json.len() as u32).to_le_bytes())?;
+        output.write_all(&[0u8; 12])?; // Reserved bytes
 
-    // Write metadata
-    output.write_all(&metadata_json)?;
+        // Write metadata
+        output.write_all(&metadata_json)?;
 
-    // Write 7z payload
-    output.write_all(&archive_data)?;
-    output.flush()?;
+        // Write 7z payload
+        output.write_all(&archive_data)?;
+        output.flush()?;
+        Ok(())
+    };
+    if let Err(e) = write_tlock() {
+        let _ = fs::remove_file(&tlock_path);
+        return Err(e);
+    }
 
     println!("done");
     println!("Created: {}", tlock_path.display());
 
-    // Delete old files if requested
-    if delete_old {
+    // Delete (or set aside) old files if requested
+    if delete_old || mark_migrated {
         print!("Verifying new file... ");
         io::stdout().flush()?;
-        if TlockArchive::validate(&tlock_path)? {
+        if !TlockArchive::validate(&tlock_path)? {
+            println!("failed");
+            println!("Warning: Verification failed, old files left in place");
+        } else if mark_migrated {
+            println!("done");
+            print!("Marking old files as migrated... ");
+            io::stdout().flush()?;
+
+            let mut kept = vec![crate::keyfile::mark_migrated(keyfile)?];
+            if archive_path.exists() {
+                kept.push(crate::keyfile::mark_migrated(archive_path)?);
+            }
+
+            println!("done");
+            for path in kept {
+                println!("Kept: {}", path.display());
+            }
+        } else {
             println!("done");
             print!("Deleting old files... ");
             io::stdout().flush()?;
@@ -512,9 +2448,6 @@ fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
             }
 
             println!("done");
-        } else {
-            println!("failed");
-            println!("Warning: Verification failed, old files not deleted");
         }
     }
 
@@ -524,29 +2457,851 @@ fn cmd_migrate(keyfile: &Path, delete_old: bool) -> Result<()> {
     Ok(())
 }
 
-/// Parse datetime from various formats
-fn parse_datetime(s: &str) -> Result<DateTime<Utc>> {
-    // Try RFC3339 first
-    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-        return Ok(dt.with_timezone(&Utc));
+/// `migrate --all` implementation
+///
+/// # Returns
+/// Whether no key file failed to migrate
+fn cmd_migrate_all(dir: &Path, delete_old: bool, mark_migrated: bool) -> Result<bool> {
+    if !dir.is_dir() {
+        return Err(TimeLockerError::FileNotFound(dir.display().to_string()));
     }
 
-    // Try "YYYY-MM-DD HH:MM:SS"
-    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-        let local = Local
-            .from_local_datetime(&dt)
-            .single()
-            .ok_or_else(|| TimeLockerError::Parse("Ambiguous datetime".to_string()))?;
-        return Ok(local.with_timezone(&Utc));
-    }
+    let key_files = crate::keyfile::scan_directory(dir)?;
+    let (mut migrated, mut skipped, mut failed) = (0, 0, 0);
+    for key_file in &key_files {
+        let Some(ref key_path) = key_file.file_path else {
+            continue;
+        };
+        if key_file.has_tlock() {
+            println!("Skipping (already migrated): {}", key_path.display());
+            skipped += 1;
+            continue;
+        }
 
-    // Try "YYYY-MM-DD HH:MM"
-    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
-        let local = Local
-            .from_local_datetime(&dt)
-            .single()
-            .ok_or_else(|| TimeLockerError::Parse("Ambiguous datetime".to_string()))?;
-        return Ok(local.with_timezone(&Utc));
+        match cmd_migrate(key_path, delete_old, mark_migrated) {
+            Ok(()) => migrated += 1,
+            Err(e) => {
+                eprintln!("Error: failed to migrate {}: {}", key_path.display(), e);
+                failed += 1;
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "{} migrated, {} skipped (already migrated), {} failed",
+        migrated, skipped, failed
+    );
+    Ok(failed == 0)
+}
+
+/// Cleanup-migrated command implementation
+fn cmd_cleanup_migrated(vault: Option<&Path>, json: bool) -> Result<()> {
+    let scan_dir = vault
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+    let deleted = crate::keyfile::cleanup_migrated(&scan_dir)?;
+    if json {
+        return print_json(deleted);
+    }
+
+    if deleted.is_empty() {
+        println!("No migrated files found in {}", scan_dir.display());
+        return Ok(());
+    }
+
+    for path in &deleted {
+        println!("Deleted: {}", path.display());
+    }
+    println!();
+    println!("Deleted {} migrated file(s)", deleted.len());
+
+    Ok(())
+}
+
+/// Export payload command implementation
+fn cmd_export_payload(file: &Path, output: Option<&Path>) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let archive = TlockArchive::read_metadata(file)?;
+    let metadata = archive
+        .get_metadata()
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+
+    let dest = match output {
+        Some(p) => p.to_path_buf(),
+        // "name.7z.tlock" -> "name.7z"
+        None => file.with_extension(""),
+    };
+    if dest.exists() {
+        return Err(TimeLockerError::Archive(format!(
+            "Destination already exists: {}",
+            dest.display()
+        )));
+    }
+
+    print!("Decrypting password... ");
+    io::stdout().flush()?;
+    let password = decrypt_archive_password(metadata)?;
+    println!("done");
+
+    print!("Exporting payload... ");
+    io::stdout().flush()?;
+    let size = TlockArchive::export_payload(file, &dest)?;
+    println!("done ({} bytes)", size);
+
+    println!();
+    println!("Exported: {}", dest.display());
+    println!("Password: {}", password);
+    println!("Open it with any 7z tool using the password above.");
+
+    Ok(())
+}
+
+/// Contents command implementation
+///
+/// The entry list is encrypted, so this needs the password and only works
+/// once the file is unlockable.
+fn cmd_contents(file: &Path, json: bool) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let archive = TlockArchive::read_metadata(file)?;
+    let metadata = archive
+        .get_metadata()
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+
+    let password = decrypt_archive_password(metadata)?;
+    let entries = TlockArchive::list_entries(file, &password)?;
+
+    if json {
+        return print_json(entries);
+    }
+
+    println!("{:>12}  {}", "Size", "Name");
+    println!("{}", "-".repeat(60));
+    for entry in &entries {
+        if entry.is_directory {
+            println!("{:>12}  {}/", "", entry.name);
+        } else {
+            println!("{:>12}  {}", entry.size, entry.name);
+        }
+    }
+    println!("{}", "-".repeat(60));
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    println!("{:>12}  {} entries", total, entries.len());
+
+    Ok(())
+}
+
+/// Capsule command implementation
+fn cmd_capsule(file: &Path, output: Option<&Path>, include_binary: bool) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let dest_dir = match output {
+        Some(p) => p.to_path_buf(),
+        None => std::env::current_dir()?,
+    };
+
+    print!("Creating time capsule... ");
+    io::stdout().flush()?;
+    let capsule_dir = capsule::export_capsule(file, &dest_dir, include_binary)?;
+    println!("done");
+
+    println!();
+    println!("Capsule created: {}", capsule_dir.display());
+    println!("Instructions: {}", capsule_dir.join(capsule::INSTRUCTIONS_FILE).display());
+
+    Ok(())
+}
+
+/// Get the archive password of a file: check the given one, or decrypt it once unlockable
+///
+/// `what` names what needs the password, for the error when neither is possible.
+fn resolve_archive_password(
+    file: &Path,
+    metadata: &TlockMetadata,
+    password: Option<String>,
+    what: &str,
+) -> Result<String> {
+    match password {
+        Some(password) => {
+            print!("Checking password... ");
+            io::stdout().flush()?;
+            let payload = TlockArchive::open_payload(file)?;
+            if !crate::archive::verify_archive_password(payload, &password)? {
+                println!("failed");
+                return Err(TimeLockerError::Decryption("Incorrect password".to_string()));
+            }
+            println!("done");
+            Ok(password)
+        }
+        None if metadata.is_unlockable() => {
+            print!("Decrypting password... ");
+            io::stdout().flush()?;
+            let password = decrypt_archive_password(metadata)?;
+            println!("done");
+            Ok(password)
+        }
+        None => Err(TimeLockerError::Encryption(format!(
+            "File is still time-locked and its password is not known; \
+             {} can only be changed with --password or after it unlocks",
+            what
+        ))),
+    }
+}
+
+/// Recompress command implementation
+fn cmd_recompress(file: &Path, compression: CompressionSettings, password: Option<String>) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let metadata = TlockArchive::read_metadata(file)?
+        .metadata
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+    let password = resolve_archive_password(file, &metadata, password, "the compression")?;
+
+    let old_size = fs::metadata(file)?.len();
+    match compression.method {
+        CompressionMethod::Lzma2 => print!("Recompressing at level {}... ", compression.level),
+        CompressionMethod::Store => print!("Re-archiving without compression... "),
+    }
+    io::stdout().flush()?;
+    TlockArchive::recompress(file, &password, &compression)?;
+    println!("done");
+
+    println!();
+    println!("Size: {} -> {} bytes", old_size, fs::metadata(file)?.len());
+
+    Ok(())
+}
+
+/// Rotate-password command implementation
+fn cmd_rotate_password(file: &Path, password: Option<String>) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let metadata = TlockArchive::read_metadata(file)?
+        .metadata
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+    let password = resolve_archive_password(file, &metadata, password, "the password")?;
+
+    print!("Re-encrypting under a new password... ");
+    io::stdout().flush()?;
+    TlockArchive::rotate_password(file, &password)?;
+    println!("done");
+
+    println!();
+    println!("The old password no longer opens {}", file.display());
+
+    Ok(())
+}
+
+/// Fingerprint command implementation
+fn cmd_fingerprint(file: &Path, json: bool) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let report = FingerprintReport {
+        file: file.display().to_string(),
+        fingerprint: TlockArchive::fingerprint(file)?,
+    };
+    if json {
+        return print_json(report);
+    }
+
+    println!("{}  {}", report.fingerprint, report.file);
+    Ok(())
+}
+
+/// Relock-time command implementation
+fn cmd_relock_time(file: &Path, unlock_at: &str, password: Option<String>) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+
+    let metadata = TlockArchive::read_metadata(file)?
+        .metadata
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+
+    let unlock_datetime = parse_datetime(unlock_at)?;
+    if unlock_datetime <= clock::now() {
+        return Err(TimeLockerError::Parse(
+            "Unlock time must be in the future".to_string(),
+        ));
+    }
+    let (unlock_datetime, adjusted) =
+        crypto::enforce_min_lead(unlock_datetime, clock::now(), crypto::min_unlock_lead());
+    if adjusted {
+        println!(
+            "Note: unlock time moved to {} (minimum lead time is {}s)",
+            unlock_datetime.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            crypto::min_unlock_lead().num_seconds()
+        );
+    }
+
+    // The archive password is needed to re-lock it for the new time
+    let password = resolve_archive_password(file, &metadata, password, "the unlock time")?;
+
+    print!("Re-locking password and rewriting metadata... ");
+    io::stdout().flush()?;
+    TlockArchive::change_unlock_time(file, &password, unlock_datetime)?;
+    println!("done");
+
+    println!();
+    println!(
+        "New unlock time: {}",
+        unlock_datetime
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+    );
+
+    Ok(())
+}
+
+/// Quarantine command implementation
+fn cmd_quarantine(vault: Option<&Path>, dry_run: bool, json: bool) -> Result<()> {
+    let scan_dir = vault
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+    let report = tlock_format::quarantine_corrupt(&scan_dir, dry_run)?;
+    if json {
+        return print_json(report);
+    }
+
+    println!("Checked {} file(s) in {}", report.checked, scan_dir.display());
+
+    for unchecked in &report.unchecked {
+        println!("SKIPPED: {}", unchecked.path.display());
+        println!("  Reason: {}", unchecked.reason);
+    }
+
+    if report.quarantined.is_empty() {
+        println!("No corrupt files found.");
+        return Ok(());
+    }
+
+    println!();
+    for quarantined in &report.quarantined {
+        println!("CORRUPT: {}", quarantined.path.display());
+        println!("  Reason: {}", quarantined.reason);
+        if dry_run {
+            println!("  Would move to: {}", quarantined.quarantined_path.display());
+        } else {
+            println!("  Moved to: {}", quarantined.quarantined_path.display());
+        }
+    }
+
+    if dry_run {
+        println!();
+        println!("Dry run: no files were moved.");
+    }
+
+    Ok(())
+}
+
+/// `init-vault` output
+#[derive(Debug, Serialize)]
+struct InitVaultReport {
+    vault: String,
+    marker: String,
+    settings: VaultMarker,
+}
+
+/// Init-vault command implementation
+fn cmd_init_vault(dir: Option<&Path>, marker: &VaultMarker, force: bool, json: bool) -> Result<()> {
+    let vault_dir = dir
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+    let marker_path = marker.write(&vault_dir, force)?;
+    if json {
+        return print_json(InitVaultReport {
+            vault: vault_dir.display().to_string(),
+            marker: marker_path.display().to_string(),
+            settings: marker.clone(),
+        });
+    }
+
+    if marker.exclude {
+        println!("Excluded from vault discovery: {}", vault_dir.display());
+        return Ok(());
+    }
+    println!("Vault initialized: {}", vault_dir.display());
+    if let Some(compression) = marker.compression {
+        match compression.method {
+            CompressionMethod::Lzma2 => println!("  Default compression: level {}", compression.level),
+            CompressionMethod::Store => println!("  Default compression: none (store)"),
+        }
+    }
+    Ok(())
+}
+
+/// Audit command implementation
+///
+/// # Returns
+/// Whether no file was flagged
+fn cmd_audit(vault: Option<&Path>, json: bool) -> Result<bool> {
+    let scan_dir = vault
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+    let report = tlock_format::audit_vault(&scan_dir)?;
+    let clean = report.flagged == 0;
+
+    if json {
+        let errors = report
+            .entries
+            .iter()
+            .flat_map(|e| e.issues.iter().map(move |issue| format!("{}: {}", e.path.display(), issue)))
+            .collect();
+        print_json_envelope(&JsonEnvelope {
+            ok: clean,
+            data: Some(report),
+            errors,
+        })?;
+        return Ok(clean);
+    }
+
+    println!(
+        "Audited {} file(s) in {} at {}",
+        report.entries.len(),
+        scan_dir.display(),
+        report.audited_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+    );
+    if report.entries.is_empty() {
+        return Ok(true);
+    }
+
+    println!();
+    for entry in &report.entries {
+        let status = if entry.issues.is_empty() { "OK" } else { "FLAG" };
+        println!("{:<6} {}", status, entry.path.display());
+        if let (Some(unlocks), Some(round)) = (entry.unlocks, entry.drand_round) {
+            println!(
+                "       Unlocks {}, round {}",
+                unlocks.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                round
+            );
+        }
+        for issue in &entry.issues {
+            println!("       {}", issue);
+        }
+    }
+
+    println!();
+    println!("{} of {} file(s) flagged", report.flagged, report.entries.len());
+
+    Ok(clean)
+}
+
+/// Verify command implementation
+///
+/// # Returns
+/// Whether every file passed
+fn cmd_verify(file_or_dir: &Path, recursive: bool, json: bool) -> Result<bool> {
+    let files = tlock_files_at(file_or_dir, recursive)?;
+
+    let results: Vec<VerifyResult> = files
+        .iter()
+        .map(|path| {
+            let error = TlockArchive::verify_integrity(path).err().map(|e| e.to_string());
+            VerifyResult {
+                file: path.display().to_string(),
+                passed: error.is_none(),
+                error,
+            }
+        })
+        .collect();
+    let failed = results.iter().filter(|r| !r.passed).count();
+
+    if json {
+        let errors = results
+            .iter()
+            .filter_map(|r| r.error.as_ref().map(|e| format!("{}: {}", r.file, e)))
+            .collect();
+        print_json_envelope(&JsonEnvelope {
+            ok: failed == 0,
+            data: Some(results),
+            errors,
+        })?;
+        return Ok(failed == 0);
+    }
+
+    if results.is_empty() {
+        println!("No .7z.tlock files found.");
+        return Ok(true);
+    }
+
+    println!("Result File");
+    println!("{}", "-".repeat(90));
+    for result in &results {
+        match &result.error {
+            None => println!("PASS   {}", result.file),
+            Some(error) => {
+                println!("FAIL   {}", result.file);
+                println!("       {}", error);
+            }
+        }
+    }
+
+    println!();
+    println!("{} of {} file(s) passed", results.len() - failed, results.len());
+
+    Ok(failed == 0)
+}
+
+/// Normalize-header command implementation
+///
+/// # Returns
+/// Whether every file was normalized (or already canonical)
+fn cmd_normalize_header(file_or_dir: &Path, recursive: bool, json: bool) -> Result<bool> {
+    let files = tlock_files_at(file_or_dir, recursive)?;
+
+    let results: Vec<NormalizeResult> = files
+        .iter()
+        .map(|path| {
+            let outcome = TlockArchive::normalize_header(path);
+            NormalizeResult {
+                file: path.display().to_string(),
+                rewritten: *outcome.as_ref().unwrap_or(&false),
+                error: outcome.err().map(|e| e.to_string()),
+            }
+        })
+        .collect();
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+
+    if json {
+        let errors = results
+            .iter()
+            .filter_map(|r| r.error.as_ref().map(|e| format!("{}: {}", r.file, e)))
+            .collect();
+        print_json_envelope(&JsonEnvelope {
+            ok: failed == 0,
+            data: Some(results),
+            errors,
+        })?;
+        return Ok(failed == 0);
+    }
+
+    if results.is_empty() {
+        println!("No .7z.tlock files found.");
+        return Ok(true);
+    }
+
+    for result in &results {
+        match &result.error {
+            None if result.rewritten => println!("REWRITTEN  {}", result.file),
+            None => println!("OK         {}", result.file),
+            Some(error) => {
+                println!("FAILED     {}", result.file);
+                println!("           {}", error);
+            }
+        }
+    }
+
+    let rewritten = results.iter().filter(|r| r.rewritten).count();
+    println!();
+    println!(
+        "{} rewritten, {} already canonical, {} failed",
+        rewritten,
+        results.len() - rewritten - failed,
+        failed
+    );
+
+    Ok(failed == 0)
+}
+
+/// `recover` output
+#[derive(Debug, Serialize)]
+struct RecoverReport {
+    file: String,
+    recovered: String,
+}
+
+/// Recover command implementation
+fn cmd_recover(
+    file: &Path,
+    original_file: Option<String>,
+    unlock_at: Option<String>,
+    encrypted_key: Option<String>,
+    yes: bool,
+    json: bool,
+) -> Result<()> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+    if json && !yes {
+        return Err(TimeLockerError::Parse(
+            "recover asks for confirmation; pass --yes with --json".to_string(),
+        ));
+    }
+
+    if !json {
+        println!("Recover is a last resort for files whose header or metadata is damaged.");
+        println!("The metadata is rebuilt from what you enter: a wrong encrypted key makes");
+        println!("the recovered file impossible to unlock. {} is left as it is.", file.display());
+        println!();
+    }
+
+    let original_file = match original_file {
+        Some(name) => name,
+        None => prompt("Original file name")?,
+    };
+    let unlock_datetime = match unlock_at {
+        Some(unlock_at) => parse_datetime(&unlock_at)?,
+        None => parse_datetime(&prompt("Unlock date/time (e.g. 2026-07-01 09:00)")?)?,
+    };
+    let encrypted_key = match encrypted_key {
+        Some(key) => key,
+        None => prompt("Encrypted key")?,
+    };
+    for (value, field) in [(&original_file, "original_file"), (&encrypted_key, "encrypted_key")] {
+        if value.trim().is_empty() {
+            return Err(TimeLockerError::MissingField(field.to_string()));
+        }
+    }
+
+    if !yes && !confirm(&format!("Rebuild {} with this metadata?", file.display()))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut metadata = TlockMetadata::new(
+        original_file.trim().to_string(),
+        unlock_datetime.format("%Y-%m-%d").to_string(),
+        unlock_datetime,
+        None,
+        Some(encrypted_key.trim().to_string()),
+    );
+    metadata.set_beacon(&crypto::BeaconConfig::active()?);
+
+    status_inline!("Scanning for the 7z payload and rebuilding... ");
+    io::stdout().flush()?;
+    let recovered = TlockArchive::recover(file, metadata)?;
+    status!("done");
+
+    if json {
+        return print_json(RecoverReport {
+            file: file.display().to_string(),
+            recovered: recovered.display().to_string(),
+        });
+    }
+
+    println!();
+    println!("Recovered: {}", recovered.display());
+    println!("Check it with `timelocker info` before relying on it.");
+    Ok(())
+}
+
+/// Ask for a line of input on stdin (the question goes to stderr, clear of `--json` output)
+fn prompt(label: &str) -> Result<String> {
+    eprint!("{}: ", label);
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Ask a yes/no question on stdin; anything but "y"/"yes" is no
+fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{} [y/N]", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// About command implementation
+fn cmd_about(json: bool) -> Result<()> {
+    let beacon = crypto::BeaconConfig::active()?;
+    let mut features = Vec::new();
+    if cfg!(feature = "custom-protocol") {
+        features.push("custom-protocol");
+    }
+
+    let report = AboutReport {
+        version: env!("CARGO_PKG_VERSION"),
+        min_format_version: tlock_format::MIN_TLOCK_VERSION,
+        max_format_version: tlock_format::TLOCK_VERSION,
+        beacon_chain_hash: beacon.chain_hash,
+        beacon_config: crypto::active_beacon_source(),
+        features,
+    };
+    if json {
+        return print_json(report);
+    }
+
+    println!("timelocker {}", report.version);
+    println!(
+        "File format: versions {}-{} (writes {} by default)",
+        report.min_format_version, report.max_format_version, report.max_format_version
+    );
+    match report.beacon_config {
+        Some(ref path) => println!("Beacon: {} (from {})", report.beacon_chain_hash, path),
+        None => println!("Beacon: {} (drand Quicknet)", report.beacon_chain_hash),
+    }
+    if report.features.is_empty() {
+        println!("Features: none");
+    } else {
+        println!("Features: {}", report.features.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Doctor command implementation
+///
+/// # Returns
+/// Whether at least one relay is healthy
+fn cmd_doctor(json: bool) -> Result<bool> {
+    if !json {
+        println!("Checking drand relays...");
+    }
+    let health = crypto::check_drand_health()?;
+    let healthy = health.iter().filter(|h| h.healthy).count();
+
+    if json {
+        let errors = health
+            .iter()
+            .filter_map(|h| h.error.as_ref().map(|e| format!("{}: {}", h.endpoint.url, e)))
+            .collect();
+        print_json_envelope(&JsonEnvelope {
+            ok: healthy > 0,
+            data: Some(health),
+            errors,
+        })?;
+        return Ok(healthy > 0);
+    }
+
+    println!();
+    for relay in &health {
+        let status = if relay.healthy { "OK" } else { "FAIL" };
+        println!("{:<6} {} ({})", status, relay.endpoint.url, relay.endpoint.source);
+        if let (Some(round), Some(drift)) = (relay.latest_round, relay.drift_seconds) {
+            println!("       Latest round {} (scheduled {}s ago by the local clock)", round, drift);
+        }
+        if let Some(latency) = relay.latency_ms {
+            println!("       Answered in {} ms", latency);
+        }
+        if let Some(error) = &relay.error {
+            println!("       {}", error);
+        }
+    }
+
+    println!();
+    println!("{} of {} relay(s) healthy", healthy, health.len());
+
+    Ok(healthy > 0)
+}
+
+/// Benchmark endpoints command implementation
+///
+/// # Returns
+/// Whether at least one relay answered
+fn cmd_benchmark_endpoints(samples: u32, json: bool) -> Result<bool> {
+    if !json {
+        println!("Fetching the latest round {} time(s) from each drand relay...", samples);
+    }
+    let report = crypto::benchmark_drand_endpoints(samples)?;
+    let answered = report.iter().any(|r| r.latency_ms.is_some());
+
+    if json {
+        let errors = report
+            .iter()
+            .filter_map(|r| r.error.as_ref().map(|e| format!("{}: {}", r.endpoint.url, e)))
+            .collect();
+        print_json_envelope(&JsonEnvelope {
+            ok: answered,
+            data: Some(report),
+            errors,
+        })?;
+        return Ok(answered);
+    }
+
+    println!();
+    println!("{:>10}  {:>10}  Relay", "Median", "Samples");
+    println!("{}", "-".repeat(60));
+    for relay in &report {
+        let median = relay
+            .latency_ms
+            .map(|ms| format!("{} ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+        let answered = format!("{}/{}", relay.samples_ms.len(), samples);
+        println!(
+            "{:>10}  {:>10}  {} ({})",
+            median,
+            answered,
+            relay.endpoint.url,
+            relay.endpoint.source
+        );
+        if let Some(error) = &relay.error {
+            println!("{:>24}{}", "", error);
+        }
+    }
+
+    if let Some(fastest) = report.first().filter(|r| r.latency_ms.is_some()) {
+        println!();
+        println!("Fastest: {}", fastest.endpoint.url);
+    }
+
+    Ok(answered)
+}
+
+/// The .7z.tlock files named by a `file_or_dir` argument
+fn tlock_files_at(file_or_dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    if file_or_dir.is_dir() {
+        Ok(tlock_format::find_tlock_files(file_or_dir, recursive))
+    } else if file_or_dir.exists() {
+        Ok(vec![file_or_dir.to_path_buf()])
+    } else {
+        Err(TimeLockerError::FileNotFound(file_or_dir.display().to_string()))
+    }
+}
+
+/// Recover the archive password, failing if the time lock is still active
+pub(crate) fn decrypt_archive_password(metadata: &TlockMetadata) -> Result<String> {
+    if !metadata.is_unlockable() {
+        return Err(TimeLockerError::TimeLockActive);
+    }
+
+    let encrypted_password = metadata
+        .encrypted_key
+        .as_ref()
+        .ok_or_else(|| TimeLockerError::MissingField("encrypted_key".to_string()))?;
+
+    crypto::decrypt_with_tlock(encrypted_password, metadata.unlocks, &metadata.beacon()?)
+}
+
+/// Parse datetime from various formats
+pub(crate) fn parse_datetime(s: &str) -> Result<DateTime<Utc>> {
+    // Try RFC3339 first
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    // Try "YYYY-MM-DD HH:MM:SS"
+    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+        let local = Local
+            .from_local_datetime(&dt)
+            .single()
+            .ok_or_else(|| TimeLockerError::Parse("Ambiguous datetime".to_string()))?;
+        return Ok(local.with_timezone(&Utc));
+    }
+
+    // Try "YYYY-MM-DD HH:MM"
+    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+        let local = Local
+            .from_local_datetime(&dt)
+            .single()
+            .ok_or_else(|| TimeLockerError::Parse("Ambiguous datetime".to_string()))?;
+        return Ok(local.with_timezone(&Utc));
     }
 
     // Try "YYYY-MM-DD" (default to midnight)
@@ -567,6 +3322,20 @@ fn parse_datetime(s: &str) -> Result<DateTime<Utc>> {
     )))
 }
 
+/// Format a byte count in MB (MiB, like `--throttle`)
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Parse a `--throttle` value in MB/s into bytes per second
+fn parse_throttle(s: &str) -> std::result::Result<u64, String> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(progress::rate_limit_from_mb_per_sec)
+        .ok_or_else(|| format!("Invalid throughput: '{}' (use a positive number of MB/s)", s))
+}
+
 /// Check if CLI arguments were provided (excluding the program name)
 pub fn has_cli_args() -> bool {
     std::env::args().count() > 1
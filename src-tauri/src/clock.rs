@@ -0,0 +1,72 @@
+//! Current-time indirection
+//!
+//! Time-dependent logic (unlockability, countdowns, lead-time checks) reads the
+//! time through [`now`] rather than calling `Utc::now()` directly, so it can be
+//! pinned to a fixed instant:
+//!
+//! - In tests, with [`set_fake_now`] (per-thread, so parallel tests don't clash).
+//! - In debug builds, with the `TIMELOCKER_FAKE_NOW` environment variable
+//!   holding an RFC 3339 timestamp. Release builds ignore the variable.
+//!
+//! Only local decisions use this clock. Whether a drand round has actually
+//! been published is still decided by the network.
+
+use chrono::{DateTime, Utc};
+
+/// Environment variable pinning the current time in debug builds (RFC 3339)
+pub const FAKE_NOW_ENV: &str = "TIMELOCKER_FAKE_NOW";
+
+#[cfg(test)]
+thread_local! {
+    static FAKE_NOW: std::cell::Cell<Option<DateTime<Utc>>> = const { std::cell::Cell::new(None) };
+}
+
+/// Current time, honouring any test or debug override
+pub fn now() -> DateTime<Utc> {
+    #[cfg(test)]
+    if let Some(fake) = FAKE_NOW.with(|f| f.get()) {
+        return fake;
+    }
+
+    #[cfg(debug_assertions)]
+    if let Some(fake) = fake_now_from_env() {
+        return fake;
+    }
+
+    Utc::now()
+}
+
+/// Pin (or with `None`, release) the current time for this test thread
+#[cfg(test)]
+pub fn set_fake_now(now: Option<DateTime<Utc>>) {
+    FAKE_NOW.with(|f| f.set(now));
+}
+
+#[cfg(debug_assertions)]
+fn fake_now_from_env() -> Option<DateTime<Utc>> {
+    let value = std::env::var(FAKE_NOW_ENV).ok()?;
+    match DateTime::parse_from_rfc3339(value.trim()) {
+        Ok(dt) => Some(dt.with_timezone(&Utc)),
+        Err(e) => {
+            eprintln!("[clock] Ignoring invalid {}: {}", FAKE_NOW_ENV, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fake_now_override() {
+        let fixed = Utc.with_ymd_and_hms(2030, 1, 1, 12, 0, 0).unwrap();
+
+        set_fake_now(Some(fixed));
+        assert_eq!(now(), fixed);
+
+        set_fake_now(None);
+        assert_ne!(now(), fixed);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::archive::CompressionSettings;
+use crate::clock;
 use crate::keyfile::KeyFile;
 use crate::progress::ProgressTracker;
 use crate::tlock_format::{TlockArchive, TlockMetadata, scan_tlock_files};
@@ -5,14 +7,17 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::fs;
 use tauri::{State, WebviewWindow};
 
 /// Global state for tracking active operations (for cancellation support)
+///
+/// The map is only reachable through the methods below, which all tolerate a
+/// poisoned mutex, so one panicked operation can't disable the others.
 pub struct OperationState {
     /// Map of operation_id -> progress tracker
-    pub active_operations: Mutex<HashMap<String, Arc<ProgressTracker>>>,
+    active_operations: Mutex<HashMap<String, Arc<ProgressTracker>>>,
 }
 
 impl Default for OperationState {
@@ -23,6 +28,94 @@ impl Default for OperationState {
     }
 }
 
+impl OperationState {
+    /// Lock the operation map, recovering it if a panicked operation poisoned the mutex
+    ///
+    /// Entries are only inserted and removed whole, so the map is still
+    /// consistent after a panic; refusing every later operation would be worse.
+    /// Poisoning needs an unwinding panic, so this matters in debug builds and
+    /// tests; release builds abort on panic.
+    fn operations(&self) -> MutexGuard<'_, HashMap<String, Arc<ProgressTracker>>> {
+        self.active_operations.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Track an operation so it can be cancelled by id
+    pub fn register(&self, operation_id: &str, tracker: &Arc<ProgressTracker>) {
+        self.operations().insert(operation_id.to_string(), Arc::clone(tracker));
+    }
+
+    /// Stop tracking a finished (or failed) operation
+    pub fn remove(&self, operation_id: &str) {
+        self.operations().remove(operation_id);
+    }
+
+    /// Request cancellation of an operation, returning false if it isn't running
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.operations().get(operation_id) {
+            Some(tracker) => {
+                tracker.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Snapshot of every tracked operation, oldest first
+    pub fn list(&self) -> Vec<ActiveOperation> {
+        let mut operations: Vec<ActiveOperation> = self
+            .operations()
+            .iter()
+            .map(|(id, tracker)| ActiveOperation {
+                id: id.clone(),
+                phase: tracker.phase(),
+                started_at: tracker.started_at(),
+                idle_seconds: tracker.idle_for().as_secs(),
+                bytes_processed: tracker.get_bytes_written(),
+                cancelled: tracker.is_cancelled(),
+            })
+            .collect();
+        operations.sort_by_key(|op| op.started_at);
+        operations
+    }
+
+    /// Cancel and forget operations that made no progress for `max_idle`
+    ///
+    /// Entries normally leave the map when their command returns; this catches
+    /// the ones left behind by a panicked command or a reloaded window.
+    /// Returns the ids that were pruned.
+    pub fn prune_stale(&self, max_idle: std::time::Duration) -> Vec<String> {
+        let mut ops = self.operations();
+        let stale: Vec<String> = ops
+            .iter()
+            .filter(|(_, tracker)| tracker.idle_for() >= max_idle)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &stale {
+            if let Some(tracker) = ops.remove(id) {
+                tracker.cancel();
+            }
+            eprintln!("[OperationState::prune_stale] Pruned stale operation: {}", id);
+        }
+        stale
+    }
+}
+
+/// How long an operation may go without progress before it counts as stale
+pub const STALE_OPERATION_IDLE: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// A tracked lock/unlock operation, as listed for the UI
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActiveOperation {
+    pub id: String,
+    /// Phase of the last progress event (None before the first one)
+    pub phase: Option<crate::progress::ProgressPhase>,
+    pub started_at: chrono::DateTime<Utc>,
+    /// Seconds since the operation last made progress
+    pub idle_seconds: u64,
+    pub bytes_processed: u64,
+    pub cancelled: bool,
+}
+
 /// Locked item representation for UI
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LockedItem {
@@ -37,6 +130,12 @@ pub struct LockedItem {
     pub created_at: String,
     pub unlocks_at: String,
     pub is_unlockable: bool,
+    /// When the drand round for the key is published (the real earliest unlock)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decryptable_at: Option<String>,
+    /// Drand round the key is time-locked to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drand_round: Option<u64>,
     pub original_file: Option<String>,
     /// Whether this is the legacy format (.key.md + .7z) vs new (.7z.tlock)
     #[serde(default)]
@@ -48,6 +147,12 @@ pub struct LockedItem {
     /// Path to the unlocked directory if it exists (indicates vault was previously unlocked)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unlocked_path: Option<String>,
+    /// Estimated entropy of a user-supplied password (set when locking with one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_entropy_bits: Option<f64>,
+    /// Set when a user-supplied password looks weak enough to be guessed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_warning: Option<String>,
 }
 
 /// Verify that a 7z archive exists and has valid structure
@@ -112,6 +217,9 @@ fn delete_source_safely(source_path: &std::path::Path) -> Result<(), String> {
 /// Creates a unified .7z.tlock file that contains:
 /// - Unencrypted header with metadata (readable without password)
 /// - Encrypted archive payload
+///
+/// An optional `note` is stored in the unencrypted metadata, readable by anyone
+/// before the unlock time.
 #[tauri::command]
 pub async fn lock_item(
     file_path: String,
@@ -119,8 +227,11 @@ pub async fn lock_item(
     password: Option<String>,
     vault: Option<String>,
     delete_original: Option<bool>,
+    compression: Option<CompressionSettings>,
+    note: Option<String>,
 ) -> Result<LockedItem, String> {
     use crate::crypto;
+    use crate::tlock_format::CreateOptions;
     use std::path::Path;
 
     let should_delete = delete_original.unwrap_or(false);
@@ -134,7 +245,7 @@ pub async fn lock_item(
     let unlock_datetime = chrono::DateTime::parse_from_rfc3339(&unlock_time)
         .map_err(|e| format!("Invalid time format: {}", e))?;
 
-    if unlock_datetime <= Utc::now() {
+    if unlock_datetime <= clock::now() {
         return Err("Unlock time must be in the future".to_string());
     }
 
@@ -143,6 +254,20 @@ pub async fn lock_item(
         return Err(format!("File not found: {}", file_path));
     }
 
+    // The locked file ends up in the vault, so the source must not contain it
+    let vault_dir = match vault {
+        Some(ref v) if !v.is_empty() => PathBuf::from(v),
+        _ => ensure_default_vault_exists()?,
+    };
+    crate::archive::check_source_outside_vault(source_path, &vault_dir).map_err(|e| e.to_string())?;
+
+    // Without explicit settings, use the vault's own defaults
+    let mut compression = compression
+        .or_else(|| crate::tlock_format::vault_compression(&vault_dir))
+        .unwrap_or_default();
+    compression.uniform |= settings_uniform_compression();
+    eprintln!("[lock_item] Compression: {:?}", compression);
+
     // Store original path for potential deletion
     let original_source_path = source_path.to_path_buf();
 
@@ -154,20 +279,34 @@ pub async fn lock_item(
 
     let is_directory = source_path.is_dir();
 
+    // A user-chosen password is kept even if weak, but the caller is warned
+    let password_entropy_bits = password.as_deref().map(crypto::estimate_entropy);
+    let password_warning = password.as_deref().and_then(crypto::weak_password_warning);
+    if let Some(ref warning) = password_warning {
+        eprintln!("[lock_item] {}", warning);
+    }
+
     // 1. Generate random password for the archive
     let archive_password = password.unwrap_or_else(|| crypto::generate_password(32));
     eprintln!("[lock_item] Generated password length: {}", archive_password.len());
 
     // 2. Encrypt the password with tlock (cryptographic time-lock)
     let unlock_utc = unlock_datetime.with_timezone(&Utc);
+    let (unlock_utc, adjusted) =
+        crypto::enforce_min_lead(unlock_utc, clock::now(), crypto::min_unlock_lead());
+    if adjusted {
+        eprintln!("[lock_item] Unlock time too close, moved to: {}", unlock_utc);
+    }
     let duration_str = unlock_datetime.format("%Y-%m-%d").to_string();
 
-    let encrypted_password = crypto::encrypt_with_tlock(&archive_password, unlock_utc)
+    let beacon = crypto::BeaconConfig::active()
+        .map_err(|e| format!("Failed to load beacon config: {}", e))?;
+    let encrypted_password = crypto::encrypt_with_tlock(&archive_password, unlock_utc, &beacon)
         .map_err(|e| format!("Failed to encrypt password with tlock: {}", e))?;
     eprintln!("[lock_item] Encrypted password with tlock");
 
     // 3. Get drand round number for metadata
-    let drand_round = Some(crypto::datetime_to_round(unlock_utc));
+    let drand_round = Some(crypto::datetime_to_round(unlock_utc, &beacon));
 
     // 4. Create TlockMetadata
     let mut metadata = TlockMetadata::new(
@@ -178,6 +317,8 @@ pub async fn lock_item(
         Some(encrypted_password),
     );
     metadata.is_directory = is_directory;
+    metadata.set_beacon(&beacon);
+    metadata.note = crate::tlock_format::normalize_note(note);
 
     // Get original size for metadata
     if let Ok((total_bytes, _)) = crate::progress::calculate_total_size(source_path) {
@@ -185,19 +326,20 @@ pub async fn lock_item(
     }
 
     // 5. Create the .7z.tlock file using TlockArchive
-    let tlock_path = TlockArchive::create(source_path, metadata.clone(), &archive_password)
+    let options = CreateOptions {
+        compression,
+        rate_limit: settings_rate_limit(),
+        ..Default::default()
+    };
+    let tlock_path = TlockArchive::create_with_options(source_path, metadata.clone(), &archive_password, &options)
         .map_err(|e| format!("Failed to create .7z.tlock file: {}", e))?;
 
     eprintln!("[lock_item] Created .7z.tlock at: {:?}", tlock_path);
 
-    // 6. Determine the vault directory and move file if needed
-    let vault_dir = match vault {
-        Some(ref v) if !v.is_empty() => PathBuf::from(v),
-        _ => ensure_default_vault_exists()?,
-    };
-
+    // 6. Move the file into the vault if needed
     let final_tlock_path = if vault_dir.exists() && vault_dir.is_dir() && tlock_path.parent() != Some(&vault_dir) {
-        let tlock_filename = tlock_path.file_name().unwrap();
+        let tlock_filename = tlock_path.file_name()
+            .ok_or_else(|| format!("Invalid .7z.tlock path: {:?}", tlock_path))?;
         let new_tlock_path = vault_dir.join(tlock_filename);
         fs::rename(&tlock_path, &new_tlock_path)
             .map_err(|e| format!("Failed to move .7z.tlock to vault: {}", e))?;
@@ -251,22 +393,92 @@ pub async fn lock_item(
         created_at: metadata.created.to_rfc3339(),
         unlocks_at: metadata.unlocks.to_rfc3339(),
         is_unlockable: false,
+        decryptable_at: metadata.decryptable_at().map(|t| t.to_rfc3339()),
+        drand_round: metadata.unlock_round(),
         original_file: Some(file_path),
         is_legacy_format: false,
         original_deleted,
         deletion_error,
         unlocked_path: None, // Just locked, not unlocked yet
+        password_entropy_bits,
+        password_warning,
     };
 
     eprintln!("[lock_item] Lock complete: {:?}", locked_item);
     Ok(locked_item)
 }
 
+/// Command to lock an unlocked directory again, until a new time
+///
+/// Runs the `lock_item` flow on the `unlocked_*` directory an unlock produced:
+/// it is re-archived under a fresh random password that is time-locked to
+/// `unlock_time`. With `delete_unlocked`, the directory is removed once the
+/// new .7z.tlock passes validation.
+#[tauri::command]
+pub async fn relock_item(
+    unlocked_path: String,
+    unlock_time: String,
+    vault: Option<String>,
+    delete_unlocked: Option<bool>,
+) -> Result<LockedItem, String> {
+    let path = std::path::Path::new(&unlocked_path);
+    if !path.is_dir() {
+        return Err(format!("Unlocked directory not found: {}", unlocked_path));
+    }
+
+    eprintln!("[relock_item] Relocking: {}", unlocked_path);
+    lock_item(unlocked_path, unlock_time, None, vault, delete_unlocked, None, None).await
+}
+
+/// Command to move the unlock time of a .7z.tlock file without re-archiving it
+///
+/// Only the time-locked archive password is re-encrypted for the new time;
+/// the payload is left byte-for-byte. Re-encrypting needs the plaintext
+/// password, which can't be recovered while the file is still locked, so
+/// this only works once the current unlock time has passed.
+///
+/// # Returns
+/// The new unlock time (RFC 3339), after any minimum lead adjustment
+#[tauri::command]
+pub async fn change_unlock_time(tlock_path: String, new_unlock_time: String) -> Result<String, String> {
+    use crate::crypto;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let unlock_datetime = chrono::DateTime::parse_from_rfc3339(&new_unlock_time)
+        .map_err(|e| format!("Invalid time format: {}", e))?
+        .with_timezone(&Utc);
+    if unlock_datetime <= clock::now() {
+        return Err("Unlock time must be in the future".to_string());
+    }
+    let (unlock_datetime, _) =
+        crypto::enforce_min_lead(unlock_datetime, clock::now(), crypto::min_unlock_lead());
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    // Fails while the current lock is active: the password can't be recovered yet
+    let archive_password = decrypt_archive_password(metadata)?;
+
+    TlockArchive::change_unlock_time(path, &archive_password, unlock_datetime)
+        .map_err(|e| format!("Failed to change unlock time: {}", e))?;
+
+    eprintln!("[change_unlock_time] {} now unlocks at {}", tlock_path, unlock_datetime);
+    Ok(unlock_datetime.to_rfc3339())
+}
+
 /// Command to lock files with time-lock encryption and progress tracking
 ///
 /// Creates a unified .7z.tlock file with progress reporting.
 /// Events are emitted on the "lock-progress" channel with ProgressPayload data.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn lock_item_with_progress(
     window: WebviewWindow,
     state: State<'_, OperationState>,
@@ -275,13 +487,14 @@ pub async fn lock_item_with_progress(
     password: Option<String>,
     vault: Option<String>,
     delete_original: Option<bool>,
+    compression: Option<CompressionSettings>,
+    note: Option<String>,
     operation_id: Option<String>,
 ) -> Result<LockedItem, String> {
     use crate::crypto;
     use crate::archive;
-    use crate::tlock_format::TLOCK_MAGIC;
+    use crate::progress::{ProgressEmitter, ProgressPhase};
     use std::path::Path;
-    use std::io::{Read, Write};
 
     let should_delete = delete_original.unwrap_or(false);
     let op_id = operation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
@@ -296,7 +509,7 @@ pub async fn lock_item_with_progress(
     let unlock_datetime = chrono::DateTime::parse_from_rfc3339(&unlock_time)
         .map_err(|e| format!("Invalid time format: {}", e))?;
 
-    if unlock_datetime <= Utc::now() {
+    if unlock_datetime <= clock::now() {
         return Err("Unlock time must be in the future".to_string());
     }
 
@@ -305,12 +518,23 @@ pub async fn lock_item_with_progress(
         return Err(format!("File not found: {}", file_path));
     }
 
+    // The locked file ends up in the vault, so the source must not contain it
+    let vault_dir = match vault {
+        Some(ref v) if !v.is_empty() => PathBuf::from(v),
+        _ => ensure_default_vault_exists()?,
+    };
+    crate::archive::check_source_outside_vault(source_path, &vault_dir).map_err(|e| e.to_string())?;
+
+    // Without explicit settings, use the vault's own defaults
+    let mut compression = compression
+        .or_else(|| crate::tlock_format::vault_compression(&vault_dir))
+        .unwrap_or_default();
+    compression.uniform |= settings_uniform_compression();
+    eprintln!("[lock_item_with_progress] Compression: {:?}", compression);
+
     // Create progress tracker and register it for potential cancellation
-    let tracker = Arc::new(ProgressTracker::new());
-    {
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.insert(op_id.clone(), Arc::clone(&tracker));
-    }
+    let tracker = Arc::new(ProgressTracker::new().with_rate_limit(settings_rate_limit()));
+    state.register(&op_id, &tracker);
 
     // Store original path for potential deletion
     let original_source_path = source_path.to_path_buf();
@@ -322,6 +546,13 @@ pub async fn lock_item_with_progress(
         .to_string();
     let is_directory = source_path.is_dir();
 
+    // A user-chosen password is kept even if weak, but the caller is warned
+    let password_entropy_bits = password.as_deref().map(crypto::estimate_entropy);
+    let password_warning = password.as_deref().and_then(crypto::weak_password_warning);
+    if let Some(ref warning) = password_warning {
+        eprintln!("[lock_item_with_progress] {}", warning);
+    }
+
     // 1. Generate random password for the archive
     let archive_password = password.unwrap_or_else(|| crypto::generate_password(32));
     eprintln!("[lock_item_with_progress] Generated password length: {}", archive_password.len());
@@ -331,6 +562,7 @@ pub async fn lock_item_with_progress(
     let archive_result = archive::create_encrypted_archive_with_progress(
         source_path,
         &archive_password,
+        &compression,
         window.clone(),
         Some(Arc::clone(&tracker)),
     );
@@ -338,31 +570,36 @@ pub async fn lock_item_with_progress(
     // Check for cancellation
     if tracker.is_cancelled() {
         // Remove from active operations
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.remove(&op_id);
+        state.remove(&op_id);
         return Err("Operation cancelled by user".to_string());
     }
 
     let temp_archive_path = archive_result
         .map_err(|e| {
             // Remove from active operations on error
-            let mut ops = state.active_operations.lock().unwrap();
-            ops.remove(&op_id);
+            state.remove(&op_id);
             format!("Failed to create encrypted archive: {}", e)
         })?;
     eprintln!("[lock_item_with_progress] Created temp 7z archive at: {:?} (took {:?})", temp_archive_path, archive_start.elapsed());
 
     // 3. Encrypt the password with tlock (cryptographic time-lock)
     let unlock_utc = unlock_datetime.with_timezone(&Utc);
+    let (unlock_utc, adjusted) =
+        crypto::enforce_min_lead(unlock_utc, clock::now(), crypto::min_unlock_lead());
+    if adjusted {
+        eprintln!("[lock_item_with_progress] Unlock time too close, moved to: {}", unlock_utc);
+    }
     let duration_str = unlock_datetime.format("%Y-%m-%d").to_string();
 
+    let beacon = crypto::BeaconConfig::active()
+        .map_err(|e| format!("Failed to load beacon config: {}", e))?;
     let tlock_start = std::time::Instant::now();
-    let encrypted_password = crypto::encrypt_with_tlock(&archive_password, unlock_utc)
+    let encrypted_password = crypto::encrypt_with_tlock(&archive_password, unlock_utc, &beacon)
         .map_err(|e| format!("Failed to encrypt password with tlock: {}", e))?;
     eprintln!("[lock_item_with_progress] Encrypted password with tlock (took {:?})", tlock_start.elapsed());
 
     // 4. Get drand round and original size for metadata
-    let drand_round = Some(crypto::datetime_to_round(unlock_utc));
+    let drand_round = Some(crypto::datetime_to_round(unlock_utc, &beacon));
     let original_size = crate::progress::calculate_total_size(source_path)
         .map(|(bytes, _)| bytes)
         .ok();
@@ -377,68 +614,36 @@ pub async fn lock_item_with_progress(
     );
     metadata.is_directory = is_directory;
     metadata.original_size = original_size;
+    metadata.set_beacon(&beacon);
+    metadata.note = crate::tlock_format::normalize_note(note);
+    metadata.record_compression(&compression.resolve_filter(&[source_path]));
+
+    // 6. Stream the 7z archive into the .7z.tlock file (removes the temp archive)
+    let emitter = ProgressEmitter::new(window.clone(), Arc::clone(&tracker), "lock-progress");
+    emitter.emit_progress_forced(Some(original_filename.clone()), ProgressPhase::WritingMetadata);
+
+    let tlock_path = TlockArchive::output_path(&[source_path]).map_err(|e| e.to_string())?;
+    if let Err(e) = TlockArchive::wrap_archive(
+        &temp_archive_path,
+        &tlock_path,
+        metadata.clone(),
+        &archive_password,
+        &crate::tlock_format::CreateOptions::default(),
+    ) {
+        state.remove(&op_id);
+        return Err(format!("Failed to create .7z.tlock file: {}", e));
+    }
 
-    // 6. Serialize metadata to JSON
-    let metadata_json = serde_json::to_vec(&metadata)
-        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-    let metadata_len = metadata_json.len() as u32;
-
-    // 7. Read the 7z archive payload
-    let mut archive_file = fs::File::open(&temp_archive_path)
-        .map_err(|e| format!("Failed to open temp archive: {}", e))?;
-    let mut archive_payload = Vec::new();
-    archive_file.read_to_end(&mut archive_payload)
-        .map_err(|e| format!("Failed to read temp archive: {}", e))?;
-
-    // 8. Create the .7z.tlock file path
-    let tlock_path = source_path.with_extension("7z.tlock");
-
-    // 9. Write the .7z.tlock file
-    let mut tlock_file = fs::File::create(&tlock_path)
-        .map_err(|e| format!("Failed to create .7z.tlock file: {}", e))?;
-
-    // Write header
-    tlock_file.write_all(TLOCK_MAGIC)
-        .map_err(|e| format!("Failed to write magic bytes: {}", e))?;
-    tlock_file.write_all(&[1u8]) // Version
-        .map_err(|e| format!("Failed to write version: {}", e))?;
-    tlock_file.write_all(&metadata_len.to_le_bytes())
-        .map_err(|e| format!("Failed to write metadata length: {}", e))?;
-    tlock_file.write_all(&[0u8; 12]) // Reserved
-        .map_err(|e| format!("Failed to write reserved bytes: {}", e))?;
-
-    // Write metadata
-    tlock_file.write_all(&metadata_json)
-        .map_err(|e| format!("Failed to write metadata: {}", e))?;
-
-    // Write payload
-    tlock_file.write_all(&archive_payload)
-        .map_err(|e| format!("Failed to write archive payload: {}", e))?;
-
-    tlock_file.flush()
-        .map_err(|e| format!("Failed to flush file: {}", e))?;
-
+    emitter.emit_progress_forced(Some(original_filename.clone()), ProgressPhase::Finalizing);
     eprintln!("[lock_item_with_progress] Created .7z.tlock at: {:?}", tlock_path);
 
-    // 10. Clean up temp 7z file
-    if let Err(e) = fs::remove_file(&temp_archive_path) {
-        eprintln!("[lock_item_with_progress] Warning: Failed to remove temp file: {}", e);
-    }
-
     // Remove from active operations
-    {
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.remove(&op_id);
-    }
-
-    // 11. Move to vault if needed
-    let vault_dir = match vault {
-        Some(ref v) if !v.is_empty() => PathBuf::from(v),
-        _ => ensure_default_vault_exists()?,
-    };
+    state.remove(&op_id);
 
+    // 7. Move to vault if needed
     let final_tlock_path = if vault_dir.exists() && vault_dir.is_dir() && tlock_path.parent() != Some(&vault_dir) {
-        let tlock_filename = tlock_path.file_name().unwrap();
+        let tlock_filename = tlock_path.file_name()
+            .ok_or_else(|| format!("Invalid .7z.tlock path: {:?}", tlock_path))?;
         let new_tlock_path = vault_dir.join(tlock_filename);
         fs::rename(&tlock_path, &new_tlock_path)
             .map_err(|e| format!("Failed to move .7z.tlock to vault: {}", e))?;
@@ -448,7 +653,7 @@ pub async fn lock_item_with_progress(
         tlock_path
     };
 
-    // 12. Handle original file deletion if requested
+    // 8. Handle original file deletion if requested
     let mut original_deleted = false;
     let mut deletion_error: Option<String> = None;
 
@@ -488,11 +693,15 @@ pub async fn lock_item_with_progress(
         created_at: metadata.created.to_rfc3339(),
         unlocks_at: metadata.unlocks.to_rfc3339(),
         is_unlockable: false,
+        decryptable_at: metadata.decryptable_at().map(|t| t.to_rfc3339()),
+        drand_round: metadata.unlock_round(),
         original_file: Some(file_path),
         is_legacy_format: false,
         original_deleted,
         deletion_error,
         unlocked_path: None, // Just locked, not unlocked yet
+        password_entropy_bits,
+        password_warning,
     };
 
     eprintln!("[lock_item_with_progress] Lock complete: {:?}", locked_item);
@@ -505,9 +714,7 @@ pub fn cancel_operation(
     state: State<'_, OperationState>,
     operation_id: String,
 ) -> Result<bool, String> {
-    let ops = state.active_operations.lock().unwrap();
-    if let Some(tracker) = ops.get(&operation_id) {
-        tracker.cancel();
+    if state.cancel(&operation_id) {
         eprintln!("[cancel_operation] Cancelled operation: {}", operation_id);
         Ok(true)
     } else {
@@ -516,112 +723,162 @@ pub fn cancel_operation(
     }
 }
 
-/// Command to unlock files with progress tracking
+/// List the lock/unlock operations currently tracked for cancellation
 #[tauri::command]
-pub async fn unlock_item_with_progress(
-    window: WebviewWindow,
+pub fn list_active_operations(state: State<'_, OperationState>) -> Vec<ActiveOperation> {
+    state.list()
+}
+
+/// Cancel and forget operations with no progress for `max_idle_seconds`
+/// (defaults to `STALE_OPERATION_IDLE`)
+///
+/// # Returns
+/// Ids of the pruned operations
+#[tauri::command]
+pub fn prune_stale_operations(
     state: State<'_, OperationState>,
-    key_path: String,
-    _password: Option<String>,
-    operation_id: Option<String>,
-) -> Result<String, String> {
-    use crate::crypto;
-    use crate::archive;
-    use std::path::Path;
+    max_idle_seconds: Option<u64>,
+) -> Vec<String> {
+    let max_idle = max_idle_seconds
+        .map(std::time::Duration::from_secs)
+        .unwrap_or(STALE_OPERATION_IDLE);
+    state.prune_stale(max_idle)
+}
 
-    let op_id = operation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+/// Error returned by the unlock commands
+///
+/// A file that is still time-locked is reported as structured data, so the
+/// UI can render (and localize) its own countdown, and so is a due beacon that
+/// couldn't be fetched (worth retrying); everything else is a message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum UnlockError {
+    TimeLockActive {
+        /// Always "time_lock_active"
+        code: &'static str,
+        unlocks_at: String,
+        remaining_seconds: i64,
+    },
+    DrandUnavailable {
+        /// Always "drand_unavailable"
+        code: &'static str,
+        message: String,
+    },
+    Message(String),
+}
 
-    let key_file_path = Path::new(&key_path);
-    if !key_file_path.exists() {
-        return Err(format!("Key file not found: {}", key_path));
+impl UnlockError {
+    /// Error for a file that can't be unlocked until `unlocks`
+    pub fn time_lock_active(unlocks: chrono::DateTime<Utc>) -> Self {
+        UnlockError::TimeLockActive {
+            code: "time_lock_active",
+            unlocks_at: unlocks.to_rfc3339(),
+            remaining_seconds: (unlocks - clock::now()).num_seconds().max(0),
+        }
     }
 
-    // Create progress tracker
-    let tracker = Arc::new(ProgressTracker::new());
-    {
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.insert(op_id.clone(), Arc::clone(&tracker));
+    /// Error for a failed tlock decryption of the key of a file unlocking at `unlocks`
+    fn key_decryption(error: crate::error::TimeLockerError, unlocks: chrono::DateTime<Utc>) -> Self {
+        use crate::error::TimeLockerError;
+
+        match error {
+            TimeLockerError::TimeLockActive => Self::time_lock_active(unlocks),
+            TimeLockerError::DrandUnavailable(_) => UnlockError::DrandUnavailable {
+                code: "drand_unavailable",
+                message: error.to_string(),
+            },
+            error => UnlockError::Message(format!("Failed to decrypt key: {}", error)),
+        }
     }
+}
 
-    // 1. Read and parse key file
-    let content = fs::read_to_string(key_file_path)
-        .map_err(|e| format!("Failed to read key file: {}", e))?;
-
-    let keyfile = KeyFile::parse(&content)
-        .map_err(|e| format!("Failed to parse key file: {}", e))?;
-
-    // 2. Check if unlock time has passed
-    if !keyfile.is_unlockable() {
-        let remaining = keyfile.time_until_unlock();
-        // Remove from active operations
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.remove(&op_id);
-        return Err(format!(
-            "Time lock still active. Unlock in {} hours, {} minutes",
-            remaining.num_hours(),
-            remaining.num_minutes() % 60
-        ));
+impl std::fmt::Display for UnlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnlockError::TimeLockActive { remaining_seconds, .. } => write!(
+                f,
+                "Time lock still active. Unlock in {} hours, {} minutes",
+                remaining_seconds / 3600,
+                remaining_seconds / 60 % 60
+            ),
+            UnlockError::DrandUnavailable { message, .. } | UnlockError::Message(message) => {
+                f.write_str(message)
+            }
+        }
     }
+}
 
-    // 3. Decrypt the AES-encrypted password
-    let archive_password = crypto::decrypt_with_tlock(&keyfile.encrypted_body, keyfile.metadata.unlocks)
-        .map_err(|e| format!("Failed to decrypt password: {}", e))?;
-
-    // 4. Extract the 7z archive with the password
-    let archive_path_str = keyfile.metadata.archive_path
-        .ok_or_else(|| "Archive path not found in key file".to_string())?;
+impl From<String> for UnlockError {
+    fn from(message: String) -> Self {
+        UnlockError::Message(message)
+    }
+}
 
-    let archive_path = Path::new(&archive_path_str);
-    if !archive_path.exists() {
-        // Remove from active operations
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.remove(&op_id);
-        return Err(format!("Archive file not found: {}", archive_path_str));
+/// For commands that still report errors as plain strings
+impl From<UnlockError> for String {
+    fn from(error: UnlockError) -> Self {
+        error.to_string()
     }
+}
 
-    // Extract to same directory as archive
-    let output_dir = archive_path.parent()
-        .unwrap_or_else(|| Path::new("."))
-        .join(format!("unlocked_{}", keyfile.metadata.original_file));
+/// Command to unlock files with progress tracking
+#[tauri::command]
+pub async fn unlock_item_with_progress(
+    window: WebviewWindow,
+    state: State<'_, OperationState>,
+    key_path: String,
+    _password: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, UnlockError> {
+    let op_id = operation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
-    let result = archive::extract_encrypted_archive_with_progress(
-        archive_path,
-        &archive_password,
-        &output_dir,
-        window,
-        Some(Arc::clone(&tracker)),
-    );
+    // Create progress tracker
+    let tracker = Arc::new(ProgressTracker::new().with_rate_limit(settings_rate_limit()));
+    state.register(&op_id, &tracker);
+
+    let result = unlock_key_file(&key_path, window, Some(Arc::clone(&tracker)));
 
     // Remove from active operations
-    {
-        let mut ops = state.active_operations.lock().unwrap();
-        ops.remove(&op_id);
-    }
+    state.remove(&op_id);
 
     // Check for cancellation
     if tracker.is_cancelled() {
-        return Err("Operation cancelled by user".to_string());
+        return Err("Operation cancelled by user".to_string().into());
     }
 
-    result.map_err(|e| format!("Failed to extract archive: {}", e))?;
-
-    Ok(output_dir.display().to_string())
+    result
 }
 
 /// Command to unlock files
+///
+/// Deprecated: use `unlock_item_with_progress`, which can also be cancelled.
+/// Kept for existing callers; it emits the same "unlock-progress" events.
 #[tauri::command]
 pub async fn unlock_item(
+    window: WebviewWindow,
     key_path: String,
     _password: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, UnlockError> {
+    unlock_key_file(&key_path, window, None)
+}
+
+/// Unlock a legacy .key.md + .7z pair, emitting "unlock-progress" events
+///
+/// # Returns
+/// Path to the extracted contents
+fn unlock_key_file(
+    key_path: &str,
+    window: WebviewWindow,
+    tracker: Option<Arc<ProgressTracker>>,
+) -> Result<String, UnlockError> {
     use crate::crypto;
     use crate::archive;
+    use crate::progress::{ProgressEmitter, ProgressPhase};
     use std::path::Path;
-    use std::fs;
 
-    let key_file_path = Path::new(&key_path);
+    let key_file_path = Path::new(key_path);
     if !key_file_path.exists() {
-        return Err(format!("Key file not found: {}", key_path));
+        return Err(format!("Key file not found: {}", key_path).into());
     }
 
     // 1. Read and parse key file
@@ -633,17 +890,21 @@ pub async fn unlock_item(
 
     // 2. Check if unlock time has passed
     if !keyfile.is_unlockable() {
-        let remaining = keyfile.time_until_unlock();
-        return Err(format!(
-            "Time lock still active. Unlock in {} hours, {} minutes",
-            remaining.num_hours(),
-            remaining.num_minutes() % 60
-        ));
+        return Err(UnlockError::time_lock_active(keyfile.metadata.unlocks));
     }
 
     // 3. Decrypt the AES-encrypted password
-    let archive_password = crypto::decrypt_with_tlock(&keyfile.encrypted_body, keyfile.metadata.unlocks)
-        .map_err(|e| format!("Failed to decrypt password: {}", e))?;
+    // Legacy key files always used Quicknet
+    if let Some(ref tracker) = tracker {
+        ProgressEmitter::new(window.clone(), Arc::clone(tracker), "unlock-progress")
+            .emit_progress_forced(None, ProgressPhase::FetchingBeacon);
+    }
+    let archive_password = crypto::decrypt_with_tlock(
+        &keyfile.encrypted_body,
+        keyfile.metadata.unlocks,
+        &crypto::BeaconConfig::quicknet(),
+    )
+        .map_err(|e| UnlockError::key_decryption(e, keyfile.metadata.unlocks))?;
 
     // 4. Extract the 7z archive with the password
     let archive_path_str = keyfile.metadata.archive_path
@@ -651,16 +912,16 @@ pub async fn unlock_item(
 
     let archive_path = Path::new(&archive_path_str);
     if !archive_path.exists() {
-        return Err(format!("Archive file not found: {}", archive_path_str));
+        return Err(format!("Archive file not found: {}", archive_path_str).into());
     }
 
     // Extract to same directory as archive
-    let output_dir = archive_path.parent()
-        .unwrap_or_else(|| Path::new("."))
-        .join(format!("unlocked_{}", keyfile.metadata.original_file));
+    let output_dir = default_unlock_dir(archive_path, &keyfile.metadata.original_file)?;
 
-    archive::extract_encrypted_archive(archive_path, &archive_password, &output_dir)
-        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+    // Staged beside the output, so a failed unlock never looks like a finished one
+    archive::extract_atomically(&output_dir, |dir| {
+        archive::extract_encrypted_archive_with_progress(archive_path, &archive_password, dir, window, tracker)
+    }).map_err(|e| format!("Failed to extract archive: {}", e))?;
 
     Ok(output_dir.display().to_string())
 }
@@ -680,7 +941,7 @@ pub async fn get_locked_items() -> Result<Vec<LockedItem>, String> {
     let mut seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
 
     // Scan for new .7z.tlock files first
-    if let Ok(tlock_archives) = scan_tlock_files(&default_vault) {
+    if let Ok(tlock_archives) = scan_tlock_files(&default_vault, false) {
         for archive in tlock_archives {
             let path_str = archive.path.display().to_string();
             if !seen_paths.contains(&path_str) {
@@ -723,7 +984,7 @@ pub async fn scan_for_keys(directory: Option<String>) -> Result<Vec<LockedItem>,
     let mut seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
 
     // Scan for new .7z.tlock files first
-    if let Ok(tlock_archives) = scan_tlock_files(&scan_dir) {
+    if let Ok(tlock_archives) = scan_tlock_files(&scan_dir, true) {
         for archive in tlock_archives {
             let path_str = archive.path.display().to_string();
             if !seen_paths.contains(&path_str) {
@@ -755,10 +1016,110 @@ pub fn validate_unlock_time(unlock_time_str: String) -> Result<bool, String> {
     let unlock_time = chrono::DateTime::parse_from_rfc3339(&unlock_time_str)
         .map_err(|e| format!("Invalid time format: {}", e))?;
 
-    let now = Utc::now();
+    let now = clock::now();
     Ok(unlock_time.timestamp() <= now.timestamp())
 }
 
+/// A drand relay and, when checked, whether it answered
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrandEndpointStatus {
+    #[serde(flatten)]
+    pub endpoint: crate::crypto::DrandEndpoint,
+    /// None when reachability wasn't checked
+    pub reachable: Option<bool>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// List the drand relays signatures are fetched from, in the order they're tried
+///
+/// With `check`, every relay is also contacted (in parallel) so the frontend
+/// can show configuration and reachability side by side.
+#[tauri::command]
+pub async fn get_drand_endpoints(check: Option<bool>) -> Result<Vec<DrandEndpointStatus>, String> {
+    use crate::crypto::{check_drand_endpoint, drand_endpoints};
+    use crate::error::TimeLockerError;
+
+    let endpoints = drand_endpoints()
+        .map_err(|e| format!("Failed to load drand endpoints: {}", e))?;
+
+    if !check.unwrap_or(false) {
+        return Ok(endpoints
+            .into_iter()
+            .map(|endpoint| DrandEndpointStatus {
+                endpoint,
+                reachable: None,
+                latency_ms: None,
+                error: None,
+            })
+            .collect());
+    }
+
+    let statuses = std::thread::scope(|scope| {
+        let checks: Vec<_> = endpoints
+            .iter()
+            .map(|endpoint| scope.spawn(move || check_drand_endpoint(endpoint)))
+            .collect();
+
+        endpoints
+            .iter()
+            .zip(checks)
+            .map(|(endpoint, check)| {
+                let result = check
+                    .join()
+                    .unwrap_or_else(|_| Err(TimeLockerError::Network("Check panicked".to_string())));
+                DrandEndpointStatus {
+                    endpoint: endpoint.clone(),
+                    reachable: Some(result.is_ok()),
+                    latency_ms: result.as_ref().ok().copied(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let reachable = statuses.iter().filter(|s| s.reachable == Some(true)).count();
+    eprintln!("[get_drand_endpoints] {}/{} endpoints reachable", reachable, statuses.len());
+    Ok(statuses)
+}
+
+/// Probe every drand relay for its latest round
+///
+/// Confirms, before locking something important, that the relays answer,
+/// serve the expected chain and agree with the local clock.
+#[tauri::command]
+pub async fn check_drand_health() -> Result<Vec<crate::crypto::DrandHealth>, String> {
+    let health = crate::crypto::check_drand_health()
+        .map_err(|e| format!("Failed to check drand health: {}", e))?;
+
+    let healthy = health.iter().filter(|h| h.healthy).count();
+    eprintln!("[check_drand_health] {}/{} endpoints healthy", healthy, health.len());
+    Ok(health)
+}
+
+/// Time fetching the latest round from every drand relay, fastest first
+///
+/// # Arguments
+/// * `samples` - Fetches per relay (defaults to 3); the median is reported
+/// * `reorder` - Try the relays in the measured order for the rest of the session
+#[tauri::command]
+pub async fn benchmark_endpoints(
+    samples: Option<u32>,
+    reorder: Option<bool>,
+) -> Result<Vec<crate::crypto::EndpointLatency>, String> {
+    let report = crate::crypto::benchmark_drand_endpoints(samples.unwrap_or(3))
+        .map_err(|e| format!("Failed to benchmark drand endpoints: {}", e))?;
+
+    if reorder.unwrap_or(false) {
+        crate::crypto::prefer_fastest_endpoints(&report);
+        eprintln!("[benchmark_endpoints] Relays reordered by measured latency");
+    }
+    if let Some(fastest) = report.first().filter(|r| r.latency_ms.is_some()) {
+        eprintln!("[benchmark_endpoints] Fastest: {} ({:?} ms)", fastest.endpoint.url, fastest.latency_ms);
+    }
+    Ok(report)
+}
+
 /// Get the executable directory
 fn get_exe_dir() -> Result<PathBuf, String> {
     std::env::current_exe()
@@ -793,6 +1154,22 @@ fn ensure_default_vault_exists() -> Result<PathBuf, String> {
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct AppSettings {
     pub vaults: Vec<String>,
+    /// Delete the .7z.tlock file after a verified successful unlock
+    #[serde(default)]
+    pub delete_after_unlock: bool,
+    /// Cap archive reads/writes at this many MB/s while locking and unlocking (unset = unlimited)
+    #[serde(default)]
+    pub throttle_mb_per_sec: Option<f64>,
+    /// drand relays to fetch Quicknet rounds from instead of the public ones (empty = built-in)
+    #[serde(default)]
+    pub drand_endpoints: Vec<String>,
+    /// Compress already-compressed files (JPEG, MP4, zip...) too, instead of only encrypting them
+    #[serde(default)]
+    pub uniform_compression: bool,
+    /// Name of the directory a file is unlocked into, with `{name}`, `{date}` and
+    /// `{timestamp}` placeholders (unset = `unlocked_{name}`)
+    #[serde(default)]
+    pub output_name_template: Option<String>,
 }
 
 /// Complete application state returned to frontend
@@ -827,6 +1204,13 @@ pub async fn get_settings() -> Result<AppSettings, String> {
 pub async fn save_settings(settings: AppSettings) -> Result<(), String> {
     let settings_path = get_settings_path()?;
 
+    crate::crypto::set_drand_endpoints(&settings.drand_endpoints)
+        .map_err(|e| format!("Invalid drand endpoints: {}", e))?;
+    if let Some(template) = &settings.output_name_template {
+        crate::tlock_format::render_output_name(template, "name", chrono::Local::now())
+            .map_err(|e| format!("Invalid output name template: {}", e))?;
+    }
+
     let content = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
 
@@ -847,64 +1231,7 @@ pub async fn get_app_state() -> Result<AppState, String> {
     // Load settings
     let settings = get_settings_internal()?;
 
-    let mut all_items: Vec<LockedItem> = Vec::new();
-    let mut seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-    // Helper closure to scan a directory for both formats
-    let scan_directory = |dir: &PathBuf, items: &mut Vec<LockedItem>, seen: &mut std::collections::HashSet<String>| {
-        if !dir.exists() {
-            return;
-        }
-
-        eprintln!("[get_app_state] Scanning directory: {:?}", dir);
-
-        // Scan for new .7z.tlock files first (preferred format)
-        if let Ok(tlock_archives) = scan_tlock_files(dir) {
-            for archive in tlock_archives {
-                let path_str = archive.path.display().to_string();
-                if !seen.contains(&path_str) {
-                    seen.insert(path_str.clone());
-                    items.push(tlock_archive_to_locked_item(&archive));
-                }
-            }
-        }
-
-        // Also scan for legacy .key.md files (backwards compatibility)
-        if let Ok(key_files) = crate::keyfile::scan_directory(dir) {
-            for kf in key_files {
-                if let Some(ref path) = kf.file_path {
-                    let path_str = path.display().to_string();
-                    // Skip if we already have this item (e.g., if both formats exist)
-                    if !seen.contains(&path_str) {
-                        // Also check if there's a .7z.tlock version of this file
-                        let tlock_version = path.with_extension("7z.tlock");
-                        let tlock_str = tlock_version.display().to_string();
-                        if !seen.contains(&tlock_str) {
-                            seen.insert(path_str.clone());
-                            items.push(keyfile_to_locked_item(&kf));
-                        }
-                    }
-                }
-            }
-        }
-    };
-
-    // Scan default vault directory ({exe_dir}/vaults/) if it exists
-    if let Ok(default_vault) = get_default_vault_path() {
-        scan_directory(&default_vault, &mut all_items, &mut seen_paths);
-    }
-
-    // Scan each user-added vault directory
-    for vault in &settings.vaults {
-        let vault_path = PathBuf::from(vault);
-        // Skip if this is the default vault (already scanned)
-        if let Ok(default_vault) = get_default_vault_path() {
-            if vault_path == default_vault {
-                continue;
-            }
-        }
-        scan_directory(&vault_path, &mut all_items, &mut seen_paths);
-    }
+    let all_items = scan_configured_vaults(&settings);
 
     eprintln!("[get_app_state] Total items found: {}", all_items.len());
 
@@ -914,47 +1241,536 @@ pub async fn get_app_state() -> Result<AppState, String> {
     })
 }
 
-/// Internal helper to get settings without async
-fn get_settings_internal() -> Result<AppSettings, String> {
-    let settings_path = get_settings_path()?;
+/// Scan the default vault and every vault in `settings`, returning their items sorted
+fn scan_configured_vaults(settings: &AppSettings) -> Vec<LockedItem> {
+    // Default vault directory ({exe_dir}/vaults/) first, then each user-added vault
+    let default_vault = get_default_vault_path().ok();
+    let mut vault_dirs: Vec<PathBuf> = default_vault.iter().cloned().collect();
+    vault_dirs.extend(
+        settings
+            .vaults
+            .iter()
+            .map(PathBuf::from)
+            .filter(|vault_path| Some(vault_path) != default_vault.as_ref()),
+    );
 
-    if !settings_path.exists() {
-        return Ok(AppSettings::default());
+    // Vaults can be slow network mounts, so scan them all at once
+    let scans: Vec<VaultScan> = std::thread::scope(|scope| {
+        let handles: Vec<_> = vault_dirs
+            .iter()
+            .map(|dir| scope.spawn(move || read_vault_dir(dir)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    });
+
+    // Deduplicate after joining, in vault order, so results don't depend on thread timing
+    let mut all_items: Vec<LockedItem> = Vec::new();
+    let mut seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for scan in scans {
+        merge_vault_scan(scan, &mut all_items, &mut seen_paths);
     }
 
-    let content = fs::read_to_string(&settings_path)
-        .map_err(|e| format!("Failed to read settings file: {}", e))?;
-
-    let settings: AppSettings = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse settings: {}", e))?;
+    sort_locked_items(&mut all_items);
+    all_items
+}
 
-    Ok(settings)
+/// Locked items found in one vault directory, before deduplication
+#[derive(Default)]
+struct VaultScan {
+    /// .7z.tlock items, keyed by path
+    tlock_items: Vec<(String, LockedItem)>,
+    /// Legacy .key.md items, keyed by path, with the path their .7z.tlock version would have
+    key_items: Vec<(String, String, LockedItem)>,
+}
+
+/// Scan one directory for locked items in both formats
+fn read_vault_dir(dir: &std::path::Path) -> VaultScan {
+    let mut scan = VaultScan::default();
+    if !dir.exists() {
+        return scan;
+    }
+
+    eprintln!("[scan_vault_dir] Scanning directory: {:?}", dir);
+
+    // Scan for new .7z.tlock files first (preferred format)
+    if let Ok(tlock_archives) = scan_tlock_files(dir, false) {
+        for archive in tlock_archives {
+            let path_str = archive.path.display().to_string();
+            scan.tlock_items.push((path_str, tlock_archive_to_locked_item(&archive)));
+        }
+    }
+
+    // Also scan for legacy .key.md files (backwards compatibility)
+    if let Ok(key_files) = crate::keyfile::scan_directory(dir) {
+        for kf in key_files {
+            if let Some(ref path) = kf.file_path {
+                let path_str = path.display().to_string();
+                let tlock_str = path.with_extension("7z.tlock").display().to_string();
+                scan.key_items.push((path_str, tlock_str, keyfile_to_locked_item(&kf)));
+            }
+        }
+    }
+
+    scan
+}
+
+/// Add a vault's items to `items`, skipping paths in `seen`
+fn merge_vault_scan(
+    scan: VaultScan,
+    items: &mut Vec<LockedItem>,
+    seen: &mut std::collections::HashSet<String>,
+) {
+    for (path_str, item) in scan.tlock_items {
+        if seen.insert(path_str) {
+            items.push(item);
+        }
+    }
+
+    for (path_str, tlock_str, item) in scan.key_items {
+        // Skip if we already have this item, or its .7z.tlock version (e.g., if both formats exist)
+        if !seen.contains(&path_str) && !seen.contains(&tlock_str) {
+            seen.insert(path_str);
+            items.push(item);
+        }
+    }
+}
+
+/// Scan one directory for locked items in both formats, skipping paths in `seen`
+fn scan_vault_dir(
+    dir: &std::path::Path,
+    items: &mut Vec<LockedItem>,
+    seen: &mut std::collections::HashSet<String>,
+) {
+    merge_vault_scan(read_vault_dir(dir), items, seen);
+}
+
+/// Scan a single vault directory and return only its items
+///
+/// Lets the frontend refresh just the vault it changed instead of calling
+/// `get_app_state`, which rescans every configured vault.
+#[tauri::command]
+pub async fn scan_single_vault(path: String) -> Result<Vec<LockedItem>, String> {
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(format!("Vault directory not found: {}", path));
+    }
+
+    let mut items = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    scan_vault_dir(&dir, &mut items, &mut seen);
+    sort_locked_items(&mut items);
+
+    eprintln!("[scan_single_vault] Found {} items in {:?}", items.len(), dir);
+    Ok(items)
+}
+
+/// Find the vaults in a directory tree, optionally adding them to the configured vaults
+///
+/// A vault is a directory with a `.timelocker-vault` marker or .7z.tlock (or
+/// legacy .key.md) files of its own. Folders below a marked vault belong to it,
+/// excluding markers hide their subtree, and `unlocked_*` output is skipped.
+///
+/// # Arguments
+/// * `root` - Directory to search from
+/// * `max_depth` - Levels below `root` to search (default 4)
+/// * `register` - Add the vaults found to the settings (default false)
+///
+/// # Returns
+/// The vaults found, whether or not they were configured already
+#[tauri::command]
+pub async fn discover_vaults(
+    root: String,
+    max_depth: Option<u32>,
+    register: Option<bool>,
+) -> Result<Vec<String>, String> {
+    use crate::tlock_format::DEFAULT_DISCOVERY_DEPTH;
+
+    let max_depth = max_depth.map_or(DEFAULT_DISCOVERY_DEPTH, |depth| depth as usize);
+    let vaults = crate::tlock_format::discover_vaults(std::path::Path::new(&root), max_depth)
+        .map_err(|e| format!("Failed to discover vaults: {}", e))?;
+
+    if register.unwrap_or(false) {
+        let mut settings = get_settings_internal()?;
+        let default_vault = get_default_vault_path().ok();
+        let mut added = 0;
+        for vault in &vaults {
+            let known = Some(vault) == default_vault.as_ref()
+                || settings.vaults.iter().any(|v| std::path::Path::new(v) == vault);
+            if !known {
+                settings.vaults.push(vault.display().to_string());
+                added += 1;
+            }
+        }
+        if added > 0 {
+            save_settings(settings).await?;
+        }
+        eprintln!("[discover_vaults] Registered {} new vaults", added);
+    }
+
+    Ok(vaults.iter().map(|v| v.display().to_string()).collect())
+}
+
+/// A path `delete_locked_item` could not remove
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletionFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Result of deleting a locked item
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteItemResult {
+    /// Paths that were removed
+    pub removed: Vec<String>,
+    /// Paths that were left in place, with why
+    pub errors: Vec<DeletionFailure>,
+}
+
+/// Permanently delete a locked item, optionally with its unlocked directory
+///
+/// Removes the .7z.tlock file, or for a legacy item (pass its .key.md path)
+/// the .key.md file and the .7z archive it points to. Each path is attempted
+/// even if an earlier one fails. Only items inside the default vault or a
+/// configured vault can be deleted, and an unlocked directory only if it
+/// carries the marker a staged unlock leaves in the directories it creates.
+///
+/// # Arguments
+/// * `tlock_path` - The item's .7z.tlock file, or .key.md file for legacy items
+/// * `also_delete_unlocked` - Also remove its `unlocked_*` directory (default false)
+#[tauri::command]
+pub async fn delete_locked_item(
+    tlock_path: String,
+    also_delete_unlocked: Option<bool>,
+) -> Result<DeleteItemResult, String> {
+    use std::path::Path;
+
+    let item_path = Path::new(&tlock_path);
+    if !item_path.is_file() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    // Never delete outside the vaults the app manages
+    let vaults: Vec<PathBuf> = vault_dirs(None)?
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect();
+    let in_vault = |path: &Path| {
+        path.canonicalize()
+            .is_ok_and(|path| vaults.iter().any(|vault| path.starts_with(vault) && path != *vault))
+    };
+    if !in_vault(item_path) {
+        return Err(format!("Refusing to delete outside the configured vaults: {}", tlock_path));
+    }
+
+    // The files making up the item, and the name its unlocked directory is based on
+    let file_name = item_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    let (mut targets, original_file) = if file_name.ends_with(".7z.tlock") {
+        // Unreadable files are listed under their file stem, as in `tlock_archive_to_locked_item`
+        let original_file = TlockArchive::read_metadata(item_path)
+            .ok()
+            .and_then(|archive| archive.metadata)
+            .map(|meta| meta.original_file)
+            .or_else(|| item_path.file_stem().and_then(|s| s.to_str()).map(str::to_string));
+        (vec![item_path.to_path_buf()], original_file)
+    } else if file_name.ends_with(".key.md") || file_name.ends_with("-key.md") {
+        let keyfile = fs::read_to_string(item_path)
+            .map_err(|e| format!("Failed to read key file: {}", e))
+            .and_then(|content| KeyFile::parse(&content).map_err(|e| format!("Failed to parse key file: {}", e)))?;
+        let mut targets = vec![item_path.to_path_buf()];
+        if let Some(ref archive_path) = keyfile.metadata.archive_path {
+            // Relative archive paths are relative to the key file
+            let archive_path = item_path.parent().unwrap_or(Path::new(".")).join(archive_path);
+            if archive_path.exists() {
+                targets.push(archive_path);
+            }
+        }
+        (targets, Some(keyfile.metadata.original_file))
+    } else {
+        return Err(format!("Not a locked item (expected .7z.tlock or .key.md): {}", tlock_path));
+    };
+
+    let mut result = DeleteItemResult { removed: Vec::new(), errors: Vec::new() };
+
+    // The unlocked directory is found by name, which could match a folder this
+    // app didn't create: only remove one an unlock marked as its own
+    if also_delete_unlocked.unwrap_or(false) {
+        if let Some(unlocked) = original_file.and_then(|name| find_unlocked_path(item_path, &name)) {
+            let unlocked = PathBuf::from(unlocked);
+            if crate::archive::is_unlock_output(&unlocked) {
+                targets.push(unlocked);
+            } else {
+                eprintln!("[delete_locked_item] Not deleting unmarked directory: {:?}", unlocked);
+                result.errors.push(DeletionFailure {
+                    path: unlocked.display().to_string(),
+                    error: "not created by an unlock (no unlock marker), delete it manually".to_string(),
+                });
+            }
+        }
+    }
+
+    for target in targets {
+        let path_str = target.display().to_string();
+        let removal = if !in_vault(&target) {
+            Err("outside the configured vaults".to_string())
+        } else if target.is_dir() {
+            fs::remove_dir_all(&target).map_err(|e| e.to_string())
+        } else {
+            fs::remove_file(&target).map_err(|e| e.to_string())
+        };
+        match removal {
+            Ok(()) => {
+                eprintln!("[delete_locked_item] Deleted: {:?}", target);
+                result.removed.push(path_str);
+            }
+            Err(error) => {
+                eprintln!("[delete_locked_item] Failed to delete {:?}: {}", target, error);
+                result.errors.push(DeletionFailure { path: path_str, error });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// What two locked items must share to count as copies of each other
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DuplicateKey {
+    /// Identical payload (format version 2+ files store its SHA-256)
+    Checksum(String),
+    /// Same source, locked at the same moment for the same time
+    Metadata {
+        original_file: String,
+        created: String,
+        unlocks: String,
+    },
+}
+
+/// A set of locked items that are likely copies of the same locked file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// How the copies were matched: "checksum" or "metadata"
+    pub matched_by: String,
+    pub original_file: Option<String>,
+    /// Paths of every copy, in scan order
+    pub paths: Vec<String>,
+    /// Bytes freed by deleting every copy but the first (for legacy items,
+    /// only their .key.md files are counted)
+    pub reclaimable_bytes: u64,
+}
+
+/// Work out what identifies `item` as a copy, or `None` if nothing reliable does
+///
+/// Payload checksums are preferred; legacy files and version 1 files fall back
+/// to their metadata. Files whose metadata can't be read are left out, since
+/// the scan fills their dates in with the current time.
+fn duplicate_key(item: &LockedItem) -> Option<DuplicateKey> {
+    let metadata_key = |original_file: &str, created: String, unlocks: String| DuplicateKey::Metadata {
+        original_file: original_file.to_string(),
+        created,
+        unlocks,
+    };
+
+    let Some(tlock_path) = &item.tlock_path else {
+        return Some(metadata_key(
+            item.original_file.as_deref()?,
+            item.created_at.clone(),
+            item.unlocks_at.clone(),
+        ));
+    };
+
+    let path = std::path::Path::new(tlock_path);
+    if let Some(checksum) = TlockArchive::open_payload(path).ok()?.checksum() {
+        return Some(DuplicateKey::Checksum(hex::encode(checksum)));
+    }
+    let metadata = TlockArchive::read_metadata(path).ok()?.metadata?;
+    Some(metadata_key(
+        &metadata.original_file,
+        metadata.created.to_rfc3339(),
+        metadata.unlocks.to_rfc3339(),
+    ))
+}
+
+/// Group keyed items into clusters of two or more, in order of first appearance
+fn group_duplicates(keyed: Vec<(DuplicateKey, LockedItem)>) -> Vec<DuplicateGroup> {
+    let mut order: Vec<DuplicateKey> = Vec::new();
+    let mut groups: HashMap<DuplicateKey, Vec<LockedItem>> = HashMap::new();
+    for (key, item) in keyed {
+        let members = groups.entry(key.clone()).or_default();
+        if members.is_empty() {
+            order.push(key);
+        }
+        members.push(item);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| {
+            let members = groups.remove(&key)?;
+            if members.len() < 2 {
+                return None;
+            }
+            let paths: Vec<String> = members
+                .iter()
+                .map(|item| item.tlock_path.clone().unwrap_or_else(|| item.key_path.clone()))
+                .collect();
+            let reclaimable_bytes = paths
+                .iter()
+                .skip(1)
+                .filter_map(|path| fs::metadata(path).ok())
+                .map(|m| m.len())
+                .sum();
+            Some(DuplicateGroup {
+                matched_by: match key {
+                    DuplicateKey::Checksum(_) => "checksum",
+                    DuplicateKey::Metadata { .. } => "metadata",
+                }
+                .to_string(),
+                original_file: members[0].original_file.clone(),
+                paths,
+                reclaimable_bytes,
+            })
+        })
+        .collect()
+}
+
+/// Find locked items that appear more than once across the configured vaults
+///
+/// Read-only: copies are reported, never removed. Items are matched by payload
+/// checksum, or by original file name, creation and unlock time when a file
+/// has no checksum.
+#[tauri::command]
+pub async fn find_duplicates() -> Result<Vec<DuplicateGroup>, String> {
+    let settings = get_settings_internal()?;
+    let keyed = scan_configured_vaults(&settings)
+        .into_iter()
+        .filter_map(|item| duplicate_key(&item).map(|key| (key, item)))
+        .collect();
+
+    let groups = group_duplicates(keyed);
+    eprintln!("[find_duplicates] Found {} groups of duplicates", groups.len());
+    Ok(groups)
+}
+
+/// Internal helper to get settings without async
+fn get_settings_internal() -> Result<AppSettings, String> {
+    let settings_path = get_settings_path()?;
+
+    if !settings_path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let content = fs::read_to_string(&settings_path)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+
+    let settings: AppSettings = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Use the relays from the `drand_endpoints` setting; called once at startup
+pub fn apply_drand_endpoints_setting() {
+    let endpoints = get_settings_internal()
+        .map(|s| s.drand_endpoints)
+        .unwrap_or_default();
+    if let Err(e) = crate::crypto::set_drand_endpoints(&endpoints) {
+        eprintln!("[apply_drand_endpoints_setting] Ignoring drand_endpoints setting: {}", e);
+    }
+}
+
+/// Throughput cap from the `throttle_mb_per_sec` setting, in bytes per second
+fn settings_rate_limit() -> Option<u64> {
+    get_settings_internal()
+        .ok()
+        .and_then(|s| s.throttle_mb_per_sec)
+        .and_then(crate::progress::rate_limit_from_mb_per_sec)
+}
+
+/// The `uniform_compression` setting (off if the settings can't be read)
+fn settings_uniform_compression() -> bool {
+    get_settings_internal().map(|s| s.uniform_compression).unwrap_or(false)
+}
+
+/// The `output_name_template` setting (the default if unset or the settings can't be read)
+fn settings_output_name_template() -> String {
+    get_settings_internal()
+        .ok()
+        .and_then(|s| s.output_name_template)
+        .unwrap_or_else(|| crate::tlock_format::DEFAULT_OUTPUT_NAME_TEMPLATE.to_string())
+}
+
+/// Directory next to `locked_file` to unlock `original_file` into, named by the template setting
+fn default_unlock_dir(locked_file: &std::path::Path, original_file: &str) -> Result<PathBuf, String> {
+    let now = clock::now().with_timezone(&chrono::Local);
+    let name = crate::tlock_format::render_output_name(&settings_output_name_template(), original_file, now)
+        .map_err(|e| format!("Failed to name the output directory: {}", e))?;
+    Ok(locked_file
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."))
+        .join(name))
 }
 
 /// Generate a deterministic ID from a file path
 fn generate_id_from_path(path: &str) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::new();
-    path.hash(&mut hasher);
-    format!("{:016x}", hasher.finish())
+    // SHA-256 rather than DefaultHasher, whose output may change between Rust releases
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(path.as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// Order items by creation time, then path, so the list is stable across scans
+fn sort_locked_items(items: &mut [LockedItem]) {
+    items.sort_by_cached_key(|item| {
+        let created = chrono::DateTime::parse_from_rfc3339(&item.created_at).ok();
+        let path = item.tlock_path.clone().unwrap_or_else(|| item.key_path.clone());
+        (created, path)
+    });
 }
 
 /// Check if an unlocked directory exists for a given vault file
+///
+/// Only the final directory counts: an unlock still in progress (or one that
+/// was interrupted) lives in a `.unlocked_<name>.partial` staging directory.
+/// Directories named by the configured output-name template or the default
+/// one are recognized; with `{date}` or `{timestamp}` in the template the
+/// newest match wins.
 fn find_unlocked_path(vault_path: &std::path::Path, original_file: &str) -> Option<String> {
-    let parent = vault_path.parent()?;
-    let unlocked_dir = parent.join(format!("unlocked_{}", original_file));
+    use crate::tlock_format::{output_name_is_fixed, output_name_matches, render_output_name};
 
-    if unlocked_dir.exists() {
-        Some(unlocked_dir.display().to_string())
-    } else {
-        None
+    let parent = vault_path.parent()?;
+    let configured = settings_output_name_template();
+    let templates = [configured.as_str(), crate::tlock_format::DEFAULT_OUTPUT_NAME_TEMPLATE];
+
+    // Templates without a date or time name one directory: no need to list the vault
+    if templates.iter().all(|template| output_name_is_fixed(template)) {
+        return templates
+            .iter()
+            .filter_map(|template| render_output_name(template, original_file, chrono::Local::now()).ok())
+            .map(|name| parent.join(name))
+            .find(|dir| dir.is_dir())
+            .map(|dir| dir.display().to_string());
     }
+
+    fs::read_dir(parent)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            templates
+                .iter()
+                .any(|template| output_name_matches(template, original_file, &name))
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path().display().to_string())
 }
 
 /// Convert KeyFile to LockedItem for frontend (legacy format)
 fn keyfile_to_locked_item(kf: &KeyFile) -> LockedItem {
-    let now = Utc::now();
+    let now = clock::now();
     let is_unlockable = kf.metadata.unlocks <= now;
     let key_path = kf.file_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
 
@@ -971,17 +1787,21 @@ fn keyfile_to_locked_item(kf: &KeyFile) -> LockedItem {
         created_at: kf.metadata.created.to_rfc3339(),
         unlocks_at: kf.metadata.unlocks.to_rfc3339(),
         is_unlockable,
+        decryptable_at: None,
+        drand_round: None,
         original_file: Some(kf.metadata.original_file.clone()),
         is_legacy_format: true, // This is the legacy format
         original_deleted: false,
         deletion_error: None,
         unlocked_path,
+        password_entropy_bits: None,
+        password_warning: None,
     }
 }
 
 /// Convert TlockArchive to LockedItem for frontend (new unified format)
 fn tlock_archive_to_locked_item(archive: &TlockArchive) -> LockedItem {
-    let now = Utc::now();
+    let now = clock::now();
     let tlock_path = archive.path.display().to_string();
 
     // Get metadata if available
@@ -1011,6 +1831,9 @@ fn tlock_archive_to_locked_item(archive: &TlockArchive) -> LockedItem {
     // Check if unlocked directory exists
     let unlocked_path = find_unlocked_path(&archive.path, &original_file_name);
 
+    let drand_round = archive.get_metadata().and_then(|meta| meta.unlock_round());
+    let decryptable_at = archive.get_metadata().and_then(|meta| meta.decryptable_at());
+
     LockedItem {
         id: generate_id_from_path(&tlock_path),
         name,
@@ -1020,11 +1843,15 @@ fn tlock_archive_to_locked_item(archive: &TlockArchive) -> LockedItem {
         created_at,
         unlocks_at,
         is_unlockable,
+        decryptable_at: decryptable_at.map(|t| t.to_rfc3339()),
+        drand_round,
         original_file: Some(original_file_name),
         is_legacy_format: false, // This is the new unified format
         original_deleted: false,
         deletion_error: None,
         unlocked_path,
+        password_entropy_bits: None,
+        password_warning: None,
     }
 }
 
@@ -1040,6 +1867,36 @@ pub struct MigrationResult {
     pub message: String,
     /// Whether old files were deleted
     pub old_files_deleted: bool,
+    /// Where the old files were kept, when they were marked migrated instead
+    #[serde(default)]
+    pub old_files_kept: Vec<String>,
+    /// Whether the key file was left alone because it already had a .7z.tlock
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+/// What happens to the legacy .key.md and .7z files after a successful migration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OldFilesAction {
+    /// Leave them in place
+    Keep,
+    /// Delete them
+    Delete,
+    /// Rename them with the `.migrated` suffix, so scans skip them but they can be recovered
+    MarkMigrated,
+}
+
+impl OldFilesAction {
+    /// Resolve the command flags; marking as migrated wins over deleting
+    fn from_flags(delete_old_files: Option<bool>, keep_as_migrated: Option<bool>) -> Self {
+        if keep_as_migrated.unwrap_or(false) {
+            Self::MarkMigrated
+        } else if delete_old_files.unwrap_or(false) {
+            Self::Delete
+        } else {
+            Self::Keep
+        }
+    }
 }
 
 /// Response structure for tlock metadata (without the encrypted key)
@@ -1053,6 +1910,38 @@ pub struct TlockMetadataResponse {
     pub is_unlockable: bool,
     pub is_directory: bool,
     pub original_size: Option<u64>,
+    pub original_path: Option<String>,
+    /// Unencrypted note stored by whoever locked the file
+    pub note: Option<String>,
+    /// Advisory "open before" time (not enforced cryptographically)
+    pub expires: Option<String>,
+    /// Whether `expires` has passed
+    pub is_expired: bool,
+    /// Whether an age recipient can unlock the file before the unlock time
+    pub has_recipient_key: bool,
+    /// Custom key/value fields set when locking
+    pub extra: std::collections::BTreeMap<String, String>,
+}
+
+impl From<&TlockMetadata> for TlockMetadataResponse {
+    fn from(metadata: &TlockMetadata) -> Self {
+        Self {
+            locked: metadata.locked,
+            created: metadata.created.to_rfc3339(),
+            unlocks: metadata.unlocks.to_rfc3339(),
+            duration: metadata.duration.clone(),
+            original_file: metadata.original_file.clone(),
+            is_unlockable: metadata.is_unlockable(),
+            is_directory: metadata.is_directory,
+            original_size: metadata.original_size,
+            original_path: metadata.original_path.clone(),
+            note: metadata.note.clone(),
+            expires: metadata.expires.map(|expires| expires.to_rfc3339()),
+            is_expired: metadata.is_expired(),
+            has_recipient_key: metadata.recipient_encrypted_key.is_some(),
+            extra: metadata.extra.clone(),
+        }
+    }
 }
 
 /// Migrate from old format (.key.md + .7z) to new unified .7z.tlock format
@@ -1060,6 +1949,8 @@ pub struct TlockMetadataResponse {
 /// # Arguments
 /// * `key_md_path` - Path to the .key.md file
 /// * `delete_old_files` - Whether to delete the old .key.md and .7z files after migration
+/// * `keep_as_migrated` - Rename the old files to `*.migrated` instead of deleting them
+///   (purge them later with `cleanup_migrated`)
 ///
 /// # Returns
 /// MigrationResult with success status and the path to the new .7z.tlock file
@@ -1067,13 +1958,24 @@ pub struct TlockMetadataResponse {
 pub async fn migrate_to_tlock(
     key_md_path: String,
     delete_old_files: Option<bool>,
+    keep_as_migrated: Option<bool>,
 ) -> Result<MigrationResult, String> {
+    migrate_key_file(
+        std::path::Path::new(&key_md_path),
+        OldFilesAction::from_flags(delete_old_files, keep_as_migrated),
+    )
+}
+
+/// Migrate a single legacy key file, validating the resulting .7z.tlock
+///
+/// The 7z payload is streamed into the new file, so large archives are never
+/// held in memory.
+fn migrate_key_file(key_path: &std::path::Path, old_files: OldFilesAction) -> Result<MigrationResult, String> {
     use crate::tlock_format::{TlockArchive, TlockMetadata, TLOCK_MAGIC};
-    use std::io::{Read, Write};
+    use std::io::{BufReader, BufWriter, Write};
     use std::path::Path;
 
-    let delete_old = delete_old_files.unwrap_or(false);
-    let key_path = Path::new(&key_md_path);
+    let key_md_path = key_path.display().to_string();
 
     eprintln!("[migrate_to_tlock] Starting migration for: {}", key_md_path);
 
@@ -1129,13 +2031,19 @@ pub async fn migrate_to_tlock(
         return Err(format!("Archive file does not have .7z extension: {}", archive_path.display()));
     }
 
-    // 4. Check if already migrated (a .7z.tlock already exists with same base name)
+    // 4. Check if already migrated (an intact .7z.tlock already exists with same base name)
     let tlock_path = archive_path.with_extension("7z.tlock");
     if tlock_path.exists() {
-        return Err(format!(
-            "A .7z.tlock file already exists: {}. Migration may have already been performed.",
-            tlock_path.display()
-        ));
+        if TlockArchive::verify_integrity(&tlock_path).is_ok() {
+            return Err(format!(
+                "A .7z.tlock file already exists: {}. Migration may have already been performed.",
+                tlock_path.display()
+            ));
+        }
+        // Left behind by an interrupted migration; the .7z it came from is still here
+        eprintln!("[migrate_to_tlock] Replacing incomplete .7z.tlock file: {:?}", tlock_path);
+        fs::remove_file(&tlock_path)
+            .map_err(|e| format!("Failed to remove incomplete .7z.tlock file: {}", e))?;
     }
 
     // 5. Create TlockMetadata from KeyFile
@@ -1148,7 +2056,18 @@ pub async fn migrate_to_tlock(
         drand_round: None, // Legacy files don't have drand round
         encrypted_key: Some(keyfile.encrypted_body.clone()),
         original_size: None,
-        is_directory: false,
+        is_directory: keyfile.archive_is_directory(&archive_path),
+        original_path: None,
+        sealed_entry_index: None,
+        beacon_chain_hash: None, // Legacy files were locked against Quicknet
+        beacon_parameters: None,
+        note: None,
+        original_files: None,
+        expires: None,
+        recipient_encrypted_key: None,
+        compression_filter: None,
+        extra: Default::default(),
+        crypto_parameters: None, // Unknown for archives written by the legacy format
     };
 
     // 6. Serialize metadata to JSON
@@ -1159,51 +2078,58 @@ pub async fn migrate_to_tlock(
 
     eprintln!("[migrate_to_tlock] Metadata JSON size: {} bytes", metadata_len);
 
-    // 7. Read the .7z archive payload
-    let mut archive_file = fs::File::open(&archive_path)
+    // 7. Open the .7z archive payload (streamed below, not read into memory)
+    let archive_file = fs::File::open(&archive_path)
         .map_err(|e| format!("Failed to open archive: {}", e))?;
-    let mut archive_payload = Vec::new();
-    archive_file.read_to_end(&mut archive_payload)
-        .map_err(|e| format!("Failed to read archive: {}", e))?;
-
-    eprintln!("[migrate_to_tlock] Archive payload size: {} bytes", archive_payload.len());
-
-    // 8. Create the .7z.tlock file with wrapper format
-    let mut tlock_file = fs::File::create(&tlock_path)
-        .map_err(|e| format!("Failed to create .7z.tlock file: {}", e))?;
-
-    // Write HEADER (unencrypted, fixed structure)
-    // Using the format from tlock_format module:
-    // - Magic bytes: "TLOCK01" (7 bytes)
-    // - Version: u8 (1 byte)
-    // - Metadata length: u32 LE (4 bytes)
-    // - Reserved: 12 bytes
-    tlock_file.write_all(TLOCK_MAGIC)
-        .map_err(|e| format!("Failed to write magic bytes: {}", e))?;
-
-    // Version byte
-    tlock_file.write_all(&[1u8])
-        .map_err(|e| format!("Failed to write version: {}", e))?;
-
-    // Metadata length (4 bytes, little-endian)
-    tlock_file.write_all(&metadata_len.to_le_bytes())
-        .map_err(|e| format!("Failed to write metadata length: {}", e))?;
-
-    // Reserved bytes (12 bytes)
-    let reserved = [0u8; 12];
-    tlock_file.write_all(&reserved)
-        .map_err(|e| format!("Failed to write reserved bytes: {}", e))?;
-
-    // Write METADATA (unencrypted JSON)
-    tlock_file.write_all(&metadata_json)
-        .map_err(|e| format!("Failed to write metadata: {}", e))?;
-
-    // Write PAYLOAD (encrypted 7z archive)
-    tlock_file.write_all(&archive_payload)
-        .map_err(|e| format!("Failed to write archive payload: {}", e))?;
-
-    tlock_file.flush()
-        .map_err(|e| format!("Failed to flush file: {}", e))?;
+    let mut archive_reader = BufReader::new(archive_file);
+
+    // 8. Create the .7z.tlock file with wrapper format, removing it again if any write fails
+    let mut write_tlock = || -> Result<(), String> {
+        let mut tlock_file = BufWriter::new(
+            fs::File::create(&tlock_path)
+                .map_err(|e| format!("Failed to create .7z.tlock file: {}", e))?,
+        );
+
+        // Write HEADER (unencrypted, fixed structure)
+        // Using the format from tlock_format module:
+        // - Magic bytes: "TLOCK01" (7 bytes)
+        // - Version: u8 (1 byte)
+        // - Metadata length: u32 LE (4 bytes)
+        // - Reserved: 12 bytes
+        tlock_file.write_all(TLOCK_MAGIC)
+            .map_err(|e| format!("Failed to write magic bytes: {}", e))?;
+
+        // Version byte
+        tlock_file.write_all(&[1u8])
+            .map_err(|e| format!("Failed to write version: {}", e))?;
+
+        // Metadata length (4 bytes, little-endian)
+        tlock_file.write_all(&metadata_len.to_le_bytes())
+            .map_err(|e| format!("Failed to write metadata length: {}", e))?;
+
+        // Reserved bytes (12 bytes)
+        let reserved = [0u8; 12];
+        tlock_file.write_all(&reserved)
+            .map_err(|e| format!("Failed to write reserved bytes: {}", e))?;
+
+        // Write METADATA (unencrypted JSON)
+        tlock_file.write_all(&metadata_json)
+            .map_err(|e| format!("Failed to write metadata: {}", e))?;
+
+        // Write PAYLOAD (encrypted 7z archive)
+        let payload_len = crate::tlock_format::copy_payload(&mut archive_reader, &mut tlock_file)
+            .map_err(|e| format!("Failed to write archive payload: {}", e))?;
+
+        eprintln!("[migrate_to_tlock] Archive payload size: {} bytes", payload_len);
+
+        tlock_file.flush()
+            .map_err(|e| format!("Failed to flush file: {}", e))?;
+        Ok(())
+    };
+    if let Err(e) = write_tlock() {
+        let _ = fs::remove_file(&tlock_path);
+        return Err(e);
+    }
 
     eprintln!("[migrate_to_tlock] Created .7z.tlock file at: {:?}", tlock_path);
 
@@ -1224,9 +2150,18 @@ pub async fn migrate_to_tlock(
         }
     }
 
-    // 10. Optionally delete old files
+    // 10. Optionally delete (or set aside) old files
     let mut old_files_deleted = false;
-    if delete_old {
+    let mut old_files_kept = Vec::new();
+    if old_files == OldFilesAction::MarkMigrated {
+        for old_path in [key_path, archive_path.as_path()] {
+            match crate::keyfile::mark_migrated(old_path) {
+                Ok(kept) => old_files_kept.push(kept.display().to_string()),
+                Err(e) => eprintln!("[migrate_to_tlock] Warning: Failed to mark {:?} migrated: {}", old_path, e),
+            }
+        }
+    }
+    if old_files == OldFilesAction::Delete {
         // Delete key file
         if let Err(e) = fs::remove_file(key_path) {
             eprintln!("[migrate_to_tlock] Warning: Failed to delete key file: {}", e);
@@ -1252,6 +2187,252 @@ pub async fn migrate_to_tlock(
             keyfile.metadata.original_file
         ),
         old_files_deleted,
+        old_files_kept,
+        skipped: false,
+    })
+}
+
+/// Per-file event emitted on the "migrate-progress" channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationProgressPayload {
+    /// 1-based position of the key file being processed
+    pub current: usize,
+    /// Total number of key files found
+    pub total: usize,
+    pub key_path: String,
+    /// "migrating", "migrated" or "failed"
+    pub status: String,
+    pub tlock_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A key file that could not be migrated
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrationFailure {
+    pub key_path: String,
+    pub error: String,
+}
+
+/// Summary of a bulk migration
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrationSummary {
+    pub total: usize,
+    pub migrated: Vec<MigrationResult>,
+    pub failed: Vec<MigrationFailure>,
+}
+
+/// Migrate every legacy key file in a vault (or all known vaults) to .7z.tlock
+///
+/// Emits a "migrate-progress" event before and after each file. Each new
+/// .7z.tlock is validated before the next file starts; a failed file is
+/// recorded in the summary and does not stop the batch.
+///
+/// # Arguments
+/// * `vault` - Directory to scan (defaults to the default vault plus all configured vaults)
+/// * `delete_old_files` - Whether to delete the old .key.md and .7z files after each migration
+/// * `keep_as_migrated` - Rename the old files to `*.migrated` instead of deleting them
+#[tauri::command]
+pub async fn migrate_all_with_progress(
+    window: WebviewWindow,
+    vault: Option<String>,
+    delete_old_files: Option<bool>,
+    keep_as_migrated: Option<bool>,
+) -> Result<MigrationSummary, String> {
+    use tauri::{Emitter, Manager};
+
+    let old_files = OldFilesAction::from_flags(delete_old_files, keep_as_migrated);
+    let scan_dirs = vault_dirs(vault)?;
+
+    let mut key_paths: Vec<PathBuf> = Vec::new();
+    for dir in &scan_dirs {
+        let key_files = crate::keyfile::scan_directory(dir)
+            .map_err(|e| format!("Failed to scan {}: {}", dir.display(), e))?;
+        for path in key_files.into_iter().filter_map(|kf| kf.file_path) {
+            if !key_paths.contains(&path) {
+                key_paths.push(path);
+            }
+        }
+    }
+
+    let total = key_paths.len();
+    eprintln!("[migrate_all_with_progress] Found {} key files to migrate", total);
+
+    let emit = |payload: MigrationProgressPayload| {
+        let _ = window.app_handle().emit("migrate-progress", &payload);
+    };
+
+    let mut summary = MigrationSummary {
+        total,
+        migrated: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for (i, key_path) in key_paths.iter().enumerate() {
+        let key_path_str = key_path.display().to_string();
+        emit(MigrationProgressPayload {
+            current: i + 1,
+            total,
+            key_path: key_path_str.clone(),
+            status: "migrating".to_string(),
+            tlock_path: None,
+            error: None,
+        });
+
+        match migrate_key_file(key_path, old_files) {
+            Ok(result) => {
+                emit(MigrationProgressPayload {
+                    current: i + 1,
+                    total,
+                    key_path: key_path_str,
+                    status: "migrated".to_string(),
+                    tlock_path: Some(result.tlock_path.clone()),
+                    error: None,
+                });
+                summary.migrated.push(result);
+            }
+            Err(e) => {
+                eprintln!("[migrate_all_with_progress] Failed to migrate {}: {}", key_path_str, e);
+                emit(MigrationProgressPayload {
+                    current: i + 1,
+                    total,
+                    key_path: key_path_str.clone(),
+                    status: "failed".to_string(),
+                    tlock_path: None,
+                    error: Some(e.clone()),
+                });
+                summary.failed.push(MigrationFailure {
+                    key_path: key_path_str,
+                    error: e,
+                });
+            }
+        }
+    }
+
+    eprintln!(
+        "[migrate_all_with_progress] Migrated {}/{} ({} failed)",
+        summary.migrated.len(),
+        total,
+        summary.failed.len()
+    );
+
+    Ok(summary)
+}
+
+/// Migrate every legacy key file in a directory (and below) to .7z.tlock
+///
+/// Key files that already have a .7z.tlock next to their archive are skipped.
+/// A file that fails is reported with `success: false` and does not stop the
+/// batch.
+///
+/// # Arguments
+/// * `dir` - Directory to scan
+/// * `delete_old_files` - Whether to delete the old .key.md and .7z files after each migration
+///
+/// # Returns
+/// One result per key file found
+#[tauri::command]
+pub async fn migrate_directory(
+    dir: String,
+    delete_old_files: Option<bool>,
+) -> Result<Vec<MigrationResult>, String> {
+    let scan_dir = PathBuf::from(&dir);
+    if !scan_dir.is_dir() {
+        return Err(format!("Directory not found: {}", dir));
+    }
+
+    let old_files = OldFilesAction::from_flags(delete_old_files, None);
+    let key_files = crate::keyfile::scan_directory(&scan_dir)
+        .map_err(|e| format!("Failed to scan {}: {}", dir, e))?;
+
+    let mut results = Vec::new();
+    for key_file in &key_files {
+        let Some(ref key_path) = key_file.file_path else {
+            continue;
+        };
+        let tlock_path = key_file
+            .resolved_archive_path()
+            .map(|archive_path| archive_path.with_extension("7z.tlock").display().to_string())
+            .unwrap_or_default();
+
+        let result = if key_file.has_tlock() {
+            MigrationResult {
+                success: false,
+                tlock_path,
+                message: format!("Skipped '{}': a .7z.tlock file already exists", key_file.metadata.original_file),
+                old_files_deleted: false,
+                old_files_kept: Vec::new(),
+                skipped: true,
+            }
+        } else {
+            migrate_key_file(key_path, old_files).unwrap_or_else(|e| {
+                eprintln!("[migrate_directory] Failed to migrate {:?}: {}", key_path, e);
+                MigrationResult {
+                    success: false,
+                    tlock_path,
+                    message: e,
+                    old_files_deleted: false,
+                    old_files_kept: Vec::new(),
+                    skipped: false,
+                }
+            })
+        };
+        results.push(result);
+    }
+
+    let migrated = results.iter().filter(|r| r.success).count();
+    let skipped = results.iter().filter(|r| r.skipped).count();
+    eprintln!(
+        "[migrate_directory] Migrated {}, skipped {}, failed {} of {} key files in {:?}",
+        migrated,
+        skipped,
+        results.len() - migrated - skipped,
+        results.len(),
+        scan_dir
+    );
+
+    Ok(results)
+}
+
+/// Delete the legacy files that migrations kept as `*.migrated`
+///
+/// # Arguments
+/// * `vault` - Directory to clean (defaults to the default vault plus all configured vaults)
+///
+/// # Returns
+/// Paths of the deleted files
+#[tauri::command]
+pub async fn cleanup_migrated(vault: Option<String>) -> Result<Vec<String>, String> {
+    let mut deleted = Vec::new();
+    for dir in vault_dirs(vault)? {
+        if !dir.is_dir() {
+            continue;
+        }
+        let removed = crate::keyfile::cleanup_migrated(&dir)
+            .map_err(|e| format!("Failed to clean up {}: {}", dir.display(), e))?;
+        deleted.extend(removed.into_iter().map(|p| p.display().to_string()));
+    }
+
+    eprintln!("[cleanup_migrated] Deleted {} migrated files", deleted.len());
+    Ok(deleted)
+}
+
+/// The given vault, or the default vault plus all configured vaults
+fn vault_dirs(vault: Option<String>) -> Result<Vec<PathBuf>, String> {
+    Ok(match vault {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => {
+            let mut dirs = Vec::new();
+            if let Ok(default_vault) = get_default_vault_path() {
+                dirs.push(default_vault);
+            }
+            for vault in get_settings_internal()?.vaults {
+                let vault_path = PathBuf::from(vault);
+                if !dirs.contains(&vault_path) {
+                    dirs.push(vault_path);
+                }
+            }
+            dirs
+        }
     })
 }
 
@@ -1283,16 +2464,361 @@ pub async fn read_tlock_metadata(tlock_path: String) -> Result<TlockMetadataResp
     let metadata = archive.get_metadata()
         .ok_or_else(|| "Metadata not found in archive".to_string())?;
 
-    Ok(TlockMetadataResponse {
-        locked: metadata.locked,
-        created: metadata.created.to_rfc3339(),
-        unlocks: metadata.unlocks.to_rfc3339(),
-        duration: metadata.duration.clone(),
-        original_file: metadata.original_file.clone(),
-        is_unlockable: metadata.is_unlockable(),
-        is_directory: metadata.is_directory,
-        original_size: metadata.original_size,
+    Ok(TlockMetadataResponse::from(metadata))
+}
+
+/// List the entries inside an unlockable .7z.tlock file without extracting it
+///
+/// Uses the sealed entry index when the file has one, otherwise reads the 7z header.
+#[tauri::command]
+pub async fn list_tlock_entries(tlock_path: String) -> Result<Vec<crate::archive::ArchiveEntryInfo>, String> {
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    let archive_password = decrypt_archive_password(metadata)?;
+
+    TlockArchive::list_entries(path, &archive_password)
+        .map_err(|e| format!("Failed to list entries: {}", e))
+}
+
+/// Contents of a single archive entry, read into memory for previewing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryPreview {
+    /// Path of the entry inside the archive
+    pub name: String,
+    /// Size of the contents in bytes
+    pub size: u64,
+    /// Entry contents, base64-encoded
+    pub data: String,
+}
+
+/// Decrypt one entry of an unlockable .7z.tlock file into memory, without writing to disk
+///
+/// # Arguments
+/// * `tlock_path` - Path to the .7z.tlock file
+/// * `entry_name` - Path of the entry inside the archive (as returned by `list_tlock_entries`)
+/// * `max_bytes` - Largest entry accepted; bigger entries are refused rather than truncated
+#[tauri::command]
+pub async fn extract_entry_to_bytes(
+    tlock_path: String,
+    entry_name: String,
+    max_bytes: u64,
+) -> Result<EntryPreview, String> {
+    use crate::tlock_format::TlockArchive;
+    use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    let archive_password = decrypt_archive_password(metadata)?;
+
+    let contents = TlockArchive::read_entry(path, &archive_password, &entry_name, max_bytes)
+        .map_err(|e| format!("Failed to read entry: {}", e))?;
+
+    eprintln!("[extract_entry_to_bytes] Read {} bytes of {}", contents.len(), entry_name);
+
+    Ok(EntryPreview {
+        name: entry_name,
+        size: contents.len() as u64,
+        data: BASE64.encode(&contents),
+    })
+}
+
+/// Dry run of `unlock_tlock_file`: report what would be extracted without writing anything
+///
+/// # Arguments
+/// * `tlock_path` - Path to the .7z.tlock file
+/// * `output_dir` - Optional output directory (defaults to same directory as tlock file)
+#[tauri::command]
+pub async fn preview_unlock_tlock_file(
+    tlock_path: String,
+    output_dir: Option<String>,
+) -> Result<crate::tlock_format::ExtractionPlan, String> {
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    let archive_password = decrypt_archive_password(metadata)?;
+
+    let output_path = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => default_unlock_dir(path, &metadata.original_file)?,
+    };
+
+    TlockArchive::plan_extraction(path, &archive_password, &output_path)
+        .map_err(|e| format!("Failed to read archive contents: {}", e))
+}
+
+/// Result of exporting the raw 7z payload of a .7z.tlock file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportPayloadResult {
+    /// Path to the exported .7z archive
+    pub payload_path: String,
+    /// Size of the exported archive in bytes
+    pub size: u64,
+    /// Password needed to open the archive in an external 7z tool
+    pub password: String,
+}
+
+/// Export the encrypted 7z payload of an unlockable .7z.tlock file for use with external 7z tools
+///
+/// # Arguments
+/// * `tlock_path` - Path to the .7z.tlock file
+/// * `dest_path` - Where to write the .7z archive
+///
+/// # Returns
+/// The exported path together with the archive password
+#[tauri::command]
+pub async fn export_payload(tlock_path: String, dest_path: String) -> Result<ExportPayloadResult, String> {
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+    let dest = Path::new(&dest_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+    if dest.exists() {
+        return Err(format!("Destination already exists: {}", dest_path));
+    }
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    let password = decrypt_archive_password(metadata)?;
+
+    let size = TlockArchive::export_payload(path, dest)
+        .map_err(|e| format!("Failed to export payload: {}", e))?;
+
+    eprintln!("[export_payload] Exported payload to: {:?}", dest);
+
+    Ok(ExportPayloadResult {
+        payload_path: dest.display().to_string(),
+        size,
+        password,
+    })
+}
+
+/// Re-encrypt an unlockable .7z.tlock file under a fresh random archive password
+///
+/// For when the password may have leaked (e.g. after `export_payload`
+/// revealed it). The contents are re-archived and the new password is
+/// time-locked to the same unlock time; the file is replaced atomically.
+#[tauri::command]
+pub async fn rotate_archive_password(tlock_path: String) -> Result<(), String> {
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+
+    let password = decrypt_archive_password(metadata)?;
+
+    TlockArchive::rotate_password(path, &password)
+        .map_err(|e| format!("Failed to rotate password: {}", e))?;
+
+    eprintln!("[rotate_archive_password] Rotated password of: {:?}", path);
+    Ok(())
+}
+
+/// SHA-256 fingerprint of a whole .7z.tlock file, for checking copies are identical
+///
+/// Covers the metadata as well as the payload. For deduplication and
+/// verification only, not security.
+#[tauri::command]
+pub async fn get_tlock_fingerprint(tlock_path: String) -> Result<String, String> {
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    TlockArchive::fingerprint(path)
+        .map_err(|e| format!("Failed to fingerprint file: {}", e))
+}
+
+/// Export a "time capsule" folder: the .7z.tlock file plus unlock instructions
+/// and, optionally, a copy of the CLI binary for the recipient
+///
+/// # Returns
+/// Path to the created capsule folder
+#[tauri::command]
+pub async fn export_capsule(
+    tlock_path: String,
+    dest_dir: String,
+    include_binary: Option<bool>,
+) -> Result<String, String> {
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    let capsule_dir = crate::capsule::export_capsule(path, Path::new(&dest_dir), include_binary.unwrap_or(false))
+        .map_err(|e| format!("Failed to export capsule: {}", e))?;
+
+    Ok(capsule_dir.display().to_string())
+}
+
+/// Build shareable unlock instructions to send along with a .7z.tlock file
+///
+/// Reads only the public metadata: filename, unlock date, drand round and the
+/// command the recipient has to run.
+#[tauri::command]
+pub async fn generate_instructions(tlock_path: String) -> Result<String, String> {
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    let archive = TlockArchive::read_metadata(path)
+        .map_err(|e| format!("Failed to read tlock file: {}", e))?;
+    let metadata = archive.get_metadata()
+        .ok_or_else(|| "Metadata not found in archive".to_string())?;
+    let tlock_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid tlock file name: {}", tlock_path))?;
+
+    Ok(crate::capsule::share_instructions(metadata, &tlock_name))
+}
+
+/// Verify every .7z.tlock file in a vault and move corrupt ones into its `_corrupt/` folder
+///
+/// Files are only relocated, never deleted; each move is logged with its reason.
+/// With `dry_run`, nothing is moved and the report shows what would be.
+#[tauri::command]
+pub async fn quarantine_corrupt(
+    vault: String,
+    dry_run: Option<bool>,
+) -> Result<crate::tlock_format::QuarantineReport, String> {
+    let report = crate::tlock_format::quarantine_corrupt(std::path::Path::new(&vault), dry_run.unwrap_or(false))
+        .map_err(|e| format!("Failed to check vault: {}", e))?;
+
+    eprintln!(
+        "[quarantine_corrupt] Checked {} files, {} corrupt, {} could not be checked",
+        report.checked,
+        report.quarantined.len(),
+        report.unchecked.len()
+    );
+
+    Ok(report)
+}
+
+/// Cross-check every .7z.tlock file in a vault for compliance reviews
+///
+/// Read-only. For each file, the recorded unlock time is compared with the
+/// round its key is locked to, and the header version, payload and (where
+/// stored) checksum are checked; every inconsistency is listed.
+#[tauri::command]
+pub async fn audit_vault(vault: String) -> Result<crate::tlock_format::AuditReport, String> {
+    crate::tlock_format::audit_vault(std::path::Path::new(&vault))
+        .map_err(|e| format!("Failed to audit vault: {}", e))
+}
+
+/// Recover the archive password from tlock metadata, failing if the time lock is still active
+fn decrypt_archive_password(metadata: &TlockMetadata) -> Result<String, UnlockError> {
+    use crate::crypto;
+
+    if !metadata.is_unlockable() {
+        return Err(UnlockError::time_lock_active(metadata.unlocks));
+    }
+
+    let encrypted_key = metadata.encrypted_key.as_ref()
+        .ok_or_else(|| "No encrypted key found in metadata".to_string())?;
+
+    let beacon = metadata.beacon().map_err(|e| e.to_string())?;
+    crypto::decrypt_with_tlock(encrypted_key, metadata.unlocks, &beacon)
+        .map_err(|e| UnlockError::key_decryption(e, metadata.unlocks))
+}
+
+/// Delay applied to every password check to slow down brute forcing
+const VERIFY_PASSWORD_DELAY_MS: u64 = 500;
+
+/// Held through each password check and its delay, so concurrent checks queue
+/// up instead of each only waiting out its own delay
+static PASSWORD_CHECK: Mutex<()> = Mutex::new(());
+
+/// Check whether a password opens a .7z.tlock archive without extracting it
+///
+/// Used by the GUI to give instant "wrong password" feedback for archives
+/// locked with a user-supplied password, before starting a full extraction.
+#[tauri::command]
+pub async fn verify_password(tlock_path: String, password: String) -> Result<bool, String> {
+    use crate::archive;
+    use crate::tlock_format::TlockArchive;
+    use std::path::Path;
+
+    let path = Path::new(&tlock_path);
+
+    if !path.exists() {
+        return Err(format!("File not found: {}", tlock_path));
+    }
+
+    // The delay and the decryption both block, so keep them off the async runtime's workers
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _turn = PASSWORD_CHECK.lock().unwrap_or_else(PoisonError::into_inner);
+        std::thread::sleep(std::time::Duration::from_millis(VERIFY_PASSWORD_DELAY_MS));
+
+        let payload = TlockArchive::open_payload(&path)
+            .map_err(|e| format!("Failed to open archive payload: {}", e))?;
+
+        let valid = archive::verify_archive_password(payload, &password)
+            .map_err(|e| format!("Failed to verify password: {}", e))?;
+
+        eprintln!("[verify_password] Password check for {:?}: {}", path, valid);
+        Ok(valid)
     })
+    .await
+    .map_err(|e| format!("Failed to verify password: {}", e))?
 }
 
 /// Check if a file is a valid .7z.tlock file
@@ -1392,20 +2918,40 @@ pub fn open_in_explorer(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Result of `unlock_tlock_file`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnlockTlockResult {
+    /// Extracted contents, or the written .7z archive with `extract_as_archive`
+    pub output_path: String,
+    /// Password of the written .7z archive, only with `extract_as_archive`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_password: Option<String>,
+}
+
 /// Unlock a .7z.tlock file and extract its contents
 ///
 /// # Arguments
 /// * `tlock_path` - Path to the .7z.tlock file
 /// * `output_dir` - Optional output directory (defaults to same directory as tlock file)
+/// * `delete_after_unlock` - Delete the .7z.tlock file once the extraction is verified
+///   (defaults to the `delete_after_unlock` setting)
+/// * `identity_path` - age identity file; if the file was also locked to its
+///   recipient, it is opened with that key regardless of the unlock time
+/// * `extract_as_archive` - Don't extract: write the 7z payload as `<name>.7z`
+///   (into `output_dir`, if given) and return its password. The archive is no
+///   longer time-locked, and the .7z.tlock file is always kept
 ///
 /// # Returns
-/// Path to the extracted contents
+/// Path to the extracted contents or the written archive
 #[tauri::command]
 pub async fn unlock_tlock_file(
     window: WebviewWindow,
     tlock_path: String,
     output_dir: Option<String>,
-) -> Result<String, String> {
+    delete_after_unlock: Option<bool>,
+    identity_path: Option<String>,
+    extract_as_archive: Option<bool>,
+) -> Result<UnlockTlockResult, UnlockError> {
     use crate::crypto;
     use crate::archive;
     use crate::tlock_format::TlockArchive;
@@ -1415,13 +2961,13 @@ pub async fn unlock_tlock_file(
     let path = Path::new(&tlock_path);
 
     if !path.exists() {
-        return Err(format!("File not found: {}", tlock_path));
+        return Err(format!("File not found: {}", tlock_path).into());
     }
 
     eprintln!("[unlock_tlock_file] Starting unlock for: {}", tlock_path);
 
     // Create progress tracker for the unlock operation
-    let tracker = Arc::new(ProgressTracker::new());
+    let tracker = Arc::new(ProgressTracker::new().with_rate_limit(settings_rate_limit()));
     let emitter = ProgressEmitter::new(window.clone(), Arc::clone(&tracker), "unlock-progress");
 
     // Emit decrypting phase
@@ -1436,48 +2982,100 @@ pub async fn unlock_tlock_file(
 
     eprintln!("[unlock_tlock_file] Parsed metadata for: {}", metadata.original_file);
 
-    // 2. Check if unlock time has passed
-    if !metadata.is_unlockable() {
-        let remaining = metadata.time_until_unlock();
-        return Err(format!(
-            "Time lock still active. Unlock in {} hours, {} minutes",
-            remaining.num_hours(),
-            remaining.num_minutes() % 60
-        ));
-    }
+    // 2. A recipient key opens the file early; otherwise fall back to the time lock
+    let recipient_password = match (identity_path, metadata.recipient_encrypted_key.as_ref()) {
+        (Some(identity_path), Some(recipient_key)) => {
+            let identity = crypto::read_identity_file(Path::new(&identity_path))
+                .map_err(|e| format!("Failed to read identity file: {}", e))?;
+            match crypto::decrypt_with_identity(recipient_key, &identity) {
+                Ok(password) => Some(password),
+                Err(e) => {
+                    eprintln!("[unlock_tlock_file] Recipient key did not open the file: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
 
-    // 3. Decrypt the encrypted key to get the archive password
-    let encrypted_key = metadata.encrypted_key.as_ref()
-        .ok_or_else(|| "No encrypted key found in metadata".to_string())?;
+    let archive_password = match recipient_password {
+        Some(password) => {
+            eprintln!("[unlock_tlock_file] Opened with recipient key");
+            password
+        }
+        None => {
+            // 3. Check if unlock time has passed
+            if !metadata.is_unlockable() {
+                return Err(UnlockError::time_lock_active(metadata.unlocks));
+            }
 
-    let archive_password = crypto::decrypt_with_tlock(encrypted_key, metadata.unlocks)
-        .map_err(|e| format!("Failed to decrypt key: {}", e))?;
+            // 4. Decrypt the encrypted key to get the archive password
+            let encrypted_key = metadata.encrypted_key.as_ref()
+                .ok_or_else(|| "No encrypted key found in metadata".to_string())?;
+
+            let beacon = metadata.beacon().map_err(|e| e.to_string())?;
+            emitter.emit_progress_forced(None, ProgressPhase::FetchingBeacon);
+            crypto::decrypt_with_tlock(encrypted_key, metadata.unlocks, &beacon)
+                .map_err(|e| UnlockError::key_decryption(e, metadata.unlocks))?
+        }
+    };
 
     eprintln!("[unlock_tlock_file] Decrypted archive password");
 
-    // 4. Determine output directory
+    if extract_as_archive.unwrap_or(false) {
+        // "name.7z.tlock" -> "name.7z"
+        let archive_path = path.with_extension("");
+        let dest = match output_dir {
+            Some(dir) => PathBuf::from(dir).join(archive_path.file_name().unwrap_or_default()),
+            None => archive_path,
+        };
+        if dest.exists() {
+            return Err(format!("Destination already exists: {}", dest.display()).into());
+        }
+
+        // Only hand out a password that is known to open the archive
+        let payload = TlockArchive::open_payload(path)
+            .map_err(|e| format!("Failed to read archive payload: {}", e))?;
+        if !archive::verify_archive_password(payload, &archive_password)
+            .map_err(|e| format!("Failed to check archive password: {}", e))?
+        {
+            return Err("Decrypted password does not open the archive".to_string().into());
+        }
+
+        if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        }
+        TlockArchive::export_payload(path, &dest)
+            .map_err(|e| format!("Failed to export payload: {}", e))?;
+        eprintln!("[unlock_tlock_file] Wrote archive to: {:?}", dest);
+
+        return Ok(UnlockTlockResult {
+            output_path: dest.display().to_string(),
+            archive_password: Some(archive_password),
+        });
+    }
+
+    emitter.emit_progress_forced(None, ProgressPhase::Extracting);
+
+    // 5. Determine output directory
     let output_path = match output_dir {
         Some(dir) => PathBuf::from(dir),
-        None => path.parent()
-            .unwrap_or(Path::new("."))
-            .join(format!("unlocked_{}", metadata.original_file)),
+        None => default_unlock_dir(path, &metadata.original_file)?,
     };
 
     eprintln!("[unlock_tlock_file] Extracting to: {:?}", output_path);
 
-    // 5. Extract the archive using progress-aware extraction
+    // 6. Extract the archive using progress-aware extraction
     // First, extract the 7z payload to a temp location then extract it
     let temp_archive = TlockArchive::extract_payload_to_temp(path)
         .map_err(|e| format!("Failed to extract archive payload: {}", e))?;
 
-    // Use progress-enabled extraction
-    archive::extract_encrypted_archive_with_progress(
-        &temp_archive,
-        &archive_password,
-        &output_path,
-        window,
-        Some(tracker),
-    ).map_err(|e| format!("Failed to extract archive: {}", e))?;
+    // Use progress-enabled extraction, staged beside the output so a failed
+    // unlock never looks like a finished one
+    archive::extract_atomically(&output_path, |dir| {
+        archive::extract_encrypted_archive_with_progress(&temp_archive, &archive_password, dir, window, Some(tracker))
+    }).map_err(|e| format!("Failed to extract archive: {}", e))?;
 
     // Clean up temp archive
     if let Err(e) = std::fs::remove_file(&temp_archive) {
@@ -1486,5 +3084,427 @@ pub async fn unlock_tlock_file(
 
     eprintln!("[unlock_tlock_file] Extraction complete");
 
-    Ok(output_path.display().to_string())
+    // 7. Optionally consume the .7z.tlock file, only once the output is verified
+    let should_delete = match delete_after_unlock {
+        Some(delete) => delete,
+        None => get_settings_internal().map(|s| s.delete_after_unlock).unwrap_or(false),
+    };
+    if should_delete {
+        match TlockArchive::verify_extraction(path, &archive_password, &output_path) {
+            Ok(true) => match fs::remove_file(path) {
+                Ok(()) => eprintln!("[unlock_tlock_file] Deleted consumed tlock file: {:?}", path),
+                Err(e) => eprintln!("[unlock_tlock_file] Warning: Failed to delete tlock file: {}", e),
+            },
+            Ok(false) => eprintln!("[unlock_tlock_file] Extraction not verified, keeping tlock file"),
+            Err(e) => eprintln!("[unlock_tlock_file] Verification error, keeping tlock file: {}", e),
+        }
+    }
+
+    Ok(UnlockTlockResult {
+        output_path: output_path.display().to_string(),
+        archive_password: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_survive_poisoned_mutex() {
+        let state = Arc::new(OperationState::default());
+        let first = Arc::new(ProgressTracker::new());
+        state.register("first", &first);
+
+        // An operation panicking while holding the lock poisons the mutex
+        let poisoner = Arc::clone(&state);
+        let _ = std::thread::spawn(move || {
+            let _ops = poisoner.active_operations.lock().unwrap();
+            panic!("operation failed");
+        })
+        .join();
+        assert!(state.active_operations.is_poisoned());
+
+        // Later operations still work
+        let second = Arc::new(ProgressTracker::new());
+        state.register("second", &second);
+        assert!(state.cancel("first"));
+        assert!(first.is_cancelled());
+        state.remove("second");
+        assert!(!state.cancel("second"));
+        assert!(!second.is_cancelled());
+    }
+
+    #[test]
+    fn test_prune_stale_operations() {
+        let state = OperationState::default();
+        let stuck = Arc::new(ProgressTracker::new());
+        state.register("stuck", &stuck);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let busy = Arc::new(ProgressTracker::new());
+        state.register("busy", &busy);
+        busy.add_bytes(10);
+
+        let listed = state.list();
+        assert_eq!(listed.iter().map(|op| op.id.as_str()).collect::<Vec<_>>(), ["stuck", "busy"]);
+        assert_eq!(listed[1].bytes_processed, 10);
+
+        let pruned = state.prune_stale(std::time::Duration::from_millis(40));
+        assert_eq!(pruned, ["stuck"]);
+        assert!(stuck.is_cancelled());
+        assert!(!busy.is_cancelled());
+        assert_eq!(state.list().len(), 1);
+    }
+
+    #[test]
+    fn test_change_unlock_time_refused_while_locked() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_change_time_locked");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("locked.txt");
+        fs::write(&source, b"Still locked").unwrap();
+
+        let unlocks = Utc::now() + chrono::Duration::days(1);
+        let encrypted = crate::crypto::encrypt_with_tlock("password", unlocks, &crate::crypto::BeaconConfig::quicknet())
+            .unwrap();
+        let metadata = TlockMetadata::new("locked.txt".to_string(), "1d".to_string(), unlocks, None, Some(encrypted));
+        let tlock_path = TlockArchive::create(&source, metadata, "password").unwrap();
+        let before = fs::read(&tlock_path).unwrap();
+
+        let new_time = (Utc::now() + chrono::Duration::days(30)).to_rfc3339();
+        let result = tauri::async_runtime::block_on(change_unlock_time(
+            tlock_path.display().to_string(),
+            new_time,
+        ));
+        assert!(result.unwrap_err().contains("Time lock still active"));
+        assert_eq!(fs::read(&tlock_path).unwrap(), before);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_change_unlock_time_after_expiry() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_change_time_expired");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("expired.txt");
+        fs::write(&source, b"Lock me again").unwrap();
+
+        let unlocked = Utc::now() - chrono::Duration::minutes(5);
+        let encrypted = crate::crypto::encrypt_with_tlock("password", unlocked, &crate::crypto::BeaconConfig::quicknet())
+            .unwrap();
+        let metadata = TlockMetadata::new("expired.txt".to_string(), "0d".to_string(), unlocked, None, Some(encrypted));
+        let tlock_path = TlockArchive::create(&source, metadata, "password").unwrap();
+
+        let new_time = Utc::now() + chrono::Duration::days(30);
+        tauri::async_runtime::block_on(change_unlock_time(
+            tlock_path.display().to_string(),
+            new_time.to_rfc3339(),
+        ))
+        .unwrap();
+
+        let metadata = TlockArchive::read_metadata(&tlock_path).unwrap().metadata.unwrap();
+        assert!(!metadata.is_unlockable());
+        assert_eq!(metadata.unlocks.timestamp(), new_time.timestamp());
+        let payload = TlockArchive::open_payload(&tlock_path).unwrap();
+        assert!(crate::archive::verify_archive_password(payload, "password").unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_vault_scans_deduplicated_after_merge() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_vault_merge");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("merged.txt");
+        fs::write(&source, b"Seen twice").unwrap();
+        let metadata = TlockMetadata::new(
+            "merged.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + chrono::Duration::days(1),
+            None,
+            None,
+        );
+        TlockArchive::create(&source, metadata, "password").unwrap();
+
+        // The same vault configured twice is scanned twice but listed once
+        let scans: Vec<VaultScan> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..2).map(|_| scope.spawn(|| read_vault_dir(&dir))).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut items = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for scan in scans {
+            merge_vault_scan(scan, &mut items, &mut seen);
+        }
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "merged.txt");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_locked_file_reports_structured_error() {
+        let unlocks = Utc::now() + chrono::Duration::hours(5);
+        let metadata = TlockMetadata::new("locked.txt".to_string(), "5h".to_string(), unlocks, None, None);
+
+        let err = decrypt_archive_password(&metadata).unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "time_lock_active");
+        assert_eq!(json["unlocks_at"], unlocks.to_rfc3339());
+        let remaining = json["remaining_seconds"].as_i64().unwrap();
+        assert!((5 * 3600 - 60..=5 * 3600).contains(&remaining));
+
+        // Commands that still report strings get the old message
+        assert!(String::from(err).starts_with("Time lock still active. Unlock in 4 hours"));
+
+        // Other failures stay plain strings
+        let message = UnlockError::from("File not found: x".to_string());
+        assert_eq!(serde_json::to_value(&message).unwrap(), "File not found: x");
+    }
+
+    #[test]
+    fn test_key_decryption_errors_are_distinguished() {
+        use crate::error::TimeLockerError;
+        let unlocks = Utc::now() + chrono::Duration::hours(1);
+
+        let locked = UnlockError::key_decryption(TimeLockerError::TimeLockActive, unlocks);
+        assert_eq!(serde_json::to_value(&locked).unwrap()["code"], "time_lock_active");
+
+        // A due round no relay could serve is a network problem, worth retrying
+        let offline = UnlockError::key_decryption(
+            TimeLockerError::DrandUnavailable("all endpoints failed".to_string()),
+            unlocks,
+        );
+        let json = serde_json::to_value(&offline).unwrap();
+        assert_eq!(json["code"], "drand_unavailable");
+        assert_eq!(json["message"], "Drand beacon unavailable: all endpoints failed");
+
+        let corrupt = UnlockError::key_decryption(TimeLockerError::Decryption("bad".to_string()), unlocks);
+        assert_eq!(corrupt.to_string(), "Failed to decrypt key: Decryption error: bad");
+    }
+
+    #[test]
+    fn test_copied_item_found_as_duplicate() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_duplicates");
+        let _ = fs::remove_dir_all(&dir);
+        let (vault_a, vault_b) = (dir.join("a"), dir.join("b"));
+        fs::create_dir_all(&vault_a).unwrap();
+        fs::create_dir_all(&vault_b).unwrap();
+        let new_metadata = |name: &str| {
+            TlockMetadata::new(name.to_string(), "1d".to_string(), Utc::now() + chrono::Duration::days(1), None, None)
+        };
+        for name in ["copied.txt", "unique.txt"] {
+            let source = vault_a.join(name);
+            fs::write(&source, name.as_bytes()).unwrap();
+            TlockArchive::create(&source, new_metadata(name), "password").unwrap();
+            fs::remove_file(&source).unwrap();
+        }
+        let copy = vault_b.join("copied.7z.tlock");
+        fs::copy(vault_a.join("copied.7z.tlock"), &copy).unwrap();
+
+        let mut items = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for vault in [&vault_a, &vault_b] {
+            scan_vault_dir(vault, &mut items, &mut seen);
+        }
+        assert_eq!(items.len(), 3);
+
+        let keyed = items
+            .into_iter()
+            .filter_map(|item| duplicate_key(&item).map(|key| (key, item)))
+            .collect();
+        let groups = group_duplicates(keyed);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].matched_by, "checksum");
+        assert_eq!(groups[0].original_file.as_deref(), Some("copied.txt"));
+        assert_eq!(groups[0].paths.len(), 2);
+        assert!(groups[0].paths.contains(&copy.display().to_string()));
+        assert_eq!(groups[0].reclaimable_bytes, fs::metadata(&copy).unwrap().len());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lock_never_writes_plaintext_password() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_password");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("secret.txt");
+        fs::write(&source, b"Secret content").unwrap();
+
+        let password = "plaintext-archive-password-3f9c1a";
+        let unlock_time = (Utc::now() + chrono::Duration::days(30)).to_rfc3339();
+        let item = tauri::async_runtime::block_on(lock_item(
+            source.display().to_string(),
+            unlock_time,
+            Some(password.to_string()),
+            Some(dir.display().to_string()),
+            None,
+            None,
+            None,
+        ))
+        .unwrap();
+
+        let tlock_path = PathBuf::from(item.tlock_path.unwrap());
+        let bytes = fs::read(&tlock_path).unwrap();
+        let payload_offset = TlockArchive::get_payload_offset(&tlock_path).unwrap() as usize;
+        let contains_password =
+            |haystack: &[u8]| haystack.windows(password.len()).any(|w| w == password.as_bytes());
+
+        // Only the tlock ciphertext of the password may be stored
+        let metadata = TlockArchive::read_metadata(&tlock_path).unwrap().metadata.unwrap();
+        let encrypted_key = metadata.encrypted_key.expect("encrypted key is stored");
+        assert_ne!(encrypted_key, password);
+        assert!(!contains_password(&bytes[..payload_offset]));
+        assert!(!contains_password(&bytes));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Lock `source` the way the legacy app did: a .7z beside a .key.md holding the tlock'd password
+    fn write_legacy_files(source: &std::path::Path, unlocks: chrono::DateTime<Utc>) -> PathBuf {
+        let archive_path =
+            crate::archive::create_encrypted_archive(source, "password", &CompressionSettings::default()).unwrap();
+        let encrypted = crate::crypto::encrypt_with_tlock("password", unlocks, &crate::crypto::BeaconConfig::quicknet())
+            .unwrap();
+        let name = source.file_name().unwrap().to_string_lossy().to_string();
+        let mut keyfile = KeyFile::create(name.clone(), "legacy".to_string(), unlocks, encrypted);
+        keyfile.metadata.archive_path = Some(archive_path.file_name().unwrap().to_string_lossy().to_string());
+        let key_path = source.with_file_name(format!("{}.key.md", name));
+        keyfile.save(&key_path).unwrap();
+        key_path
+    }
+
+    #[test]
+    fn test_migrated_legacy_file_reads_back() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_migrate_locked");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("legacy.txt");
+        fs::write(&source, b"Locked the old way").unwrap();
+
+        let unlocks = Utc::now() + chrono::Duration::days(1);
+        let key_path = write_legacy_files(&source, unlocks);
+        let result = migrate_key_file(&key_path, OldFilesAction::Keep).unwrap();
+        assert!(result.success);
+        assert!(key_path.exists());
+
+        let metadata = tauri::async_runtime::block_on(read_tlock_metadata(result.tlock_path.clone())).unwrap();
+        assert_eq!(metadata.original_file, "legacy.txt");
+        let recorded = chrono::DateTime::parse_from_rfc3339(&metadata.unlocks).unwrap();
+        assert_eq!(recorded.timestamp(), unlocks.timestamp());
+        assert!(!metadata.is_unlockable);
+        assert!(!metadata.is_directory);
+
+        // The legacy 7z is carried over unchanged and still opens with its password
+        let tlock_path = PathBuf::from(&result.tlock_path);
+        let payload = TlockArchive::open_payload(&tlock_path).unwrap();
+        assert!(crate::archive::verify_archive_password(payload, "password").unwrap());
+        let error = decrypt_archive_password(&TlockArchive::read_metadata(&tlock_path).unwrap().metadata.unwrap());
+        assert!(matches!(error, Err(UnlockError::TimeLockActive { .. })));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migration_replaces_incomplete_tlock() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_migrate_incomplete");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("legacy.txt");
+        fs::write(&source, b"Interrupted the first time").unwrap();
+
+        let key_path = write_legacy_files(&source, Utc::now() + chrono::Duration::days(1));
+        let keyfile = crate::keyfile::scan_directory(&dir).unwrap().remove(0);
+        let tlock_path = keyfile.resolved_archive_path().unwrap().with_extension("7z.tlock");
+
+        // A truncated file from an interrupted run doesn't count as migrated and is replaced
+        fs::write(&tlock_path, b"TLOCK01").unwrap();
+        assert!(!keyfile.has_tlock());
+        let result = migrate_key_file(&key_path, OldFilesAction::Keep).unwrap();
+        assert!(result.success);
+        assert!(keyfile.has_tlock());
+
+        // An intact one does
+        assert!(migrate_key_file(&key_path, OldFilesAction::Keep).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrated_legacy_directory_labeled_as_directory() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_migrate_dir");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("legacy-folder");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("inside.txt"), b"In a folder").unwrap();
+
+        // Still locked, so the entry names can't be read yet
+        let key_path = write_legacy_files(&source, Utc::now() + chrono::Duration::days(1));
+        let result = migrate_key_file(&key_path, OldFilesAction::Keep).unwrap();
+
+        let metadata = tauri::async_runtime::block_on(read_tlock_metadata(result.tlock_path)).unwrap();
+        assert_eq!(metadata.original_file, "legacy-folder");
+        assert!(metadata.is_directory);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_migrated_legacy_directory_unlocks() {
+        let dir = std::env::temp_dir().join("tlock_test_commands_migrate_unlock");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("legacy-folder");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("top.txt"), b"Top level").unwrap();
+        fs::write(source.join("nested").join("deep.txt"), b"Nested file").unwrap();
+
+        let unlocks = Utc::now() - chrono::Duration::minutes(5);
+        let key_path = write_legacy_files(&source, unlocks);
+        let result = migrate_key_file(&key_path, OldFilesAction::Delete).unwrap();
+        assert!(result.old_files_deleted);
+
+        let response = tauri::async_runtime::block_on(read_tlock_metadata(result.tlock_path.clone())).unwrap();
+        assert_eq!(response.original_file, "legacy-folder");
+        assert!(response.is_unlockable);
+        assert!(response.is_directory);
+
+        let tlock_path = PathBuf::from(&result.tlock_path);
+        let metadata = TlockArchive::read_metadata(&tlock_path).unwrap().metadata.unwrap();
+        let password = decrypt_archive_password(&metadata).unwrap();
+        assert_eq!(password, "password");
+
+        let output = dir.join("output");
+        TlockArchive::extract(&tlock_path, &password, &output).unwrap();
+        assert_eq!(fs::read(output.join("legacy-folder").join("top.txt")).unwrap(), b"Top level");
+        assert_eq!(
+            fs::read(output.join("legacy-folder").join("nested").join("deep.txt")).unwrap(),
+            b"Nested file"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_settings_drand_endpoints_round_trip() {
+        // Settings files written before the field existed use the built-in relays
+        let old: AppSettings = serde_json::from_str(r#"{ "vaults": ["/vault"] }"#).unwrap();
+        assert!(old.drand_endpoints.is_empty());
+
+        let settings = AppSettings {
+            drand_endpoints: vec!["https://drand.internal.example".to_string()],
+            ..old
+        };
+        let json = serde_json::to_string_pretty(&settings).unwrap();
+        let loaded: AppSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.vaults, vec!["/vault".to_string()]);
+        assert_eq!(loaded.drand_endpoints, settings.drand_endpoints);
+    }
 }
@@ -3,14 +3,18 @@ use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
 use chrono::{DateTime, Utc};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
 use std::io::Cursor;
 
 // ============================================================================
-// DRAND QUICKNET BEACON CONFIGURATION
+// DRAND BEACON CONFIGURATION
 // ============================================================================
 // Quicknet is the recommended unchained beacon for tlock encryption.
 // It produces randomness every 3 seconds with BLS signatures on G1.
 // See: https://drand.love/developer/http-api/
+//
+// Other unchained networks (e.g. a self-hosted drand deployment) can be used
+// by pointing TIMELOCKER_BEACON at a JSON file describing a `BeaconConfig`.
 
 /// Drand Quicknet chain hash (hex encoded)
 const QUICKNET_CHAIN_HASH: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
@@ -30,6 +34,554 @@ const DRAND_ENDPOINTS: &[&str] = &[
     "https://drand.cloudflare.com",
 ];
 
+/// Quicknet relays set by the user (`drand_endpoints` setting or `--drand-endpoint`),
+/// replacing `DRAND_ENDPOINTS` when not empty
+static CUSTOM_DRAND_ENDPOINTS: std::sync::RwLock<Vec<String>> = std::sync::RwLock::new(Vec::new());
+
+/// Fetch Quicknet rounds from `endpoints` instead of the public relays
+///
+/// For networks where those are blocked (e.g. behind a corporate proxy). An
+/// empty list restores the built-in relays.
+pub fn set_drand_endpoints(endpoints: &[String]) -> Result<()> {
+    let mut relays = Vec::with_capacity(endpoints.len());
+    for endpoint in endpoints {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(TimeLockerError::Parse(format!(
+                "Drand endpoint must be an http(s) URL: {}",
+                endpoint
+            )));
+        }
+        relays.push(endpoint.to_string());
+    }
+
+    *CUSTOM_DRAND_ENDPOINTS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = relays;
+    Ok(())
+}
+
+/// Relays for Quicknet: the user's, or the built-in ones
+fn quicknet_endpoints() -> Vec<String> {
+    let custom = CUSTOM_DRAND_ENDPOINTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if custom.is_empty() {
+        DRAND_ENDPOINTS.iter().map(|e| e.to_string()).collect()
+    } else {
+        custom.clone()
+    }
+}
+
+/// Environment variable pointing at a JSON `BeaconConfig` to lock new files with
+pub const BEACON_CONFIG_ENV: &str = "TIMELOCKER_BEACON";
+
+/// Beacon set for this process (the CLI's experimental beacon flags), ahead of `TIMELOCKER_BEACON`
+static BEACON_OVERRIDE: std::sync::OnceLock<BeaconConfig> = std::sync::OnceLock::new();
+
+/// Use `beacon` as the active beacon for the rest of the process
+///
+/// Meant for testing against a custom drand deployment without writing a
+/// config file. Can only be set once.
+pub fn set_beacon_override(beacon: BeaconConfig) -> Result<()> {
+    beacon.validate()?;
+    BEACON_OVERRIDE
+        .set(beacon)
+        .map_err(|_| TimeLockerError::Parse("Beacon override is already set".to_string()))
+}
+
+/// Where the active beacon comes from, when it isn't the built-in Quicknet
+///
+/// "command line" for `set_beacon_override`, else the `TIMELOCKER_BEACON` path.
+pub fn active_beacon_source() -> Option<String> {
+    if BEACON_OVERRIDE.get().is_some() {
+        return Some("command line".to_string());
+    }
+    std::env::var(BEACON_CONFIG_ENV).ok().filter(|path| !path.is_empty())
+}
+
+/// Parameters of a drand beacon network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconConfig {
+    /// Chain hash (hex encoded), stored in the metadata of files locked with it
+    pub chain_hash: String,
+    /// Group public key (hex encoded BLS12-381 G2 point)
+    pub public_key: String,
+    /// Unix timestamp of round 1
+    pub genesis_time: u64,
+    /// Seconds between rounds
+    pub period: u64,
+    /// HTTP relays serving the chain
+    pub endpoints: Vec<String>,
+}
+
+impl Default for BeaconConfig {
+    fn default() -> Self {
+        Self::quicknet()
+    }
+}
+
+impl BeaconConfig {
+    /// The public drand Quicknet beacon
+    pub fn quicknet() -> Self {
+        Self {
+            chain_hash: QUICKNET_CHAIN_HASH.to_string(),
+            public_key: QUICKNET_PUBLIC_KEY.to_string(),
+            genesis_time: QUICKNET_GENESIS_TIME,
+            period: QUICKNET_PERIOD,
+            endpoints: quicknet_endpoints(),
+        }
+    }
+
+    /// Beacon to lock new files with (the override, `TIMELOCKER_BEACON` or Quicknet)
+    pub fn active() -> Result<Self> {
+        if let Some(beacon) = BEACON_OVERRIDE.get() {
+            return Ok(beacon.clone());
+        }
+        match std::env::var(BEACON_CONFIG_ENV) {
+            Ok(path) if !path.is_empty() => Self::load(std::path::Path::new(&path)),
+            _ => Ok(Self::quicknet()),
+        }
+    }
+
+    /// Load a beacon description from a JSON file
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let config: Self = serde_json::from_str(&content).map_err(|e| {
+            TimeLockerError::Parse(format!("Invalid beacon config {}: {}", path.display(), e))
+        })?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Sanity-check the parameters before any round math or encryption uses them
+    ///
+    /// The chain hash must be 32 bytes and the public key a 96-byte compressed
+    /// G2 point (hex), and the chain must already have started.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_parameters()?;
+        if self.endpoints.is_empty() {
+            return Err(TimeLockerError::Parse(
+                "Beacon config needs at least one endpoint".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check everything but the relays
+    fn validate_parameters(&self) -> Result<()> {
+        let hex_len = |value: &str, what: &str, bytes: usize| match hex::decode(value) {
+            Ok(decoded) if decoded.len() == bytes => Ok(()),
+            Ok(decoded) => Err(TimeLockerError::Parse(format!(
+                "Beacon {} must be {} bytes, got {}",
+                what,
+                bytes,
+                decoded.len()
+            ))),
+            Err(e) => Err(TimeLockerError::Parse(format!("Beacon {} is not hex: {}", what, e))),
+        };
+        hex_len(&self.chain_hash, "chain hash", 32)?;
+        hex_len(&self.public_key, "public key", 96)?;
+
+        if self.period == 0 {
+            return Err(TimeLockerError::Parse(
+                "Beacon period must be at least 1 second".to_string(),
+            ));
+        }
+        if self.genesis_time == 0 || self.genesis_time as i64 > Utc::now().timestamp() {
+            return Err(TimeLockerError::Parse(format!(
+                "Beacon genesis time {} must be a Unix timestamp in the past",
+                self.genesis_time
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Beacon a file was locked with, from the chain hash in its metadata
+    ///
+    /// Files without a chain hash predate alternate beacons and use Quicknet.
+    /// Any other chain must be the one configured through `TIMELOCKER_BEACON`.
+    pub fn for_chain_hash(chain_hash: Option<&str>) -> Result<Self> {
+        let chain_hash = match chain_hash {
+            None => return Ok(Self::quicknet()),
+            Some(hash) if hash.eq_ignore_ascii_case(QUICKNET_CHAIN_HASH) => {
+                return Ok(Self::quicknet())
+            }
+            Some(hash) => hash,
+        };
+
+        let active = Self::active()?;
+        if active.chain_hash.eq_ignore_ascii_case(chain_hash) {
+            Ok(active)
+        } else {
+            Err(TimeLockerError::Decryption(format!(
+                "File was locked with drand chain {}; set {} to that beacon's config file",
+                chain_hash, BEACON_CONFIG_ENV
+            )))
+        }
+    }
+}
+
+/// The parameters of a beacon that don't change: everything needed to check
+/// its signatures and do round math, without knowing any relay
+///
+/// Stored in the metadata of each locked file, so the file can be verified
+/// and unlocked offline (with a signature from elsewhere) on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconParameters {
+    /// Chain hash (hex encoded)
+    pub chain_hash: String,
+    /// Group public key (hex encoded BLS12-381 G2 point)
+    pub public_key: String,
+    /// Unix timestamp of round 1
+    pub genesis_time: u64,
+    /// Seconds between rounds
+    pub period: u64,
+}
+
+impl BeaconParameters {
+    /// The fixed parameters of `beacon`
+    pub fn of(beacon: &BeaconConfig) -> Self {
+        Self {
+            chain_hash: beacon.chain_hash.clone(),
+            public_key: beacon.public_key.clone(),
+            genesis_time: beacon.genesis_time,
+            period: beacon.period,
+        }
+    }
+
+    /// A beacon with these parameters and no relays, which is enough for
+    /// round math and offline decryption but can't fetch signatures
+    pub fn to_offline_config(&self) -> Result<BeaconConfig> {
+        let beacon = BeaconConfig {
+            chain_hash: self.chain_hash.clone(),
+            public_key: self.public_key.clone(),
+            genesis_time: self.genesis_time,
+            period: self.period,
+            endpoints: Vec::new(),
+        };
+        beacon.validate_parameters()?;
+        Ok(beacon)
+    }
+}
+
+/// Seconds to wait for a relay when checking whether it's reachable
+const ENDPOINT_CHECK_TIMEOUT_SECS: u64 = 5;
+
+/// A drand relay signatures may be fetched from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrandEndpoint {
+    /// Base URL of the relay
+    pub url: String,
+    /// Chain requested from it
+    pub chain_hash: String,
+    /// Where the relay is configured: "built-in", "custom" (user-set Quicknet relays),
+    /// "command line" or the `TIMELOCKER_BEACON` file
+    pub source: String,
+}
+
+/// Every relay this build talks to, in the order they're tried
+///
+/// The `TIMELOCKER_BEACON` relays come first when that beacon is a different
+/// chain, followed by the built-in Quicknet relays (always used for Quicknet
+/// files, whatever the variable says).
+pub fn drand_endpoints() -> Result<Vec<DrandEndpoint>> {
+    let active = BeaconConfig::active()?;
+
+    let mut endpoints = Vec::new();
+    if let Some(source) = active_beacon_source() {
+        if !active.chain_hash.eq_ignore_ascii_case(QUICKNET_CHAIN_HASH) {
+            endpoints.extend(beacon_endpoints(&active, &source));
+        }
+    }
+    let custom = !CUSTOM_DRAND_ENDPOINTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .is_empty();
+    let source = if custom { "custom" } else { "built-in" };
+    endpoints.extend(beacon_endpoints(&BeaconConfig::quicknet(), source));
+
+    Ok(endpoints)
+}
+
+fn beacon_endpoints(beacon: &BeaconConfig, source: &str) -> Vec<DrandEndpoint> {
+    by_measured_latency(&beacon.endpoints)
+        .iter()
+        .map(|url| DrandEndpoint {
+            url: url.trim_end_matches('/').to_string(),
+            chain_hash: beacon.chain_hash.clone(),
+            source: source.to_string(),
+        })
+        .collect()
+}
+
+/// Check that a relay answers for its chain
+///
+/// # Returns
+/// Round-trip time of the chain info request in milliseconds
+pub fn check_drand_endpoint(endpoint: &DrandEndpoint) -> Result<u64> {
+    let url = format!("{}/{}/info", endpoint.url, endpoint.chain_hash);
+    let started = std::time::Instant::now();
+
+    ureq::get(&url)
+        .timeout(std::time::Duration::from_secs(ENDPOINT_CHECK_TIMEOUT_SECS))
+        .call()
+        .map_err(|e| TimeLockerError::Network(format!("{}: {}", endpoint.url, e)))?;
+
+    Ok(started.elapsed().as_millis() as u64)
+}
+
+/// Seconds the latest round may be off from the local clock (beyond one period) before it's flagged
+const HEALTH_DRIFT_TOLERANCE_SECS: i64 = 10;
+
+/// What a relay reported when probed by `check_drand_health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrandHealth {
+    #[serde(flatten)]
+    pub endpoint: DrandEndpoint,
+    /// Reachable, serving the expected chain, and in step with the local clock
+    pub healthy: bool,
+    pub latest_round: Option<u64>,
+    /// Chain hash the relay reported for itself
+    pub reported_chain_hash: Option<String>,
+    /// Local time minus the latest round's scheduled time, in seconds
+    pub drift_seconds: Option<i64>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Probe every relay from `drand_endpoints` (in parallel) for its latest round
+///
+/// Each relay must report the chain hash it was asked for, and its latest
+/// round must match the local clock, which catches both clock skew and a
+/// wrong genesis time or period.
+pub fn check_drand_health() -> Result<Vec<DrandHealth>> {
+    let endpoints = drand_endpoints()?;
+
+    Ok(std::thread::scope(|scope| {
+        let probes: Vec<_> = endpoints
+            .iter()
+            .map(|endpoint| scope.spawn(move || probe_drand_endpoint(endpoint)))
+            .collect();
+
+        endpoints
+            .iter()
+            .zip(probes)
+            .map(|(endpoint, probe)| {
+                probe.join().unwrap_or_else(|_| DrandHealth {
+                    endpoint: endpoint.clone(),
+                    healthy: false,
+                    latest_round: None,
+                    reported_chain_hash: None,
+                    drift_seconds: None,
+                    latency_ms: None,
+                    error: Some("Check panicked".to_string()),
+                })
+            })
+            .collect()
+    }))
+}
+
+fn probe_drand_endpoint(endpoint: &DrandEndpoint) -> DrandHealth {
+    let mut health = DrandHealth {
+        endpoint: endpoint.clone(),
+        healthy: false,
+        latest_round: None,
+        reported_chain_hash: None,
+        drift_seconds: None,
+        latency_ms: None,
+        error: None,
+    };
+
+    let get_json = |path: &str| -> Result<serde_json::Value> {
+        let url = format!("{}/{}/{}", endpoint.url, endpoint.chain_hash, path);
+        let body = ureq::get(&url)
+            .timeout(std::time::Duration::from_secs(ENDPOINT_CHECK_TIMEOUT_SECS))
+            .call()
+            .map_err(|e| TimeLockerError::Network(format!("{}: {}", endpoint.url, e)))?
+            .into_string()
+            .map_err(|e| TimeLockerError::Network(format!("{}: {}", endpoint.url, e)))?;
+        serde_json::from_str(&body)
+            .map_err(|e| TimeLockerError::Parse(format!("{}: invalid response: {}", endpoint.url, e)))
+    };
+
+    let started = std::time::Instant::now();
+    let result = (|| -> Result<()> {
+        let info = get_json("info")?;
+        let latest = get_json("public/latest")?;
+        health.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+        let reported = info["hash"].as_str().unwrap_or_default().to_lowercase();
+        health.reported_chain_hash = Some(reported.clone());
+        if !reported.eq_ignore_ascii_case(&endpoint.chain_hash) {
+            return Err(TimeLockerError::Parse(format!(
+                "relay serves chain {}, expected {}",
+                reported, endpoint.chain_hash
+            )));
+        }
+
+        let round = latest["round"].as_u64().ok_or_else(|| {
+            TimeLockerError::Parse("latest beacon has no round number".to_string())
+        })?;
+        health.latest_round = Some(round);
+
+        let beacon = BeaconConfig::for_chain_hash(Some(&endpoint.chain_hash))?;
+        let drift = round_drift(round, crate::clock::now().timestamp(), &beacon);
+        health.drift_seconds = Some(drift);
+        if !drift_in_tolerance(drift, &beacon) {
+            return Err(TimeLockerError::Parse(format!(
+                "latest round {} is {}s off the local clock; check the system time and beacon config",
+                round, drift
+            )));
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => health.healthy = true,
+        Err(e) => health.error = Some(e.to_string()),
+    }
+    health
+}
+
+/// Relays ranked fastest first by the last `prefer_fastest_endpoints`, for this session
+static FASTEST_ENDPOINTS: std::sync::RwLock<Vec<String>> = std::sync::RwLock::new(Vec::new());
+
+/// How one relay did in `benchmark_drand_endpoints`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointLatency {
+    #[serde(flatten)]
+    pub endpoint: DrandEndpoint,
+    /// Median time to fetch the latest round, absent if every attempt failed
+    pub latency_ms: Option<u64>,
+    /// Every successful attempt, in milliseconds
+    pub samples_ms: Vec<u64>,
+    pub latest_round: Option<u64>,
+    /// Last error, if any attempt failed
+    pub error: Option<String>,
+}
+
+/// Time fetching the latest round from every relay from `drand_endpoints`
+///
+/// Each relay is asked `samples` times through the same client unlocking
+/// uses; relays are measured in parallel. The result is sorted fastest
+/// first, with relays that never answered at the end.
+pub fn benchmark_drand_endpoints(samples: u32) -> Result<Vec<EndpointLatency>> {
+    let endpoints = drand_endpoints()?;
+    let samples = samples.max(1);
+
+    let mut report: Vec<EndpointLatency> = std::thread::scope(|scope| {
+        let runs: Vec<_> = endpoints
+            .iter()
+            .map(|endpoint| scope.spawn(move || time_drand_endpoint(endpoint, samples)))
+            .collect();
+
+        endpoints
+            .iter()
+            .zip(runs)
+            .map(|(endpoint, run)| {
+                run.join().unwrap_or_else(|_| EndpointLatency {
+                    endpoint: endpoint.clone(),
+                    latency_ms: None,
+                    samples_ms: Vec::new(),
+                    latest_round: None,
+                    error: Some("Benchmark panicked".to_string()),
+                })
+            })
+            .collect()
+    });
+
+    report.sort_by_key(|result| result.latency_ms.unwrap_or(u64::MAX));
+    Ok(report)
+}
+
+fn time_drand_endpoint(endpoint: &DrandEndpoint, samples: u32) -> EndpointLatency {
+    use drand_core::HttpClient;
+
+    let mut result = EndpointLatency {
+        endpoint: endpoint.clone(),
+        latency_ms: None,
+        samples_ms: Vec::new(),
+        latest_round: None,
+        error: None,
+    };
+
+    let url = format!("{}/{}", endpoint.url, endpoint.chain_hash);
+    let client = match HttpClient::new(&url, None) {
+        Ok(client) => client,
+        Err(e) => {
+            result.error = Some(format!("{}: {}", endpoint.url, e));
+            return result;
+        }
+    };
+
+    for _ in 0..samples {
+        let started = std::time::Instant::now();
+        match client.latest() {
+            Ok(beacon) => {
+                result.samples_ms.push(started.elapsed().as_millis() as u64);
+                result.latest_round = Some(beacon.round());
+            }
+            Err(e) => result.error = Some(format!("{}: {}", endpoint.url, e)),
+        }
+    }
+
+    let mut sorted = result.samples_ms.clone();
+    sorted.sort_unstable();
+    result.latency_ms = sorted.get(sorted.len() / 2).copied();
+    result
+}
+
+/// Try relays in the order `report` measured them (fastest first) for the rest of the session
+///
+/// Relays that never answered keep their configured place after the measured
+/// ones. Nothing is saved; the next start uses the configured order again.
+pub fn prefer_fastest_endpoints(report: &[EndpointLatency]) {
+    let ranking = report
+        .iter()
+        .filter(|result| result.latency_ms.is_some())
+        .map(|result| result.endpoint.url.clone())
+        .collect();
+    *FASTEST_ENDPOINTS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = ranking;
+}
+
+/// `endpoints`, with the ones `prefer_fastest_endpoints` ranked moved to the front
+fn by_measured_latency(endpoints: &[String]) -> Vec<String> {
+    let ranking = FASTEST_ENDPOINTS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    rank_endpoints(endpoints, &ranking)
+}
+
+fn rank_endpoints(endpoints: &[String], ranking: &[String]) -> Vec<String> {
+    let mut ranked = endpoints.to_vec();
+    // Stable, so unranked relays keep their configured order
+    ranked.sort_by_key(|endpoint| {
+        let endpoint = endpoint.trim_end_matches('/');
+        ranking
+            .iter()
+            .position(|fast| fast == endpoint)
+            .unwrap_or(usize::MAX)
+    });
+    ranked
+}
+
+/// Local time minus the time `round` was scheduled for
+fn round_drift(round: u64, now: i64, beacon: &BeaconConfig) -> i64 {
+    now - round_to_timestamp(round, beacon) as i64
+}
+
+/// Whether the latest round is the one due now: published at most one period
+/// ago, with some slack for relay lag and clock skew
+fn drift_in_tolerance(drift: i64, beacon: &BeaconConfig) -> bool {
+    (-HEALTH_DRIFT_TOLERANCE_SECS..=beacon.period as i64 + HEALTH_DRIFT_TOLERANCE_SECS).contains(&drift)
+}
+
 // ============================================================================
 // ROUND CALCULATION
 // ============================================================================
@@ -41,29 +593,36 @@ const DRAND_ENDPOINTS: &[&str] = &[
 ///
 /// # Arguments
 /// * `unix_timestamp` - Unix timestamp in seconds
+/// * `beacon` - The beacon network
 ///
 /// # Returns
 /// The round number that will be available at or after the given timestamp
-pub fn timestamp_to_round(unix_timestamp: u64) -> u64 {
-    if unix_timestamp <= QUICKNET_GENESIS_TIME {
+pub fn timestamp_to_round(unix_timestamp: u64, beacon: &BeaconConfig) -> u64 {
+    if unix_timestamp <= beacon.genesis_time {
         return 1;
     }
-    let elapsed = unix_timestamp - QUICKNET_GENESIS_TIME;
-    (elapsed / QUICKNET_PERIOD) + 1
+    let elapsed = unix_timestamp - beacon.genesis_time;
+    (elapsed / beacon.period) + 1
 }
 
 /// Calculate the Unix timestamp when a specific round becomes available.
 ///
 /// # Arguments
 /// * `round` - The drand round number
+/// * `beacon` - The beacon network
 ///
 /// # Returns
 /// Unix timestamp when the round signature will be published
-pub fn round_to_timestamp(round: u64) -> u64 {
+pub fn round_to_timestamp(round: u64, beacon: &BeaconConfig) -> u64 {
     if round <= 1 {
-        return QUICKNET_GENESIS_TIME;
+        return beacon.genesis_time;
     }
-    QUICKNET_GENESIS_TIME + ((round - 1) * QUICKNET_PERIOD)
+    beacon.genesis_time + ((round - 1) * beacon.period)
+}
+
+/// Time at which a specific round becomes available, as a DateTime.
+pub fn round_to_datetime(round: u64, beacon: &BeaconConfig) -> DateTime<Utc> {
+    DateTime::from_timestamp(round_to_timestamp(round, beacon) as i64, 0).unwrap_or_default()
 }
 
 /// Convert a DateTime to the corresponding drand round number.
@@ -71,37 +630,202 @@ pub fn round_to_timestamp(round: u64) -> u64 {
 ///
 /// # Arguments
 /// * `datetime` - The unlock DateTime in UTC
+/// * `beacon` - The beacon network
 ///
 /// # Returns
 /// The round number to encrypt for
-pub fn datetime_to_round(datetime: DateTime<Utc>) -> u64 {
+pub fn datetime_to_round(datetime: DateTime<Utc>, beacon: &BeaconConfig) -> u64 {
     let timestamp = datetime.timestamp() as u64;
     // Add 1 to ensure we're past the unlock time when this round is available
-    timestamp_to_round(timestamp) + 1
+    timestamp_to_round(timestamp, beacon) + 1
+}
+
+/// Furthest ahead an unlock time may be set, in years
+///
+/// Later rounds are representable, but nobody can promise a beacon network
+/// will still be publishing them.
+pub const MAX_UNLOCK_HORIZON_YEARS: i64 = 100;
+
+/// Check that an unlock time maps to a round the beacon can actually publish
+///
+/// Times before the beacon's genesis have no round (they would silently clamp
+/// to round 1), and times beyond `MAX_UNLOCK_HORIZON_YEARS` are almost
+/// certainly typos (e.g. year 9999).
+pub fn validate_unlock_time(unlock_time: DateTime<Utc>, beacon: &BeaconConfig) -> Result<()> {
+    if unlock_time.timestamp() < beacon.genesis_time as i64 {
+        return Err(TimeLockerError::Parse(format!(
+            "Unlock time {} is before the drand beacon started ({})",
+            unlock_time.to_rfc3339(),
+            DateTime::<Utc>::from_timestamp(beacon.genesis_time as i64, 0)
+                .map(|genesis| genesis.to_rfc3339())
+                .unwrap_or_else(|| beacon.genesis_time.to_string())
+        )));
+    }
+
+    let horizon = crate::clock::now() + chrono::Duration::days(MAX_UNLOCK_HORIZON_YEARS * 365);
+    if unlock_time > horizon {
+        return Err(TimeLockerError::Parse(format!(
+            "Unlock time {} is more than {} years away",
+            unlock_time.to_rfc3339(),
+            MAX_UNLOCK_HORIZON_YEARS
+        )));
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// UNLOCK LEAD TIME
+// ============================================================================
+
+/// Default minimum distance between now and an unlock time, in seconds.
+///
+/// A few beacon periods, so the target round is reliably published (and
+/// reachable through the HTTP relays) by the time the user tries to unlock.
+pub const DEFAULT_MIN_UNLOCK_LEAD_SECS: u64 = 5 * QUICKNET_PERIOD;
+
+/// Environment variable overriding the minimum lead time (seconds), for testing
+pub const MIN_UNLOCK_LEAD_ENV: &str = "TIMELOCKER_MIN_LEAD_SECS";
+
+/// Minimum lead time for unlock times (`TIMELOCKER_MIN_LEAD_SECS` or the default)
+pub fn min_unlock_lead() -> chrono::Duration {
+    let secs = std::env::var(MIN_UNLOCK_LEAD_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MIN_UNLOCK_LEAD_SECS);
+    chrono::Duration::seconds(secs as i64)
+}
+
+/// Nudge an unlock time forward so it is at least `min_lead` after `now`.
+///
+/// # Returns
+/// The (possibly adjusted) unlock time and whether it was adjusted
+pub fn enforce_min_lead(
+    unlock_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    min_lead: chrono::Duration,
+) -> (DateTime<Utc>, bool) {
+    let earliest = now + min_lead;
+    if unlock_time < earliest {
+        (earliest, true)
+    } else {
+        (unlock_time, false)
+    }
 }
 
 // ============================================================================
 // ENCRYPTION
 // ============================================================================
 
-/// Generate a secure random password
+/// Symbols used by `PasswordCharset::AlphanumericSymbols`
+///
+/// Limited to characters no common shell or the 7z command line treats
+/// specially (no quotes, spaces, `@` list files, wildcards, `%`/`$` expansion
+/// or redirection), so the password can be pasted as `-p<password>` as is.
+const PASSWORD_SYMBOLS: &str = "#+,-.:=_~";
+
+const ALPHANUMERIC_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+const HEX_CHARS: &str = "0123456789abcdef";
+
+/// Characters a generated password is drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PasswordCharset {
+    /// A-Z, a-z, 0-9 (about 5.95 bits per character)
+    #[default]
+    Alphanumeric,
+    /// Alphanumerics plus command-line safe symbols (about 6.1 bits per character)
+    AlphanumericSymbols,
+    /// Lowercase hex digits (4 bits per character)
+    Hex,
+}
+
+impl PasswordCharset {
+    /// Every character passwords from this set may contain
+    pub fn chars(self) -> String {
+        match self {
+            Self::Alphanumeric => ALPHANUMERIC_CHARS.to_string(),
+            Self::AlphanumericSymbols => format!("{}{}", ALPHANUMERIC_CHARS, PASSWORD_SYMBOLS),
+            Self::Hex => HEX_CHARS.to_string(),
+        }
+    }
+}
+
+/// Generate a secure random alphanumeric password
 pub fn generate_password(length: u32) -> String {
-    thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(length as usize)
-        .map(char::from)
-        .collect()
+    generate_password_with_charset(length, PasswordCharset::Alphanumeric)
+}
+
+/// Generate a secure random password from the given character set
+pub fn generate_password_with_charset(length: u32, charset: PasswordCharset) -> String {
+    let mut rng = thread_rng();
+    match charset {
+        PasswordCharset::Alphanumeric => rng
+            .sample_iter(&Alphanumeric)
+            .take(length as usize)
+            .map(char::from)
+            .collect(),
+        _ => {
+            let chars: Vec<char> = charset.chars().chars().collect();
+            (0..length).map(|_| chars[rng.gen_range(0..chars.len())]).collect()
+        }
+    }
+}
+
+/// Passwords with fewer estimated bits of entropy than this are reported as weak
+pub const WEAK_PASSWORD_BITS: f64 = 60.0;
+
+/// Estimate the entropy of a password in bits
+///
+/// Brute-force estimate: length times log2 of the combined size of the
+/// character classes used (lowercase, uppercase, digits, ASCII symbols, other).
+/// Dictionary words and repeats aren't detected, so this is an upper bound.
+pub fn estimate_entropy(password: &str) -> f64 {
+    let (mut lower, mut upper, mut digit, mut symbol, mut other) = (false, false, false, false, false);
+    for c in password.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digit = true,
+            c if c.is_ascii() => symbol = true,
+            _ => other = true,
+        }
+    }
+
+    let charset = [(lower, 26), (upper, 26), (digit, 10), (symbol, 33), (other, 100)]
+        .iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum::<u32>();
+    if charset == 0 {
+        return 0.0;
+    }
+
+    password.chars().count() as f64 * f64::from(charset).log2()
+}
+
+/// Warning for a user-chosen password estimated below `WEAK_PASSWORD_BITS`
+pub fn weak_password_warning(password: &str) -> Option<String> {
+    let bits = estimate_entropy(password);
+    (bits < WEAK_PASSWORD_BITS).then(|| {
+        format!(
+            "Password is weak (about {:.0} bits of entropy, {:.0}+ recommended); \
+             it could be guessed without waiting for the unlock time",
+            bits, WEAK_PASSWORD_BITS
+        )
+    })
 }
 
 /// Encrypt data using tlock timelock encryption.
 ///
-/// This uses the drand Quicknet beacon for cryptographic time-locking.
+/// This uses a drand beacon (normally Quicknet) for cryptographic time-locking.
 /// The encrypted data can ONLY be decrypted after the specified drand round
 /// has been published, which corresponds to the unlock_time.
 ///
 /// # Arguments
 /// * `password` - The password/data to encrypt
 /// * `unlock_time` - The DateTime when decryption should become possible
+/// * `beacon` - The beacon network to lock against
 ///
 /// # Returns
 /// Base64-encoded tlock ciphertext with round metadata prepended
@@ -110,15 +834,21 @@ pub fn generate_password(length: u32) -> String {
 /// This is cryptographically enforced - no one (not even the encryptor) can
 /// decrypt the data until the drand network publishes the signature for the
 /// target round. The security is based on BLS threshold signatures.
-pub fn encrypt_with_tlock(password: &str, unlock_time: DateTime<Utc>) -> Result<String> {
+pub fn encrypt_with_tlock(
+    password: &str,
+    unlock_time: DateTime<Utc>,
+    beacon: &BeaconConfig,
+) -> Result<String> {
+    validate_unlock_time(unlock_time, beacon)?;
+
     // Calculate the target drand round for this unlock time
-    let round = datetime_to_round(unlock_time);
+    let round = datetime_to_round(unlock_time, beacon);
 
     // Decode chain hash and public key from hex
-    let chain_hash = hex::decode(QUICKNET_CHAIN_HASH)
+    let chain_hash = hex::decode(&beacon.chain_hash)
         .map_err(|e| TimeLockerError::Encryption(format!("Invalid chain hash: {}", e)))?;
 
-    let public_key = hex::decode(QUICKNET_PUBLIC_KEY)
+    let public_key = hex::decode(&beacon.public_key)
         .map_err(|e| TimeLockerError::Encryption(format!("Invalid public key: {}", e)))?;
 
     // Prepare input and output buffers
@@ -145,19 +875,50 @@ pub fn encrypt_with_tlock(password: &str, unlock_time: DateTime<Utc>) -> Result<
 
 /// Fetch the drand beacon signature for a specific round.
 ///
-/// Tries multiple endpoints for redundancy.
+/// Checks the on-disk signature cache first, then tries multiple endpoints
+/// for redundancy. Fetched signatures are added to the cache.
 ///
 /// # Arguments
 /// * `round` - The round number to fetch
+/// * `beacon` - The beacon network the round belongs to
 ///
 /// # Returns
 /// The BLS signature bytes for the round
-fn fetch_drand_signature(round: u64) -> Result<Vec<u8>> {
+fn fetch_drand_signature(round: u64, beacon: &BeaconConfig) -> Result<Vec<u8>> {
+    let cache_path = signature_cache_path();
+    let cache_key = signature_cache_key(beacon, round);
+
+    if let Some(signature) = cache_path.as_deref().and_then(|p| cached_signature(p, &cache_key)) {
+        return Ok(signature);
+    }
+
+    let signature = fetch_drand_signature_uncached(round, beacon)?;
+
+    if let Some(path) = cache_path.as_deref() {
+        if let Err(e) = cache_signature(path, &cache_key, &signature) {
+            eprintln!("[fetch_drand_signature] Warning: Failed to cache signature: {}", e);
+        }
+    }
+
+    Ok(signature)
+}
+
+/// Fetch a round's signature from the beacon's HTTP relays
+///
+/// # Errors
+/// - `TimeLockActive` if the round isn't due yet, so no relay can have it
+/// - `DrandUnavailable` if the round is due but every relay failed
+fn fetch_drand_signature_uncached(round: u64, beacon: &BeaconConfig) -> Result<Vec<u8>> {
     use drand_core::HttpClient;
 
-    let chain_path = format!("/{}", QUICKNET_CHAIN_HASH);
+    if !is_round_available(round, beacon) {
+        return Err(TimeLockerError::TimeLockActive);
+    }
+
+    let chain_path = format!("/{}", beacon.chain_hash);
+    let mut last_error = String::from("no endpoints configured");
 
-    for endpoint in DRAND_ENDPOINTS {
+    for endpoint in &by_measured_latency(&beacon.endpoints) {
         let url = format!("{}{}", endpoint, chain_path);
 
         match HttpClient::new(&url, None) {
@@ -171,34 +932,117 @@ fn fetch_drand_signature(round: u64) -> Result<Vec<u8>> {
                     Err(e) => {
                         // Try next endpoint
                         eprintln!("Drand endpoint {} failed for round {}: {}", endpoint, round, e);
+                        last_error = format!("{}: {}", endpoint, e);
                         continue;
                     }
                 }
             }
             Err(e) => {
                 eprintln!("Failed to create client for {}: {}", endpoint, e);
+                last_error = format!("{}: {}", endpoint, e);
                 continue;
             }
         }
     }
 
-    Err(TimeLockerError::Decryption(format!(
-        "Failed to fetch drand signature for round {} from all endpoints. \
-         The round may not have been published yet (time lock still active).",
-        round
+    Err(TimeLockerError::DrandUnavailable(format!(
+        "round {} is published but no endpoint returned it (last error: {}); \
+         check your connection and try again",
+        round, last_error
     )))
 }
 
+// ============================================================================
+// SIGNATURE CACHE
+// ============================================================================
+// Beacon signatures never change once published, so they are kept forever in
+// a small JSON map ("<chain hash>:<round>" -> hex signature). Unlocking several
+// files locked to the same round then only hits the network once.
+
+/// Signature cache file name, next to the executable
+const SIGNATURE_CACHE_FILE: &str = "drand_signatures.json";
+
+/// Environment variable overriding the signature cache location
+pub const SIGNATURE_CACHE_ENV: &str = "TIMELOCKER_SIGNATURE_CACHE";
+
+/// Location of the signature cache (`TIMELOCKER_SIGNATURE_CACHE` or next to the executable)
+pub fn signature_cache_path() -> Option<std::path::PathBuf> {
+    if let Ok(path) = std::env::var(SIGNATURE_CACHE_ENV) {
+        if !path.is_empty() {
+            return Some(std::path::PathBuf::from(path));
+        }
+    }
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(SIGNATURE_CACHE_FILE)))
+}
+
+/// Delete all cached signatures
+pub fn clear_signature_cache() -> Result<()> {
+    match signature_cache_path() {
+        Some(path) => match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        },
+        None => Ok(()),
+    }
+}
+
+fn signature_cache_key(beacon: &BeaconConfig, round: u64) -> String {
+    format!("{}:{}", beacon.chain_hash.to_ascii_lowercase(), round)
+}
+
+/// Read the cache; a missing or unreadable cache counts as empty
+fn load_signature_cache(path: &std::path::Path) -> std::collections::BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn cached_signature(path: &std::path::Path, key: &str) -> Option<Vec<u8>> {
+    load_signature_cache(path)
+        .get(key)
+        .and_then(|sig| hex::decode(sig).ok())
+}
+
+/// Add a signature to the cache
+///
+/// The cache is rewritten through a uniquely named temp file and renamed into
+/// place, so concurrent unlocks never see a half-written file. A racing writer
+/// may drop the other's new entry, which only costs a refetch later.
+fn cache_signature(path: &std::path::Path, key: &str, signature: &[u8]) -> Result<()> {
+    let mut cache = load_signature_cache(path);
+    cache.insert(key.to_string(), hex::encode(signature));
+
+    let json = serde_json::to_string_pretty(&cache)
+        .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize signature cache: {}", e)))?;
+
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let temp_path = std::path::PathBuf::from(temp_name);
+
+    std::fs::write(&temp_path, json)?;
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
 /// Check if a specific drand round is available (time has passed).
 ///
 /// # Arguments
 /// * `round` - The round number to check
+/// * `beacon` - The beacon network the round belongs to
 ///
 /// # Returns
 /// true if the round signature is available, false otherwise
-pub fn is_round_available(round: u64) -> bool {
-    let round_time = round_to_timestamp(round);
-    let now = Utc::now().timestamp() as u64;
+pub fn is_round_available(round: u64, beacon: &BeaconConfig) -> bool {
+    let round_time = round_to_timestamp(round, beacon);
+    let now = crate::clock::now().timestamp() as u64;
     now >= round_time
 }
 
@@ -207,17 +1051,53 @@ pub fn is_round_available(round: u64) -> bool {
 /// This fetches the drand signature for the encrypted round and uses it
 /// to decrypt the data. Will fail if the round hasn't been published yet.
 ///
+/// The round prepended to the ciphertext is authoritative, exactly as in
+/// `decrypt_with_tlock_auto`: `unlock_time` comes from metadata, which may
+/// be rounded, edited or (for migrated files) reconstructed, so a mismatch
+/// is only logged.
+///
 /// # Arguments
 /// * `encrypted` - The base64-encoded tlock ciphertext (with round prepended)
-/// * `unlock_time` - The expected unlock time (used for verification)
+/// * `unlock_time` - The expected unlock time, for a sanity check in the log
+/// * `beacon` - The beacon network the data was locked against
 ///
 /// # Returns
 /// The decrypted password/data
 ///
 /// # Errors
 /// - `TimeLockActive` if the drand round hasn't been published yet
-/// - `Decryption` if the data is corrupted or signature fetch fails
-pub fn decrypt_with_tlock(encrypted: &str, unlock_time: DateTime<Utc>) -> Result<String> {
+/// - `DrandUnavailable` if it has, but no drand endpoint could be reached
+/// - `Decryption` if the data is corrupted
+pub fn decrypt_with_tlock(
+    encrypted: &str,
+    unlock_time: DateTime<Utc>,
+    beacon: &BeaconConfig,
+) -> Result<String> {
+    if let Ok(round) = ciphertext_round(encrypted) {
+        let expected_round = datetime_to_round(unlock_time, beacon);
+        if round != expected_round {
+            eprintln!(
+                "[decrypt_with_tlock] Ciphertext round {} differs from round {} of the recorded unlock time; using the ciphertext's",
+                round, expected_round
+            );
+        }
+    }
+
+    decrypt_with_tlock_auto(encrypted, beacon)
+}
+
+/// Decrypt time-locked data by extracting round from the ciphertext.
+///
+/// This version doesn't require the unlock_time parameter - it extracts
+/// the round number from the encrypted data itself.
+///
+/// # Arguments
+/// * `encrypted` - The base64-encoded tlock ciphertext (with round prepended)
+/// * `beacon` - The beacon network the data was locked against
+///
+/// # Returns
+/// The decrypted password/data
+pub fn decrypt_with_tlock_auto(encrypted: &str, beacon: &BeaconConfig) -> Result<String> {
     // Decode from base64
     let encrypted_bytes = BASE64.decode(encrypted)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid base64: {}", e)))?;
@@ -233,22 +1113,16 @@ pub fn decrypt_with_tlock(encrypted: &str, unlock_time: DateTime<Utc>) -> Result
 
     let ciphertext = &encrypted_bytes[8..];
 
-    // Check if the unlock time has passed (optional early check)
-    let expected_round = datetime_to_round(unlock_time);
-    if round != expected_round {
-        eprintln!("Warning: Round mismatch. Stored: {}, Expected: {}", round, expected_round);
-    }
-
     // Check if we can even attempt decryption
-    if !is_round_available(round) {
+    if !is_round_available(round, beacon) {
         return Err(TimeLockerError::TimeLockActive);
     }
 
     // Fetch the drand signature for this round
-    let signature = fetch_drand_signature(round)?;
+    let signature = fetch_drand_signature(round, beacon)?;
 
     // Decode chain hash
-    let chain_hash = hex::decode(QUICKNET_CHAIN_HASH)
+    let chain_hash = hex::decode(&beacon.chain_hash)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid chain hash: {}", e)))?;
 
     // Prepare input and output buffers
@@ -264,65 +1138,95 @@ pub fn decrypt_with_tlock(encrypted: &str, unlock_time: DateTime<Utc>) -> Result
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid UTF-8 in decrypted data: {}", e)))
 }
 
-/// Decrypt time-locked data by extracting round from the ciphertext.
+/// Decrypt time-locked data with a drand signature the caller already has.
 ///
-/// This version doesn't require the unlock_time parameter - it extracts
-/// the round number from the encrypted data itself.
+/// For air-gapped machines: no network request is made and the local clock
+/// is not consulted, since a signature that decrypts the data proves its
+/// round has been published. The signature is the raw BLS signature of the
+/// round stored in the ciphertext.
 ///
 /// # Arguments
 /// * `encrypted` - The base64-encoded tlock ciphertext (with round prepended)
+/// * `signature` - The beacon's signature for that round
+/// * `beacon` - The beacon network the data was locked against
 ///
-/// # Returns
-/// The decrypted password/data
-pub fn decrypt_with_tlock_auto(encrypted: &str) -> Result<String> {
-    // Decode from base64
+/// # Errors
+/// `Decryption` if the signature has the wrong length or belongs to another
+/// round or beacon
+pub fn decrypt_with_tlock_offline(
+    encrypted: &str,
+    signature: &[u8],
+    beacon: &BeaconConfig,
+) -> Result<String> {
+    if !BLS_SIGNATURE_LENGTHS.contains(&signature.len()) {
+        return Err(TimeLockerError::Decryption(format!(
+            "Invalid signature length: {} bytes (expected 48 or 96)",
+            signature.len()
+        )));
+    }
+
+    let round = ciphertext_round(encrypted)?;
     let encrypted_bytes = BASE64.decode(encrypted)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid base64: {}", e)))?;
-
-    // Extract round number (first 8 bytes)
     if encrypted_bytes.len() < 9 {
         return Err(TimeLockerError::Decryption("Invalid encrypted data: too short".to_string()));
     }
-
-    let round_bytes: [u8; 8] = encrypted_bytes[0..8].try_into()
-        .map_err(|_| TimeLockerError::Decryption("Invalid round bytes".to_string()))?;
-    let round = u64::from_be_bytes(round_bytes);
-
     let ciphertext = &encrypted_bytes[8..];
 
-    // Check if we can even attempt decryption
-    if !is_round_available(round) {
-        return Err(TimeLockerError::TimeLockActive);
-    }
-
-    // Fetch the drand signature for this round
-    let signature = fetch_drand_signature(round)?;
-
     // Decode chain hash
-    let chain_hash = hex::decode(QUICKNET_CHAIN_HASH)
+    let chain_hash = hex::decode(&beacon.chain_hash)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid chain hash: {}", e)))?;
 
-    // Prepare input and output buffers
-    let input = Cursor::new(ciphertext);
     let mut output = Vec::new();
+    tlock_age::decrypt(&mut output, Cursor::new(ciphertext), &chain_hash, signature)
+        .map_err(|e| {
+            TimeLockerError::Decryption(format!(
+                "Signature does not decrypt this file (it must be the signature of round {}): {}",
+                round, e
+            ))
+        })?;
 
-    // Perform tlock decryption using the drand signature
-    tlock_age::decrypt(&mut output, input, &chain_hash, &signature)
-        .map_err(|e| TimeLockerError::Decryption(format!("Tlock decryption failed: {}", e)))?;
-
-    // Convert to string
     String::from_utf8(output)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid UTF-8 in decrypted data: {}", e)))
 }
 
+/// Valid BLS signature sizes: G1 (Quicknet-style) and G2 (unchained mainnet-style)
+const BLS_SIGNATURE_LENGTHS: [usize; 2] = [48, 96];
+
+/// Parse a hex-encoded drand signature, as shown by drand relays (`/public/<round>`)
+pub fn parse_signature_hex(signature_hex: &str) -> Result<Vec<u8>> {
+    let trimmed = signature_hex.trim();
+    let trimmed = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+
+    let signature = hex::decode(trimmed)
+        .map_err(|e| TimeLockerError::Decryption(format!("Malformed signature hex: {}", e)))?;
+    if !BLS_SIGNATURE_LENGTHS.contains(&signature.len()) {
+        return Err(TimeLockerError::Decryption(format!(
+            "Invalid signature length: {} bytes (expected 48 or 96)",
+            signature.len()
+        )));
+    }
+    Ok(signature)
+}
+
 /// Get information about an encrypted tlock ciphertext.
 ///
 /// # Arguments
 /// * `encrypted` - The base64-encoded tlock ciphertext
+/// * `beacon` - The beacon network the data was locked against
 ///
 /// # Returns
 /// Tuple of (round_number, unlock_timestamp, is_available)
-pub fn get_tlock_info(encrypted: &str) -> Result<(u64, u64, bool)> {
+pub fn get_tlock_info(encrypted: &str, beacon: &BeaconConfig) -> Result<(u64, u64, bool)> {
+    let round = ciphertext_round(encrypted)?;
+    let unlock_time = round_to_timestamp(round, beacon);
+    let available = is_round_available(round, beacon);
+
+    Ok((round, unlock_time, available))
+}
+
+/// Read the drand round prepended to a tlock ciphertext.
+pub fn ciphertext_round(encrypted: &str) -> Result<u64> {
     let encrypted_bytes = BASE64.decode(encrypted)
         .map_err(|e| TimeLockerError::Decryption(format!("Invalid base64: {}", e)))?;
 
@@ -332,11 +1236,172 @@ pub fn get_tlock_info(encrypted: &str) -> Result<(u64, u64, bool)> {
 
     let round_bytes: [u8; 8] = encrypted_bytes[0..8].try_into()
         .map_err(|_| TimeLockerError::Decryption("Invalid round bytes".to_string()))?;
-    let round = u64::from_be_bytes(round_bytes);
-    let unlock_time = round_to_timestamp(round);
-    let available = is_round_available(round);
+    Ok(u64::from_be_bytes(round_bytes))
+}
 
-    Ok((round, unlock_time, available))
+// ============================================================================
+// RECIPIENT KEYS
+// ============================================================================
+// A file can additionally have its archive password encrypted to an age X25519
+// recipient, whose holder can then unlock it at any time. This deliberately
+// bypasses the time lock for that one key holder.
+
+/// Encrypt the archive password to an age X25519 recipient (`age1...`)
+///
+/// # Returns
+/// Base64-encoded age ciphertext
+pub fn encrypt_to_recipient(password: &str, recipient: &str) -> Result<String> {
+    use std::io::Write;
+
+    let recipient: age::x25519::Recipient = recipient
+        .trim()
+        .parse()
+        .map_err(|e| TimeLockerError::Encryption(format!("Invalid age recipient: {}", e)))?;
+
+    let encryptor = age::Encryptor::with_recipients(vec![Box::new(recipient)])
+        .ok_or_else(|| TimeLockerError::Encryption("No age recipient given".to_string()))?;
+
+    let mut output = Vec::new();
+    let mut writer = encryptor
+        .wrap_output(&mut output)
+        .map_err(|e| TimeLockerError::Encryption(format!("Recipient encryption failed: {}", e)))?;
+    writer.write_all(password.as_bytes())?;
+    writer
+        .finish()
+        .map_err(|e| TimeLockerError::Encryption(format!("Recipient encryption failed: {}", e)))?;
+
+    Ok(BASE64.encode(&output))
+}
+
+/// Read the X25519 identity (`AGE-SECRET-KEY-1...`) from an age identity file
+///
+/// Comment lines are skipped; the first secret key found is used.
+pub fn read_identity_file(path: &std::path::Path) -> Result<String> {
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("AGE-SECRET-KEY-"))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            TimeLockerError::Parse(format!("No age secret key found in {}", path.display()))
+        })
+}
+
+/// Decrypt a password encrypted by `encrypt_to_recipient`
+///
+/// # Arguments
+/// * `encrypted` - Base64-encoded age ciphertext
+/// * `identity` - The recipient's secret key (`AGE-SECRET-KEY-1...`)
+///
+/// # Errors
+/// - `Decryption` if the identity doesn't match or the data is malformed
+pub fn decrypt_with_identity(encrypted: &str, identity: &str) -> Result<String> {
+    use std::io::Read;
+
+    let identity: age::x25519::Identity = identity
+        .trim()
+        .parse()
+        .map_err(|e| TimeLockerError::Decryption(format!("Invalid age identity: {}", e)))?;
+
+    let bytes = BASE64.decode(encrypted)
+        .map_err(|e| TimeLockerError::Decryption(format!("Invalid base64: {}", e)))?;
+
+    let decryptor = match age::Decryptor::new(&bytes[..])
+        .map_err(|e| TimeLockerError::Decryption(format!("Invalid recipient key data: {}", e)))?
+    {
+        age::Decryptor::Recipients(decryptor) => decryptor,
+        _ => {
+            return Err(TimeLockerError::Decryption(
+                "Recipient key data is passphrase-encrypted".to_string(),
+            ))
+        }
+    };
+
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|e| TimeLockerError::Decryption(format!("Identity does not open this file: {}", e)))?;
+    let mut password = String::new();
+    reader.read_to_string(&mut password)?;
+
+    Ok(password)
+}
+
+// ============================================================================
+// PASSWORD-KEYED SEALING
+// ============================================================================
+// Small blobs stored next to the archive (e.g. the entry index) are sealed with
+// AES-256-GCM under a key derived from the archive password, so they are only
+// readable by someone who can already open the archive.
+
+/// PBKDF2-HMAC-SHA256 iterations used to derive a sealing key from a password
+///
+/// At least the 2^19 SHA-256 rounds 7z spends on the archive password, so a
+/// sealed blob is never a cheaper way to test password guesses than the archive.
+const SEAL_KDF_ROUNDS: u32 = 1 << 19;
+
+/// Salt length for sealed blobs
+const SEAL_SALT_LEN: usize = 16;
+
+/// AES-GCM nonce length
+const SEAL_NONCE_LEN: usize = 12;
+
+/// Derive a 256-bit key from a password and salt (PBKDF2-HMAC-SHA256)
+fn derive_seal_key(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(password.as_bytes(), salt, SEAL_KDF_ROUNDS, &mut key);
+    key
+}
+
+/// Seal data with a password (AES-256-GCM).
+///
+/// # Returns
+/// Base64 of `salt || nonce || ciphertext`
+pub fn seal_with_password(plaintext: &[u8], password: &str) -> Result<String> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    let mut salt = [0u8; SEAL_SALT_LEN];
+    let mut nonce = [0u8; SEAL_NONCE_LEN];
+    thread_rng().fill(&mut salt[..]);
+    thread_rng().fill(&mut nonce[..]);
+
+    let key = derive_seal_key(password, &salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| TimeLockerError::Encryption(format!("Sealing failed: {}", e)))?;
+
+    let mut result = Vec::with_capacity(SEAL_SALT_LEN + SEAL_NONCE_LEN + ciphertext.len());
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(&result))
+}
+
+/// Open data sealed by `seal_with_password`.
+///
+/// # Errors
+/// - `Decryption` if the data is malformed or the password is wrong
+pub fn open_with_password(sealed: &str, password: &str) -> Result<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    let bytes = BASE64.decode(sealed)
+        .map_err(|e| TimeLockerError::Decryption(format!("Invalid base64: {}", e)))?;
+
+    if bytes.len() < SEAL_SALT_LEN + SEAL_NONCE_LEN {
+        return Err(TimeLockerError::Decryption("Invalid sealed data: too short".to_string()));
+    }
+
+    let (salt, rest) = bytes.split_at(SEAL_SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(SEAL_NONCE_LEN);
+
+    let key = derive_seal_key(password, salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| TimeLockerError::Decryption("Invalid password or corrupted data".to_string()))
 }
 
 #[cfg(test)]
@@ -350,6 +1415,29 @@ mod tests {
         assert!(password.chars().all(|c| c.is_alphanumeric()));
     }
 
+    #[test]
+    fn test_generate_password_with_charset() {
+        for charset in [
+            PasswordCharset::Alphanumeric,
+            PasswordCharset::AlphanumericSymbols,
+            PasswordCharset::Hex,
+        ] {
+            let allowed = charset.chars();
+            let password = generate_password_with_charset(256, charset);
+            assert_eq!(password.chars().count(), 256);
+            assert!(
+                password.chars().all(|c| allowed.contains(c)),
+                "{:?} produced {}",
+                charset,
+                password
+            );
+        }
+
+        // Symbols 7z or a shell would interpret are never used
+        let symbols = generate_password_with_charset(1024, PasswordCharset::AlphanumericSymbols);
+        assert!(!symbols.chars().any(|c| "\"' @*?%$!&|<>^`\\".contains(c)));
+    }
+
     #[test]
     fn test_generate_password_different_lengths() {
         for length in [8, 16, 32, 64] {
@@ -358,48 +1446,327 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_estimate_entropy() {
+        assert_eq!(estimate_entropy(""), 0.0);
+
+        // Short or single-class passwords are weak
+        for weak in ["password", "123456", "letmein1", "Summer2024"] {
+            assert!(estimate_entropy(weak) < WEAK_PASSWORD_BITS, "{} should be weak", weak);
+        }
+
+        // Generated passwords and long mixed ones are strong
+        assert!(estimate_entropy(&generate_password(32)) > 180.0);
+        assert!(weak_password_warning("password").is_some());
+        assert!(weak_password_warning(&generate_password(32)).is_none());
+        for strong in ["correct horse battery staple", "T1me-L0cker!vault#2030"] {
+            assert!(estimate_entropy(strong) >= WEAK_PASSWORD_BITS, "{} should be strong", strong);
+        }
+
+        // Counted in characters, not bytes
+        assert_eq!(estimate_entropy("ééé"), 3.0 * 100f64.log2());
+    }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let sealed = seal_with_password(b"index data", "archive_password").unwrap();
+        assert_eq!(open_with_password(&sealed, "archive_password").unwrap(), b"index data");
+        assert!(open_with_password(&sealed, "other_password").is_err());
+    }
+
+    #[test]
+    fn test_enforce_min_lead() {
+        use chrono::{Duration, TimeZone};
+
+        let now = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+        let lead = Duration::seconds(15);
+
+        // Far enough in the future: untouched
+        let later = now + Duration::minutes(5);
+        assert_eq!(enforce_min_lead(later, now, lead), (later, false));
+
+        // Too close: nudged to now + lead
+        let soon = now + Duration::seconds(3);
+        assert_eq!(enforce_min_lead(soon, now, lead), (now + lead, true));
+    }
+
     #[test]
     fn test_timestamp_to_round() {
+        let beacon = BeaconConfig::quicknet();
+
         // Genesis time should give round 1
-        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME), 1);
+        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME, &beacon), 1);
 
         // 3 seconds after genesis should give round 2
-        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME + 3), 2);
+        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME + 3, &beacon), 2);
 
         // 6 seconds after genesis should give round 3
-        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME + 6), 3);
+        assert_eq!(timestamp_to_round(QUICKNET_GENESIS_TIME + 6, &beacon), 3);
     }
 
     #[test]
     fn test_round_to_timestamp() {
+        let beacon = BeaconConfig::quicknet();
+
         // Round 1 should give genesis time
-        assert_eq!(round_to_timestamp(1), QUICKNET_GENESIS_TIME);
+        assert_eq!(round_to_timestamp(1, &beacon), QUICKNET_GENESIS_TIME);
 
         // Round 2 should be 3 seconds after genesis
-        assert_eq!(round_to_timestamp(2), QUICKNET_GENESIS_TIME + 3);
+        assert_eq!(round_to_timestamp(2, &beacon), QUICKNET_GENESIS_TIME + 3);
     }
 
     #[test]
     fn test_round_conversion_roundtrip() {
+        let beacon = BeaconConfig::quicknet();
         let original_round = 1000000u64;
-        let timestamp = round_to_timestamp(original_round);
-        let recovered_round = timestamp_to_round(timestamp);
+        let timestamp = round_to_timestamp(original_round, &beacon);
+        let recovered_round = timestamp_to_round(timestamp, &beacon);
         assert_eq!(original_round, recovered_round);
     }
 
+    #[test]
+    fn test_custom_beacon_rounds() {
+        let beacon = BeaconConfig {
+            chain_hash: "ab".repeat(32),
+            genesis_time: 1_700_000_000,
+            period: 30,
+            ..BeaconConfig::quicknet()
+        };
+        assert_eq!(timestamp_to_round(1_700_000_000 + 60, &beacon), 3);
+        assert_eq!(round_to_timestamp(3, &beacon), 1_700_000_000 + 60);
+
+        // Hex and round math are checked before a beacon is used
+        assert!(BeaconConfig::quicknet().validate().is_ok());
+        assert!(beacon.validate().is_ok());
+        let bad_key = BeaconConfig { public_key: "zz".repeat(96), ..beacon.clone() };
+        assert!(bad_key.validate().is_err());
+        let short_hash = BeaconConfig { chain_hash: "ab".repeat(16), ..beacon.clone() };
+        assert!(short_hash.validate().is_err());
+        let no_period = BeaconConfig { period: 0, ..beacon.clone() };
+        assert!(no_period.validate().is_err());
+        let future = BeaconConfig {
+            genesis_time: Utc::now().timestamp() as u64 + 3600,
+            ..beacon.clone()
+        };
+        assert!(future.validate().is_err());
+
+        // Files without a chain hash, or with Quicknet's, resolve to Quicknet
+        assert_eq!(BeaconConfig::for_chain_hash(None).unwrap(), BeaconConfig::quicknet());
+        assert_eq!(
+            BeaconConfig::for_chain_hash(Some(QUICKNET_CHAIN_HASH)).unwrap(),
+            BeaconConfig::quicknet()
+        );
+
+        // Round-trips through the JSON config file format
+        let json = serde_json::to_string(&beacon).unwrap();
+        assert_eq!(serde_json::from_str::<BeaconConfig>(&json).unwrap(), beacon);
+    }
+
+    #[test]
+    fn test_signature_cache() {
+        let dir = std::env::temp_dir().join("tlock_test_signature_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SIGNATURE_CACHE_FILE);
+
+        let beacon = BeaconConfig::quicknet();
+        let key = signature_cache_key(&beacon, 42);
+        assert_eq!(cached_signature(&path, &key), None);
+
+        cache_signature(&path, &key, &[1, 2, 3]).unwrap();
+        cache_signature(&path, &signature_cache_key(&beacon, 43), &[4, 5]).unwrap();
+        assert_eq!(cached_signature(&path, &key), Some(vec![1, 2, 3]));
+        assert_eq!(cached_signature(&path, &signature_cache_key(&beacon, 43)), Some(vec![4, 5]));
+
+        // Other chains don't share entries
+        let other = BeaconConfig { chain_hash: "ab".repeat(32), ..BeaconConfig::quicknet() };
+        assert_eq!(cached_signature(&path, &signature_cache_key(&other, 42)), None);
+
+        // No temp files left behind; a corrupt cache reads as empty
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::write(&path, b"{not json").unwrap();
+        assert_eq!(cached_signature(&path, &key), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_datetime_to_round() {
         use chrono::TimeZone;
 
         // Create a datetime after genesis
         let dt = Utc.timestamp_opt(QUICKNET_GENESIS_TIME as i64 + 10, 0).unwrap();
-        let round = datetime_to_round(dt);
+        let round = datetime_to_round(dt, &BeaconConfig::quicknet());
 
         // Should be round 4 + 1 (for safety margin) = 5
         // (10 seconds / 3 second period) + 1 = 4, then +1 for margin = 5
         assert!(round >= 4);
     }
 
+    #[test]
+    fn test_recipient_roundtrip() {
+        use age::secrecy::ExposeSecret;
+
+        let identity = age::x25519::Identity::generate();
+        let recipient = identity.to_public().to_string();
+        let secret = identity.to_string().expose_secret().clone();
+
+        let encrypted = encrypt_to_recipient("archive_password", &recipient).unwrap();
+        assert_eq!(decrypt_with_identity(&encrypted, &secret).unwrap(), "archive_password");
+
+        let other = age::x25519::Identity::generate();
+        assert!(decrypt_with_identity(&encrypted, other.to_string().expose_secret()).is_err());
+        assert!(encrypt_to_recipient("archive_password", "age1notakey").is_err());
+
+        let dir = std::env::temp_dir().join("timelocker_test_identity_file");
+        std::fs::create_dir_all(&dir).unwrap();
+        let identity_path = dir.join("key.txt");
+        std::fs::write(&identity_path, format!("# created: today\n# public key: {}\n{}\n", recipient, secret)).unwrap();
+        assert_eq!(read_identity_file(&identity_path).unwrap(), secret);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_unlock_time_bounds() {
+        use chrono::{Duration, TimeZone};
+
+        let beacon = BeaconConfig::quicknet();
+        let genesis = Utc.timestamp_opt(QUICKNET_GENESIS_TIME as i64, 0).unwrap();
+
+        assert!(validate_unlock_time(genesis, &beacon).is_ok());
+        let err = validate_unlock_time(genesis - Duration::seconds(1), &beacon).unwrap_err();
+        assert!(err.to_string().contains("before the drand beacon started"), "{}", err);
+        assert!(validate_unlock_time(Utc.timestamp_opt(0, 0).unwrap(), &beacon).is_err());
+
+        let now = crate::clock::now();
+        assert!(validate_unlock_time(now + Duration::days(99 * 365), &beacon).is_ok());
+        let err = validate_unlock_time(now + Duration::days(101 * 365), &beacon).unwrap_err();
+        assert!(err.to_string().contains("years away"), "{}", err);
+
+        // A typo'd year is rejected before anything is encrypted
+        let far = Utc.with_ymd_and_hms(9999, 1, 1, 0, 0, 0).unwrap();
+        assert!(encrypt_with_tlock("password", far, &beacon).is_err());
+    }
+
+    #[test]
+    fn test_round_availability_follows_clock() {
+        use chrono::{Duration, TimeZone};
+
+        let beacon = BeaconConfig::quicknet();
+        let unlocks = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+        let round = datetime_to_round(unlocks, &beacon);
+
+        crate::clock::set_fake_now(Some(unlocks - Duration::hours(1)));
+        assert!(!is_round_available(round, &beacon));
+        crate::clock::set_fake_now(Some(unlocks + Duration::hours(1)));
+        assert!(is_round_available(round, &beacon));
+        crate::clock::set_fake_now(None);
+    }
+
+    #[test]
+    fn test_drand_endpoints_default_order() {
+        // No TIMELOCKER_BEACON in the test environment: just the built-in relays
+        let endpoints = drand_endpoints().unwrap();
+        let urls: Vec<&str> = endpoints.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls, DRAND_ENDPOINTS);
+        assert!(endpoints.iter().all(|e| e.source == "built-in"));
+        assert!(endpoints.iter().all(|e| e.chain_hash == QUICKNET_CHAIN_HASH));
+
+        let mut beacon = BeaconConfig::quicknet();
+        beacon.endpoints = vec!["http://localhost:8080/".to_string()];
+        let custom = beacon_endpoints(&beacon, "my-beacon.json");
+        assert_eq!(custom[0].url, "http://localhost:8080");
+        assert_eq!(custom[0].source, "my-beacon.json");
+    }
+
+    #[test]
+    fn test_round_drift() {
+        let beacon = BeaconConfig::quicknet();
+        let round = 1_000;
+        let scheduled = round_to_timestamp(round, &beacon) as i64;
+
+        assert_eq!(round_drift(round, scheduled + 2, &beacon), 2);
+        assert!(drift_in_tolerance(round_drift(round, scheduled + 2, &beacon), &beacon));
+        // A relay stuck on an old round, or a clock far ahead or behind
+        assert!(!drift_in_tolerance(round_drift(round, scheduled + 600, &beacon), &beacon));
+        assert!(!drift_in_tolerance(round_drift(round, scheduled - 600, &beacon), &beacon));
+    }
+
+    #[test]
+    fn test_beacon_parameters_offline_config() {
+        let quicknet = BeaconConfig::quicknet();
+        let params = BeaconParameters::of(&quicknet);
+        let offline = params.to_offline_config().unwrap();
+        assert_eq!(offline.chain_hash, quicknet.chain_hash);
+        assert_eq!(offline.public_key, quicknet.public_key);
+        assert_eq!(
+            datetime_to_round(Utc::now(), &offline),
+            datetime_to_round(Utc::now(), &quicknet)
+        );
+        assert!(offline.endpoints.is_empty());
+
+        let broken = BeaconParameters { public_key: "abcd".to_string(), ..params };
+        assert!(broken.to_offline_config().is_err());
+    }
+
+    #[test]
+    fn test_rank_endpoints() {
+        let endpoints = vec![
+            "https://a.example".to_string(),
+            "https://b.example/".to_string(),
+            "https://c.example".to_string(),
+        ];
+        assert_eq!(rank_endpoints(&endpoints, &[]), endpoints);
+
+        // Measured relays first, fastest first; the rest keep their order
+        let ranking = vec!["https://c.example".to_string(), "https://b.example".to_string()];
+        assert_eq!(
+            rank_endpoints(&endpoints, &ranking),
+            vec![
+                "https://c.example".to_string(),
+                "https://b.example/".to_string(),
+                "https://a.example".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_benchmark_drand_endpoints() {
+        let report = benchmark_drand_endpoints(2).unwrap();
+        assert!(!report.is_empty());
+        assert!(report[0].latency_ms.is_some(), "{:?}", report);
+        assert!(report
+            .windows(2)
+            .all(|pair| pair[0].latency_ms.unwrap_or(u64::MAX) <= pair[1].latency_ms.unwrap_or(u64::MAX)));
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_check_drand_health() {
+        let health = check_drand_health().unwrap();
+        assert!(!health.is_empty());
+        assert!(health.iter().any(|h| h.healthy), "{:?}", health);
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_check_drand_endpoint() {
+        for endpoint in drand_endpoints().unwrap() {
+            check_drand_endpoint(&endpoint).expect("Built-in relay should answer");
+        }
+
+        let unreachable = DrandEndpoint {
+            url: "http://127.0.0.1:1".to_string(),
+            chain_hash: QUICKNET_CHAIN_HASH.to_string(),
+            source: "built-in".to_string(),
+        };
+        assert!(matches!(
+            check_drand_endpoint(&unreachable),
+            Err(TimeLockerError::Network(_))
+        ));
+    }
+
     // Note: Integration tests for encrypt/decrypt require network access
     // and a future unlock time that has passed. These should be run
     // as integration tests with appropriate timeouts.
@@ -413,10 +1780,10 @@ mod tests {
         // Use a time in the past (already unlockable)
         let unlock_time = Utc::now() - Duration::minutes(5);
 
-        let encrypted = encrypt_with_tlock(password, unlock_time)
+        let encrypted = encrypt_with_tlock(password, unlock_time, &BeaconConfig::quicknet())
             .expect("Encryption should succeed");
 
-        let decrypted = decrypt_with_tlock(&encrypted, unlock_time)
+        let decrypted = decrypt_with_tlock(&encrypted, unlock_time, &BeaconConfig::quicknet())
             .expect("Decryption should succeed for past time");
 
         assert_eq!(password, decrypted);
@@ -429,7 +1796,7 @@ mod tests {
         let password = "test_password";
         let unlock_time = Utc::now() + Duration::hours(1);
 
-        let encrypted = encrypt_with_tlock(password, unlock_time)
+        let encrypted = encrypt_with_tlock(password, unlock_time, &BeaconConfig::quicknet())
             .expect("Encryption should succeed");
 
         // Should be valid base64
@@ -452,10 +1819,10 @@ mod tests {
         let password = "test";
         let unlock_time = Utc::now() + Duration::hours(24);
 
-        let encrypted = encrypt_with_tlock(password, unlock_time)
+        let encrypted = encrypt_with_tlock(password, unlock_time, &BeaconConfig::quicknet())
             .expect("Encryption should succeed");
 
-        let (round, unlock_ts, available) = get_tlock_info(&encrypted)
+        let (round, unlock_ts, available) = get_tlock_info(&encrypted, &BeaconConfig::quicknet())
             .expect("Should extract info");
 
         assert!(round > 0);
@@ -471,11 +1838,67 @@ mod tests {
         let password = "secret";
         let unlock_time = Utc::now() + Duration::hours(24);
 
-        let encrypted = encrypt_with_tlock(password, unlock_time)
+        let encrypted = encrypt_with_tlock(password, unlock_time, &BeaconConfig::quicknet())
             .expect("Encryption should succeed");
 
         // Attempting to decrypt should fail with TimeLockActive
-        let result = decrypt_with_tlock(&encrypted, unlock_time);
+        let result = decrypt_with_tlock(&encrypted, unlock_time, &BeaconConfig::quicknet());
         assert!(matches!(result, Err(TimeLockerError::TimeLockActive)));
     }
+
+    #[test]
+    fn test_decrypt_trusts_ciphertext_round() {
+        use chrono::Duration;
+
+        // Metadata claiming an unlock time long past doesn't open a future round
+        let encrypted = encrypt_with_tlock("secret", Utc::now() + Duration::hours(24), &BeaconConfig::quicknet())
+            .expect("Encryption should succeed");
+        let result = decrypt_with_tlock(&encrypted, Utc::now() - Duration::days(365), &BeaconConfig::quicknet());
+        assert!(matches!(result, Err(TimeLockerError::TimeLockActive)));
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_decrypt_with_mismatched_unlock_time() {
+        use chrono::Duration;
+
+        let password = "migrated_secret";
+        let unlock_time = Utc::now() - Duration::minutes(5);
+        let encrypted = encrypt_with_tlock(password, unlock_time, &BeaconConfig::quicknet())
+            .expect("Encryption should succeed");
+
+        // E.g. a migrated file whose recorded unlock time is off by days
+        let wrong_time = unlock_time + Duration::days(3);
+        let decrypted = decrypt_with_tlock(&encrypted, wrong_time, &BeaconConfig::quicknet())
+            .expect("The round in the ciphertext decides");
+        assert_eq!(password, decrypted);
+    }
+
+    #[test]
+    fn test_parse_signature_hex() {
+        let hex_48 = "ab".repeat(48);
+        assert_eq!(parse_signature_hex(&hex_48).unwrap().len(), 48);
+        assert_eq!(parse_signature_hex(&format!(" 0x{} ", hex_48)).unwrap().len(), 48);
+        assert_eq!(parse_signature_hex(&"cd".repeat(96)).unwrap().len(), 96);
+
+        assert!(parse_signature_hex("not hex").is_err());
+        assert!(parse_signature_hex("abc").is_err());
+        assert!(parse_signature_hex(&"ab".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_offline_rejects_wrong_signature() {
+        use chrono::Duration;
+
+        let unlock_time = Utc::now() + Duration::hours(24);
+        let encrypted = encrypt_with_tlock("secret", unlock_time, &BeaconConfig::quicknet())
+            .expect("Encryption should succeed");
+
+        // No network: a signature that isn't the round's fails to decrypt
+        let result = decrypt_with_tlock_offline(&encrypted, &[0u8; 48], &BeaconConfig::quicknet());
+        assert!(matches!(result, Err(TimeLockerError::Decryption(_))));
+
+        let result = decrypt_with_tlock_offline(&encrypted, &[0u8; 10], &BeaconConfig::quicknet());
+        assert!(matches!(result, Err(TimeLockerError::Decryption(_))));
+    }
 }
@@ -0,0 +1,534 @@
+//! Resumable download of remote .7z.tlock files
+//!
+//! Large locked files stored on web/cloud storage are downloaded to a partial
+//! file in the temp directory. If the connection drops, the next attempt (or
+//! the next run) resumes from the bytes already received using an HTTP `Range`
+//! request instead of starting over.
+//!
+//! The response's `ETag` (or `Last-Modified`) is saved next to the partial file
+//! and sent back as `If-Range`, so a file that changed on the server between
+//! attempts is downloaded again from the start rather than spliced onto the
+//! bytes of the old version.
+//!
+//! Partial files live in a directory only the current user can write to, so
+//! another user can't plant a file or a symlink at the predictable path.
+
+use crate::error::{Result, TimeLockerError};
+use crate::progress::ProgressTracker;
+use crate::tlock_format::{TlockArchive, TlockMetadata};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Number of attempts before a download is given up
+pub const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// Delay between download attempts
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Size of the buffer used when streaming the response body
+const DOWNLOAD_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Directory under the temp directory that holds partial downloads
+const DOWNLOAD_DIR: &str = "time-locker-downloads";
+
+/// Check whether a source string refers to a remote file
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Partial-download path for a URL
+///
+/// Deterministic per URL, so an interrupted download is resumed by later runs.
+pub fn partial_path(url: &str) -> PathBuf {
+    // SHA-256 keeps the name the same after a toolchain upgrade, so the partial file is still found
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(url.as_bytes());
+    std::env::temp_dir()
+        .join(DOWNLOAD_DIR)
+        .join(format!("{}.7z.tlock.part", hex::encode(&digest[..16])))
+}
+
+/// Make sure other users can't plant or redirect a partial download at `dest`
+///
+/// Creates the parent directory (private to the current user on Unix) when it
+/// is missing, and refuses one that is a symlink or writable by other users.
+fn prepare_download_dir(dest: &Path) -> Result<()> {
+    let Some(dir) = dest.parent().filter(|dir| !dir.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+
+    if !dir.exists() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(dir)?;
+    }
+
+    let meta = fs::symlink_metadata(dir)?;
+    #[cfg(unix)]
+    let shared = std::os::unix::fs::PermissionsExt::mode(&meta.permissions()) & 0o022 != 0;
+    #[cfg(not(unix))]
+    let shared = false;
+    if !meta.is_dir() || shared {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!(
+                "Refusing to download into {}: it is a symlink or writable by other users",
+                dir.display()
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Length of the partial download at `dest`, or 0 if there is none
+///
+/// Anything other than a regular file (such as a symlink) is removed rather than followed.
+fn partial_len(dest: &Path) -> Result<u64> {
+    match fs::symlink_metadata(dest) {
+        Ok(meta) if meta.is_file() => Ok(meta.len()),
+        Ok(_) => {
+            eprintln!("[download_resumable] Partial download is not a regular file, removing it");
+            discard_partial(dest)?;
+            Ok(0)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Create `path` as a new file, removing whatever was there first
+///
+/// `create_new` fails rather than following a symlink that shows up at the path.
+fn create_fresh(path: &Path) -> Result<File> {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(OpenOptions::new().write(true).create_new(true).open(path)?)
+}
+
+/// Where the validator of the response a partial download came from is kept
+fn validator_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".validator");
+    PathBuf::from(name)
+}
+
+/// Validator identifying the remote file's version, for `If-Range`
+///
+/// A strong `ETag` if there is one, otherwise `Last-Modified`. Weak ETags
+/// (`W/"..."`) can't be used with `If-Range`.
+fn response_validator(response: &ureq::Response) -> Option<String> {
+    response
+        .header("ETag")
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| response.header("Last-Modified"))
+        .map(str::to_string)
+}
+
+/// Drop a partial download and its validator so the next request starts from zero
+fn discard_partial(dest: &Path) -> Result<()> {
+    for path in [dest.to_path_buf(), validator_path(dest)] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Fetch only the header and metadata of a remote .7z.tlock file
+///
+/// The response body is dropped as soon as the metadata has been read, so this
+/// is cheap even for multi-GB files and can be used to show info (or refuse a
+/// still-locked file) before committing to the full download.
+pub fn fetch_metadata(url: &str) -> Result<TlockMetadata> {
+    eprintln!("[fetch_metadata] Fetching header from: {}", url);
+
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| TimeLockerError::Network(format!("Failed to fetch {}: {}", url, e)))?;
+
+    let mut reader = response.into_reader();
+    TlockArchive::read_metadata_from(&mut reader)
+}
+
+/// Download a URL to `dest`, resuming from any bytes already in `dest`
+///
+/// Progress is recorded in `tracker` and `on_progress` is called after every
+/// chunk. Transient failures are retried up to `MAX_DOWNLOAD_ATTEMPTS` times,
+/// each retry continuing where the previous one stopped.
+///
+/// # Returns
+/// Path to the completed download (`dest`)
+pub fn download_resumable(
+    url: &str,
+    dest: &Path,
+    tracker: &ProgressTracker,
+    mut on_progress: impl FnMut(&ProgressTracker),
+) -> Result<PathBuf> {
+    prepare_download_dir(dest)?;
+
+    let mut last_error = None;
+
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        if tracker.is_cancelled() {
+            return Err(TimeLockerError::Network("Download cancelled".to_string()));
+        }
+
+        match download_attempt(url, dest, tracker, &mut on_progress) {
+            Ok(()) => {
+                let _ = fs::remove_file(validator_path(dest));
+                eprintln!("[download_resumable] Download complete: {:?}", dest);
+                return Ok(dest.to_path_buf());
+            }
+            Err(e) => {
+                eprintln!(
+                    "[download_resumable] Attempt {}/{} failed: {}",
+                    attempt, MAX_DOWNLOAD_ATTEMPTS, e
+                );
+                last_error = Some(e);
+                if attempt < MAX_DOWNLOAD_ATTEMPTS {
+                    std::thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| TimeLockerError::Network("Download failed".to_string())))
+}
+
+/// A single download attempt, continuing from the current size of `dest`
+///
+/// Only resumes when the validator saved with the partial file still matches
+/// the remote file; otherwise the partial file is discarded and the download
+/// starts over.
+fn download_attempt(
+    url: &str,
+    dest: &Path,
+    tracker: &ProgressTracker,
+    on_progress: &mut impl FnMut(&ProgressTracker),
+) -> Result<()> {
+    let offset = partial_len(dest)?;
+    let saved_validator = fs::read_to_string(validator_path(dest)).ok();
+
+    // Without a validator there's no telling whether the bytes on disk are still current
+    if offset > 0 && saved_validator.is_none() {
+        eprintln!("[download_resumable] No validator for partial download, starting over");
+        discard_partial(dest)?;
+        return download_attempt(url, dest, tracker, on_progress);
+    }
+
+    let request = ureq::get(url);
+    let request = match (&saved_validator, offset > 0) {
+        (Some(validator), true) => {
+            eprintln!("[download_resumable] Resuming from byte {}", offset);
+            request
+                .set("Range", &format!("bytes={}-", offset))
+                .set("If-Range", validator)
+        }
+        _ => request,
+    };
+
+    let response = match request.call() {
+        Ok(response) => response,
+        // The partial file is at least as long as the remote one, so it can't be a prefix of it
+        Err(ureq::Error::Status(416, _)) if offset > 0 => {
+            eprintln!("[download_resumable] Range not satisfiable, starting over");
+            discard_partial(dest)?;
+            return download_attempt(url, dest, tracker, on_progress);
+        }
+        Err(e) => {
+            return Err(TimeLockerError::Network(format!("Failed to fetch {}: {}", url, e)));
+        }
+    };
+
+    let validator = response_validator(&response);
+    // 206 continues the partial file; 200 means the server ignored the range or
+    // the file changed (If-Range didn't match) and the whole file is being sent
+    let resumed = response.status() == 206;
+    if resumed && validator != saved_validator {
+        eprintln!("[download_resumable] Remote file changed, starting over");
+        discard_partial(dest)?;
+        return download_attempt(url, dest, tracker, on_progress);
+    }
+    if !resumed {
+        match &validator {
+            Some(validator) => create_fresh(&validator_path(dest))?.write_all(validator.as_bytes())?,
+            None => {
+                let _ = fs::remove_file(validator_path(dest));
+            }
+        }
+    }
+
+    let start = if resumed { offset } else { 0 };
+    let total = if resumed {
+        response
+            .header("Content-Range")
+            .and_then(|range| range.rsplit('/').next())
+            .and_then(|total| total.parse::<u64>().ok())
+    } else {
+        response
+            .header("Content-Length")
+            .and_then(|len| len.parse::<u64>().ok())
+    };
+
+    let file = if resumed {
+        OpenOptions::new().append(true).open(dest)?
+    } else {
+        create_fresh(dest)?
+    };
+    let mut writer = BufWriter::new(file);
+
+    if let Some(total) = total {
+        tracker.set_total(total, 1);
+    }
+    tracker.set_bytes_written(start);
+
+    let mut reader = response.into_reader();
+    let mut buf = vec![0u8; DOWNLOAD_BUFFER_SIZE];
+    loop {
+        if tracker.is_cancelled() {
+            writer.flush()?;
+            return Err(TimeLockerError::Network("Download cancelled".to_string()));
+        }
+
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        tracker.add_bytes(n as u64);
+        on_progress(tracker);
+    }
+    writer.flush()?;
+
+    // A connection closed early looks like EOF; treat a short body as a failure to retry
+    if let Some(total) = total {
+        let received = tracker.get_bytes_written();
+        if received < total {
+            return Err(TimeLockerError::Network(format!(
+                "Connection closed early: received {} of {} bytes",
+                received, total
+            )));
+        }
+    }
+
+    tracker.increment_files();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::{Ipv4Addr, TcpListener};
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/vault/file.7z.tlock"));
+        assert!(is_url("http://localhost:8080/file.7z.tlock"));
+        assert!(!is_url("/home/user/vault/file.7z.tlock"));
+        assert!(!is_url("C:\\vault\\file.7z.tlock"));
+    }
+
+    #[test]
+    fn test_partial_path_is_stable_per_url() {
+        let a = partial_path("https://example.com/a.7z.tlock");
+        assert_eq!(a, partial_path("https://example.com/a.7z.tlock"));
+        assert_ne!(a, partial_path("https://example.com/b.7z.tlock"));
+        assert!(a.starts_with(std::env::temp_dir().join(DOWNLOAD_DIR)));
+    }
+
+    #[test]
+    fn test_discard_partial_removes_validator() -> Result<()> {
+        let dest = std::env::temp_dir().join("tlock_test_download_discard.7z.tlock.part");
+        fs::write(&dest, b"old bytes")?;
+        fs::write(validator_path(&dest), "\"v1\"")?;
+
+        discard_partial(&dest)?;
+        assert!(!dest.exists());
+        assert!(!validator_path(&dest).exists());
+
+        // Nothing left to remove is fine
+        discard_partial(&dest)?;
+        Ok(())
+    }
+
+    /// Serve one canned response per connection on a local port
+    ///
+    /// Returns the URL to fetch and a handle yielding the head of every request received.
+    fn serve(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let url = format!("http://{}/file.7z.tlock", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut head = String::new();
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                            break;
+                        }
+                        head.push_str(&line);
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    head
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        response.push_str(body);
+        response
+    }
+
+    /// A partial download in a fresh private directory, holding `bytes` saved under `validator`
+    fn partial_download(name: &str, bytes: &str, validator: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        let dest = dir.join("file.7z.tlock.part");
+        prepare_download_dir(&dest)?;
+        fs::write(&dest, bytes)?;
+        fs::write(validator_path(&dest), validator)?;
+        Ok(dest)
+    }
+
+    #[test]
+    fn test_download_resumes_on_206() -> Result<()> {
+        let dest = partial_download("tlock_test_download_206", "01234", "\"v1\"")?;
+        let (url, server) = serve(vec![response(
+            "206 Partial Content",
+            &[("ETag", "\"v1\""), ("Content-Range", "bytes 5-9/10")],
+            "56789",
+        )]);
+
+        download_resumable(&url, &dest, &ProgressTracker::new(), |_| {})?;
+
+        assert_eq!(fs::read_to_string(&dest)?, "0123456789");
+        assert!(!validator_path(&dest).exists());
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("Range: bytes=5-"));
+        assert!(requests[0].contains("If-Range: \"v1\""));
+
+        fs::remove_dir_all(dest.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_restarts_on_200() -> Result<()> {
+        // The server ignored the range (or If-Range didn't match) and sent the whole file
+        let dest = partial_download("tlock_test_download_200", "abcde", "\"v1\"")?;
+        let (url, server) = serve(vec![response("200 OK", &[("ETag", "\"v2\"")], "0123456789")]);
+
+        download_resumable(&url, &dest, &ProgressTracker::new(), |_| {})?;
+
+        assert_eq!(fs::read_to_string(&dest)?, "0123456789");
+        assert_eq!(server.join().unwrap().len(), 1);
+
+        fs::remove_dir_all(dest.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_restarts_on_416() -> Result<()> {
+        let dest = partial_download("tlock_test_download_416", "0123456789extra", "\"v1\"")?;
+        let (url, server) = serve(vec![
+            response("416 Range Not Satisfiable", &[], ""),
+            response("200 OK", &[("ETag", "\"v1\"")], "0123456789"),
+        ]);
+
+        download_resumable(&url, &dest, &ProgressTracker::new(), |_| {})?;
+
+        assert_eq!(fs::read_to_string(&dest)?, "0123456789");
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("Range: bytes=15-"));
+        assert!(!requests[1].contains("Range:"));
+
+        fs::remove_dir_all(dest.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_restarts_when_validator_changes() -> Result<()> {
+        // A 206 for a different version of the file must not be spliced onto the old bytes
+        let dest = partial_download("tlock_test_download_changed", "01234", "\"v1\"")?;
+        let (url, server) = serve(vec![
+            response(
+                "206 Partial Content",
+                &[("ETag", "\"v2\""), ("Content-Range", "bytes 5-9/10")],
+                "FGHIJ",
+            ),
+            response("200 OK", &[("ETag", "\"v2\"")], "ABCDEFGHIJ"),
+        ]);
+
+        download_resumable(&url, &dest, &ProgressTracker::new(), |_| {})?;
+
+        assert_eq!(fs::read_to_string(&dest)?, "ABCDEFGHIJ");
+        let requests = server.join().unwrap();
+        assert!(!requests[1].contains("Range:"));
+
+        fs::remove_dir_all(dest.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_download_does_not_follow_symlink() -> Result<()> {
+        let dir = std::env::temp_dir().join("tlock_test_download_symlink");
+        let _ = fs::remove_dir_all(&dir);
+        let dest = dir.join("file.7z.tlock.part");
+        prepare_download_dir(&dest)?;
+        let target = dir.join("target.txt");
+        fs::write(&target, "keep me")?;
+        std::os::unix::fs::symlink(&target, &dest)?;
+        let (url, server) = serve(vec![response("200 OK", &[], "0123456789")]);
+
+        download_resumable(&url, &dest, &ProgressTracker::new(), |_| {})?;
+
+        assert_eq!(fs::read_to_string(&target)?, "keep me");
+        assert!(!fs::symlink_metadata(&dest)?.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&dest)?, "0123456789");
+        server.join().unwrap();
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_download_dir_refuses_shared_dir() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("tlock_test_download_shared");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777))?;
+
+        assert!(prepare_download_dir(&dir.join("file.7z.tlock.part")).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -43,6 +43,12 @@ pub enum TimeLockerError {
 
     #[error("File not found: {0}")]
     FileNotFound(String),
+
+    #[error("Unsupported .7z.tlock version: {version} (max supported: {max}); it was written by a newer version of Time Locker")]
+    UnsupportedVersion { version: u8, max: u8 },
+
+    #[error("Payload checksum mismatch (expected {expected}, got {actual}): the file is corrupted")]
+    ChecksumMismatch { expected: String, actual: String },
 }
 
 pub type Result<T> = std::result::Result<T, TimeLockerError>;
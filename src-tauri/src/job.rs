@@ -0,0 +1,190 @@
+//! Resumable lock job files
+//!
+//! A job file records, per source, whether it has been locked and where the
+//! resulting .7z.tlock went. It is rewritten after every completed item, so a
+//! batch of `lock --job-file` runs interrupted by a crash or Ctrl+C can be
+//! re-run with the same job file and `--unlock-at` and only the remaining
+//! sources are locked.
+//!
+//! ```json
+//! {
+//!   "unlock_at": "2026-07-01",
+//!   "entries": [
+//!     { "source": "/data/a", "tlock_path": "/vault/a.7z.tlock", "done": true },
+//!     { "source": "/data/b", "tlock_path": null, "done": false }
+//!   ]
+//! }
+//! ```
+
+use crate::error::{Result, TimeLockerError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One source in a lock job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobEntry {
+    /// File or directory to lock
+    pub source: PathBuf,
+    /// Resulting .7z.tlock file, once locked
+    pub tlock_path: Option<PathBuf>,
+    /// Whether this source has been locked
+    pub done: bool,
+}
+
+/// A lock job, persisted as JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LockJob {
+    /// Unlock time the job was started with, as given on the command line
+    pub unlock_at: String,
+    pub entries: Vec<JobEntry>,
+}
+
+impl LockJob {
+    /// Load a job file, or start a new job if it doesn't exist yet
+    ///
+    /// # Errors
+    /// - `Parse` if the job file was started with a different `unlock_at`
+    pub fn load_or_new(path: &Path, unlock_at: &str) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                unlock_at: unlock_at.to_string(),
+                entries: Vec::new(),
+            });
+        }
+
+        let content = fs::read_to_string(path)?;
+        let job: Self = serde_json::from_str(&content)
+            .map_err(|e| TimeLockerError::Parse(format!("Invalid job file {}: {}", path.display(), e)))?;
+
+        // Resuming with another unlock time would lock the rest of the batch differently
+        if job.unlock_at != unlock_at {
+            return Err(TimeLockerError::Parse(format!(
+                "Job file {} was started with --unlock-at {}, not {}",
+                path.display(),
+                job.unlock_at,
+                unlock_at
+            )));
+        }
+        Ok(job)
+    }
+
+    /// Save the job file, replacing the previous version atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize job file: {}", e)))?;
+
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+
+        fs::write(&temp_path, json)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Add sources that aren't in the job yet
+    ///
+    /// Sources are stored canonicalized, so `./a` and `/data/a` are the same entry.
+    pub fn add_sources(&mut self, sources: &[PathBuf]) {
+        for source in sources {
+            if self.position(source).is_none() {
+                self.entries.push(JobEntry {
+                    source: canonical_source(source),
+                    tlock_path: None,
+                    done: false,
+                });
+            }
+        }
+    }
+
+    /// Index of the entry for `source`, however the path is spelled
+    pub fn position(&self, source: &Path) -> Option<usize> {
+        let source = canonical_source(source);
+        self.entries.iter().position(|e| canonical_source(&e.source) == source)
+    }
+
+    /// Indices of entries that still need locking
+    ///
+    /// An entry marked done whose .7z.tlock has since disappeared is locked again.
+    pub fn pending(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.done || !e.tlock_path.as_ref().is_some_and(|p| p.exists()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Record a completed entry
+    pub fn mark_done(&mut self, index: usize, tlock_path: PathBuf) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.tlock_path = Some(tlock_path);
+            entry.done = true;
+        }
+    }
+}
+
+/// `source` as an absolute path with symlinks resolved, or unchanged if it can't be (e.g. missing)
+fn canonical_source(source: &Path) -> PathBuf {
+    fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_resume() -> Result<()> {
+        let dir = std::env::temp_dir().join("tlock_test_job");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let job_path = dir.join("job.json");
+
+        let sources = vec![dir.join("a.txt"), dir.join("b.txt")];
+        let mut job = LockJob::load_or_new(&job_path, "2030-01-01")?;
+        job.add_sources(&sources);
+        assert_eq!(job.pending(), vec![0, 1]);
+
+        // First item completes, then the run is "interrupted"
+        let tlock_a = dir.join("a.7z.tlock");
+        fs::write(&tlock_a, b"locked")?;
+        job.mark_done(0, tlock_a.clone());
+        job.save(&job_path)?;
+
+        // Re-run with the same sources: only the second is pending
+        let mut resumed = LockJob::load_or_new(&job_path, "2030-01-01")?;
+        resumed.add_sources(&sources);
+        assert_eq!(resumed, job);
+        assert_eq!(resumed.pending(), vec![1]);
+
+        // A completed item whose output was removed is redone
+        fs::remove_file(&tlock_a)?;
+        assert_eq!(resumed.pending(), vec![0, 1]);
+
+        // Resuming with a different unlock time is refused
+        assert!(LockJob::load_or_new(&job_path, "2031-01-01").is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_job_sources_canonicalized() -> Result<()> {
+        let dir = std::env::temp_dir().join("tlock_test_job_canonical");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub"))?;
+        let source = dir.join("a.txt");
+        fs::write(&source, b"data")?;
+
+        // The same file spelled two ways is one entry
+        let mut job = LockJob::load_or_new(&dir.join("job.json"), "2030-01-01")?;
+        job.add_sources(&[source.clone()]);
+        job.add_sources(&[dir.join("sub").join("..").join("a.txt")]);
+        assert_eq!(job.entries.len(), 1);
+        assert_eq!(job.position(&source), Some(0));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
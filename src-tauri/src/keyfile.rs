@@ -1,4 +1,6 @@
+use crate::clock;
 use crate::error::{Result, TimeLockerError};
+use crate::tlock_format::TlockArchive;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -45,7 +47,7 @@ impl KeyFile {
         Self {
             metadata: KeyMetadata {
                 locked: true,
-                created: Utc::now(),
+                created: clock::now(),
                 unlocks,
                 duration,
                 original_file,
@@ -73,24 +75,24 @@ impl KeyFile {
     /// -----END AGE ENCRYPTED FILE-----
     /// ```
     pub fn parse(content: &str) -> Result<Self> {
-        // Split frontmatter and body
-        let parts: Vec<&str> = content.splitn(3, "---").collect();
+        // Normalize Windows (CRLF) line endings so no stray '\r' ends up in the body
+        let content = content.replace('\r', "");
 
-        if parts.len() < 3 {
-            eprintln!("[KeyFile::parse] Not enough parts after splitting by '---': {}", parts.len());
-            return Err(TimeLockerError::InvalidKeyFile);
-        }
+        // Split frontmatter and body
+        let (yaml_str, body_str) = split_frontmatter(&content).ok_or_else(|| {
+            eprintln!("[KeyFile::parse] Missing or unterminated '---' frontmatter");
+            TimeLockerError::InvalidKeyFile
+        })?;
 
-        // Parse YAML frontmatter (parts[1])
-        let yaml_str = parts[1].trim();
-        let metadata: KeyMetadata = serde_yaml::from_str(yaml_str)
+        // Parse YAML frontmatter
+        let metadata: KeyMetadata = serde_yaml::from_str(yaml_str.trim())
             .map_err(|e| {
                 eprintln!("[KeyFile::parse] YAML parse error: {}", e);
                 TimeLockerError::YamlParse(e.to_string())
             })?;
 
-        // Extract encrypted body (parts[2])
-        let body_str = parts[2].trim();
+        // Extract encrypted body
+        let body_str = body_str.trim();
 
         // Check for AGE markers and extract the base64 content
         let encrypted_body = if body_str.contains("-----BEGIN AGE ENCRYPTED FILE-----") {
@@ -158,15 +160,88 @@ impl KeyFile {
 
     /// Check if the time lock has expired
     pub fn is_unlockable(&self) -> bool {
-        Utc::now() >= self.metadata.unlocks
+        clock::now() >= self.metadata.unlocks
     }
 
     /// Get time remaining until unlock
     pub fn time_until_unlock(&self) -> chrono::Duration {
-        self.metadata.unlocks - Utc::now()
+        self.metadata.unlocks - clock::now()
+    }
+
+    /// Where this key's .7z archive is
+    ///
+    /// A relative `archive_path` is relative to the key file's directory.
+    pub fn resolved_archive_path(&self) -> Option<PathBuf> {
+        let archive_path = Path::new(self.metadata.archive_path.as_ref()?);
+        let key_dir = self.file_path.as_deref().and_then(Path::parent).unwrap_or(Path::new("."));
+        Some(key_dir.join(archive_path))
+    }
+
+    /// Whether this key was already migrated: an intact .7z.tlock sits next to its archive
+    ///
+    /// A .7z.tlock that fails `TlockArchive::verify_integrity` (e.g. left
+    /// truncated by an interrupted migration) doesn't count.
+    pub fn has_tlock(&self) -> bool {
+        self.resolved_archive_path().is_some_and(|archive_path| {
+            let tlock_path = archive_path.with_extension("7z.tlock");
+            tlock_path.exists() && TlockArchive::verify_integrity(&tlock_path).is_ok()
+        })
+    }
+
+    /// Whether this key's archive holds a directory rather than a single file
+    ///
+    /// Key files never recorded it. Once unlockable, the archive's entry list
+    /// settles it: a directory is archived under its own name with its contents
+    /// below it. Before that the names are encrypted, so it's guessed from
+    /// `original_file`: directories rarely have an extension, files usually do.
+    pub fn archive_is_directory(&self, archive_path: &Path) -> bool {
+        if self.is_unlockable() {
+            match self.list_archive_entries(archive_path) {
+                Ok(entries) => return entries.iter().any(|e| e.is_directory || e.name.contains('/')),
+                Err(e) => eprintln!("[KeyFile::archive_is_directory] Could not list archive: {}", e),
+            }
+        }
+        Path::new(&self.metadata.original_file).extension().is_none()
+    }
+
+    fn list_archive_entries(&self, archive_path: &Path) -> Result<Vec<crate::archive::ArchiveEntryInfo>> {
+        // Legacy files were always locked against Quicknet
+        let password = crate::crypto::decrypt_with_tlock(
+            &self.encrypted_body,
+            self.metadata.unlocks,
+            &crate::crypto::BeaconConfig::quicknet(),
+        )?;
+        let archive = std::io::BufReader::new(fs::File::open(archive_path)?);
+        crate::archive::list_archive_entries(archive, &password)
     }
 }
 
+/// Split a key file into its YAML frontmatter and body
+///
+/// The file must start with a `---` line; the frontmatter ends at the next line
+/// that is exactly `---`. Everything after that line is the body, so `---`
+/// sequences inside YAML values or the encrypted body are left intact.
+fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let rest = content.strip_prefix("---")?;
+
+    // The opening delimiter must be alone on its line
+    let newline = rest.find('\n')?;
+    if !rest[..newline].trim().is_empty() {
+        return None;
+    }
+    let rest = &rest[newline + 1..];
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
 /// Scan a directory for all key files (.key.md or -key.md)
 ///
 /// # Arguments
@@ -216,10 +291,71 @@ pub fn scan_directory(dir: &Path) -> Result<Vec<KeyFile>> {
         }
     }
 
+    // Stable order across scans (WalkDir order depends on the filesystem)
+    keyfiles.sort_by(|a, b| {
+        a.metadata
+            .created
+            .cmp(&b.metadata.created)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+
     eprintln!("[scan_directory] Found {} key files", keyfiles.len());
     Ok(keyfiles)
 }
 
+/// Suffix for legacy files kept after migration
+///
+/// `foo.key.md` becomes `foo.key.md.migrated`, which no scan picks up, so the
+/// originals stay recoverable until `cleanup_migrated` removes them.
+pub const MIGRATED_SUFFIX: &str = ".migrated";
+
+/// Rename a migrated legacy file (key file or archive) to `<name>.migrated`
+///
+/// # Returns
+/// The new path
+pub fn mark_migrated(path: &Path) -> Result<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(MIGRATED_SUFFIX);
+    let mut target = PathBuf::from(name);
+    if target.exists() {
+        target = crate::archive::next_available_path(&target);
+    }
+
+    fs::rename(path, &target)?;
+    eprintln!("[mark_migrated] Kept {:?} as {:?}", path, target);
+    Ok(target)
+}
+
+/// Find legacy files kept after migration (`*.migrated`) in a directory, recursively
+pub fn find_migrated(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.file_name().to_string_lossy().ends_with(MIGRATED_SUFFIX))
+        .map(|e| e.into_path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Delete the legacy files kept after migration in a directory
+///
+/// # Returns
+/// Paths of the deleted files
+pub fn cleanup_migrated(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Err(TimeLockerError::FileNotFound(dir.display().to_string()));
+    }
+
+    let migrated = find_migrated(dir);
+    for path in &migrated {
+        fs::remove_file(path)?;
+        eprintln!("[cleanup_migrated] Deleted {:?}", path);
+    }
+    Ok(migrated)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,6 +402,67 @@ SGVsbG8gV29ybGQgYmFzZTY0IGVuY29kZWQ=
         assert_eq!(keyfile.encrypted_body, "SGVsbG8gV29ybGQgYmFzZTY0IGVuY29kZWQ=");
     }
 
+    #[test]
+    fn test_keyfile_parse_with_dashes_in_frontmatter_and_body() {
+        let content = r#"---
+locked: true
+created: 2025-12-20T12:17:42Z
+unlocks: 2026-07-01T06:00:00Z
+duration: "2026-07-01"
+original_file: notes---draft.md
+---
+
+-----BEGIN AGE ENCRYPTED FILE-----
+YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IHRsb2NrIDY2ZDcwMDAwIDEgMTU5
+---
+LS0tIGFiYw==
+-----END AGE ENCRYPTED FILE-----
+"#;
+
+        let keyfile = KeyFile::parse(content).unwrap();
+        assert_eq!(keyfile.metadata.original_file, "notes---draft.md");
+        assert_eq!(
+            keyfile.encrypted_body,
+            "YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IHRsb2NrIDY2ZDcwMDAwIDEgMTU5\n---\nLS0tIGFiYw=="
+        );
+    }
+
+    #[test]
+    fn test_keyfile_parse_requires_frontmatter() {
+        // Does not start with a delimiter line
+        assert!(KeyFile::parse("locked: true\n---\nbody\n").is_err());
+        // Opening delimiter not alone on its line
+        assert!(KeyFile::parse("--- locked: true\n---\nbody\n").is_err());
+        // Unterminated frontmatter
+        assert!(KeyFile::parse("---\nlocked: true\n-----BEGIN AGE ENCRYPTED FILE-----\n").is_err());
+    }
+
+    #[test]
+    fn test_keyfile_parse_crlf() {
+        let lf = "---\nlocked: true\ncreated: 2025-12-20T12:17:42Z\nunlocks: 2026-07-01T06:00:00Z\nduration: \"2026-07-01\"\noriginal_file: vault-1.md\n---\n\n-----BEGIN AGE ENCRYPTED FILE-----\nYWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+\nIHRsb2NrIDY2ZDcwMDAwIDEgMTU5\n-----END AGE ENCRYPTED FILE-----\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let parsed = KeyFile::parse(&crlf).unwrap();
+        assert_eq!(parsed.metadata.original_file, "vault-1.md");
+        assert!(!parsed.encrypted_body.contains('\r'));
+        assert_eq!(parsed.encrypted_body, KeyFile::parse(lf).unwrap().encrypted_body);
+    }
+
+    #[test]
+    #[ignore] // Requires network access
+    fn test_keyfile_crlf_roundtrip_decrypts() {
+        let unlocks = Utc::now() - Duration::minutes(5);
+        let beacon = crate::crypto::BeaconConfig::quicknet();
+        let encrypted = crate::crypto::encrypt_with_tlock("crlf_password", unlocks, &beacon).unwrap();
+        let keyfile = KeyFile::create("crlf.txt".to_string(), "0d".to_string(), unlocks, encrypted);
+
+        let crlf = keyfile.to_string().replace('\n', "\r\n");
+        let parsed = KeyFile::parse(&crlf).unwrap();
+
+        let password = crate::crypto::decrypt_with_tlock(&parsed.encrypted_body, unlocks, &beacon).unwrap();
+        assert_eq!(password, "crlf_password");
+    }
+
     #[test]
     fn test_keyfile_create_and_save() -> Result<()> {
         let temp_dir = std::env::temp_dir().join("test_keyfile");
@@ -298,4 +495,47 @@ SGVsbG8gV29ybGQgYmFzZTY0IGVuY29kZWQ=
         fs::remove_dir_all(&temp_dir)?;
         Ok(())
     }
+
+    #[test]
+    fn test_migrated_files_are_hidden_then_cleaned_up() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("test_keyfile_migrated");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+
+        let mut keyfile = KeyFile::create(
+            "test.txt".to_string(),
+            "30d".to_string(),
+            Utc::now() + Duration::days(30),
+            "SGVsbG8gV29ybGQgYmFzZTY0".to_string(),
+        );
+        keyfile.metadata.archive_path = Some("test.7z".to_string());
+        let key_path = temp_dir.join("test.key.md");
+        keyfile.save(&key_path)?;
+        let archive_path = temp_dir.join("test.7z");
+        fs::write(&archive_path, b"archive")?;
+        assert_eq!(scan_directory(&temp_dir)?.len(), 1);
+
+        // The relative archive path resolves next to the key file
+        assert_eq!(keyfile.resolved_archive_path(), Some(archive_path.clone()));
+        assert!(!keyfile.has_tlock());
+        // A truncated .7z.tlock (an interrupted migration) doesn't count as migrated
+        fs::write(temp_dir.join("test.7z.tlock"), b"TLOCK01")?;
+        assert!(!keyfile.has_tlock());
+        fs::remove_file(temp_dir.join("test.7z.tlock"))?;
+
+        let kept_key = mark_migrated(&key_path)?;
+        let kept_archive = mark_migrated(&archive_path)?;
+        assert_eq!(kept_key, temp_dir.join("test.key.md.migrated"));
+        assert!(!key_path.exists() && kept_key.exists());
+
+        // Kept files no longer show up as key files
+        assert!(scan_directory(&temp_dir)?.is_empty());
+        assert_eq!(find_migrated(&temp_dir), vec![kept_archive.clone(), kept_key.clone()]);
+
+        assert_eq!(cleanup_migrated(&temp_dir)?.len(), 2);
+        assert!(!kept_key.exists() && !kept_archive.exists());
+
+        fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
 }
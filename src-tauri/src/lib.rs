@@ -9,29 +9,108 @@ pub mod tlock_format;
 pub mod commands;
 pub mod progress;
 pub mod cli;
+pub mod download;
+pub mod capsule;
+pub mod clock;
+pub mod job;
+pub mod server;
+
+use tauri::Manager;
+
+/// File in the temp directory that panics are appended to (the release GUI has no console)
+const PANIC_LOG_FILE: &str = "time-locker-panic.log";
+
+/// Log panics to stderr and the panic log instead of letting them vanish
+///
+/// Release builds abort on panic (`panic = "abort"` in Cargo.toml), so a
+/// panicking command still ends the app there; the hook only records the
+/// failure before the abort. Debug builds unwind, and a panicking async
+/// command ends just its own task.
+fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let thread = std::thread::current();
+        let message = format!(
+            "[panic] {} thread '{}' {}\n{}",
+            chrono::Utc::now().to_rfc3339(),
+            thread.name().unwrap_or("<unnamed>"),
+            info,
+            std::backtrace::Backtrace::capture()
+        );
+        eprintln!("{}", message);
+
+        let log_path = std::env::temp_dir().join(PANIC_LOG_FILE);
+        if let Ok(mut log) = std::fs::OpenOptions::new().create(true).append(true).open(&log_path) {
+            use std::io::Write;
+            let _ = writeln!(log, "{}", message);
+        }
+    }));
+}
+
+/// How often the janitor looks for stale operations
+const OPERATION_JANITOR_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
 
 /// Run the Tauri GUI application
 pub fn run() {
+    install_panic_hook();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .manage(commands::OperationState::default())
+        .setup(|app| {
+            commands::apply_drand_endpoints_setting();
+
+            // Janitor: drop operations whose command died without cleaning up
+            let handle = app.handle().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(OPERATION_JANITOR_INTERVAL);
+                handle
+                    .state::<commands::OperationState>()
+                    .prune_stale(commands::STALE_OPERATION_IDLE);
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::lock_item,
             commands::lock_item_with_progress,
+            commands::relock_item,
+            commands::change_unlock_time,
             commands::unlock_item,
             commands::unlock_item_with_progress,
             commands::cancel_operation,
+            commands::list_active_operations,
+            commands::prune_stale_operations,
             commands::get_locked_items,
             commands::scan_for_keys,
             commands::get_settings,
             commands::save_settings,
             commands::get_app_state,
+            commands::scan_single_vault,
+            commands::discover_vaults,
+            commands::delete_locked_item,
+            commands::find_duplicates,
+            commands::get_drand_endpoints,
+            commands::check_drand_health,
+            commands::benchmark_endpoints,
             // Migration commands: .key.md + .7z -> .7z.tlock
             commands::migrate_to_tlock,
+            commands::migrate_all_with_progress,
+            commands::migrate_directory,
+            commands::cleanup_migrated,
             commands::read_tlock_metadata,
             commands::is_tlock_file,
             commands::is_legacy_key_file,
+            commands::verify_password,
+            commands::list_tlock_entries,
+            commands::extract_entry_to_bytes,
             commands::unlock_tlock_file,
+            commands::preview_unlock_tlock_file,
+            commands::export_payload,
+            commands::rotate_archive_password,
+            commands::get_tlock_fingerprint,
+            commands::export_capsule,
+            commands::generate_instructions,
+            commands::quarantine_corrupt,
+            commands::audit_vault,
             commands::open_in_explorer,
         ])
         .run(tauri::generate_context!())
@@ -3,12 +3,37 @@
 //! This module provides utilities for tracking and reporting progress during
 //! archive operations, including event emission to the Tauri frontend.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::io::Read;
+use std::sync::{Arc, PoisonError};
 use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager, WebviewWindow};
 
+/// Default minimum interval between progress emissions (milliseconds)
+pub const DEFAULT_THROTTLE_MS: u64 = 100;
+
+/// How far back `ProgressTracker::eta_seconds` looks to measure the current rate
+const ETA_WINDOW: Duration = Duration::from_secs(10);
+
+/// Shortest span the windowed rate is trusted over; before that the average since the start is used
+const MIN_ETA_SPAN: Duration = Duration::from_secs(1);
+/// Bytes in one MB of a throughput cap (binary, 2^20)
+/// Bytes in one MB of a throughput cap (matches the M suffix of `--pad-to`)
+const BYTES_PER_MB: f64 = (1u64 << 20) as f64;
+
+/// Convert a throughput cap in MB/s to bytes per second
+///
+/// Returns `None` (no cap) for zero, negative or non-finite values.
+pub fn rate_limit_from_mb_per_sec(mb_per_sec: f64) -> Option<u64> {
+    if !mb_per_sec.is_finite() || mb_per_sec <= 0.0 {
+        return None;
+    }
+    Some(((mb_per_sec * BYTES_PER_MB) as u64).max(1))
+}
+
 /// Progress update payload sent to the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +48,8 @@ pub struct ProgressPayload {
     pub total_bytes: Option<u64>,
     /// Estimated time remaining in seconds, None if cannot be calculated
     pub eta_seconds: Option<f64>,
+    /// Seconds since the operation started
+    pub elapsed_seconds: f64,
     /// Current file being processed
     pub current_file: Option<String>,
     /// Number of files processed
@@ -44,12 +71,16 @@ pub enum ProgressPhase {
     Compressing,
     /// Encrypting the archive
     Encrypting,
+    /// Serializing metadata and writing the .7z.tlock header
+    WritingMetadata,
     /// Finalizing the archive
     Finalizing,
     /// Operation complete
     Complete,
     /// Extracting files
     Extracting,
+    /// Fetching the drand beacon signature needed to decrypt the key
+    FetchingBeacon,
 }
 
 /// Thread-safe progress tracker that can be shared across operations
@@ -69,10 +100,20 @@ pub struct ProgressTracker {
     cancelled: AtomicBool,
     /// Start time for ETA calculation
     start_time: Instant,
+    /// Wall-clock start time, for listing operations
+    started_at: DateTime<Utc>,
+    /// Milliseconds after `start_time` of the last progress update (for spotting stuck operations)
+    last_activity_ms: AtomicU64,
+    /// Phase of the most recent progress payload
+    phase: std::sync::Mutex<Option<ProgressPhase>>,
     /// Last emission time for throttling
     last_emit: std::sync::Mutex<Instant>,
     /// Minimum interval between emissions (milliseconds)
     throttle_ms: u64,
+    /// Maximum throughput in bytes per second, enforced by `pace`
+    rate_limit: Option<u64>,
+    /// Recent (time, bytes written) samples, for the rate behind the ETA
+    rate_samples: std::sync::Mutex<VecDeque<(Instant, u64)>>,
 }
 
 impl ProgressTracker {
@@ -86,8 +127,13 @@ impl ProgressTracker {
             total_known: AtomicBool::new(false),
             cancelled: AtomicBool::new(false),
             start_time: Instant::now(),
+            started_at: Utc::now(),
+            last_activity_ms: AtomicU64::new(0),
+            phase: std::sync::Mutex::new(None),
             last_emit: std::sync::Mutex::new(Instant::now()),
-            throttle_ms: 100, // Default: emit at most every 100ms
+            throttle_ms: DEFAULT_THROTTLE_MS,
+            rate_limit: None,
+            rate_samples: std::sync::Mutex::new(VecDeque::new()),
         }
     }
 
@@ -98,21 +144,60 @@ impl ProgressTracker {
         tracker
     }
 
+    /// Use a different minimum interval between emissions (e.g. for benchmarking)
+    pub fn with_throttle_ms(mut self, throttle_ms: u64) -> Self {
+        self.throttle_ms = throttle_ms;
+        self
+    }
+
+    /// Cap throughput at `bytes_per_sec` (`None` = unlimited); see `pace`
+    pub fn with_rate_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.rate_limit = bytes_per_sec.filter(|&limit| limit > 0);
+        self
+    }
+
+    /// Throughput cap in bytes per second, if any
+    pub fn rate_limit(&self) -> Option<u64> {
+        self.rate_limit
+    }
+
+    /// Sleep until the bytes processed so far are within the rate limit
+    ///
+    /// Called from read/write loops after each chunk. Returns immediately when
+    /// there is no limit or the operation has been cancelled.
+    pub fn pace(&self) {
+        let Some(limit) = self.rate_limit else {
+            return;
+        };
+        if self.is_cancelled() {
+            return;
+        }
+        let bytes = self.bytes_written.load(Ordering::SeqCst);
+        let allowed = Duration::from_secs_f64(bytes as f64 / limit as f64);
+        let elapsed = self.start_time.elapsed();
+        if allowed > elapsed {
+            std::thread::sleep(allowed - elapsed);
+        }
+    }
+
     /// Set the total bytes and files (can be called after scanning)
     pub fn set_total(&self, total_bytes: u64, total_files: u32) {
         self.total_bytes.store(total_bytes, Ordering::SeqCst);
         self.total_files.store(total_files as u64, Ordering::SeqCst);
         self.total_known.store(true, Ordering::SeqCst);
+        self.touch();
     }
 
     /// Add bytes to the processed count
     pub fn add_bytes(&self, bytes: u64) {
         self.bytes_written.fetch_add(bytes, Ordering::SeqCst);
+        self.touch();
     }
 
     /// Set the total bytes written (for when we know exact amount)
     pub fn set_bytes_written(&self, bytes: u64) {
         self.bytes_written.store(bytes, Ordering::SeqCst);
+        self.touch();
     }
 
     /// Get the current bytes written count
@@ -123,6 +208,29 @@ impl ProgressTracker {
     /// Increment the file counter
     pub fn increment_files(&self) {
         self.files_processed.fetch_add(1, Ordering::SeqCst);
+        self.touch();
+    }
+
+    /// Record that the operation made progress
+    fn touch(&self) {
+        let elapsed_ms = self.start_time.elapsed().as_millis() as u64;
+        self.last_activity_ms.store(elapsed_ms, Ordering::SeqCst);
+    }
+
+    /// When the operation started
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Time since the operation last made progress (or started, if it never has)
+    pub fn idle_for(&self) -> Duration {
+        let last_activity = Duration::from_millis(self.last_activity_ms.load(Ordering::SeqCst));
+        self.start_time.elapsed().saturating_sub(last_activity)
+    }
+
+    /// Phase of the most recent progress update, if any was emitted
+    pub fn phase(&self) -> Option<ProgressPhase> {
+        self.phase.lock().unwrap_or_else(PoisonError::into_inner).clone()
     }
 
     /// Check if the operation has been cancelled
@@ -149,6 +257,10 @@ impl ProgressTracker {
     }
 
     /// Calculate estimated time remaining in seconds
+    ///
+    /// Based on the throughput of the last `ETA_WINDOW`, so the estimate
+    /// follows changes in speed (a slow disk, a run of incompressible files)
+    /// instead of being anchored to the average since the start.
     pub fn eta_seconds(&self) -> Option<f64> {
         let percentage = self.percentage()?;
         if percentage <= 0.0 {
@@ -158,9 +270,20 @@ impl ProgressTracker {
             return Some(0.0);
         }
 
-        let elapsed = self.start_time.elapsed().as_secs_f64();
-        let total_estimated = elapsed / (percentage / 100.0);
-        let remaining = total_estimated - elapsed;
+        let written = self.bytes_written.load(Ordering::SeqCst);
+        let left = self.total_bytes.load(Ordering::SeqCst).saturating_sub(written);
+        let mut remaining = match self.recent_rate(written) {
+            Some(rate) => left as f64 / rate,
+            None => {
+                let elapsed = self.start_time.elapsed().as_secs_f64();
+                elapsed / (percentage / 100.0) - elapsed
+            }
+        };
+
+        // Under a rate limit the rest can't go faster than the cap
+        if let Some(limit) = self.rate_limit {
+            remaining = remaining.max(left as f64 / limit as f64);
+        }
 
         if remaining.is_finite() && remaining >= 0.0 {
             Some(remaining)
@@ -169,9 +292,29 @@ impl ProgressTracker {
         }
     }
 
+    /// Bytes per second over the last `ETA_WINDOW`, recording `written` as a new sample
+    ///
+    /// `None` until the samples span `MIN_ETA_SPAN`, or if nothing was written in the window.
+    fn recent_rate(&self, written: u64) -> Option<f64> {
+        let now = Instant::now();
+        let mut samples = self.rate_samples.lock().unwrap_or_else(PoisonError::into_inner);
+        samples.push_back((now, written));
+        // Keep the newest sample that's at least a window old, as the baseline
+        while samples.len() > 2 && now.duration_since(samples[1].0) >= ETA_WINDOW {
+            samples.pop_front();
+        }
+
+        let (since, written_then) = *samples.front()?;
+        let span = now.duration_since(since);
+        if span < MIN_ETA_SPAN || written <= written_then {
+            return None;
+        }
+        Some((written - written_then) as f64 / span.as_secs_f64())
+    }
+
     /// Check if enough time has passed since last emission (for throttling)
     pub fn should_emit(&self) -> bool {
-        let mut last = self.last_emit.lock().unwrap();
+        let mut last = self.last_emit.lock().unwrap_or_else(PoisonError::into_inner);
         let now = Instant::now();
         if now.duration_since(*last) >= Duration::from_millis(self.throttle_ms) {
             *last = now;
@@ -183,12 +326,20 @@ impl ProgressTracker {
 
     /// Force the next should_emit() to return true
     pub fn force_next_emit(&self) {
-        let mut last = self.last_emit.lock().unwrap();
+        let mut last = self.last_emit.lock().unwrap_or_else(PoisonError::into_inner);
         *last = Instant::now() - Duration::from_millis(self.throttle_ms + 1);
     }
 
     /// Build a progress payload for the current state
     pub fn build_payload(&self, current_file: Option<String>, phase: ProgressPhase) -> ProgressPayload {
+        // A phase change counts as progress, so slow phases aren't mistaken for stuck ones
+        {
+            let mut last_phase = self.phase.lock().unwrap_or_else(PoisonError::into_inner);
+            if last_phase.as_ref() != Some(&phase) {
+                *last_phase = Some(phase.clone());
+                self.touch();
+            }
+        }
         let total_known = self.total_known.load(Ordering::SeqCst);
         let bytes_written = self.bytes_written.load(Ordering::SeqCst);
         let files_processed = self.files_processed.load(Ordering::SeqCst) as u32;
@@ -202,6 +353,7 @@ impl ProgressTracker {
                 None
             },
             eta_seconds: self.eta_seconds(),
+            elapsed_seconds: self.start_time.elapsed().as_secs_f64(),
             current_file,
             files_processed,
             total_files: if total_known {
@@ -220,23 +372,114 @@ impl Default for ProgressTracker {
     }
 }
 
-/// Progress emitter that sends events to the Tauri frontend
-pub struct ProgressEmitter {
+/// Reader that counts what it reads into a tracker and paces itself to its rate limit
+pub struct PacedReader<'a, R> {
+    inner: R,
+    tracker: &'a ProgressTracker,
+}
+
+impl<'a, R: Read> PacedReader<'a, R> {
+    /// Wrap `inner`, counting into and pacing by `tracker`
+    pub fn new(inner: R, tracker: &'a ProgressTracker) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<R: Read> Read for PacedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.tracker.add_bytes(n as u64);
+        self.tracker.pace();
+        Ok(n)
+    }
+}
+
+/// `PacedReader` that calls `on_read` after every read, e.g. to emit throttled progress
+///
+/// Progress then follows the bytes as they're consumed, instead of jumping
+/// once per file.
+pub struct ReportingReader<'a, R, F> {
+    inner: PacedReader<'a, R>,
+    on_read: F,
+}
+
+impl<'a, R: Read, F: FnMut()> ReportingReader<'a, R, F> {
+    /// Wrap `inner`, counting into and pacing by `tracker`
+    pub fn new(inner: R, tracker: &'a ProgressTracker, on_read: F) -> Self {
+        Self {
+            inner: PacedReader::new(inner, tracker),
+            on_read,
+        }
+    }
+}
+
+impl<R: Read, F: FnMut()> Read for ReportingReader<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        (self.on_read)();
+        Ok(n)
+    }
+}
+
+/// Where a `ProgressEmitter` delivers progress payloads
+pub trait ProgressSink: Send + Sync {
+    /// Deliver one payload; returns whether it got through
+    fn send(&self, payload: &ProgressPayload) -> bool;
+}
+
+/// Sends payloads to the frontend as global events
+struct WindowSink {
     window: WebviewWindow,
-    tracker: Arc<ProgressTracker>,
     event_name: String,
 }
 
+impl ProgressSink for WindowSink {
+    fn send(&self, payload: &ProgressPayload) -> bool {
+        // Use app_handle().emit() for global events that frontend can listen to
+        self.window.app_handle().emit(&self.event_name, payload).is_ok()
+    }
+}
+
+/// Progress emitter that sends a tracker's progress to a sink: the Tauri
+/// frontend, or e.g. a terminal for the CLI
+pub struct ProgressEmitter {
+    tracker: Arc<ProgressTracker>,
+    sink: Box<dyn ProgressSink>,
+}
+
+impl std::fmt::Debug for ProgressEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressEmitter")
+            .field("tracker", &self.tracker)
+            .finish_non_exhaustive()
+    }
+}
+
 impl ProgressEmitter {
-    /// Create a new progress emitter
+    /// Create a new progress emitter sending `event_name` events to the frontend
     pub fn new(window: WebviewWindow, tracker: Arc<ProgressTracker>, event_name: impl Into<String>) -> Self {
+        Self::with_sink(
+            tracker,
+            WindowSink {
+                window,
+                event_name: event_name.into(),
+            },
+        )
+    }
+
+    /// Create a progress emitter delivering to any sink
+    pub fn with_sink(tracker: Arc<ProgressTracker>, sink: impl ProgressSink + 'static) -> Self {
         Self {
-            window,
             tracker,
-            event_name: event_name.into(),
+            sink: Box::new(sink),
         }
     }
 
+    /// The tracker whose progress is emitted
+    pub fn tracker(&self) -> &ProgressTracker {
+        &self.tracker
+    }
+
     /// Emit progress if throttle allows, returns true if emitted
     pub fn emit_progress(&self, current_file: Option<String>, phase: ProgressPhase) -> bool {
         if !self.tracker.should_emit() {
@@ -248,16 +491,14 @@ impl ProgressEmitter {
     /// Emit progress regardless of throttle
     pub fn emit_progress_forced(&self, current_file: Option<String>, phase: ProgressPhase) -> bool {
         let payload = self.tracker.build_payload(current_file, phase);
-        // Use app_handle().emit() for global events that frontend can listen to
-        self.window.app_handle().emit(&self.event_name, &payload).is_ok()
+        self.sink.send(&payload)
     }
 
     /// Emit a completion event
     pub fn emit_complete(&self) {
         self.tracker.force_next_emit();
         let payload = self.tracker.build_payload(None, ProgressPhase::Complete);
-        // Use app_handle().emit() for global events
-        let _ = self.window.app_handle().emit(&self.event_name, &payload);
+        self.sink.send(&payload);
     }
 
     /// Check if operation was cancelled
@@ -379,4 +620,104 @@ mod tests {
         tracker.force_next_emit();
         assert!(tracker.should_emit());
     }
+
+    #[test]
+    fn test_unthrottled_tracker() {
+        let tracker = ProgressTracker::new().with_throttle_ms(0);
+        assert!(tracker.should_emit());
+        assert!(tracker.should_emit());
+    }
+
+    #[test]
+    fn test_idle_time_resets_on_progress() {
+        let tracker = ProgressTracker::new();
+        assert_eq!(tracker.phase(), None);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(tracker.idle_for() >= Duration::from_millis(30));
+
+        tracker.add_bytes(1);
+        assert!(tracker.idle_for() < Duration::from_millis(30));
+
+        tracker.build_payload(None, ProgressPhase::Extracting);
+        assert_eq!(tracker.phase(), Some(ProgressPhase::Extracting));
+        assert!(tracker.started_at() <= Utc::now());
+    }
+
+    #[test]
+    fn test_payload_reports_elapsed_time() {
+        let tracker = ProgressTracker::new();
+        std::thread::sleep(Duration::from_millis(20));
+        let payload = tracker.build_payload(None, ProgressPhase::Compressing);
+        assert!(payload.elapsed_seconds >= 0.02);
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert!(json.get("elapsedSeconds").is_some());
+    }
+
+    #[test]
+    fn test_rate_limit_paces_and_floors_eta() {
+        // 64 KiB/s: 16 KiB must take at least a quarter of a second
+        let tracker = ProgressTracker::with_total(64 * 1024, 1).with_rate_limit(Some(64 * 1024));
+        let start = Instant::now();
+        let mut reader = PacedReader::new(&[0u8; 16 * 1024][..], &tracker);
+        std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(240));
+        assert_eq!(tracker.get_bytes_written(), 16 * 1024);
+
+        // The remaining 48 KiB can't take less than 0.75s at the cap
+        assert!(tracker.eta_seconds().unwrap() >= 0.75);
+    }
+
+    #[test]
+    fn test_eta_follows_recent_rate() {
+        let tracker = ProgressTracker::with_total(1000, 1);
+        let now = Instant::now();
+        {
+            // 100 bytes in the first 20s, then 500 in the last 10s
+            let mut samples = tracker.rate_samples.lock().unwrap();
+            samples.push_back((now - Duration::from_secs(30), 0));
+            samples.push_back((now - Duration::from_secs(10), 100));
+        }
+        tracker.add_bytes(600);
+
+        // 400 bytes left at 50 B/s; the 30s average (20 B/s) would say 20s
+        let eta = tracker.eta_seconds().unwrap();
+        assert!((7.5..8.5).contains(&eta), "eta was {}", eta);
+        assert_eq!(tracker.rate_samples.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_reporting_reader_advances_within_a_file() {
+        let tracker = ProgressTracker::with_total(64 * 1024, 1);
+        let mut seen = Vec::new();
+        let mut reader = ReportingReader::new(&[0u8; 64 * 1024][..], &tracker, || {
+            seen.push(tracker.get_bytes_written())
+        });
+        let mut buf = [0u8; 8 * 1024];
+        while reader.read(&mut buf).unwrap() > 0 {}
+        drop(reader);
+
+        // Every chunk moved the count on, so one big file doesn't look frozen
+        assert!(seen.len() >= 8);
+        assert!(seen.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(seen[0], 8 * 1024);
+        assert_eq!(tracker.get_bytes_written(), 64 * 1024);
+    }
+
+    #[test]
+    fn test_rate_limit_conversion() {
+        assert_eq!(rate_limit_from_mb_per_sec(2.0), Some(2 * 1024 * 1024));
+        assert_eq!(rate_limit_from_mb_per_sec(0.0), None);
+        assert_eq!(rate_limit_from_mb_per_sec(f64::NAN), None);
+        assert_eq!(ProgressTracker::new().with_rate_limit(Some(0)).rate_limit(), None);
+    }
+
+    #[test]
+    fn test_phase_serialization() {
+        let json = serde_json::to_string(&ProgressPhase::FetchingBeacon).unwrap();
+        assert_eq!(json, "\"fetching_beacon\"");
+        let json = serde_json::to_string(&ProgressPhase::WritingMetadata).unwrap();
+        assert_eq!(json, "\"writing_metadata\"");
+    }
 }
@@ -0,0 +1,434 @@
+//! Local JSON API for driving Time Locker from other applications
+//!
+//! `timelocker serve` listens on 127.0.0.1 only and answers `POST` requests
+//! with the same `{ "ok", "data", "errors" }` envelope as `--json`:
+//!
+//! | Route     | Body                                          |
+//! |-----------|-----------------------------------------------|
+//! | `/lock`   | `{ "source", "unlock_at", "vault"? }`         |
+//! | `/unlock` | `{ "file", "output"?, "output_name"? }`       |
+//! | `/info`   | `{ "file" }`                                  |
+//! | `/list`   | `{ "vault"? }`                                |
+//!
+//! Every request must carry `Authorization: Bearer <token>`, where the token is
+//! generated at startup and printed to the console. Each connection is handled
+//! on its own thread, and a client that stalls mid-request is dropped after
+//! `IO_TIMEOUT`.
+
+use crate::archive::{check_source_outside_vault, extract_atomically, ExtractOptions};
+use crate::cli::{self, InfoReport, JsonEnvelope, ListEntry};
+use crate::clock;
+use crate::crypto;
+use crate::error::{Result, TimeLockerError};
+use crate::tlock_format::{self, CreateOptions, TlockArchive, TlockMetadata};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Largest request body accepted (requests only carry paths and dates)
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Length of the generated bearer token
+const TOKEN_LENGTH: u32 = 32;
+
+/// How long a connection may stall while sending its request or reading the response
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A parsed HTTP request
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockRequest {
+    source: PathBuf,
+    unlock_at: String,
+    vault: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnlockRequest {
+    file: PathBuf,
+    output: Option<PathBuf>,
+    /// Template for the output folder name when `output` is not given
+    output_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InfoRequest {
+    file: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ListRequest {
+    vault: Option<PathBuf>,
+}
+
+/// Serve the JSON API on the loopback interface until the process is stopped
+///
+/// Pass port 0 to let the OS pick a free port.
+pub fn serve(port: u16) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let token: Arc<str> = crypto::generate_password(TOKEN_LENGTH).into();
+
+    println!("Listening on http://{}", listener.local_addr()?);
+    println!("Token: {}", token);
+    println!("Send requests with the header: Authorization: Bearer <token>");
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                // A slow request (or a long unlock) must not hold up the next client
+                let token = Arc::clone(&token);
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &token) {
+                        eprintln!("[serve] Connection error: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("[serve] Failed to accept connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let (status, body) = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => route(&request, token),
+        Err(e) => (400, envelope_error(&e)),
+    };
+
+    write_response(&mut stream, status, &body)
+}
+
+/// Read a single HTTP/1.1 request (request line, headers, Content-Length body)
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Err(TimeLockerError::Parse("Malformed request line".to_string())),
+    };
+
+    let mut authorization = None;
+    let mut content_length = 0usize;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .parse()
+                    .map_err(|_| TimeLockerError::Parse("Invalid Content-Length".to_string()))?;
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.to_string());
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(TimeLockerError::Parse(format!(
+            "Request body too large ({} bytes, max {})",
+            content_length, MAX_BODY_SIZE
+        )));
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        authorization,
+        body,
+    })
+}
+
+/// Authenticate and dispatch a request, returning the status code and JSON body
+fn route(request: &Request, token: &str) -> (u16, String) {
+    let authorized = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()));
+    if !authorized {
+        return (
+            401,
+            envelope_error(&TimeLockerError::Parse("Missing or invalid token".to_string())),
+        );
+    }
+
+    if request.method != "POST" {
+        return (
+            405,
+            envelope_error(&TimeLockerError::Parse("Use POST with a JSON body".to_string())),
+        );
+    }
+
+    let result = match request.path.as_str() {
+        "/lock" => parse_body(&request.body).and_then(handle_lock),
+        "/unlock" => parse_body(&request.body).and_then(handle_unlock),
+        "/info" => parse_body(&request.body).and_then(handle_info),
+        "/list" => parse_body(&request.body).and_then(handle_list),
+        other => {
+            return (
+                404,
+                envelope_error(&TimeLockerError::Parse(format!("Unknown route: {}", other))),
+            )
+        }
+    };
+
+    match result {
+        Ok(data) => (200, to_json(&JsonEnvelope::success(data))),
+        Err(e) => (422, envelope_error(&e)),
+    }
+}
+
+/// Parse a JSON request body; an empty body counts as `{}`
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T> {
+    let body = if body.iter().all(u8::is_ascii_whitespace) {
+        b"{}".as_slice()
+    } else {
+        body
+    };
+    serde_json::from_slice(body)
+        .map_err(|e| TimeLockerError::Parse(format!("Invalid request body: {}", e)))
+}
+
+fn handle_lock(request: LockRequest) -> Result<Value> {
+    if !request.source.exists() {
+        return Err(TimeLockerError::FileNotFound(request.source.display().to_string()));
+    }
+    if let Some(ref vault_dir) = request.vault {
+        check_source_outside_vault(&request.source, vault_dir)?;
+    }
+
+    let unlock_datetime = cli::parse_datetime(&request.unlock_at)?;
+    if unlock_datetime <= clock::now() {
+        return Err(TimeLockerError::Parse(
+            "Unlock time must be in the future".to_string(),
+        ));
+    }
+    let (unlock_datetime, _) =
+        crypto::enforce_min_lead(unlock_datetime, clock::now(), crypto::min_unlock_lead());
+
+    let password = crypto::generate_password(32);
+    let beacon = crypto::BeaconConfig::active()?;
+    let encrypted_password = crypto::encrypt_with_tlock(&password, unlock_datetime, &beacon)?;
+
+    let original_filename = request
+        .source
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let mut metadata = TlockMetadata::new(
+        original_filename,
+        unlock_datetime.format("%Y-%m-%d").to_string(),
+        unlock_datetime,
+        None,
+        Some(encrypted_password),
+    );
+    metadata.is_directory = request.source.is_dir();
+    metadata.set_beacon(&beacon);
+
+    let tlock_path = TlockArchive::create_with_options(
+        &request.source,
+        metadata,
+        &password,
+        &CreateOptions::default(),
+    )?;
+
+    let final_path = match request.vault {
+        Some(vault_dir) if vault_dir.is_dir() => {
+            let dest_path = vault_dir.join(tlock_path.file_name().unwrap());
+            fs::rename(&tlock_path, &dest_path)?;
+            dest_path
+        }
+        Some(vault_dir) => {
+            return Err(TimeLockerError::FileNotFound(vault_dir.display().to_string()));
+        }
+        None => tlock_path,
+    };
+
+    Ok(serde_json::json!({
+        "file": final_path.display().to_string(),
+        "unlocks": unlock_datetime,
+    }))
+}
+
+fn handle_unlock(request: UnlockRequest) -> Result<Value> {
+    let metadata = read_metadata(&request.file)?;
+    let password = cli::decrypt_archive_password(&metadata)?;
+
+    // Same default as `timelocker unlock`: a folder named from the template next to the file
+    let output_dir = match request.output {
+        Some(output) => output,
+        None => {
+            let template = request
+                .output_name
+                .as_deref()
+                .unwrap_or(tlock_format::DEFAULT_OUTPUT_NAME_TEMPLATE);
+            let parent = request.file.parent().unwrap_or_else(|| Path::new("."));
+            parent.join(cli::render_output_name(template, &metadata.original_file)?)
+        }
+    };
+
+    extract_atomically(&output_dir, |dir| {
+        TlockArchive::extract_with_options(&request.file, &password, dir, &ExtractOptions::default())
+    })?;
+
+    Ok(serde_json::json!({
+        "output": output_dir.display().to_string(),
+        "original_file": metadata.original_file,
+    }))
+}
+
+fn handle_info(request: InfoRequest) -> Result<Value> {
+    let metadata = read_metadata(&request.file)?;
+    to_value(InfoReport::new(&request.file, &metadata))
+}
+
+fn handle_list(request: ListRequest) -> Result<Value> {
+    let scan_dir = request
+        .vault
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    let archives = tlock_format::scan_tlock_files(&scan_dir, true)?;
+    to_value(ListEntry::from_archives(&archives))
+}
+
+fn read_metadata(file: &Path) -> Result<TlockMetadata> {
+    if !file.exists() {
+        return Err(TimeLockerError::FileNotFound(file.display().to_string()));
+    }
+    TlockArchive::read_metadata(file)?
+        .metadata
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))
+}
+
+fn to_value<T: serde::Serialize>(data: T) -> Result<Value> {
+    serde_json::to_value(data)
+        .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize output: {}", e)))
+}
+
+fn to_json<T: serde::Serialize>(envelope: &JsonEnvelope<T>) -> String {
+    serde_json::to_string(envelope).unwrap_or_else(|_| {
+        r#"{"ok":false,"data":null,"errors":["Failed to serialize output"]}"#.to_string()
+    })
+}
+
+fn envelope_error(error: &TimeLockerError) -> String {
+    to_json(&JsonEnvelope::<()>::failure(error))
+}
+
+fn write_response<W: Write>(writer: &mut W, status: u16, body: &str) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Unprocessable Entity",
+    };
+
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Compare tokens without leaking the position of the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &str) -> Request {
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read_request() {
+        let req = request(
+            "POST /info HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer abc\r\nContent-Length: 13\r\n\r\n{\"file\":\"x\"}\n",
+        );
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/info");
+        assert_eq!(req.authorization.as_deref(), Some("Bearer abc"));
+        assert_eq!(req.body, b"{\"file\":\"x\"}\n");
+    }
+
+    #[test]
+    fn test_route_requires_token() {
+        let req = request("POST /list HTTP/1.1\r\nAuthorization: Bearer wrong\r\n\r\n");
+        assert_eq!(route(&req, "secret").0, 401);
+
+        let req = request("POST /list HTTP/1.1\r\n\r\n");
+        assert_eq!(route(&req, "secret").0, 401);
+    }
+
+    #[test]
+    fn test_route_list_and_errors() -> Result<()> {
+        let dir = std::env::temp_dir().join("tlock_test_serve");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+
+        let body = serde_json::json!({ "vault": dir }).to_string();
+        let raw = format!(
+            "POST /list HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (status, response) = route(&request(&raw), "secret");
+        assert_eq!(status, 200);
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["data"], serde_json::json!([]));
+
+        let req = request("POST /nope HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n");
+        assert_eq!(route(&req, "secret").0, 404);
+
+        let req = request("GET /list HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n");
+        assert_eq!(route(&req, "secret").0, 405);
+
+        let raw = "POST /info HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 2\r\n\r\n{}";
+        let (status, response) = route(&request(raw), "secret");
+        assert_eq!(status, 422);
+        assert!(response.contains("\"ok\":false"));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
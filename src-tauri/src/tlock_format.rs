@@ -14,20 +14,34 @@
 //! |   Metadata length: u32 LE (4 B)  |
 //! |   Reserved: 12 bytes             |
 //! +----------------------------------+
+//! | CHECKSUM (32 bytes, version 2+)  |
+//! |   SHA-256 of the payload         |
+//! +----------------------------------+
 //! | METADATA (variable, unencrypted) |
-//! |   JSON blob                      |
+//! |   JSON blob, max 16 MB           |
 //! +----------------------------------+
 //! | PAYLOAD (encrypted 7z archive)   |
 //! +----------------------------------+
 //! ```
 
-use crate::archive::{create_encrypted_archive, extract_encrypted_archive};
+use crate::archive::{
+    archive_kdf_cycles_power, create_encrypted_archive, create_encrypted_archive_with_rate_limit,
+    extract_encrypted_archive, extract_encrypted_archive_with_options, file_crc32,
+    create_encrypted_archive_multi, create_encrypted_archive_multi_with_progress, list_archive_entries, read_archive_entry, verify_archive_password,
+    ArchiveEntryInfo, CompressionFilter, CompressionMethod, CompressionSettings, ExtractOptions,
+    ARCHIVE_CIPHER, ARCHIVE_KDF,
+};
+use crate::clock;
+use crate::crypto;
 use crate::error::{Result, TimeLockerError};
+use crate::progress::ProgressEmitter;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 // ============================================================================
 // Constants
@@ -37,13 +51,63 @@ use std::path::{Path, PathBuf};
 pub const TLOCK_MAGIC: &[u8; 7] = b"TLOCK01";
 
 /// Current format version
-pub const TLOCK_VERSION: u8 = 1;
+pub const TLOCK_VERSION: u8 = 2;
+
+/// Oldest format version that can still be written
+pub const MIN_TLOCK_VERSION: u8 = 1;
 
 /// Fixed header size in bytes
 pub const HEADER_SIZE: usize = 24;
 
-/// Maximum allowed metadata size (1 MB should be more than enough)
-pub const MAX_METADATA_SIZE: u32 = 1024 * 1024;
+/// First format version that stores a payload checksum after the fixed header
+pub const CHECKSUM_VERSION: u8 = 2;
+
+/// Size of the payload checksum (SHA-256) in bytes
+pub const CHECKSUM_SIZE: usize = 32;
+
+/// SHA-256 digest of a payload
+pub type PayloadChecksum = [u8; CHECKSUM_SIZE];
+
+/// Maximum allowed metadata size
+///
+/// Plain metadata is a few hundred bytes, but optional extras grow with the
+/// archive: a sealed entry index costs roughly 100 bytes per file, so 1 MB
+/// would cap indexed archives at ~10k files. 16 MB covers well over 100k
+/// files while still bounding the allocation made when reading a header.
+pub const MAX_METADATA_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Default buffer size for streaming payload copies
+///
+/// Much larger than `std::io::copy`'s 8 KB, which leaves multi-GB payloads on
+/// fast storage bound by syscall overhead.
+pub const DEFAULT_COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Environment variable overriding the copy buffer size (bytes), for benchmarking
+pub const COPY_BUFFER_ENV: &str = "TIMELOCKER_COPY_BUFFER";
+
+/// Vault subfolder that corrupt .7z.tlock files are moved into
+pub const QUARANTINE_DIR: &str = "_corrupt";
+
+/// Log of quarantined files, inside `QUARANTINE_DIR`
+pub const QUARANTINE_LOG: &str = "quarantine.log";
+
+/// Name prefix of the directories unlocked files are extracted into
+pub const UNLOCKED_DIR_PREFIX: &str = "unlocked_";
+
+/// Marker file that makes a directory a vault, even before it holds any locked files
+pub const VAULT_MARKER: &str = ".timelocker-vault";
+
+/// How many levels below the root `discover_vaults` looks by default
+pub const DEFAULT_DISCOVERY_DEPTH: usize = 4;
+
+/// Name of the directory a file is unlocked into when no template is configured
+pub const DEFAULT_OUTPUT_NAME_TEMPLATE: &str = "unlocked_{name}";
+
+/// Signature at the start of every 7z archive
+const SEVENZ_SIGNATURE: [u8; 6] = [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
+
+/// Size of the 7z signature header (signature, version, CRCs, next-header location)
+const SEVENZ_START_HEADER_SIZE: u64 = 32;
 
 // ============================================================================
 // Metadata Structure
@@ -85,6 +149,112 @@ pub struct TlockMetadata {
     /// Whether the original was a directory
     #[serde(default)]
     pub is_directory: bool,
+
+    /// Directory the original was locked from, relative and `/`-separated: its
+    /// parent folder's name, or its folder below a chosen root. Only recorded
+    /// when asked for (`lock --record-path`), since it is stored unencrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+
+    /// Entry index sealed with the archive password (fast listing without parsing the 7z)
+    ///
+    /// Names, sizes and CRCs only. Entries have no usable offsets: they sit in
+    /// compressed, encrypted 7z blocks that can only be decoded from the start
+    /// of their block, so reading one still goes through the 7z reader.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_entry_index: Option<String>,
+
+    /// Chain hash of the drand beacon the key is locked against (absent means Quicknet)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beacon_chain_hash: Option<String>,
+
+    /// Public key, genesis time and period of that beacon, so the file can be
+    /// checked and unlocked offline without built-in knowledge of the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beacon_parameters: Option<crypto::BeaconParameters>,
+
+    /// Free-form note from whoever locked the file (e.g. why it was locked)
+    ///
+    /// Stored unencrypted like the rest of the metadata, so anyone holding the
+    /// file can read it before the unlock time. Never put secrets here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Names of every source, for files locked from several paths at once
+    /// (`original_file` is then only a display summary)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_files: Option<Vec<String>>,
+
+    /// When the contents are meant to have been opened by
+    ///
+    /// Advisory only: nothing cryptographic stops a file being unlocked after
+    /// this time, and anyone can edit the unencrypted metadata to remove it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+
+    /// The archive password encrypted to an age X25519 recipient, who can
+    /// unlock the file at any time, bypassing the time lock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient_encrypted_key: Option<String>,
+
+    /// Filter applied to the payload ahead of compression ("bcj_x86"), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_filter: Option<String>,
+
+    /// Custom key/value fields (e.g. a project id or case number)
+    ///
+    /// Unencrypted like `note`, so they must not hold secrets.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+
+    /// How the contents were protected (absent for files locked before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crypto_parameters: Option<CryptoParameters>,
+}
+
+/// Non-secret description of the scheme protecting a file, for audits
+///
+/// Describes the algorithms and their settings, never keys or passwords.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CryptoParameters {
+    /// Cipher of the 7z payload
+    pub cipher: String,
+    /// How the cipher key is derived from the archive password
+    pub kdf: String,
+    /// The key derivation iterates 2^kdf_cycles_power times
+    pub kdf_cycles_power: u8,
+    /// Whether entry names and sizes are encrypted along with the contents
+    pub header_encryption: bool,
+    /// "lzma2" or "store"
+    pub compression: String,
+    /// LZMA2 level (absent for "store")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<u32>,
+    /// Whether already-compressed files (JPEG, MP4, zip...) were only encrypted
+    pub precompressed_stored: bool,
+    /// How the archive password is time-locked
+    pub time_lock: String,
+}
+
+impl CryptoParameters {
+    /// Parameters of an archive this build writes with `compression`
+    pub fn for_compression(compression: &CompressionSettings) -> Self {
+        let (name, level) = match compression.method {
+            CompressionMethod::Lzma2 => ("lzma2", Some(compression.level.min(9))),
+            CompressionMethod::Store => ("store", None),
+        };
+        Self {
+            cipher: ARCHIVE_CIPHER.to_string(),
+            kdf: ARCHIVE_KDF.to_string(),
+            kdf_cycles_power: archive_kdf_cycles_power(),
+            // Every archive writer encrypts the headers
+            header_encryption: true,
+            compression: name.to_string(),
+            compression_level: level,
+            precompressed_stored: compression.method == CompressionMethod::Lzma2 && !compression.uniform,
+            time_lock: "tlock (age, drand BLS12-381 identity-based encryption)".to_string(),
+        }
+    }
 }
 
 impl TlockMetadata {
@@ -98,7 +268,7 @@ impl TlockMetadata {
     ) -> Self {
         Self {
             locked: true,
-            created: Utc::now(),
+            created: clock::now(),
             unlocks,
             duration,
             original_file,
@@ -106,17 +276,433 @@ impl TlockMetadata {
             encrypted_key,
             original_size: None,
             is_directory: false,
+            original_path: None,
+            sealed_entry_index: None,
+            beacon_chain_hash: None,
+            beacon_parameters: None,
+            note: None,
+            original_files: None,
+            expires: None,
+            recipient_encrypted_key: None,
+            compression_filter: None,
+            extra: BTreeMap::new(),
+            crypto_parameters: None,
+        }
+    }
+
+    /// Record how the payload is compressed and encrypted (call `resolve_filter` first)
+    pub fn record_compression(&mut self, compression: &CompressionSettings) {
+        self.compression_filter = compression.filter_name().map(str::to_string);
+        self.crypto_parameters = Some(CryptoParameters::for_compression(compression));
+    }
+
+    /// Serialize for the file header, enforcing `MAX_METADATA_SIZE`
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize metadata: {}", e)))?;
+        if json.len() > MAX_METADATA_SIZE as usize {
+            return Err(TimeLockerError::Parse(format!(
+                "Metadata too large: {} bytes (max: {})",
+                json.len(),
+                MAX_METADATA_SIZE
+            )));
         }
+        Ok(json)
     }
 
     /// Check if the time lock has expired and file is unlockable
     pub fn is_unlockable(&self) -> bool {
-        Utc::now() >= self.unlocks
+        clock::now() >= self.unlocks
+    }
+
+    /// Check if the advisory expiry time has passed
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| clock::now() >= expires)
     }
 
     /// Get time remaining until unlock
     pub fn time_until_unlock(&self) -> chrono::Duration {
-        self.unlocks - Utc::now()
+        self.unlocks - clock::now()
+    }
+
+    /// Record the beacon the archive key is locked against
+    pub fn set_beacon(&mut self, beacon: &crypto::BeaconConfig) {
+        self.beacon_chain_hash = Some(beacon.chain_hash.clone());
+        self.beacon_parameters = Some(crypto::BeaconParameters::of(beacon));
+    }
+
+    /// Drand beacon the archive key is locked against
+    ///
+    /// Quicknet and the configured beacon come with their relays. Any other
+    /// chain falls back to the parameters stored at lock time, which have no
+    /// relays: enough to unlock with a signature fetched elsewhere.
+    pub fn beacon(&self) -> Result<crypto::BeaconConfig> {
+        match crypto::BeaconConfig::for_chain_hash(self.beacon_chain_hash.as_deref()) {
+            Ok(beacon) => Ok(beacon),
+            Err(e) => match &self.beacon_parameters {
+                Some(params)
+                    if self
+                        .beacon_chain_hash
+                        .as_deref()
+                        .is_some_and(|hash| hash.eq_ignore_ascii_case(&params.chain_hash)) =>
+                {
+                    params.to_offline_config()
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Drand round the archive key is time-locked to
+    ///
+    /// Read from the encrypted key itself, falling back to the stored `drand_round`.
+    pub fn unlock_round(&self) -> Option<u64> {
+        self.encrypted_key
+            .as_deref()
+            .and_then(|key| crypto::ciphertext_round(key).ok())
+            .or(self.drand_round)
+    }
+
+    /// When the key's drand round is expected to be published
+    ///
+    /// This is the real earliest unlock: `unlocks` is only the nominal time,
+    /// and decryption keeps failing until the beacon for the round is out.
+    /// Unknown when the file's beacon isn't configured.
+    pub fn decryptable_at(&self) -> Option<DateTime<Utc>> {
+        let beacon = self.beacon().ok()?;
+        self.unlock_round()
+            .map(|round| crypto::round_to_datetime(round, &beacon))
+    }
+
+    /// Get time remaining until the key can actually be decrypted
+    ///
+    /// Falls back to the nominal `time_until_unlock` when the round is unknown.
+    pub fn time_until_cryptographic_unlock(&self) -> chrono::Duration {
+        match self.decryptable_at() {
+            Some(at) => at.max(self.unlocks) - clock::now(),
+            None => self.time_until_unlock(),
+        }
+    }
+
+    /// Resolve the directory to extract into when restoring the original location
+    ///
+    /// The stored `original_path` is re-rooted under `base` (root and prefix
+    /// components are dropped) so extraction can never escape the output directory.
+    /// Falls back to `base` when no path hint was stored.
+    pub fn restore_dir(&self, base: &Path) -> PathBuf {
+        use std::path::Component;
+
+        let mut dir = base.to_path_buf();
+        if let Some(ref original_path) = self.original_path {
+            for component in Path::new(original_path).components() {
+                if let Component::Normal(part) = component {
+                    dir.push(part);
+                }
+            }
+        }
+        dir
+    }
+}
+
+/// Which part of a source's location `original_path_hint` records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathHint {
+    /// Just the name of the folder holding the source
+    Parent,
+    /// The folder holding the source, relative to this root
+    RelativeTo(PathBuf),
+}
+
+/// Build the `original_path` hint for a source
+///
+/// Never an absolute path: that would put the user name and directory layout
+/// in the plaintext metadata. `None` when there is nothing to record (the
+/// source sits at the filesystem root, or directly in `root`).
+///
+/// # Errors
+/// - `Parse` if the source isn't inside the `PathHint::RelativeTo` root
+pub fn original_path_hint(source_path: &Path, hint: &PathHint) -> Result<Option<String>> {
+    let absolute = canonicalize_plain(source_path)?;
+    let Some(parent) = absolute.parent() else {
+        return Ok(None);
+    };
+
+    let relative = match hint {
+        PathHint::Parent => match parent.file_name() {
+            Some(name) => PathBuf::from(name),
+            None => return Ok(None),
+        },
+        PathHint::RelativeTo(root) => {
+            let root = canonicalize_plain(root)?;
+            parent
+                .strip_prefix(&root)
+                .map_err(|_| {
+                    TimeLockerError::Parse(format!(
+                        "{} is not inside {}",
+                        source_path.display(),
+                        root.display()
+                    ))
+                })?
+                .to_path_buf()
+        }
+    };
+
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Ok((!parts.is_empty()).then(|| parts.join("/")))
+}
+
+/// `fs::canonicalize`, without the `\\?\` verbatim prefix Windows adds to drive paths
+///
+/// Verbatim paths can't be compared with or stripped from ordinary ones.
+fn canonicalize_plain(path: &Path) -> Result<PathBuf> {
+    let canonical = fs::canonicalize(path)?;
+
+    #[cfg(windows)]
+    {
+        use std::path::{Component, Prefix};
+        if let Some(Component::Prefix(prefix)) = canonical.components().next() {
+            if let Prefix::VerbatimDisk(_) = prefix.kind() {
+                let plain = canonical.to_string_lossy().trim_start_matches(r"\\?\").to_string();
+                return Ok(PathBuf::from(plain));
+            }
+        }
+    }
+
+    Ok(canonical)
+}
+
+/// Display name for several sources locked together, e.g. "a.txt (+2 more)"
+pub fn multi_source_summary(sources: &[&Path]) -> String {
+    let first = sources
+        .first()
+        .and_then(|s| s.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    match sources.len() {
+        0 | 1 => first,
+        n => format!("{} (+{} more)", first, n - 1),
+    }
+}
+
+/// Trim a user-supplied note, treating a blank one as no note
+pub fn normalize_note(note: Option<String>) -> Option<String> {
+    note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+/// Parse `key=value` pairs into custom metadata fields
+///
+/// # Errors
+/// - `Parse` if a pair has no `=` or an empty key, or a key is repeated
+pub fn parse_extra_fields(pairs: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut extra = BTreeMap::new();
+    for pair in pairs {
+        let (key, value) = pair.split_once('=').ok_or_else(|| {
+            TimeLockerError::Parse(format!("Invalid metadata field '{}': expected key=value", pair))
+        })?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(TimeLockerError::Parse(format!("Metadata field '{}' has no key", pair)));
+        }
+        if extra.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(TimeLockerError::Parse(format!("Duplicate metadata field '{}'", key)));
+        }
+    }
+    Ok(extra)
+}
+
+/// Piece of an output-name template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OutputNamePart {
+    Literal(String),
+    /// `{name}`: the original file name
+    Name,
+    /// `{date}`: local date, `2026-07-01`
+    Date,
+    /// `{timestamp}`: local time to the second, `20260701_093000`
+    Timestamp,
+}
+
+/// Shape of what `{date}` and `{timestamp}` render to (`d` is any digit)
+const DATE_SHAPE: &str = "dddd-dd-dd";
+const TIMESTAMP_SHAPE: &str = "dddddddd_dddddd";
+
+fn parse_output_name_template(template: &str) -> Result<Vec<OutputNamePart>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            parts.push(OutputNamePart::Literal(rest[..open].to_string()));
+        }
+        let close = rest[open..].find('}').map(|i| open + i).ok_or_else(|| {
+            TimeLockerError::Parse(format!("Unclosed '{{' in output name template '{}'", template))
+        })?;
+        parts.push(match &rest[open + 1..close] {
+            "name" => OutputNamePart::Name,
+            "date" => OutputNamePart::Date,
+            "timestamp" => OutputNamePart::Timestamp,
+            other => {
+                return Err(TimeLockerError::Parse(format!(
+                    "Unknown placeholder '{{{}}}' in output name template (use {{name}}, {{date}} or {{timestamp}})",
+                    other
+                )))
+            }
+        });
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(OutputNamePart::Literal(rest.to_string()));
+    }
+
+    // Without {name} every file would unlock into the same directory, and
+    // without fixed text the name could be a folder the user made themselves
+    if !parts.contains(&OutputNamePart::Name) {
+        return Err(TimeLockerError::Parse(format!(
+            "Output name template '{}' needs a {{name}} placeholder",
+            template
+        )));
+    }
+    let has_text = parts
+        .iter()
+        .any(|part| matches!(part, OutputNamePart::Literal(text) if !text.trim().is_empty()));
+    if !has_text {
+        return Err(TimeLockerError::Parse(format!(
+            "Output name template '{}' needs some fixed text besides placeholders, e.g. unlocked_{{name}}",
+            template
+        )));
+    }
+    Ok(parts)
+}
+
+/// Render an output-name template into the name of the directory to unlock into
+///
+/// `{name}` is the original file name, `{date}` the local date and
+/// `{timestamp}` the local time to the second, so unlocking the same file
+/// twice needn't collide. The default is `DEFAULT_OUTPUT_NAME_TEMPLATE`.
+///
+/// # Errors
+/// - `Parse` for unknown placeholders, a template without `{name}` or without
+///   fixed text, or a result that isn't a single path component
+pub fn render_output_name(
+    template: &str,
+    original_file: &str,
+    now: DateTime<chrono::Local>,
+) -> Result<String> {
+    let name: String = parse_output_name_template(template)?
+        .into_iter()
+        .map(|part| match part {
+            OutputNamePart::Literal(text) => text,
+            OutputNamePart::Name => original_file.to_string(),
+            OutputNamePart::Date => now.format("%Y-%m-%d").to_string(),
+            OutputNamePart::Timestamp => now.format("%Y%m%d_%H%M%S").to_string(),
+        })
+        .collect();
+
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(TimeLockerError::Parse(format!(
+            "Output name template '{}' gives '{}', which isn't a valid directory name",
+            template, name
+        )));
+    }
+    Ok(name)
+}
+
+/// Whether `dir_name` could have been rendered from `template` for `original_file`,
+/// at any date or time
+///
+/// For finding earlier unlocks of a file; an invalid template matches nothing.
+pub fn output_name_matches(template: &str, original_file: &str, dir_name: &str) -> bool {
+    let strip_shape = |text: &str, shape: &str| -> Option<usize> {
+        let head = text.get(..shape.len())?;
+        head.chars()
+            .zip(shape.chars())
+            .all(|(c, s)| if s == 'd' { c.is_ascii_digit() } else { c == s })
+            .then_some(shape.len())
+    };
+
+    let parts = match parse_output_name_template(template) {
+        Ok(parts) => parts,
+        Err(_) => return false,
+    };
+    let mut rest = dir_name;
+    for part in &parts {
+        let matched = match part {
+            OutputNamePart::Literal(text) => rest.starts_with(text.as_str()).then_some(text.len()),
+            OutputNamePart::Name => rest.starts_with(original_file).then_some(original_file.len()),
+            OutputNamePart::Date => strip_shape(rest, DATE_SHAPE),
+            OutputNamePart::Timestamp => strip_shape(rest, TIMESTAMP_SHAPE),
+        };
+        match matched {
+            Some(len) => rest = &rest[len..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// Whether a template renders the same name whenever it's used (no date or time)
+pub fn output_name_is_fixed(template: &str) -> bool {
+    parse_output_name_template(template).is_ok_and(|parts| {
+        parts
+            .iter()
+            .all(|part| matches!(part, OutputNamePart::Literal(_) | OutputNamePart::Name))
+    })
+}
+
+/// Files in a locked archive and their total uncompressed size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentSummary {
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+/// What an extraction would write, computed without writing anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionPlan {
+    /// Directory the entries would be extracted into
+    pub output_dir: PathBuf,
+    /// Entries in the archive
+    pub entries: Vec<ArchiveEntryInfo>,
+    /// Total uncompressed size of all entries
+    pub total_size: u64,
+    /// Entries whose output file already exists
+    pub conflicts: Vec<String>,
+}
+
+/// Options controlling how `TlockArchive::create_with_options` builds the file
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Store a sealed entry index in the metadata for fast listing
+    pub entry_index: bool,
+    /// Format version to write, for sharing with older builds (`None` = `TLOCK_VERSION`)
+    pub format_version: Option<u8>,
+    /// Record where the source was locked from as `original_path` (`None` = don't)
+    pub path_hint: Option<PathHint>,
+    /// Compression method and level for the 7z payload
+    pub compression: CompressionSettings,
+    /// Maximum read throughput while archiving, in bytes per second (`None` = unlimited)
+    pub rate_limit: Option<u64>,
+    /// Where to report progress while the sources are archived
+    pub progress: Option<Arc<ProgressEmitter>>,
+}
+
+impl CreateOptions {
+    /// Resolve the version to write, rejecting versions this build can't write
+    ///
+    /// Every option can be written in any supported version: older readers
+    /// ignore metadata fields they don't know. Version 1 files just carry no
+    /// payload checksum (see `CHECKSUM_VERSION`).
+    pub fn resolve_version(&self) -> Result<u8> {
+        let version = self.format_version.unwrap_or(TLOCK_VERSION);
+        if !(MIN_TLOCK_VERSION..=TLOCK_VERSION).contains(&version) {
+            return Err(TimeLockerError::Parse(format!(
+                "Unsupported format version: {} (supported: {}-{})",
+                version, MIN_TLOCK_VERSION, TLOCK_VERSION
+            )));
+        }
+        Ok(version)
     }
 }
 
@@ -158,6 +744,18 @@ impl TlockArchive {
         source_path: &Path,
         metadata: TlockMetadata,
         password: &str,
+    ) -> Result<PathBuf> {
+        Self::create_with_options(source_path, metadata, password, &CreateOptions::default())
+    }
+
+    /// Create a new .7z.tlock file with creation options
+    ///
+    /// Same as `create`; `options` enables optional extras such as the entry index.
+    pub fn create_with_options(
+        source_path: &Path,
+        mut metadata: TlockMetadata,
+        password: &str,
+        options: &CreateOptions,
     ) -> Result<PathBuf> {
         if !source_path.exists() {
             return Err(TimeLockerError::FileNotFound(
@@ -165,55 +763,286 @@ impl TlockArchive {
             ));
         }
 
-        eprintln!("[TlockArchive::create] Creating .7z.tlock from: {:?}", source_path);
+        let version = options.resolve_version()?;
+
+        eprintln!("[TlockArchive::create] Creating .7z.tlock (v{}) from: {:?}", version, source_path);
+
+        let compression = options.compression.resolve_filter(&[source_path]);
+        metadata.record_compression(&compression);
 
         // Step 1: Create the encrypted 7z archive
-        let temp_7z_path = create_encrypted_archive(source_path, password)?;
+        let temp_7z_path = create_encrypted_archive_with_rate_limit(
+            source_path,
+            password,
+            &compression,
+            options.rate_limit,
+            options.progress.as_deref(),
+        )?;
 
-        // Step 2: Serialize metadata to JSON
-        let metadata_json = serde_json::to_vec(&metadata)
-            .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize metadata: {}", e)))?;
+        let tlock_path = Self::output_path(&[source_path])?;
+        Self::wrap_payload(&temp_7z_path, &tlock_path, metadata, password, options, version)?;
 
-        let metadata_len = metadata_json.len() as u32;
-        if metadata_len > MAX_METADATA_SIZE {
-            // Clean up temp file
-            let _ = fs::remove_file(&temp_7z_path);
-            return Err(TimeLockerError::Parse(format!(
-                "Metadata too large: {} bytes (max: {})",
-                metadata_len, MAX_METADATA_SIZE
-            )));
+        eprintln!("[TlockArchive::create] Successfully created .7z.tlock file");
+        Ok(tlock_path)
+    }
+
+    /// Path of the .7z.tlock file `create` (one source) or `create_multi` writes
+    ///
+    /// Next to the first source: `<name>.7z.tlock`, or
+    /// `<first name>_and_<n>_more.7z.tlock` for several sources.
+    pub fn output_path(sources: &[&Path]) -> Result<PathBuf> {
+        match sources {
+            [] => Err(TimeLockerError::Archive("No sources to lock".to_string())),
+            [source] => Ok(source.with_extension("7z.tlock")),
+            [first, rest @ ..] => Ok(first.with_file_name(format!(
+                "{}_and_{}_more.7z.tlock",
+                first.file_name().unwrap_or_default().to_string_lossy(),
+                rest.len()
+            ))),
         }
+    }
+
+    /// Create one .7z.tlock file holding several independent files or directories
+    ///
+    /// Each source becomes a top-level entry of the archive, so unlocking
+    /// recreates them side by side. All source names are recorded in
+    /// `original_files`; `original_file` stays the caller's display summary
+    /// (see `multi_source_summary`). The file is written next to the first source.
+    pub fn create_multi(
+        sources: &[&Path],
+        metadata: TlockMetadata,
+        password: &str,
+    ) -> Result<PathBuf> {
+        Self::create_multi_with_options(sources, metadata, password, &CreateOptions::default())
+    }
 
-        // Step 3: Build the output path
-        let tlock_path = source_path.with_extension("7z.tlock");
+    /// Same as `create_multi`, with creation options
+    pub fn create_multi_with_options(
+        sources: &[&Path],
+        mut metadata: TlockMetadata,
+        password: &str,
+        options: &CreateOptions,
+    ) -> Result<PathBuf> {
+        let tlock_path = Self::output_path(sources)?;
+        for source in sources {
+            if !source.exists() {
+                return Err(TimeLockerError::FileNotFound(source.display().to_string()));
+            }
+        }
 
-        eprintln!("[TlockArchive::create] Writing .7z.tlock to: {:?}", tlock_path);
+        let version = options.resolve_version()?;
+
+        eprintln!("[TlockArchive::create_multi] Creating .7z.tlock (v{}) from {} sources", version, sources.len());
+
+        let names: Vec<String> = sources
+            .iter()
+            .map(|s| s.file_name().unwrap_or_default().to_string_lossy().to_string())
+            .collect();
+        metadata.original_files = Some(names);
+        metadata.is_directory = false;
+
+        let compression = options.compression.resolve_filter(sources);
+        metadata.record_compression(&compression);
+
+        let temp_7z_path = tlock_path.with_extension("7z");
+        create_encrypted_archive_multi_with_progress(
+            sources,
+            &temp_7z_path,
+            password,
+            &compression,
+            options.rate_limit,
+            options.progress.as_deref(),
+        )?;
+        Self::wrap_payload(&temp_7z_path, &tlock_path, metadata, password, options, version)?;
+
+        eprintln!("[TlockArchive::create_multi] Successfully created .7z.tlock file");
+        Ok(tlock_path)
+    }
+
+    /// Wrap an encrypted 7z archive built by the caller into a .7z.tlock file
+    ///
+    /// For callers that archive on their own (e.g. with progress reporting).
+    /// The archive is streamed into `tlock_path`, never read into memory, and
+    /// removed afterwards whether or not wrapping succeeds.
+    pub fn wrap_archive(
+        archive_path: &Path,
+        tlock_path: &Path,
+        metadata: TlockMetadata,
+        password: &str,
+        options: &CreateOptions,
+    ) -> Result<()> {
+        let version = match options.resolve_version() {
+            Ok(version) => version,
+            Err(e) => {
+                let _ = fs::remove_file(archive_path);
+                return Err(e);
+            }
+        };
+        Self::wrap_payload(archive_path, tlock_path, metadata, password, options, version)
+    }
+
+    /// Lock the same sources into several .7z.tlock files, compressing them only once
+    ///
+    /// `metadata` is the template for every output; each of `outputs` names the
+    /// file to write and its unlock time, for which `password` is tlock-encrypted
+    /// on the template's beacon. All outputs carry a copy of one encrypted 7z
+    /// payload, so they share `password` as well: whoever opens one output early
+    /// can open all of them. The shared temp archive is removed once every output
+    /// is written; if any output fails, the ones already written are removed too.
+    ///
+    /// # Returns
+    /// The paths of the created files, in the order of `outputs`
+    pub fn create_many(
+        sources: &[&Path],
+        mut metadata: TlockMetadata,
+        password: &str,
+        outputs: &[(PathBuf, DateTime<Utc>)],
+        options: &CreateOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let first_output = match outputs.first() {
+            Some((path, _)) => path,
+            None => return Err(TimeLockerError::Archive("No outputs to write".to_string())),
+        };
+        for source in sources {
+            if !source.exists() {
+                return Err(TimeLockerError::FileNotFound(source.display().to_string()));
+            }
+        }
+
+        let version = options.resolve_version()?;
+        let beacon = metadata.beacon()?;
+
+        eprintln!(
+            "[TlockArchive::create_many] Creating {} .7z.tlock files (v{}) from {} sources",
+            outputs.len(),
+            version,
+            sources.len()
+        );
 
-        // Step 4: Write the .7z.tlock file
-        let result = Self::write_tlock_file(&tlock_path, &metadata_json, &temp_7z_path);
+        let compression = options.compression.resolve_filter(sources);
+        metadata.record_compression(&compression);
+
+        // Step 1: Compress and encrypt the sources once
+        let temp_7z_path = match sources {
+            [source] => create_encrypted_archive_with_rate_limit(
+                source,
+                password,
+                &compression,
+                options.rate_limit,
+                options.progress.as_deref(),
+            )?,
+            _ => {
+                metadata.original_files = Some(
+                    sources
+                        .iter()
+                        .map(|s| s.file_name().unwrap_or_default().to_string_lossy().to_string())
+                        .collect(),
+                );
+                metadata.is_directory = false;
+                let temp_7z_path = first_output.with_extension("7z");
+                create_encrypted_archive_multi_with_progress(
+                    sources,
+                    &temp_7z_path,
+                    password,
+                    &compression,
+                    options.rate_limit,
+                    options.progress.as_deref(),
+                )?;
+                temp_7z_path
+            }
+        };
+
+        // Step 2: Wrap the shared payload once per unlock time
+        let mut created = Vec::with_capacity(outputs.len());
+        let mut result = Ok(());
+        for (tlock_path, unlocks) in outputs {
+            let mut output_metadata = metadata.clone();
+            result = crypto::encrypt_with_tlock(password, *unlocks, &beacon).and_then(|key| {
+                output_metadata.encrypted_key = Some(key);
+                output_metadata.unlocks = *unlocks;
+                output_metadata.duration = unlocks.format("%Y-%m-%d").to_string();
+                output_metadata.drand_round = Some(crypto::datetime_to_round(*unlocks, &beacon));
+                Self::write_wrapper(&temp_7z_path, tlock_path, output_metadata, password, options, version)
+            });
+            if result.is_err() {
+                let _ = fs::remove_file(tlock_path);
+                break;
+            }
+            created.push(tlock_path.clone());
+        }
 
-        // Step 5: Clean up temp 7z file
+        // Step 3: Clean up the shared payload, and any partial set of outputs
         if let Err(e) = fs::remove_file(&temp_7z_path) {
+            eprintln!("[TlockArchive::create_many] Warning: Failed to remove temp file: {}", e);
+        }
+        if let Err(e) = result {
+            for path in &created {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e);
+        }
+
+        eprintln!("[TlockArchive::create_many] Successfully created {} .7z.tlock files", created.len());
+        Ok(created)
+    }
+
+    /// Wrap a finished 7z archive into a .7z.tlock file, removing the archive afterwards
+    fn wrap_payload(
+        temp_7z_path: &Path,
+        tlock_path: &Path,
+        metadata: TlockMetadata,
+        password: &str,
+        options: &CreateOptions,
+        version: u8,
+    ) -> Result<()> {
+        let result = Self::write_wrapper(temp_7z_path, tlock_path, metadata, password, options, version);
+
+        // Step 4: Clean up temp 7z file
+        if let Err(e) = fs::remove_file(temp_7z_path) {
             eprintln!("[TlockArchive::create] Warning: Failed to remove temp file: {}", e);
         }
 
-        result?;
+        result
+    }
 
-        eprintln!("[TlockArchive::create] Successfully created .7z.tlock file");
-        Ok(tlock_path)
+    /// Wrap a finished 7z archive into a .7z.tlock file, leaving the archive in place
+    fn write_wrapper(
+        temp_7z_path: &Path,
+        tlock_path: &Path,
+        mut metadata: TlockMetadata,
+        password: &str,
+        options: &CreateOptions,
+        version: u8,
+    ) -> Result<()> {
+        // Optional: seal an index of the archive entries into the metadata
+        if options.entry_index {
+            let file = File::open(temp_7z_path)?;
+            metadata.sealed_entry_index = Some(build_entry_index(BufReader::new(file), password)?);
+        }
+
+        // Step 2: Serialize metadata to JSON
+        let metadata_json = metadata.to_json()?;
+
+        eprintln!("[TlockArchive::create] Writing .7z.tlock to: {:?}", tlock_path);
+
+        // Step 3: Write the .7z.tlock file
+        let checksum = payload_checksum(&mut BufReader::new(File::open(temp_7z_path)?))?;
+        Self::write_tlock_file(tlock_path, version, &checksum, &metadata_json, temp_7z_path)
     }
 
     /// Write the complete .7z.tlock file
     fn write_tlock_file(
         tlock_path: &Path,
+        version: u8,
+        checksum: &PayloadChecksum,
         metadata_json: &[u8],
         payload_path: &Path,
     ) -> Result<()> {
         let file = File::create(tlock_path)?;
         let mut writer = BufWriter::new(file);
 
-        // Write header
-        Self::write_header(&mut writer, metadata_json.len() as u32)?;
+        // Write header (and checksum, for versions that have one)
+        Self::write_header(&mut writer, version, metadata_json.len() as u32, checksum)?;
 
         // Write metadata
         writer.write_all(metadata_json)?;
@@ -221,27 +1050,42 @@ impl TlockArchive {
         // Write payload (the encrypted 7z archive)
         let payload_file = File::open(payload_path)?;
         let mut payload_reader = BufReader::new(payload_file);
-        std::io::copy(&mut payload_reader, &mut writer)?;
+        copy_payload(&mut payload_reader, &mut writer)?;
 
         writer.flush()?;
         Ok(())
     }
 
-    /// Write the fixed-size header
-    fn write_header<W: Write>(writer: &mut W, metadata_len: u32) -> Result<()> {
+    /// Write the fixed-size header, followed by the payload checksum from version 2 on
+    fn write_header<W: Write>(
+        writer: &mut W,
+        version: u8,
+        metadata_len: u32,
+        checksum: &PayloadChecksum,
+    ) -> Result<()> {
+        writer.write_all(&Self::build_header(version, metadata_len))?;
+        if version >= CHECKSUM_VERSION {
+            writer.write_all(checksum)?;
+        }
+        Ok(())
+    }
+
+    /// Build the canonical fixed-size header
+    fn build_header(version: u8, metadata_len: u32) -> [u8; HEADER_SIZE] {
+        let mut header = [0u8; HEADER_SIZE];
+
         // Magic bytes (7 bytes)
-        writer.write_all(TLOCK_MAGIC)?;
+        header[0..7].copy_from_slice(TLOCK_MAGIC);
 
         // Version (1 byte)
-        writer.write_all(&[TLOCK_VERSION])?;
+        header[7] = version;
 
         // Metadata length (4 bytes, little-endian)
-        writer.write_all(&metadata_len.to_le_bytes())?;
+        header[8..12].copy_from_slice(&metadata_len.to_le_bytes());
 
-        // Reserved bytes (12 bytes)
-        writer.write_all(&[0u8; 12])?;
+        // Reserved bytes (12 bytes) stay zero
 
-        Ok(())
+        header
     }
 
     /// Read just the metadata from a .7z.tlock file (no password needed)
@@ -267,8 +1111,26 @@ impl TlockArchive {
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
 
+        let metadata = Self::read_metadata_from(&mut reader)?;
+
+        eprintln!(
+            "[TlockArchive::read_metadata] Loaded metadata for: {}",
+            metadata.original_file
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            metadata: Some(metadata),
+        })
+    }
+
+    /// Read the header and metadata from any stream positioned at the start of a .7z.tlock file
+    ///
+    /// Only the header and metadata bytes are consumed, so this also works on
+    /// partial data such as the beginning of a download.
+    pub fn read_metadata_from<R: Read>(reader: &mut R) -> Result<TlockMetadata> {
         // Read and validate header
-        let (version, metadata_len) = Self::read_and_validate_header(&mut reader)?;
+        let (version, metadata_len, _checksum) = Self::read_and_validate_header(reader)?;
 
         eprintln!(
             "[TlockArchive::read_metadata] Version: {}, Metadata len: {}",
@@ -282,29 +1144,39 @@ impl TlockArchive {
         })?;
 
         // Parse metadata
-        let metadata: TlockMetadata = serde_json::from_slice(&metadata_bytes)
-            .map_err(|e| TimeLockerError::Parse(format!("Invalid metadata JSON: {}", e)))?;
-
-        eprintln!(
-            "[TlockArchive::read_metadata] Loaded metadata for: {}",
-            metadata.original_file
-        );
-
-        Ok(Self {
-            path: path.to_path_buf(),
-            metadata: Some(metadata),
-        })
-    }
+        serde_json::from_slice(&metadata_bytes)
+            .map_err(|e| TimeLockerError::Parse(format!("Invalid metadata JSON: {}", e)))
+    }
 
     /// Read and validate the file header
     ///
-    /// Returns (version, metadata_length)
-    fn read_and_validate_header<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
+    /// Returns (version, metadata_length, payload checksum). Version 1 files
+    /// have no checksum.
+    fn read_and_validate_header<R: Read>(reader: &mut R) -> Result<(u8, u32, Option<PayloadChecksum>)> {
         let mut header = [0u8; HEADER_SIZE];
         reader.read_exact(&mut header).map_err(|e| {
             TimeLockerError::Parse(format!("Failed to read header: {}", e))
         })?;
 
+        let (version, metadata_len) = Self::parse_header(&header)?;
+
+        let checksum = if version >= CHECKSUM_VERSION {
+            let mut checksum = [0u8; CHECKSUM_SIZE];
+            reader.read_exact(&mut checksum).map_err(|e| {
+                TimeLockerError::Parse(format!("Failed to read payload checksum: {}", e))
+            })?;
+            Some(checksum)
+        } else {
+            None
+        };
+
+        Ok((version, metadata_len, checksum))
+    }
+
+    /// Validate the fixed-size header
+    ///
+    /// Returns (version, metadata_length)
+    fn parse_header(header: &[u8; HEADER_SIZE]) -> Result<(u8, u32)> {
         // Validate magic bytes
         if &header[0..7] != TLOCK_MAGIC {
             return Err(TimeLockerError::Parse(
@@ -315,10 +1187,10 @@ impl TlockArchive {
         // Check version
         let version = header[7];
         if version > TLOCK_VERSION {
-            return Err(TimeLockerError::Parse(format!(
-                "Unsupported .7z.tlock version: {} (max supported: {})",
-                version, TLOCK_VERSION
-            )));
+            return Err(TimeLockerError::UnsupportedVersion {
+                version,
+                max: TLOCK_VERSION,
+            });
         }
 
         // Read metadata length
@@ -355,14 +1227,8 @@ impl TlockArchive {
         eprintln!("[TlockArchive::extract] Extracting: {:?}", path);
         eprintln!("[TlockArchive::extract] Destination: {:?}", dest);
 
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
-        // Read and validate header
-        let (_version, metadata_len) = Self::read_and_validate_header(&mut reader)?;
-
-        // Skip metadata section
-        reader.seek(SeekFrom::Current(metadata_len as i64))?;
+        // Validate header and skip metadata
+        let mut payload = Self::open_payload(path)?;
 
         // Create temp file for 7z payload
         let temp_dir = std::env::temp_dir();
@@ -373,16 +1239,10 @@ impl TlockArchive {
 
         eprintln!("[TlockArchive::extract] Temp 7z: {:?}", temp_7z_path);
 
-        // Extract payload to temp file
-        {
-            let temp_file = File::create(&temp_7z_path)?;
-            let mut temp_writer = BufWriter::new(temp_file);
-            std::io::copy(&mut reader, &mut temp_writer)?;
-            temp_writer.flush()?;
-        }
-
-        // Extract the 7z archive
-        let result = extract_encrypted_archive(&temp_7z_path, password, dest);
+        // Extract payload to temp file, checking it against the stored checksum,
+        // then extract the 7z archive
+        let result = copy_verified_payload(&mut payload, &temp_7z_path)
+            .and_then(|_| extract_encrypted_archive(&temp_7z_path, password, dest));
 
         // Clean up temp file
         if let Err(e) = fs::remove_file(&temp_7z_path) {
@@ -395,6 +1255,122 @@ impl TlockArchive {
         Ok(())
     }
 
+    /// Extract the contents of a .7z.tlock file with extraction options
+    ///
+    /// Same as `extract`, but entries are written one at a time so options such
+    /// as `resume` can be applied to each of them.
+    pub fn extract_with_options(
+        path: &Path,
+        password: &str,
+        dest: &Path,
+        options: &ExtractOptions,
+    ) -> Result<()> {
+        let temp_7z_path = Self::extract_payload_to_temp(path)?;
+
+        let result = extract_encrypted_archive_with_options(&temp_7z_path, password, dest, options);
+
+        if let Err(e) = fs::remove_file(&temp_7z_path) {
+            eprintln!("[TlockArchive::extract_with_options] Warning: Failed to remove temp file: {}", e);
+        }
+
+        result
+    }
+
+    /// List the entries inside a .7z.tlock file
+    ///
+    /// Uses the sealed entry index from the metadata when present, and falls
+    /// back to parsing the 7z header otherwise.
+    pub fn list_entries(path: &Path, password: &str) -> Result<Vec<ArchiveEntryInfo>> {
+        let archive = Self::read_metadata(path)?;
+
+        if let Some(sealed) = archive.get_metadata().and_then(|m| m.sealed_entry_index.as_ref()) {
+            let json = crypto::open_with_password(sealed, password)?;
+            return serde_json::from_slice(&json)
+                .map_err(|e| TimeLockerError::Parse(format!("Invalid entry index: {}", e)));
+        }
+
+        let payload = Self::open_payload(path)?;
+        list_archive_entries(payload, password)
+    }
+
+    /// Count the files in a .7z.tlock file and their uncompressed size
+    ///
+    /// Read from the archive's own entry table, so unlike `original_size` this
+    /// also works for files locked or migrated before sizes were recorded.
+    /// The 7z headers are encrypted, so it needs the password.
+    pub fn content_summary(path: &Path, password: &str) -> Result<ContentSummary> {
+        let files: Vec<_> = Self::list_entries(path, password)?
+            .into_iter()
+            .filter(|e| !e.is_directory)
+            .collect();
+        Ok(ContentSummary {
+            file_count: files.len() as u64,
+            total_size: files.iter().map(|e| e.size).sum(),
+        })
+    }
+
+    /// Decrypt one file entry into memory, for previews that shouldn't touch disk
+    ///
+    /// Fails if the entry is larger than `max_bytes`.
+    pub fn read_entry(path: &Path, password: &str, entry_name: &str, max_bytes: u64) -> Result<Vec<u8>> {
+        let payload = Self::open_payload(path)?;
+        read_archive_entry(payload, password, entry_name, max_bytes)
+    }
+
+    /// Work out what extracting into `dest` would write (dry run)
+    ///
+    /// Requires the archive password, since the entry list is encrypted.
+    pub fn plan_extraction(path: &Path, password: &str, dest: &Path) -> Result<ExtractionPlan> {
+        let entries = Self::list_entries(path, password)?;
+
+        let total_size = entries.iter().map(|e| e.size).sum();
+        let conflicts = entries
+            .iter()
+            .filter(|e| !e.is_directory && dest.join(&e.name).exists())
+            .map(|e| e.name.clone())
+            .collect();
+
+        Ok(ExtractionPlan {
+            output_dir: dest.to_path_buf(),
+            entries,
+            total_size,
+            conflicts,
+        })
+    }
+
+    /// Check that every file entry of the archive exists in `dest` with the expected content
+    ///
+    /// Used before deleting a .7z.tlock file after unlocking, so the locked copy
+    /// is only removed once its contents are known to be on disk. Each file is
+    /// compared against the CRC-32 the archive records for it; a same-sized
+    /// file left over from an earlier unlock doesn't pass, and neither does a
+    /// non-empty entry without a recorded CRC.
+    pub fn verify_extraction(path: &Path, password: &str, dest: &Path) -> Result<bool> {
+        // From the 7z header itself, not an entry index that may predate CRCs
+        let entries = list_archive_entries(Self::open_payload(path)?, password)?;
+
+        for entry in entries.iter().filter(|e| !e.is_directory) {
+            let extracted = dest.join(&entry.name);
+            let same_size = fs::metadata(&extracted)
+                .map(|m| m.is_file() && m.len() == entry.size)
+                .unwrap_or(false);
+            let matches = same_size
+                && match entry.crc {
+                    Some(crc) => file_crc32(&extracted)? == crc,
+                    None => entry.size == 0,
+                };
+            if !matches {
+                eprintln!(
+                    "[TlockArchive::verify_extraction] Missing or incomplete: {:?}",
+                    extracted
+                );
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
     /// Get the metadata (if loaded)
     pub fn get_metadata(&self) -> Option<&TlockMetadata> {
         self.metadata.as_ref()
@@ -431,9 +1407,471 @@ impl TlockArchive {
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
 
-        let (_version, metadata_len) = Self::read_and_validate_header(&mut reader)?;
+        let (version, metadata_len, _checksum) = Self::read_and_validate_header(&mut reader)?;
+
+        Ok(header_len(version) + metadata_len as u64)
+    }
+
+    /// Open the 7z payload in place, without copying it to a temp file
+    ///
+    /// The returned reader presents the payload as if it were a standalone
+    /// 7z file, so it can be handed directly to the 7z reader.
+    pub fn open_payload(path: &Path) -> Result<PayloadReader> {
+        if !path.exists() {
+            return Err(TimeLockerError::FileNotFound(path.display().to_string()));
+        }
+
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let (version, metadata_len, checksum) = Self::read_and_validate_header(&mut reader)?;
+        let offset = header_len(version) + metadata_len as u64;
+        if offset > file_len {
+            return Err(TimeLockerError::Parse(
+                "File is truncated: payload offset is past end of file".to_string(),
+            ));
+        }
+
+        reader.seek(SeekFrom::Start(offset))?;
+
+        Ok(PayloadReader {
+            inner: reader,
+            offset,
+            len: file_len - offset,
+            pos: 0,
+            checksum,
+        })
+    }
+
+    /// Check a .7z.tlock file for corruption, without the password
+    ///
+    /// Verifies the header and metadata, then the structure of the 7z payload:
+    /// its signature and start-header CRC, that the payload is long enough to
+    /// hold the 7z end header, and that end header's CRC. From version 2 on the
+    /// whole payload is also checked against the stored checksum; for version 1
+    /// files the encrypted contents can only be checked by extracting them.
+    ///
+    /// # Errors
+    /// Describes the first problem found
+    pub fn verify_integrity(path: &Path) -> Result<()> {
+        let archive = Self::read_metadata(path)?;
+        let has_key = archive
+            .get_metadata()
+            .map(|m| m.encrypted_key.is_some())
+            .unwrap_or(false);
+        if !has_key {
+            return Err(TimeLockerError::Parse(
+                "Metadata has no encrypted key".to_string(),
+            ));
+        }
+
+        let mut payload = Self::open_payload(path)?;
+        verify_7z_structure(&mut payload)?;
+
+        if let Some(expected) = payload.checksum().copied() {
+            payload.seek(SeekFrom::Start(0))?;
+            check_payload_checksum(&expected, &payload_checksum(&mut payload)?)?;
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the whole file (header, metadata and payload), as hex
+    ///
+    /// For telling whether two copies, e.g. in different backups, are
+    /// byte-identical. Unlike the payload checksum it covers the metadata too,
+    /// so even editing the note changes it. Not a security measure: anyone
+    /// able to modify the file can recompute it.
+    pub fn fingerprint(path: &Path) -> Result<String> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_and_validate_header(&mut reader)?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(hex::encode(payload_checksum(&mut reader)?))
+    }
+
+    /// Write the raw (still encrypted) 7z payload to `dest`
+    ///
+    /// The result is a standard 7z archive that can be opened with external
+    /// tools given the archive password.
+    ///
+    /// # Returns
+    /// Number of bytes written
+    pub fn export_payload(path: &Path, dest: &Path) -> Result<u64> {
+        let mut payload = Self::open_payload(path)?;
+
+        let file = File::create(dest)?;
+        let mut writer = BufWriter::new(file);
+        let written = copy_payload(&mut payload, &mut writer)?;
+        writer.flush()?;
+
+        eprintln!("[TlockArchive::export_payload] Wrote {} bytes to {:?}", written, dest);
+        Ok(written)
+    }
+
+    /// Replace the metadata of a .7z.tlock file, keeping its payload byte-for-byte
+    ///
+    /// When the new metadata serializes to the same length as the old, only the
+    /// metadata region is overwritten in place, so multi-gigabyte payloads
+    /// aren't copied. Otherwise the new file is written next to the original
+    /// and renamed over it, so an interruption never leaves a half-written
+    /// .7z.tlock behind. The header version is preserved.
+    pub fn rewrite_metadata(path: &Path, metadata: &TlockMetadata) -> Result<()> {
+        let (version, old_metadata_len) = {
+            let mut reader = BufReader::new(File::open(path)?);
+            let (version, metadata_len, _checksum) = Self::read_and_validate_header(&mut reader)?;
+            (version, metadata_len)
+        };
+
+        let metadata_json = metadata.to_json()?;
+
+        if metadata_json.len() == old_metadata_len as usize {
+            // Same length: the header stays valid, so overwrite just the metadata
+            let mut metadata_offset = HEADER_SIZE as u64;
+            if version >= CHECKSUM_VERSION {
+                metadata_offset += CHECKSUM_SIZE as u64;
+            }
+            let mut file = fs::OpenOptions::new().write(true).open(path)?;
+            file.seek(SeekFrom::Start(metadata_offset))?;
+            file.write_all(&metadata_json)?;
+            file.sync_all()?;
+            eprintln!("[TlockArchive::rewrite_metadata] Rewrote metadata of {:?} in place", path);
+            return Ok(());
+        }
+
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+
+        if let Err(e) = Self::write_with_payload_of(path, &temp_path, version, &metadata_json) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        fs::rename(&temp_path, path)?;
+        eprintln!("[TlockArchive::rewrite_metadata] Rewrote metadata of: {:?}", path);
+        Ok(())
+    }
+
+    /// Re-lock a .7z.tlock file for a new unlock time, leaving its payload untouched
+    ///
+    /// Only the tlock-encrypted archive password and the time fields of the
+    /// metadata change. That needs the plaintext archive password, which can
+    /// only be recovered once the current lock has expired (or is known because
+    /// the user chose it), so a still-locked file can't be moved this way. The
+    /// password is checked against the payload before anything is rewritten.
+    ///
+    /// # Returns
+    /// The rewritten metadata
+    pub fn change_unlock_time(path: &Path, password: &str, unlocks: DateTime<Utc>) -> Result<TlockMetadata> {
+        let mut metadata = Self::read_metadata(path)?
+            .metadata
+            .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+
+        if !verify_archive_password(Self::open_payload(path)?, password)? {
+            return Err(TimeLockerError::Decryption("Incorrect password".to_string()));
+        }
+
+        // Stay on the beacon the file was locked against
+        let beacon = metadata.beacon()?;
+        metadata.encrypted_key = Some(crypto::encrypt_with_tlock(password, unlocks, &beacon)?);
+        metadata.unlocks = unlocks;
+        metadata.duration = unlocks.format("%Y-%m-%d").to_string();
+        metadata.drand_round = Some(crypto::datetime_to_round(unlocks, &beacon));
+        metadata.locked = true;
+
+        Self::rewrite_metadata(path, &metadata)?;
+        eprintln!("[TlockArchive::change_unlock_time] {:?} now unlocks at {}", path, unlocks);
+        Ok(metadata)
+    }
+
+    /// Rewrite the header of a .7z.tlock file in the canonical layout
+    ///
+    /// Readers ignore the reserved header bytes today, so files written by
+    /// other tools may carry anything there. Before a format version gives
+    /// those bytes a meaning they have to be zeroed. The version, checksum,
+    /// metadata and payload are kept byte-for-byte; the rewritten file is
+    /// checked with `verify_integrity` before it atomically replaces the original.
+    ///
+    /// # Returns
+    /// Whether the header had to be rewritten
+    pub fn normalize_header(path: &Path) -> Result<bool> {
+        if !path.exists() {
+            return Err(TimeLockerError::FileNotFound(path.display().to_string()));
+        }
+
+        let (version, metadata_json) = {
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut header = [0u8; HEADER_SIZE];
+            reader.read_exact(&mut header).map_err(|e| {
+                TimeLockerError::Parse(format!("Failed to read header: {}", e))
+            })?;
+
+            let (version, metadata_len) = Self::parse_header(&header)?;
+            if header == Self::build_header(version, metadata_len) {
+                return Ok(false);
+            }
+
+            let checksum_len = header_len(version) - HEADER_SIZE as u64;
+            reader.seek(SeekFrom::Current(checksum_len as i64))?;
+
+            let mut metadata_json = vec![0u8; metadata_len as usize];
+            reader.read_exact(&mut metadata_json).map_err(|e| {
+                TimeLockerError::Parse(format!("Failed to read metadata: {}", e))
+            })?;
+            serde_json::from_slice::<TlockMetadata>(&metadata_json)
+                .map_err(|e| TimeLockerError::Parse(format!("Invalid metadata JSON: {}", e)))?;
+            (version, metadata_json)
+        };
+
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+
+        let written = Self::write_with_payload_of(path, &temp_path, version, &metadata_json)
+            .and_then(|_| Self::verify_integrity(&temp_path));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        fs::rename(&temp_path, path)?;
+        eprintln!("[TlockArchive::normalize_header] Normalized header of: {:?}", path);
+        Ok(true)
+    }
+
+    /// Rebuild a .7z.tlock file whose header or metadata is damaged, from the
+    /// 7z payload still intact further in
+    ///
+    /// A last resort, e.g. after a transfer truncated the start of the file.
+    /// The file is scanned for a 7z signature whose start and end headers
+    /// check out; everything from there to the end of that archive becomes the
+    /// payload, and `metadata` (at least the original file name, unlock time
+    /// and encrypted key, which only the locker knows) replaces whatever was
+    /// left of the old metadata. The result is written next to the damaged
+    /// file as `<name>.recovered.7z.tlock`; the damaged file is left alone.
+    ///
+    /// # Returns
+    /// Path of the rebuilt file
+    pub fn recover(corrupt_path: &Path, metadata: TlockMetadata) -> Result<PathBuf> {
+        if !corrupt_path.exists() {
+            return Err(TimeLockerError::FileNotFound(corrupt_path.display().to_string()));
+        }
+        if metadata.encrypted_key.is_none() {
+            return Err(TimeLockerError::MissingField("encrypted_key".to_string()));
+        }
+
+        let output = recovered_path(corrupt_path);
+        if output.exists() {
+            return Err(TimeLockerError::Archive(format!(
+                "{} already exists",
+                output.display()
+            )));
+        }
+
+        let mut payload = find_7z_payload(corrupt_path)?;
+        eprintln!(
+            "[TlockArchive::recover] Found a {}-byte 7z payload at offset {}",
+            payload.len, payload.offset
+        );
+
+        let temp_7z_path = output.with_extension("7z");
+        let written = (|| -> Result<()> {
+            payload.seek(SeekFrom::Start(0))?;
+            let mut writer = BufWriter::new(File::create(&temp_7z_path)?);
+            copy_payload(&mut payload, &mut writer)?;
+            writer.flush()?;
+            drop(writer);
+
+            // The password only matters for an entry index, which isn't built
+            Self::write_wrapper(
+                &temp_7z_path,
+                &output,
+                metadata,
+                "",
+                &CreateOptions::default(),
+                TLOCK_VERSION,
+            )?;
+            Self::verify_integrity(&output)
+        })();
+        let _ = fs::remove_file(&temp_7z_path);
+        if let Err(e) = written {
+            let _ = fs::remove_file(&output);
+            return Err(e);
+        }
+
+        eprintln!("[TlockArchive::recover] Rebuilt: {:?}", output);
+        Ok(output)
+    }
+
+    /// Re-archive the contents of a .7z.tlock file with other compression settings
+    ///
+    /// The contents are extracted to a temp directory and archived again under
+    /// the same password, so the metadata (unlock time, encrypted key, entry
+    /// index) stays valid as it is. The new file must list the same entries
+    /// before it atomically replaces the original.
+    pub fn recompress(path: &Path, password: &str, compression: &CompressionSettings) -> Result<()> {
+        let metadata = Self::read_metadata(path)?
+            .metadata
+            .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+        Self::rearchive(path, password, password, compression, metadata)?;
+        eprintln!("[TlockArchive::recompress] Recompressed {:?} with {:?}", path, compression);
+        Ok(())
+    }
+
+    /// Re-encrypt the contents of a .7z.tlock file under a fresh random password
+    ///
+    /// For when the archive password may have leaked, e.g. after it was shown
+    /// to open the payload with another 7z tool. 7z encryption covers the
+    /// whole stream, so the contents are re-archived as in `recompress` (at
+    /// the default level, keeping any BCJ filter). The new password is
+    /// time-locked to the same unlock time and round, and an entry index is
+    /// resealed under it.
+    ///
+    /// # Errors
+    /// - `Encryption` if the file has an age recipient key: it can't be
+    ///   re-encrypted without the recipient's public key
+    pub fn rotate_password(path: &Path, password: &str) -> Result<()> {
+        let mut metadata = Self::read_metadata(path)?
+            .metadata
+            .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+        if metadata.recipient_encrypted_key.is_some() {
+            return Err(TimeLockerError::Encryption(
+                "File has an age recipient key, which would still hold the old password".to_string(),
+            ));
+        }
+
+        let new_password = crypto::generate_password(32);
+        metadata.encrypted_key = Some(crypto::encrypt_with_tlock(
+            &new_password,
+            metadata.unlocks,
+            &metadata.beacon()?,
+        )?);
+        if let Some(sealed) = &metadata.sealed_entry_index {
+            let index = crypto::open_with_password(sealed, password)?;
+            metadata.sealed_entry_index = Some(crypto::seal_with_password(&index, &new_password)?);
+        }
+
+        let compression = CompressionSettings {
+            filter: if metadata.compression_filter.is_some() {
+                CompressionFilter::BcjX86
+            } else {
+                CompressionFilter::None
+            },
+            ..Default::default()
+        };
+        Self::rearchive(path, password, &new_password, &compression, metadata)?;
+        eprintln!("[TlockArchive::rotate_password] Rotated the archive password of {:?}", path);
+        Ok(())
+    }
+
+    /// Extract a file's contents and archive them again, replacing the file atomically
+    ///
+    /// `metadata` is written to the new file as given, apart from the
+    /// compression filter and payload length. The new payload (under
+    /// `new_password`) must list the same files as the old one.
+    fn rearchive(
+        path: &Path,
+        password: &str,
+        new_password: &str,
+        compression: &CompressionSettings,
+        mut metadata: TlockMetadata,
+    ) -> Result<()> {
+        let version = {
+            let mut reader = BufReader::new(File::open(path)?);
+            Self::read_and_validate_header(&mut reader)?.0
+        };
+        // Directory entries are left out: the GUI and CLI writers record them differently
+        let file_entries = |payload: PayloadReader, password: &str| -> Result<Vec<ArchiveEntryInfo>> {
+            let mut files: Vec<_> = list_archive_entries(payload, password)?
+                .into_iter()
+                .filter(|e| !e.is_directory)
+                .collect();
+            files.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(files)
+        };
+        let original_files = file_entries(Self::open_payload(path)?, password)?;
+
+        let work_dir = std::env::temp_dir().join(format!("tlock_rearchive_{}", uuid::Uuid::new_v4()));
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+
+        let result = (|| -> Result<()> {
+            Self::extract(path, password, &work_dir)?;
+
+            // Several sources were archived side by side, a directory by its
+            // contents, a single file by itself
+            let payload_path = if let Some(names) = &metadata.original_files {
+                let sources: Vec<PathBuf> = names.iter().map(|n| work_dir.join(n)).collect();
+                let sources: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+                let payload_path = work_dir.with_extension("7z");
+                let compression = compression.resolve_filter(&sources);
+                metadata.record_compression(&compression);
+                create_encrypted_archive_multi(&sources, &payload_path, new_password, &compression, None)?;
+                payload_path
+            } else {
+                let source = if metadata.is_directory {
+                    work_dir.clone()
+                } else {
+                    fs::read_dir(&work_dir)?
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .find(|p| p.is_file())
+                        .ok_or_else(|| TimeLockerError::Archive("Archive contains no file".to_string()))?
+                };
+                let compression = compression.resolve_filter(&[source.as_path()]);
+                metadata.record_compression(&compression);
+                create_encrypted_archive(&source, new_password, &compression)?
+            };
+
+            let written = (|| -> Result<()> {
+                let metadata_json = metadata.to_json()?;
+                let checksum = payload_checksum(&mut BufReader::new(File::open(&payload_path)?))?;
+                Self::write_tlock_file(&temp_path, version, &checksum, &metadata_json, &payload_path)
+            })();
+            let _ = fs::remove_file(&payload_path);
+            written?;
+
+            // Same entries under the new password, and a sound file around them
+            Self::verify_integrity(&temp_path)?;
+            if file_entries(Self::open_payload(&temp_path)?, new_password)? != original_files {
+                return Err(TimeLockerError::Archive(
+                    "Re-archived payload does not match the original entries".to_string(),
+                ));
+            }
+            Ok(())
+        })();
+
+        let _ = fs::remove_dir_all(&work_dir);
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
 
-        Ok(HEADER_SIZE as u64 + metadata_len as u64)
+    /// Write a new .7z.tlock at `dest` with the given metadata and the payload of `source`
+    ///
+    /// `version` must be the version of `source`, so its checksum still applies.
+    fn write_with_payload_of(source: &Path, dest: &Path, version: u8, metadata_json: &[u8]) -> Result<()> {
+        let mut reader = BufReader::new(File::open(source)?);
+        let (_version, old_metadata_len, checksum) = Self::read_and_validate_header(&mut reader)?;
+        reader.seek(SeekFrom::Current(old_metadata_len as i64))?;
+
+        let mut writer = BufWriter::new(File::create(dest)?);
+        Self::write_header(
+            &mut writer,
+            version,
+            metadata_json.len() as u32,
+            &checksum.unwrap_or_default(),
+        )?;
+        writer.write_all(metadata_json)?;
+        copy_payload(&mut reader, &mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        Ok(())
     }
 
     /// Extract the 7z payload to a temporary file
@@ -452,14 +1890,8 @@ impl TlockArchive {
 
         eprintln!("[TlockArchive::extract_payload_to_temp] Extracting payload from: {:?}", path);
 
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
-        // Read and validate header
-        let (_version, metadata_len) = Self::read_and_validate_header(&mut reader)?;
-
-        // Skip metadata section
-        reader.seek(SeekFrom::Current(metadata_len as i64))?;
+        // Validate header and skip metadata
+        let mut payload = Self::open_payload(path)?;
 
         // Create temp file for 7z payload
         let temp_dir = std::env::temp_dir();
@@ -470,335 +1902,2449 @@ impl TlockArchive {
 
         eprintln!("[TlockArchive::extract_payload_to_temp] Temp 7z: {:?}", temp_7z_path);
 
-        // Extract payload to temp file
-        {
-            let temp_file = File::create(&temp_7z_path)?;
-            let mut temp_writer = BufWriter::new(temp_file);
-            std::io::copy(&mut reader, &mut temp_writer)?;
-            temp_writer.flush()?;
+        // Extract payload to temp file, checking it against the stored checksum
+        if let Err(e) = copy_verified_payload(&mut payload, &temp_7z_path) {
+            let _ = fs::remove_file(&temp_7z_path);
+            return Err(e);
         }
 
         Ok(temp_7z_path)
     }
 }
 
-// ============================================================================
-// Scanning Functions
-// ============================================================================
+/// Buffer size for payload copies (`TIMELOCKER_COPY_BUFFER` or the default)
+pub fn copy_buffer_size() -> usize {
+    std::env::var(COPY_BUFFER_ENV)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(DEFAULT_COPY_BUFFER_SIZE)
+}
 
-/// Scan a directory for .7z.tlock files
-///
-/// # Arguments
-/// * `dir` - Directory to scan (recursively)
+/// Stream `reader` into `writer` using the configured copy buffer size
 ///
 /// # Returns
-/// Vector of TlockArchive with loaded metadata
-pub fn scan_tlock_files(dir: &Path) -> Result<Vec<TlockArchive>> {
-    use walkdir::WalkDir;
+/// Number of bytes copied
+pub fn copy_payload<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+) -> std::io::Result<u64> {
+    copy_with_buffer(reader, writer, copy_buffer_size())
+}
 
-    let mut archives = Vec::new();
+/// Size of the header for `version`, including the checksum field if it has one
+pub fn header_len(version: u8) -> u64 {
+    if version >= CHECKSUM_VERSION {
+        (HEADER_SIZE + CHECKSUM_SIZE) as u64
+    } else {
+        HEADER_SIZE as u64
+    }
+}
 
-    if !dir.exists() || !dir.is_dir() {
-        eprintln!("[scan_tlock_files] Directory does not exist or is not a dir: {:?}", dir);
-        return Ok(archives);
+/// SHA-256 of everything `reader` yields
+pub fn payload_checksum<R: Read + ?Sized>(reader: &mut R) -> std::io::Result<PayloadChecksum> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    copy_payload(reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Compare a computed payload checksum with the stored one
+fn check_payload_checksum(expected: &PayloadChecksum, actual: &PayloadChecksum) -> Result<()> {
+    if expected != actual {
+        return Err(TimeLockerError::ChecksumMismatch {
+            expected: hex::encode(expected),
+            actual: hex::encode(actual),
+        });
     }
+    Ok(())
+}
 
-    eprintln!("[scan_tlock_files] Scanning directory: {:?}", dir);
+/// Copy the payload to `dest`, failing if it doesn't match its stored checksum
+///
+/// Version 1 files have no checksum and are copied unchecked.
+fn copy_verified_payload(payload: &mut PayloadReader, dest: &Path) -> Result<()> {
+    use sha2::{Digest, Sha256};
 
-    for entry in WalkDir::new(dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
+    let expected = payload.checksum().copied();
+    let mut hasher = Sha256::new();
     {
-        let path = entry.path();
+        let mut writer = BufWriter::new(File::create(dest)?);
+        let mut tee = TeeWriter {
+            inner: &mut writer,
+            hasher: &mut hasher,
+        };
+        copy_payload(payload, &mut tee)?;
+        writer.flush()?;
+    }
 
-        // Check for .7z.tlock extension
-        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-            if name.ends_with(".7z.tlock") {
-                eprintln!("[scan_tlock_files] Found .7z.tlock file: {:?}", path);
+    match expected {
+        Some(expected) => check_payload_checksum(&expected, &hasher.finalize().into()),
+        None => Ok(()),
+    }
+}
 
-                match TlockArchive::read_metadata(path) {
-                    Ok(archive) => {
-                        archives.push(archive);
-                    }
-                    Err(e) => {
-                        eprintln!("[scan_tlock_files] Failed to read {:?}: {:?}", path, e);
-                    }
-                }
-            }
-        }
+/// Writer that hashes everything passed through to `inner`
+struct TeeWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hasher: &'a mut sha2::Sha256,
+}
+
+impl<W: Write> Write for TeeWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
     }
 
-    eprintln!("[scan_tlock_files] Found {} .7z.tlock files", archives.len());
-    Ok(archives)
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
 }
 
-// ============================================================================
-// Tests
-// ============================================================================
+/// Stream `reader` into `writer` through a buffer of `buffer_size` bytes
+pub fn copy_with_buffer<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    buffer_size: usize,
+) -> std::io::Result<u64> {
+    let mut buf = vec![0u8; buffer_size.max(1)];
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Duration;
-    use std::fs;
+/// Read/seek view over the 7z payload of a .7z.tlock file
+///
+/// Positions are relative to the start of the payload.
+#[derive(Debug)]
+pub struct PayloadReader {
+    inner: BufReader<File>,
+    offset: u64,
+    len: u64,
+    /// Current position, relative to the payload start
+    pos: u64,
+    /// Stored payload checksum (version 2+)
+    checksum: Option<PayloadChecksum>,
+}
 
-    fn setup_test_dir(name: &str) -> PathBuf {
-        let dir = std::env::temp_dir().join(format!("tlock_test_{}", name));
-        let _ = fs::remove_dir_all(&dir);
-        fs::create_dir_all(&dir).unwrap();
-        dir
+impl PayloadReader {
+    /// Size of the payload in bytes
+    pub fn len(&self) -> u64 {
+        self.len
     }
 
-    fn cleanup_test_dir(dir: &Path) {
-        let _ = fs::remove_dir_all(dir);
+    /// SHA-256 of the payload recorded in the header, if the version has one
+    pub fn checksum(&self) -> Option<&PayloadChecksum> {
+        self.checksum.as_ref()
     }
 
-    #[test]
-    fn test_metadata_serialization() {
-        let metadata = TlockMetadata::new(
-            "test.txt".to_string(),
-            "30d".to_string(),
+    /// Whether the payload is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Read for PayloadReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Stop at the end of the payload
+        let remaining = self.len.saturating_sub(self.pos);
+        let max = (buf.len() as u64).min(remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let n = self.inner.read(&mut buf[..max])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for PayloadReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.len.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+
+        match target.and_then(|t| self.offset.checked_add(t).map(|abs| (t, abs))) {
+            Some((t, absolute)) => {
+                self.inner.seek(SeekFrom::Start(absolute))?;
+                self.pos = t;
+                Ok(t)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Seek before start of payload",
+            )),
+        }
+    }
+}
+
+/// Where `TlockArchive::recover` writes: "name.7z.tlock" -> "name.recovered.7z.tlock"
+fn recovered_path(corrupt_path: &Path) -> PathBuf {
+    let name = corrupt_path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = name
+        .strip_suffix(".7z.tlock")
+        .or_else(|| name.strip_suffix(".tlock"))
+        .unwrap_or(&name);
+    corrupt_path.with_file_name(format!("{}.recovered.7z.tlock", stem))
+}
+
+/// Find the first intact 7z archive inside a damaged file
+///
+/// Every occurrence of the 7z signature is a candidate; the first one whose
+/// start and end headers verify wins, so stray signature bytes in the old
+/// metadata or in encrypted data are skipped.
+fn find_7z_payload(path: &Path) -> Result<PayloadReader> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut buf = vec![0u8; copy_buffer_size().max(SEVENZ_SIGNATURE.len())];
+    let mut carried = 0;
+    let mut base = 0u64; // File offset of buf[0]
+    loop {
+        let n = file.read(&mut buf[carried..])?;
+        if n == 0 {
+            break;
+        }
+        let filled = carried + n;
+
+        for (i, window) in buf[..filled].windows(SEVENZ_SIGNATURE.len()).enumerate() {
+            if window != SEVENZ_SIGNATURE {
+                continue;
+            }
+            let offset = base + i as u64;
+            let mut candidate = PayloadReader {
+                inner: BufReader::new(File::open(path)?),
+                offset,
+                len: file_len - offset,
+                pos: 0,
+                checksum: None,
+            };
+            candidate.seek(SeekFrom::Start(0))?;
+            if let Ok(archive_len) = verify_7z_structure(&mut candidate) {
+                candidate.len = archive_len;
+                return Ok(candidate);
+            }
+        }
+
+        // Keep the tail, in case a signature straddles two reads
+        let keep = (SEVENZ_SIGNATURE.len() - 1).min(filled);
+        buf.copy_within(filled - keep..filled, 0);
+        base += (filled - keep) as u64;
+        carried = keep;
+    }
+
+    Err(TimeLockerError::Parse(
+        "No intact 7z payload found in the file".to_string(),
+    ))
+}
+
+/// Where a 7z archive's end header is, from its unencrypted start header
+struct SevenZStartHeader {
+    next_header_offset: u64,
+    next_header_size: u64,
+    next_header_crc: u32,
+}
+
+impl SevenZStartHeader {
+    /// Parse the start header (signature already checked), verifying its CRC
+    fn parse(start: &[u8; SEVENZ_START_HEADER_SIZE as usize]) -> Result<Self> {
+        let le_u32 = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        let le_u64 = |b: &[u8]| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&b[..8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        if crc32fast::hash(&start[12..32]) != le_u32(&start[8..12]) {
+            return Err(TimeLockerError::Parse(
+                "7z start header checksum mismatch".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            next_header_offset: le_u64(&start[12..20]),
+            next_header_size: le_u64(&start[20..28]),
+            next_header_crc: le_u32(&start[28..32]),
+        })
+    }
+
+    /// Length of the whole archive, which ends with the end header
+    fn archive_len(&self) -> Result<u64> {
+        SEVENZ_START_HEADER_SIZE
+            .checked_add(self.next_header_offset)
+            .and_then(|n| n.checked_add(self.next_header_size))
+            .ok_or_else(|| TimeLockerError::Parse("Invalid 7z header location".to_string()))
+    }
+}
+
+/// Check the unencrypted framing of a 7z payload (signature, lengths, header CRCs)
+///
+/// # Returns
+/// Length of the 7z archive, which may be shorter than the payload
+fn verify_7z_structure(payload: &mut PayloadReader) -> Result<u64> {
+    let mut start = [0u8; SEVENZ_START_HEADER_SIZE as usize];
+    payload.read_exact(&mut start).map_err(|_| {
+        TimeLockerError::Parse("Payload is truncated: missing 7z signature header".to_string())
+    })?;
+
+    if start[..6] != SEVENZ_SIGNATURE {
+        return Err(TimeLockerError::Parse(
+            "Payload is not a 7z archive (bad signature)".to_string(),
+        ));
+    }
+
+    let header = SevenZStartHeader::parse(&start)?;
+    let (next_header_offset, next_header_size, next_header_crc) =
+        (header.next_header_offset, header.next_header_size, header.next_header_crc);
+
+    let required = header.archive_len()?;
+    if required > payload.len() {
+        return Err(TimeLockerError::Parse(format!(
+            "Payload is truncated: {} of {} bytes present",
+            payload.len(),
+            required
+        )));
+    }
+
+    payload.seek(SeekFrom::Start(SEVENZ_START_HEADER_SIZE + next_header_offset))?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut remaining = payload.take(next_header_size);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = remaining.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    if hasher.finalize() != next_header_crc {
+        return Err(TimeLockerError::Parse(
+            "7z end header checksum mismatch".to_string(),
+        ));
+    }
+
+    Ok(required)
+}
+
+/// Read the entry list of a 7z archive and seal it with the archive password
+fn build_entry_index<R: Read + Seek>(reader: R, password: &str) -> Result<String> {
+    let entries = list_archive_entries(reader, password)?;
+    let json = serde_json::to_vec(&entries)
+        .map_err(|e| TimeLockerError::Parse(format!("Failed to serialize entry index: {}", e)))?;
+    crypto::seal_with_password(&json, password)
+}
+
+// ============================================================================
+// Scanning Functions
+// ============================================================================
+
+/// Time until a .7z.tlock file can actually be decrypted
+///
+/// # Returns
+/// The drand round the key is locked to (if known) and the time until its
+/// beacon is published; negative once the file is decryptable
+pub fn time_until_cryptographic_unlock(path: &Path) -> Result<(Option<u64>, chrono::Duration)> {
+    let metadata = TlockArchive::read_metadata(path)?
+        .metadata
+        .ok_or_else(|| TimeLockerError::Parse("Failed to read metadata".to_string()))?;
+    Ok((metadata.unlock_round(), metadata.time_until_cryptographic_unlock()))
+}
+
+/// Scan a directory for .7z.tlock files
+///
+/// Quarantine folders and `unlocked_*` extraction directories below `dir`
+/// are never entered.
+///
+/// # Arguments
+/// * `dir` - Directory to scan
+/// * `recursive` - Also scan subdirectories, rather than just `dir` itself
+///
+/// # Returns
+/// Vector of TlockArchive with loaded metadata
+pub fn scan_tlock_files(dir: &Path, recursive: bool) -> Result<Vec<TlockArchive>> {
+    use walkdir::WalkDir;
+
+    let mut archives = Vec::new();
+
+    if !dir.exists() || !dir.is_dir() {
+        eprintln!("[scan_tlock_files] Directory does not exist or is not a dir: {:?}", dir);
+        return Ok(archives);
+    }
+
+    eprintln!("[scan_tlock_files] Scanning directory: {:?}", dir);
+
+    let max_depth = if recursive { usize::MAX } else { 1 };
+    for entry in WalkDir::new(dir)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| !is_quarantine_dir(e) && !is_extraction_dir(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+
+        // Check for .7z.tlock extension
+        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+            if name.ends_with(".7z.tlock") {
+                eprintln!("[scan_tlock_files] Found .7z.tlock file: {:?}", path);
+
+                match TlockArchive::read_metadata(path) {
+                    Ok(archive) => {
+                        archives.push(archive);
+                    }
+                    Err(e) => {
+                        eprintln!("[scan_tlock_files] Failed to read {:?}: {:?}", path, e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Stable order across scans (WalkDir order depends on the filesystem)
+    archives.sort_by(|a, b| {
+        let created = |archive: &TlockArchive| archive.metadata.as_ref().map(|m| m.created);
+        created(a)
+            .cmp(&created(b))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    eprintln!("[scan_tlock_files] Found {} .7z.tlock files", archives.len());
+    Ok(archives)
+}
+
+/// Paths of the .7z.tlock files in a directory, sorted
+///
+/// Only `dir` itself is searched unless `recursive` is set. A vault's
+/// quarantine folder, and subfolders whose `VaultMarker` excludes them, are
+/// always skipped.
+pub fn find_tlock_files(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    use walkdir::WalkDir;
+
+    let max_depth = if recursive { usize::MAX } else { 1 };
+    let mut paths: Vec<PathBuf> = WalkDir::new(dir)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| !is_quarantine_dir(e) && !(e.depth() > 0 && is_excluded_dir(e)))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".7z.tlock"))
+        .map(|e| e.into_path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Contents of a `VAULT_MARKER` file
+///
+/// The marker is JSON; an empty file is a plain vault with no settings of its own.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultMarker {
+    /// Keep this directory and everything below it out of vault discovery and recursive scans
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub exclude: bool,
+    /// Compression for files locked into this vault when none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionSettings>,
+}
+
+impl VaultMarker {
+    /// Read the marker in `dir`, if it has one
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(dir.join(VAULT_MARKER)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if content.trim().is_empty() {
+            return Ok(Some(Self::default()));
+        }
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| TimeLockerError::Parse(format!("Invalid {} in {:?}: {}", VAULT_MARKER, dir, e)))
+    }
+
+    /// Write the marker into `dir`, creating the directory if needed
+    ///
+    /// An existing marker is only replaced when `overwrite` is set.
+    ///
+    /// # Returns
+    /// The path of the marker file
+    pub fn write(&self, dir: &Path, overwrite: bool) -> Result<PathBuf> {
+        let path = dir.join(VAULT_MARKER);
+        if path.exists() && !overwrite {
+            return Err(TimeLockerError::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{:?} is already a vault", dir),
+            )));
+        }
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| TimeLockerError::Parse(e.to_string()))?;
+        fs::write(&path, json + "\n")?;
+        eprintln!("[VaultMarker::write] Wrote {:?}", path);
+        Ok(path)
+    }
+}
+
+/// Default compression for files locked into `vault_dir`, from its marker
+///
+/// An unreadable marker is logged and treated as having no defaults.
+pub fn vault_compression(vault_dir: &Path) -> Option<CompressionSettings> {
+    match VaultMarker::read(vault_dir) {
+        Ok(marker) => marker.and_then(|marker| marker.compression),
+        Err(e) => {
+            eprintln!("[vault_compression] Ignoring marker in {:?}: {}", vault_dir, e);
+            None
+        }
+    }
+}
+
+/// Whether a walked entry is a directory whose marker opts it out of scanning
+fn is_excluded_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.file_type().is_dir()
+        && VaultMarker::read(entry.path()).is_ok_and(|marker| marker.is_some_and(|m| m.exclude))
+}
+
+/// Find the vaults in a directory tree
+///
+/// A vault is a directory with a `VAULT_MARKER`, or one holding .7z.tlock
+/// files (or legacy .key.md files) of its own. A marked vault owns its whole
+/// subtree, so unmarked folders of locked files below it aren't reported
+/// separately, and a marker with `exclude` set hides its directory and
+/// everything below it. Directories up to `max_depth` levels below `root` are
+/// checked (0 checks only `root`). Quarantine folders, `unlocked_*` output and
+/// hidden directories are never descended into.
+///
+/// # Returns
+/// The vault directories, sorted
+pub fn discover_vaults(root: &Path, max_depth: usize) -> Result<Vec<PathBuf>> {
+    use walkdir::WalkDir;
+
+    if !root.is_dir() {
+        return Err(TimeLockerError::FileNotFound(root.display().to_string()));
+    }
+
+    let is_hidden_dir = |entry: &walkdir::DirEntry| {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && entry.file_name().to_string_lossy().starts_with('.')
+    };
+
+    // A vault's files sit one level below the vault itself
+    let mut marked = std::collections::BTreeSet::new();
+    let mut unmarked = std::collections::BTreeSet::new();
+    let entries = WalkDir::new(root)
+        .max_depth(max_depth.saturating_add(1))
+        .into_iter()
+        .filter_entry(|e| {
+            !is_quarantine_dir(e) && !is_extraction_dir(e) && !is_hidden_dir(e) && !is_excluded_dir(e)
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+    for entry in entries {
+        let Some(parent) = entry.path().parent() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy();
+        if name == VAULT_MARKER {
+            marked.insert(parent.to_path_buf());
+        } else if name.ends_with(".7z.tlock") || name.ends_with(".key.md") {
+            unmarked.insert(parent.to_path_buf());
+        }
+    }
+
+    let owned = |dir: &PathBuf| marked.iter().any(|vault| dir.starts_with(vault));
+    let vaults: std::collections::BTreeSet<PathBuf> = unmarked
+        .iter()
+        .filter(|dir| !owned(dir))
+        .cloned()
+        .chain(marked.iter().cloned())
+        .collect();
+
+    eprintln!("[discover_vaults] Found {} vaults under {:?}", vaults.len(), root);
+    Ok(vaults.into_iter().collect())
+}
+
+/// Whether a walked entry is a vault's quarantine folder
+fn is_quarantine_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.file_type().is_dir() && entry.file_name() == QUARANTINE_DIR
+}
+
+/// Whether a walked entry is an `unlocked_*` directory (or its staging directory) below the scan root
+fn is_extraction_dir(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    // Unlocks in progress are staged in ".unlocked_<name>.partial"
+    let name = name.strip_prefix('.').unwrap_or(&name);
+    entry.depth() > 0 && entry.file_type().is_dir() && name.starts_with(UNLOCKED_DIR_PREFIX)
+}
+
+/// A .7z.tlock file that failed integrity verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedFile {
+    /// Where the file was found
+    pub path: PathBuf,
+    /// Where it was (or, in a dry run, would be) moved to
+    pub quarantined_path: PathBuf,
+    /// Why verification failed
+    pub reason: String,
+}
+
+/// Result of a quarantine pass over a vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineReport {
+    /// Number of .7z.tlock files checked
+    pub checked: usize,
+    /// Files that failed verification
+    pub quarantined: Vec<QuarantinedFile>,
+    /// Files that couldn't be checked (unreadable, or from a newer version), left in place
+    #[serde(default)]
+    pub unchecked: Vec<UncheckedFile>,
+    /// Whether files were only reported, not moved
+    pub dry_run: bool,
+}
+
+/// A .7z.tlock file `quarantine_corrupt` couldn't check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedFile {
+    pub path: PathBuf,
+    /// Why it couldn't be checked
+    pub reason: String,
+}
+
+/// Whether a `verify_integrity` error means the file itself is damaged
+///
+/// Transient I/O errors (permissions, a file locked by another process) and
+/// files written by a newer version say nothing about the file's health. A
+/// read that ends early does: the file is truncated.
+fn is_corruption(error: &TimeLockerError) -> bool {
+    match error {
+        TimeLockerError::Io(e) => e.kind() == std::io::ErrorKind::UnexpectedEof,
+        TimeLockerError::Parse(_) | TimeLockerError::Archive(_) | TimeLockerError::ChecksumMismatch { .. } => true,
+        _ => false,
+    }
+}
+
+/// Verify every .7z.tlock file in a vault and move corrupt ones into `QUARANTINE_DIR`
+///
+/// Files are only ever relocated, never deleted. Each move is appended to
+/// `QUARANTINE_DIR/QUARANTINE_LOG` with the reason. With `dry_run` nothing is
+/// moved or logged; the report shows what would happen.
+pub fn quarantine_corrupt(dir: &Path, dry_run: bool) -> Result<QuarantineReport> {
+    if !dir.is_dir() {
+        return Err(TimeLockerError::FileNotFound(dir.display().to_string()));
+    }
+
+    let quarantine_dir = dir.join(QUARANTINE_DIR);
+    let mut report = QuarantineReport {
+        checked: 0,
+        quarantined: Vec::new(),
+        unchecked: Vec::new(),
+        dry_run,
+    };
+
+    for path in find_tlock_files(dir, true) {
+        report.checked += 1;
+
+        let reason = match TlockArchive::verify_integrity(&path) {
+            Ok(()) => continue,
+            Err(e) if is_corruption(&e) => e.to_string(),
+            Err(e) => {
+                eprintln!("[quarantine_corrupt] {:?} could not be checked: {}", path, e);
+                report.unchecked.push(UncheckedFile {
+                    path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        // Keep the vault-relative layout so files from different folders can't collide
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let mut quarantined_path = quarantine_dir.join(relative);
+        if quarantined_path.exists() {
+            quarantined_path = crate::archive::next_available_path(&quarantined_path);
+        }
+
+        eprintln!("[quarantine_corrupt] {:?} failed verification: {}", path, reason);
+
+        if !dry_run {
+            if let Some(parent) = quarantined_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&path, &quarantined_path)?;
+
+            let mut log = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(quarantine_dir.join(QUARANTINE_LOG))?;
+            writeln!(
+                log,
+                "{}\t{}\t{}",
+                clock::now().to_rfc3339(),
+                relative.display(),
+                reason
+            )?;
+        }
+
+        report.quarantined.push(QuarantinedFile {
+            path,
+            quarantined_path,
+            reason,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Read-only consistency findings for one .7z.tlock file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub path: PathBuf,
+    /// Header format version, None when the header couldn't be read
+    pub version: Option<u8>,
+    /// Nominal unlock time from the metadata
+    pub unlocks: Option<DateTime<Utc>>,
+    /// Round the key is time-locked to
+    pub drand_round: Option<u64>,
+    /// When that round is published, by the file's beacon
+    pub round_time: Option<DateTime<Utc>>,
+    pub payload_bytes: Option<u64>,
+    /// Whether the payload matches its stored checksum; None for version 1 files
+    pub checksum_ok: Option<bool>,
+    /// Every inconsistency found; empty when the file checks out
+    pub issues: Vec<String>,
+}
+
+/// Result of auditing every .7z.tlock file in a vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub vault: PathBuf,
+    pub audited_at: DateTime<Utc>,
+    pub entries: Vec<AuditEntry>,
+    /// Number of files with at least one issue
+    pub flagged: usize,
+}
+
+/// Cross-check every .7z.tlock file in a vault, without the password
+///
+/// Read-only: unlike `quarantine_corrupt`, nothing is moved and every
+/// inconsistency is reported rather than just the first.
+pub fn audit_vault(dir: &Path) -> Result<AuditReport> {
+    if !dir.is_dir() {
+        return Err(TimeLockerError::FileNotFound(dir.display().to_string()));
+    }
+
+    let entries: Vec<AuditEntry> = find_tlock_files(dir, true)
+        .into_iter()
+        .map(|path| audit_file(&path))
+        .collect();
+    let flagged = entries.iter().filter(|e| !e.issues.is_empty()).count();
+
+    eprintln!("[audit_vault] Audited {} files, {} flagged", entries.len(), flagged);
+    Ok(AuditReport {
+        vault: dir.to_path_buf(),
+        audited_at: clock::now(),
+        entries,
+        flagged,
+    })
+}
+
+/// Check a file's header version, key round against its unlock time, payload and checksum
+pub fn audit_file(path: &Path) -> AuditEntry {
+    let mut entry = AuditEntry {
+        path: path.to_path_buf(),
+        version: None,
+        unlocks: None,
+        drand_round: None,
+        round_time: None,
+        payload_bytes: None,
+        checksum_ok: None,
+        issues: Vec::new(),
+    };
+
+    let header = File::open(path)
+        .map_err(TimeLockerError::from)
+        .and_then(|f| TlockArchive::read_and_validate_header(&mut BufReader::new(f)));
+    match header {
+        Ok((version, _, _)) => entry.version = Some(version),
+        Err(e) => {
+            entry.issues.push(format!("Header: {}", e));
+            return entry;
+        }
+    }
+
+    let metadata = match TlockArchive::read_metadata(path) {
+        Ok(archive) => archive.metadata,
+        Err(e) => {
+            entry.issues.push(format!("Metadata: {}", e));
+            return entry;
+        }
+    };
+    if let Some(metadata) = metadata {
+        audit_round(&metadata, &mut entry);
+    }
+
+    match TlockArchive::open_payload(path) {
+        Ok(mut payload) => {
+            entry.payload_bytes = Some(payload.len());
+            if payload.is_empty() {
+                entry.issues.push("Payload is missing".to_string());
+                return entry;
+            }
+            if let Err(e) = verify_7z_structure(&mut payload) {
+                entry.issues.push(format!("Payload: {}", e));
+            }
+            if let Some(expected) = payload.checksum().copied() {
+                let actual = payload
+                    .seek(SeekFrom::Start(0))
+                    .and_then(|_| payload_checksum(&mut payload));
+                let ok = matches!(actual, Ok(actual) if actual == expected);
+                entry.checksum_ok = Some(ok);
+                if !ok {
+                    entry.issues.push("Payload does not match its stored checksum".to_string());
+                }
+            }
+        }
+        Err(e) => entry.issues.push(format!("Payload: {}", e)),
+    }
+
+    entry
+}
+
+/// Compare the nominal unlock time with the round the key is actually locked to
+fn audit_round(metadata: &TlockMetadata, entry: &mut AuditEntry) {
+    entry.unlocks = Some(metadata.unlocks);
+
+    let Some(encrypted_key) = metadata.encrypted_key.as_deref() else {
+        entry.issues.push("Metadata has no encrypted key".to_string());
+        return;
+    };
+    let key_round = crypto::ciphertext_round(encrypted_key).ok();
+    if let (Some(key_round), Some(stored)) = (key_round, metadata.drand_round) {
+        if key_round != stored {
+            entry.issues.push(format!(
+                "Metadata records round {} but the key is locked to round {}",
+                stored, key_round
+            ));
+        }
+    }
+
+    let Some(round) = metadata.unlock_round() else {
+        return;
+    };
+    entry.drand_round = Some(round);
+
+    let beacon = match metadata.beacon() {
+        Ok(beacon) => beacon,
+        Err(e) => {
+            entry.issues.push(format!("Beacon: {}", e));
+            return;
+        }
+    };
+    let round_time = crypto::round_to_datetime(round, &beacon);
+    entry.round_time = Some(round_time);
+
+    // The key is locked to the first round after `unlocks`, at most one period later
+    let period = chrono::Duration::seconds(beacon.period as i64);
+    if round_time < metadata.unlocks {
+        entry.issues.push(format!(
+            "Key can be decrypted at {}, before the recorded unlock time {}",
+            round_time.to_rfc3339(),
+            metadata.unlocks.to_rfc3339()
+        ));
+    } else if round_time > metadata.unlocks + period {
+        entry.issues.push(format!(
+            "Key can't be decrypted until {}, after the recorded unlock time {}",
+            round_time.to_rfc3339(),
+            metadata.unlocks.to_rfc3339()
+        ));
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::CompressionMethod;
+    use chrono::Duration;
+    use std::fs;
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tlock_test_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn cleanup_test_dir(dir: &Path) {
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_metadata_serialization() {
+        let metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "30d".to_string(),
             Utc::now() + Duration::days(30),
             Some(12345678),
             Some("encrypted_key_data".to_string()),
         );
 
-        let json = serde_json::to_string(&metadata).unwrap();
-        let parsed: TlockMetadata = serde_json::from_str(&json).unwrap();
+        let json = serde_json::to_string(&metadata).unwrap();
+        let parsed: TlockMetadata = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.original_file, "test.txt");
+        assert_eq!(parsed.duration, "30d");
+        assert_eq!(parsed.drand_round, Some(12345678));
+        assert!(parsed.locked);
+    }
+
+    #[test]
+    fn test_note_is_optional_in_metadata() {
+        let mut metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "30d".to_string(),
+            Utc::now() + Duration::days(30),
+            None,
+            None,
+        );
+
+        // Files without a note don't mention it, and old files still parse
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(!json.contains("note"));
+        let parsed: TlockMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.note, None);
+
+        metadata.note = normalize_note(Some("  Tax records for the audit  ".to_string()));
+        let json = serde_json::to_string(&metadata).unwrap();
+        let parsed: TlockMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.note.as_deref(), Some("Tax records for the audit"));
+
+        assert_eq!(normalize_note(Some("   ".to_string())), None);
+    }
+
+    #[test]
+    fn test_output_name_template() {
+        use chrono::TimeZone;
+
+        let now = chrono::Local.with_ymd_and_hms(2026, 7, 1, 9, 30, 0).unwrap();
+        let render = |template| render_output_name(template, "photos", now);
+        assert_eq!(render(DEFAULT_OUTPUT_NAME_TEMPLATE).unwrap(), "unlocked_photos");
+        assert_eq!(render("{name} unlocked {date}").unwrap(), "photos unlocked 2026-07-01");
+        assert_eq!(render("{timestamp}_{name}").unwrap(), "20260701_093000_photos");
+
+        // Every file needs its own directory, marked as an unlock by some fixed text
+        for shared in ["restored", "{date}", "unlocked_{date}", "{name}", "{name} {date}"] {
+            assert!(render(shared).is_err(), "{:?} should be rejected", shared);
+        }
+
+        for bad in ["{nmae}", "unlocked_{name", "", "../{name}", "a/{name}", ".."] {
+            assert!(render(bad).is_err(), "{:?} should be rejected", bad);
+        }
+
+        // Earlier unlocks are recognized whenever they happened
+        assert!(output_name_matches("{name} unlocked {date}", "photos", "photos unlocked 2025-12-31"));
+        assert!(output_name_matches("{timestamp}_{name}", "photos", "20260701_093000_photos"));
+        assert!(output_name_matches(DEFAULT_OUTPUT_NAME_TEMPLATE, "photos", "unlocked_photos"));
+        assert!(!output_name_matches("{name} unlocked {date}", "photos", "photos unlocked 2025-12"));
+        assert!(!output_name_matches("{name} unlocked {date}", "photos", "videos unlocked 2025-12-31"));
+        assert!(!output_name_matches(DEFAULT_OUTPUT_NAME_TEMPLATE, "photos", "unlocked_photos2"));
+
+        assert!(output_name_is_fixed(DEFAULT_OUTPUT_NAME_TEMPLATE));
+        assert!(!output_name_is_fixed("{name} unlocked {date}"));
+    }
+
+    #[test]
+    fn test_extra_fields() {
+        let fields = ["project=atlas".to_string(), "case=12=34".to_string()];
+        let extra = parse_extra_fields(&fields).unwrap();
+        assert_eq!(extra["project"], "atlas");
+        assert_eq!(extra["case"], "12=34");
+
+        for bad in [vec!["no-equals"], vec!["=value"], vec!["case=1", "case=2"]] {
+            let bad: Vec<String> = bad.into_iter().map(str::to_string).collect();
+            assert!(matches!(parse_extra_fields(&bad), Err(TimeLockerError::Parse(_))));
+        }
+
+        let mut metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "30d".to_string(),
+            Utc::now() + Duration::days(30),
+            None,
+            None,
+        );
+        assert!(!String::from_utf8(metadata.to_json().unwrap()).unwrap().contains("extra"));
+
+        metadata.extra = extra;
+        let parsed: TlockMetadata = serde_json::from_slice(&metadata.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.extra, metadata.extra);
+
+        // Custom fields can't push the header past the size limit
+        metadata
+            .extra
+            .insert("blob".to_string(), "x".repeat(MAX_METADATA_SIZE as usize));
+        assert!(matches!(metadata.to_json(), Err(TimeLockerError::Parse(_))));
+    }
+
+    #[test]
+    fn test_expiry_is_advisory_metadata() {
+        let mut metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() - Duration::days(2),
+            None,
+            None,
+        );
+        assert!(!metadata.is_expired());
+        assert!(!serde_json::to_string(&metadata).unwrap().contains("expires"));
+
+        metadata.expires = Some(Utc::now() + Duration::days(1));
+        assert!(!metadata.is_expired());
+
+        metadata.expires = Some(Utc::now() - Duration::days(1));
+        assert!(metadata.is_expired());
+        // Expiry never affects whether the time lock itself is open
+        assert!(metadata.is_unlockable());
+
+        let parsed: TlockMetadata = serde_json::from_str(&serde_json::to_string(&metadata).unwrap()).unwrap();
+        assert_eq!(parsed.expires, metadata.expires);
+    }
+
+    #[test]
+    fn test_metadata_is_unlockable() {
+        // Future unlock time
+        let future_metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "30d".to_string(),
+            Utc::now() + Duration::days(30),
+            None,
+            None,
+        );
+        assert!(!future_metadata.is_unlockable());
+
+        // Past unlock time
+        let past_metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "0d".to_string(),
+            Utc::now() - Duration::days(1),
+            None,
+            None,
+        );
+        assert!(past_metadata.is_unlockable());
+    }
+
+    #[test]
+    fn test_metadata_unlock_boundary_with_fake_clock() {
+        let unlocks = Utc::now() + Duration::days(30);
+        let metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "30d".to_string(),
+            unlocks,
+            None,
+            None,
+        );
+
+        clock::set_fake_now(Some(unlocks - Duration::seconds(1)));
+        assert!(!metadata.is_unlockable());
+        assert_eq!(metadata.time_until_unlock(), Duration::seconds(1));
+
+        clock::set_fake_now(Some(unlocks));
+        assert!(metadata.is_unlockable());
+        assert_eq!(metadata.time_until_unlock(), Duration::zero());
+
+        clock::set_fake_now(None);
+    }
+
+    #[test]
+    fn test_cryptographic_unlock_follows_round() {
+        use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+        let round = 20_000_000u64;
+        let round_time = crypto::round_to_datetime(round, &crypto::BeaconConfig::quicknet());
+
+        // Nominal unlock is a few seconds before the round is published
+        let mut key = round.to_be_bytes().to_vec();
+        key.extend_from_slice(b"ciphertext");
+        let metadata = TlockMetadata::new(
+            "notes.txt".to_string(),
+            "1d".to_string(),
+            round_time - Duration::seconds(4),
+            None,
+            Some(BASE64.encode(&key)),
+        );
+        assert_eq!(metadata.unlock_round(), Some(round));
+        assert_eq!(metadata.decryptable_at(), Some(round_time));
+
+        clock::set_fake_now(Some(round_time - Duration::seconds(2)));
+        assert!(metadata.is_unlockable());
+        assert_eq!(metadata.time_until_cryptographic_unlock(), Duration::seconds(2));
+
+        // Without a key, only the nominal time is known
+        let nominal = TlockMetadata::new(
+            "notes.txt".to_string(),
+            "1d".to_string(),
+            round_time,
+            None,
+            None,
+        );
+        assert_eq!(nominal.unlock_round(), None);
+        assert_eq!(nominal.time_until_cryptographic_unlock(), Duration::seconds(2));
+
+        clock::set_fake_now(None);
+    }
+
+    #[test]
+    fn test_restore_dir_stays_under_base() {
+        let mut metadata = TlockMetadata::new(
+            "notes.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let base = Path::new("out");
+        assert_eq!(metadata.restore_dir(base), PathBuf::from("out"));
+
+        metadata.original_path = Some("docs/work".to_string());
+        assert_eq!(metadata.restore_dir(base), PathBuf::from("out").join("docs").join("work"));
+
+        // Absolute or escaping paths from hand-edited metadata stay under base too
+        metadata.original_path = Some("/home/user/../docs/work".to_string());
+        assert_eq!(
+            metadata.restore_dir(base),
+            PathBuf::from("out").join("home").join("user").join("docs").join("work")
+        );
+
+        // Old metadata without the field still parses
+        let json = r#"{"locked":true,"created":"2025-01-01T00:00:00Z","unlocks":"2025-01-02T00:00:00Z","duration":"1d","original_file":"a.txt"}"#;
+        let parsed: TlockMetadata = serde_json::from_str(json).unwrap();
+        assert!(parsed.original_path.is_none());
+    }
+
+    #[test]
+    fn test_original_path_hint_is_relative() -> Result<()> {
+        let test_dir = setup_test_dir("path_hint");
+        let nested = test_dir.join("docs").join("work");
+        fs::create_dir_all(&nested)?;
+        let source = nested.join("notes.txt");
+        fs::write(&source, b"notes")?;
+
+        assert_eq!(original_path_hint(&source, &PathHint::Parent)?, Some("work".to_string()));
+        let root = PathHint::RelativeTo(test_dir.clone());
+        assert_eq!(original_path_hint(&source, &root)?, Some("docs/work".to_string()));
+        assert_eq!(original_path_hint(&test_dir.join("docs"), &root)?, None);
+
+        // A source outside the root is refused rather than recorded absolutely
+        let elsewhere = PathHint::RelativeTo(nested.clone());
+        assert!(original_path_hint(&test_dir.join("docs"), &elsewhere).is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_and_read_metadata() -> Result<()> {
+        let test_dir = setup_test_dir("create_read");
+
+        // Create a test file
+        let source_file = test_dir.join("secret.txt");
+        fs::write(&source_file, b"This is secret content!")?;
+
+        // Create metadata
+        let metadata = TlockMetadata::new(
+            "secret.txt".to_string(),
+            "7d".to_string(),
+            Utc::now() + Duration::days(7),
+            Some(99999),
+            Some("AGE_ENCRYPTED_KEY".to_string()),
+        );
+
+        // Create .7z.tlock file
+        let password = "test_password_123";
+        let tlock_path = TlockArchive::create(&source_file, metadata, password)?;
+
+        assert!(tlock_path.exists());
+        assert!(tlock_path.to_str().unwrap().ends_with(".7z.tlock"));
+
+        // Read metadata (no password needed)
+        let archive = TlockArchive::read_metadata(&tlock_path)?;
+        let loaded_meta = archive.get_metadata().unwrap();
+
+        assert_eq!(loaded_meta.original_file, "secret.txt");
+        assert_eq!(loaded_meta.duration, "7d");
+        assert_eq!(loaded_meta.drand_round, Some(99999));
+        assert_eq!(loaded_meta.encrypted_key, Some("AGE_ENCRYPTED_KEY".to_string()));
+        assert!(loaded_meta.locked);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_and_extract() -> Result<()> {
+        let test_dir = setup_test_dir("create_extract");
+
+        // Create a test file
+        let source_file = test_dir.join("document.txt");
+        let content = b"Important document content for testing extraction!";
+        fs::write(&source_file, content)?;
+
+        // Create metadata
+        let metadata = TlockMetadata::new(
+            "document.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+
+        // Create .7z.tlock file
+        let password = "extraction_test_pwd";
+        let tlock_path = TlockArchive::create(&source_file, metadata, password)?;
+
+        // Extract to new directory
+        let extract_dir = test_dir.join("extracted");
+        TlockArchive::extract(&tlock_path, password, &extract_dir)?;
+
+        // Verify extracted file
+        let extracted_file = extract_dir.join("document.txt");
+        assert!(extracted_file.exists(), "Extracted file should exist");
+
+        let extracted_content = fs::read(&extracted_file)?;
+        assert_eq!(extracted_content, content);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_extraction() -> Result<()> {
+        let test_dir = setup_test_dir("verify_extraction");
+
+        let source_file = test_dir.join("capsule.txt");
+        fs::write(&source_file, b"Time capsule content")?;
+
+        let metadata = TlockMetadata::new(
+            "capsule.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let tlock_path = TlockArchive::create(&source_file, metadata, "password")?;
+
+        let extract_dir = test_dir.join("extracted");
+        assert!(!TlockArchive::verify_extraction(&tlock_path, "password", &extract_dir)?);
+
+        TlockArchive::extract(&tlock_path, "password", &extract_dir)?;
+        assert!(TlockArchive::verify_extraction(&tlock_path, "password", &extract_dir)?);
+
+        // A truncated output no longer verifies
+        fs::write(extract_dir.join("capsule.txt"), b"Time")?;
+        assert!(!TlockArchive::verify_extraction(&tlock_path, "password", &extract_dir)?);
+
+        // Nor does an older file of the same size, e.g. one skipped by --resume
+        fs::write(extract_dir.join("capsule.txt"), b"Time capsule CONTENT")?;
+        assert!(!TlockArchive::verify_extraction(&tlock_path, "password", &extract_dir)?);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_password_fails() -> Result<()> {
+        let test_dir = setup_test_dir("wrong_pwd");
+
+        // Create a test file
+        let source_file = test_dir.join("secret.txt");
+        fs::write(&source_file, b"Secret!")?;
+
+        // Create .7z.tlock file
+        let metadata = TlockMetadata::new(
+            "secret.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let tlock_path = TlockArchive::create(&source_file, metadata, "correct_password")?;
+
+        // Try to extract with wrong password
+        let extract_dir = test_dir.join("extracted");
+        let result = TlockArchive::extract(&tlock_path, "wrong_password", &extract_dir);
+
+        assert!(result.is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_tlock_file() -> Result<()> {
+        let test_dir = setup_test_dir("validate");
+
+        // Create a valid .7z.tlock file
+        let source_file = test_dir.join("test.txt");
+        fs::write(&source_file, b"Test content")?;
+
+        let metadata = TlockMetadata::new(
+            "test.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let tlock_path = TlockArchive::create(&source_file, metadata, "password")?;
+
+        // Valid file should validate
+        assert!(TlockArchive::validate(&tlock_path)?);
+
+        // Non-existent file should return false
+        let fake_path = test_dir.join("nonexistent.7z.tlock");
+        assert!(!TlockArchive::validate(&fake_path)?);
+
+        // Create a fake file with wrong magic bytes
+        let bad_file = test_dir.join("bad.7z.tlock");
+        fs::write(&bad_file, b"NOT_A_TLOCK_FILE_AT_ALL")?;
+        assert!(!TlockArchive::validate(&bad_file)?);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_file_errors() -> Result<()> {
+        let test_dir = setup_test_dir("invalid");
+
+        // Test file too small
+        let small_file = test_dir.join("small.7z.tlock");
+        fs::write(&small_file, b"tiny")?;
+        assert!(TlockArchive::read_metadata(&small_file).is_err());
+
+        // Test wrong magic bytes
+        let wrong_magic = test_dir.join("wrong_magic.7z.tlock");
+        let mut bad_data = vec![0u8; 100];
+        bad_data[0..7].copy_from_slice(b"BADMAGC");
+        fs::write(&wrong_magic, &bad_data)?;
+        let err = TlockArchive::read_metadata(&wrong_magic).unwrap_err();
+        assert!(err.to_string().contains("bad magic bytes"));
+
+        // Test unsupported version
+        let bad_version = test_dir.join("bad_version.7z.tlock");
+        let mut bad_ver_data = vec![0u8; 100];
+        bad_ver_data[0..7].copy_from_slice(TLOCK_MAGIC);
+        bad_ver_data[7] = 99; // Unsupported version
+        fs::write(&bad_version, &bad_ver_data)?;
+        let err = TlockArchive::read_metadata(&bad_version).unwrap_err();
+        assert!(err.to_string().contains("Unsupported"));
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_tlock_files() -> Result<()> {
+        let test_dir = setup_test_dir("scan");
+
+        // Create test files
+        for i in 0..3 {
+            let source = test_dir.join(format!("file{}.txt", i));
+            fs::write(&source, format!("Content {}", i))?;
+
+            let metadata = TlockMetadata::new(
+                format!("file{}.txt", i),
+                "1d".to_string(),
+                Utc::now() + Duration::days(1),
+                None,
+                None,
+            );
+            TlockArchive::create(&source, metadata, "password")?;
+        }
+
+        // Create a subdirectory with another file
+        let sub_dir = test_dir.join("subdir");
+        fs::create_dir_all(&sub_dir)?;
+        let sub_source = sub_dir.join("nested.txt");
+        fs::write(&sub_source, b"Nested content")?;
+        let nested_metadata = TlockMetadata::new(
+            "nested.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        TlockArchive::create(&sub_source, nested_metadata, "password")?;
+
+        // Files inside extracted output are never picked up
+        let unlocked_dir = test_dir.join("unlocked_file0.txt");
+        fs::create_dir_all(&unlocked_dir)?;
+        fs::copy(test_dir.join("file0.7z.tlock"), unlocked_dir.join("copy.7z.tlock"))?;
+        let staging_dir = test_dir.join(".unlocked_file1.txt.partial");
+        fs::create_dir_all(&staging_dir)?;
+        fs::copy(test_dir.join("file0.7z.tlock"), staging_dir.join("copy.7z.tlock"))?;
+
+        // Scan directory
+        let archives = scan_tlock_files(&test_dir, true)?;
+
+        assert_eq!(archives.len(), 4, "Should find 4 .7z.tlock files");
+        assert_eq!(scan_tlock_files(&test_dir, false)?.len(), 3);
+        assert_eq!(scan_tlock_files(&unlocked_dir, false)?.len(), 1);
+
+        // Same order on every scan, oldest first
+        let paths: Vec<_> = archives.iter().map(|a| a.path.clone()).collect();
+        let rescanned: Vec<_> = scan_tlock_files(&test_dir, true)?
+            .into_iter()
+            .map(|a| a.path)
+            .collect();
+        assert_eq!(paths, rescanned);
+        assert!(archives
+            .windows(2)
+            .all(|w| w[0].metadata.as_ref().unwrap().created <= w[1].metadata.as_ref().unwrap().created));
+
+        // Non-recursive search only sees the top level
+        assert_eq!(find_tlock_files(&test_dir, false).len(), 3);
+        assert_eq!(find_tlock_files(&test_dir, true).len(), 5);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_vaults() -> Result<()> {
+        let test_dir = setup_test_dir("discover_vaults");
+        let tlock = |dir: &Path| -> Result<()> {
+            fs::create_dir_all(dir)?;
+            fs::write(dir.join("locked.7z.tlock"), b"TLOCK01")?;
+            Ok(())
+        };
+
+        tlock(&test_dir.join("projects/alpha/vault"))?;
+        tlock(&test_dir.join("projects/beta"))?;
+        tlock(&test_dir.join("a/b/c/d/e/too-deep"))?;
+        fs::create_dir_all(test_dir.join("empty-vault"))?;
+        fs::write(test_dir.join("empty-vault").join(VAULT_MARKER), b"")?;
+
+        // Extracted output, quarantine and hidden folders aren't vaults
+        tlock(&test_dir.join("projects/beta/unlocked_photos"))?;
+        tlock(&test_dir.join("projects/beta").join(QUARANTINE_DIR))?;
+        tlock(&test_dir.join(".cache"))?;
+
+        let vaults = discover_vaults(&test_dir, DEFAULT_DISCOVERY_DEPTH)?;
+        assert_eq!(
+            vaults,
+            vec![
+                test_dir.join("empty-vault"),
+                test_dir.join("projects/alpha/vault"),
+                test_dir.join("projects/beta"),
+            ]
+        );
+        assert_eq!(discover_vaults(&test_dir, 2)?, vec![test_dir.join("empty-vault"), test_dir.join("projects/beta")]);
+        assert!(discover_vaults(&test_dir, 0)?.is_empty());
+        assert!(discover_vaults(&test_dir.join("missing"), 1).is_err());
+
+        // A marked vault owns the folders below it; an excluding marker hides its subtree
+        VaultMarker::default().write(&test_dir.join("projects"), false)?;
+        let excluded = VaultMarker { exclude: true, ..Default::default() };
+        excluded.write(&test_dir.join("projects/alpha"), false)?;
+        assert_eq!(
+            discover_vaults(&test_dir, DEFAULT_DISCOVERY_DEPTH)?,
+            vec![test_dir.join("empty-vault"), test_dir.join("projects")]
+        );
+        let alpha = test_dir.join("projects/alpha");
+        assert!(find_tlock_files(&test_dir.join("projects"), true).iter().all(|p| !p.starts_with(&alpha)));
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vault_marker() -> Result<()> {
+        let test_dir = setup_test_dir("vault_marker");
+
+        assert_eq!(VaultMarker::read(&test_dir)?, None);
+        assert_eq!(vault_compression(&test_dir), None);
+
+        // An empty marker is a vault without settings
+        fs::write(test_dir.join(VAULT_MARKER), b"")?;
+        assert_eq!(VaultMarker::read(&test_dir)?, Some(VaultMarker::default()));
+
+        let marker = VaultMarker {
+            compression: Some(CompressionSettings {
+                level: 9,
+                method: CompressionMethod::Lzma2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(marker.write(&test_dir, false).is_err());
+        marker.write(&test_dir, true)?;
+        assert_eq!(VaultMarker::read(&test_dir)?, Some(marker.clone()));
+        assert_eq!(vault_compression(&test_dir), marker.compression);
+
+        // A broken marker still marks a vault, just without defaults
+        fs::write(test_dir.join(VAULT_MARKER), b"{not json")?;
+        assert!(VaultMarker::read(&test_dir).is_err());
+        assert_eq!(vault_compression(&test_dir), None);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quarantine_corrupt() -> Result<()> {
+        let test_dir = setup_test_dir("quarantine");
+
+        let mut tlock_paths = Vec::new();
+        for name in ["good", "truncated", "bitflip"] {
+            let source = test_dir.join(format!("{}.txt", name));
+            fs::write(&source, format!("Content of {}", name).repeat(100))?;
+            let metadata = TlockMetadata::new(
+                format!("{}.txt", name),
+                "1d".to_string(),
+                Utc::now() + Duration::days(1),
+                None,
+                Some("encrypted_key".to_string()),
+            );
+            tlock_paths.push(TlockArchive::create(&source, metadata, "password")?);
+        }
+        TlockArchive::verify_integrity(&tlock_paths[0])?;
+
+        // Cut off the end of one file and flip a byte in the 7z end header of another
+        let data = fs::read(&tlock_paths[1])?;
+        fs::write(&tlock_paths[1], &data[..data.len() - 10])?;
+        let mut data = fs::read(&tlock_paths[2])?;
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        fs::write(&tlock_paths[2], &data)?;
+
+        // Dry run reports without moving
+        let report = quarantine_corrupt(&test_dir, true)?;
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.quarantined.len(), 2);
+        assert!(report.quarantined.iter().all(|q| q.path.exists()));
+        assert!(!test_dir.join(QUARANTINE_DIR).exists());
+
+        let report = quarantine_corrupt(&test_dir, false)?;
+        assert_eq!(report.quarantined.len(), 2);
+        for quarantined in &report.quarantined {
+            assert!(!quarantined.path.exists());
+            assert!(quarantined.quarantined_path.exists());
+        }
+        let log = fs::read_to_string(test_dir.join(QUARANTINE_DIR).join(QUARANTINE_LOG))?;
+        assert!(log.contains("truncated.7z.tlock"));
+        assert!(log.contains("checksum mismatch"));
+
+        // Quarantined files no longer show up as vault items or get re-checked
+        assert_eq!(scan_tlock_files(&test_dir, true)?.len(), 1);
+        assert_eq!(quarantine_corrupt(&test_dir, false)?.checked, 1);
+
+        // A file from a newer version is reported but stays put
+        let mut data = fs::read(&tlock_paths[0])?;
+        data[7] = TLOCK_VERSION + 1;
+        let newer = test_dir.join("newer.7z.tlock");
+        fs::write(&newer, &data)?;
+        let report = quarantine_corrupt(&test_dir, false)?;
+        assert!(report.quarantined.is_empty());
+        assert_eq!(report.unchecked.len(), 1);
+        assert!(newer.exists());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_vault_flags_inconsistencies() -> Result<()> {
+        let test_dir = setup_test_dir("audit");
+        let beacon = crypto::BeaconConfig::quicknet();
+        let unlocks = Utc::now() + Duration::days(1);
+        let encrypted = crypto::encrypt_with_tlock("password", unlocks, &beacon)?;
+        let round = crypto::datetime_to_round(unlocks, &beacon);
+
+        let mut tlock_paths = Vec::new();
+        for name in ["good", "skewed", "bitflip"] {
+            let source = test_dir.join(format!("{}.txt", name));
+            fs::write(&source, format!("Content of {}", name).repeat(100))?;
+            let mut metadata = TlockMetadata::new(
+                format!("{}.txt", name),
+                "1d".to_string(),
+                unlocks,
+                Some(round),
+                Some(encrypted.clone()),
+            );
+            if name == "skewed" {
+                // Claims an earlier unlock than the key allows, and a different round
+                metadata.unlocks = unlocks - Duration::days(1);
+                metadata.drand_round = Some(round - 1);
+            }
+            tlock_paths.push(TlockArchive::create(&source, metadata, "password")?);
+        }
+        let mut data = fs::read(&tlock_paths[2])?;
+        let middle = data.len() - 100;
+        data[middle] ^= 0xFF;
+        fs::write(&tlock_paths[2], &data)?;
+
+        let report = audit_vault(&test_dir)?;
+        assert_eq!(report.entries.len(), 3);
+        assert_eq!(report.flagged, 2);
+
+        let entry = |name: &str| {
+            report
+                .entries
+                .iter()
+                .find(|e| e.path.file_name().unwrap().to_string_lossy().starts_with(name))
+                .unwrap()
+        };
+        let good = entry("good");
+        assert!(good.issues.is_empty(), "{:?}", good.issues);
+        assert_eq!(good.version, Some(TLOCK_VERSION));
+        assert_eq!(good.drand_round, Some(round));
+        assert_eq!(good.checksum_ok, Some(true));
+
+        let skewed = entry("skewed");
+        assert_eq!(skewed.issues.len(), 2, "{:?}", skewed.issues);
+        assert!(skewed.issues.iter().any(|i| i.contains("records round")));
+        assert!(skewed.issues.iter().any(|i| i.contains("after the recorded unlock time")));
+
+        let bitflip = entry("bitflip");
+        assert_eq!(bitflip.checksum_ok, Some(false));
+
+        // Auditing moves and changes nothing
+        assert!(tlock_paths.iter().all(|p| p.exists()));
+        assert_eq!(fs::read(&tlock_paths[2])?, data);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_payload_starts_at_7z_signature() -> Result<()> {
+        let test_dir = setup_test_dir("open_payload");
+
+        let source_file = test_dir.join("payload.txt");
+        fs::write(&source_file, b"Payload content")?;
+
+        let metadata = TlockMetadata::new(
+            "payload.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let tlock_path = TlockArchive::create(&source_file, metadata, "password")?;
+
+        let mut payload = TlockArchive::open_payload(&tlock_path)?;
+        let expected_len = fs::metadata(&tlock_path)?.len() - TlockArchive::get_payload_offset(&tlock_path)?;
+        assert_eq!(payload.len(), expected_len);
+
+        let mut magic = [0u8; 6];
+        payload.read_exact(&mut magic)?;
+        assert_eq!(magic, [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]);
+
+        // Seeking is relative to the payload start
+        assert_eq!(payload.seek(SeekFrom::Start(0))?, 0);
+        assert_eq!(payload.seek(SeekFrom::End(0))?, expected_len);
+        assert!(payload.seek(SeekFrom::Current(-(expected_len as i64) - 1)).is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_entries_with_and_without_index() -> Result<()> {
+        let test_dir = setup_test_dir("list_entries");
+
+        let source_file = test_dir.join("listed.txt");
+        fs::write(&source_file, b"Listed content")?;
+
+        let new_metadata = || TlockMetadata::new(
+            "listed.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+
+        // Without index: falls back to the 7z header
+        let plain = TlockArchive::create(&source_file, new_metadata(), "password")?;
+        let from_header = TlockArchive::list_entries(&plain, "password")?;
+        let plain_renamed = test_dir.join("plain.7z.tlock");
+        fs::rename(&plain, &plain_renamed)?;
+
+        // With index: read from the sealed metadata
+        let options = CreateOptions {
+            entry_index: true,
+            ..Default::default()
+        };
+        let indexed = TlockArchive::create_with_options(&source_file, new_metadata(), "password", &options)?;
+        let metadata = TlockArchive::read_metadata(&indexed)?.metadata.unwrap();
+        let sealed = metadata.sealed_entry_index.expect("index should be stored");
+        assert!(!sealed.contains("listed.txt"), "index must not leak names");
+
+        let from_index = TlockArchive::list_entries(&indexed, "password")?;
+        assert_eq!(from_index, from_header);
+        assert!(from_index.iter().any(|e| e.name.ends_with("listed.txt") && e.size == 14));
+
+        assert!(TlockArchive::list_entries(&indexed, "wrong").is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_entry_into_memory() -> Result<()> {
+        let test_dir = setup_test_dir("read_entry");
+
+        let source = test_dir.join("docs");
+        fs::create_dir_all(&source)?;
+        fs::write(source.join("a.txt"), b"first file")?;
+        fs::write(source.join("note.txt"), b"Why I locked this")?;
+
+        let metadata = TlockMetadata::new(
+            "docs".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+        let name = TlockArchive::list_entries(&tlock_path, "password")?
+            .into_iter()
+            .find(|e| e.name.ends_with("note.txt"))
+            .unwrap()
+            .name;
+
+        let contents = TlockArchive::read_entry(&tlock_path, "password", &name, 1024)?;
+        assert_eq!(contents, b"Why I locked this");
+
+        // Too large, missing, or the wrong password
+        assert!(TlockArchive::read_entry(&tlock_path, "password", &name, 4).is_err());
+        assert!(TlockArchive::read_entry(&tlock_path, "password", "missing.txt", 1024).is_err());
+        assert!(TlockArchive::read_entry(&tlock_path, "wrong", &name, 1024).is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_path() {
+        let dir = Path::new("vault");
+        let (a, b, c) = (dir.join("a.txt"), dir.join("b"), dir.join("c.pdf"));
+
+        assert_eq!(TlockArchive::output_path(&[&a]).unwrap(), dir.join("a.7z.tlock"));
+        assert_eq!(
+            TlockArchive::output_path(&[&a, &b, &c]).unwrap(),
+            dir.join("a.txt_and_2_more.7z.tlock")
+        );
+        assert!(TlockArchive::output_path(&[]).is_err());
+    }
+
+    #[test]
+    fn test_create_multi_recreates_each_source() -> Result<()> {
+        let test_dir = setup_test_dir("create_multi");
+
+        let letter = test_dir.join("letter.txt");
+        fs::write(&letter, b"Dear future me")?;
+        let photos = test_dir.join("photos");
+        fs::create_dir_all(photos.join("2026"))?;
+        fs::write(photos.join("2026").join("beach.jpg"), b"jpeg bytes")?;
+        let sources = [letter.as_path(), photos.as_path()];
+
+        let summary = multi_source_summary(&sources);
+        assert_eq!(summary, "letter.txt (+1 more)");
+        let metadata = TlockMetadata::new(summary, "1d".to_string(), Utc::now() + Duration::days(1), None, None);
+        let tlock_path = TlockArchive::create_multi(&sources, metadata, "password")?;
+
+        let stored = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(stored.original_files, Some(vec!["letter.txt".to_string(), "photos".to_string()]));
+
+        let out = test_dir.join("out");
+        TlockArchive::extract(&tlock_path, "password", &out)?;
+        assert_eq!(fs::read(out.join("letter.txt"))?, b"Dear future me");
+        assert_eq!(fs::read(out.join("photos").join("2026").join("beach.jpg"))?, b"jpeg bytes");
+
+        // Sources sharing a name would overwrite each other on extraction
+        let other = test_dir.join("other");
+        fs::create_dir_all(&other)?;
+        let other_letter = other.join("letter.txt");
+        fs::write(&other_letter, b"another letter")?;
+        let clash = [letter.as_path(), other_letter.as_path()];
+        let metadata = TlockMetadata::new("clash".to_string(), "1d".to_string(), Utc::now() + Duration::days(1), None, None);
+        assert!(TlockArchive::create_multi(&clash, metadata, "password").is_err());
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_with_buffer() -> std::io::Result<()> {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        for buffer_size in [1, 4096, DEFAULT_COPY_BUFFER_SIZE] {
+            let mut out = Vec::new();
+            let copied = copy_with_buffer(&mut data.as_slice(), &mut out, buffer_size)?;
+            assert_eq!(copied, data.len() as u64);
+            assert_eq!(out, data);
+        }
+        Ok(())
+    }
+
+    /// Throughput of file-to-file payload copies at different buffer sizes.
+    ///
+    /// Run with: `cargo test --release bench_copy_buffer_sizes -- --ignored --nocapture`
+    #[test]
+    #[ignore] // Benchmark: writes a 512 MB temp file
+    fn bench_copy_buffer_sizes() -> std::io::Result<()> {
+        const SIZE: usize = 512 * 1024 * 1024;
+
+        let test_dir = setup_test_dir("bench_copy");
+        let source = test_dir.join("source.bin");
+        {
+            let chunk = vec![0xA5u8; 1024 * 1024];
+            let mut writer = BufWriter::new(File::create(&source)?);
+            for _ in 0..SIZE / chunk.len() {
+                writer.write_all(&chunk)?;
+            }
+            writer.flush()?;
+        }
+
+        for buffer_size in [8 * 1024, 64 * 1024, DEFAULT_COPY_BUFFER_SIZE, 4 * 1024 * 1024] {
+            let dest = test_dir.join("dest.bin");
+            let start = std::time::Instant::now();
+            {
+                let mut reader = BufReader::new(File::open(&source)?);
+                let mut writer = BufWriter::new(File::create(&dest)?);
+                copy_with_buffer(&mut reader, &mut writer, buffer_size)?;
+                writer.flush()?;
+                writer.get_ref().sync_all()?;
+            }
+            let secs = start.elapsed().as_secs_f64();
+            println!(
+                "buffer {:>8} KB: {:>8.1} MB/s",
+                buffer_size / 1024,
+                SIZE as f64 / (1024.0 * 1024.0) / secs
+            );
+            fs::remove_file(&dest)?;
+        }
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_metadata_keeps_payload() -> Result<()> {
+        let test_dir = setup_test_dir("rewrite_metadata");
+
+        let source = test_dir.join("rewrite.txt");
+        fs::write(&source, b"payload that must not change")?;
+        let metadata = TlockMetadata::new(
+            "rewrite.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            Some("old_key".to_string()),
+        );
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+
+        let payload_before = test_dir.join("before.7z");
+        TlockArchive::export_payload(&tlock_path, &payload_before)?;
 
-        assert_eq!(parsed.original_file, "test.txt");
-        assert_eq!(parsed.duration, "30d");
-        assert_eq!(parsed.drand_round, Some(12345678));
-        assert!(parsed.locked);
+        let mut updated = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        updated.unlocks = Utc::now() + Duration::days(30);
+        updated.encrypted_key = Some("new_key".to_string());
+        TlockArchive::rewrite_metadata(&tlock_path, &updated)?;
+
+        let reread = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(reread.unlocks, updated.unlocks);
+        assert_eq!(reread.encrypted_key.as_deref(), Some("new_key"));
+
+        let payload_after = test_dir.join("after.7z");
+        TlockArchive::export_payload(&tlock_path, &payload_after)?;
+        assert_eq!(fs::read(&payload_before)?, fs::read(&payload_after)?);
+        let payload = TlockArchive::open_payload(&tlock_path)?;
+        assert!(crate::archive::verify_archive_password(payload, "password")?);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
     }
 
     #[test]
-    fn test_metadata_is_unlockable() {
-        // Future unlock time
-        let future_metadata = TlockMetadata::new(
-            "test.txt".to_string(),
-            "30d".to_string(),
-            Utc::now() + Duration::days(30),
-            None,
-            None,
-        );
-        assert!(!future_metadata.is_unlockable());
+    fn test_same_length_metadata_rewritten_in_place() -> Result<()> {
+        let test_dir = setup_test_dir("rewrite_metadata_in_place");
 
-        // Past unlock time
-        let past_metadata = TlockMetadata::new(
-            "test.txt".to_string(),
-            "0d".to_string(),
-            Utc::now() - Duration::days(1),
-            None,
+        let source = test_dir.join("in_place.txt");
+        fs::write(&source, b"payload that must not move")?;
+        let metadata = TlockMetadata::new(
+            "in_place.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
             None,
+            Some("old_key".to_string()),
         );
-        assert!(past_metadata.is_unlockable());
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+        let before = fs::read(&tlock_path)?;
+        #[cfg(unix)]
+        let inode_before = std::os::unix::fs::MetadataExt::ino(&fs::metadata(&tlock_path)?);
+
+        let mut updated = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        updated.encrypted_key = Some("new_key".to_string());
+        TlockArchive::rewrite_metadata(&tlock_path, &updated)?;
+
+        let after = fs::read(&tlock_path)?;
+        assert_eq!(before.len(), after.len());
+        let payload_start = HEADER_SIZE + CHECKSUM_SIZE + serde_json::to_vec(&updated).unwrap().len();
+        assert_eq!(before[payload_start..], after[payload_start..]);
+        assert_ne!(before[..payload_start], after[..payload_start]);
+        #[cfg(unix)]
+        assert_eq!(inode_before, std::os::unix::fs::MetadataExt::ino(&fs::metadata(&tlock_path)?));
+
+        let reread = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(reread.encrypted_key.as_deref(), Some("new_key"));
+        TlockArchive::verify_integrity(&tlock_path)?;
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
     }
 
     #[test]
-    fn test_create_and_read_metadata() -> Result<()> {
-        let test_dir = setup_test_dir("create_read");
+    fn test_oversized_metadata_rewrite_rejected() -> Result<()> {
+        let test_dir = setup_test_dir("rewrite_metadata_too_large");
 
-        // Create a test file
-        let source_file = test_dir.join("secret.txt");
-        fs::write(&source_file, b"This is secret content!")?;
+        let source = test_dir.join("big_note.txt");
+        fs::write(&source, b"small payload")?;
+        let metadata = TlockMetadata::new("big_note.txt".to_string(), "1d".to_string(), Utc::now(), None, None);
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+        let before = fs::read(&tlock_path)?;
 
-        // Create metadata
-        let metadata = TlockMetadata::new(
-            "secret.txt".to_string(),
-            "7d".to_string(),
-            Utc::now() + Duration::days(7),
-            Some(99999),
-            Some("AGE_ENCRYPTED_KEY".to_string()),
-        );
+        let mut updated = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        updated.note = Some("x".repeat(MAX_METADATA_SIZE as usize));
+        let err = TlockArchive::rewrite_metadata(&tlock_path, &updated).unwrap_err();
+        assert!(err.to_string().contains("Metadata too large"), "{}", err);
+        assert_eq!(fs::read(&tlock_path)?, before);
 
-        // Create .7z.tlock file
-        let password = "test_password_123";
-        let tlock_path = TlockArchive::create(&source_file, metadata, password)?;
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
 
-        assert!(tlock_path.exists());
-        assert!(tlock_path.to_str().unwrap().ends_with(".7z.tlock"));
+    #[test]
+    fn test_change_unlock_time_rewraps_key() -> Result<()> {
+        let test_dir = setup_test_dir("change_unlock_time");
 
-        // Read metadata (no password needed)
-        let archive = TlockArchive::read_metadata(&tlock_path)?;
-        let loaded_meta = archive.get_metadata().unwrap();
+        let source = test_dir.join("later.txt");
+        fs::write(&source, b"payload that must not change")?;
+        let metadata = TlockMetadata::new(
+            "later.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            Some("old_key".to_string()),
+        );
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+        let payload_before = test_dir.join("before.7z");
+        TlockArchive::export_payload(&tlock_path, &payload_before)?;
+
+        // A wrong password is rejected before anything is rewritten
+        let new_time = Utc::now() + Duration::days(365);
+        assert!(TlockArchive::change_unlock_time(&tlock_path, "wrong", new_time).is_err());
+        let unchanged = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(unchanged.encrypted_key.as_deref(), Some("old_key"));
+
+        let updated = TlockArchive::change_unlock_time(&tlock_path, "password", new_time)?;
+        let reread = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(reread.unlocks, new_time);
+        assert_eq!(reread.encrypted_key, updated.encrypted_key);
+        assert_ne!(reread.encrypted_key.as_deref(), Some("old_key"));
+        assert_eq!(
+            crypto::ciphertext_round(reread.encrypted_key.as_ref().unwrap())?,
+            crypto::datetime_to_round(new_time, &crypto::BeaconConfig::quicknet())
+        );
 
-        assert_eq!(loaded_meta.original_file, "secret.txt");
-        assert_eq!(loaded_meta.duration, "7d");
-        assert_eq!(loaded_meta.drand_round, Some(99999));
-        assert_eq!(loaded_meta.encrypted_key, Some("AGE_ENCRYPTED_KEY".to_string()));
-        assert!(loaded_meta.locked);
+        let payload_after = test_dir.join("after.7z");
+        TlockArchive::export_payload(&tlock_path, &payload_after)?;
+        assert_eq!(fs::read(&payload_before)?, fs::read(&payload_after)?);
 
         cleanup_test_dir(&test_dir);
         Ok(())
     }
 
     #[test]
-    fn test_create_and_extract() -> Result<()> {
-        let test_dir = setup_test_dir("create_extract");
-
-        // Create a test file
-        let source_file = test_dir.join("document.txt");
-        let content = b"Important document content for testing extraction!";
-        fs::write(&source_file, content)?;
+    fn test_recover_from_truncated_header() -> Result<()> {
+        let test_dir = setup_test_dir("recover");
+        let source = test_dir.join("salvage.txt");
+        fs::write(&source, b"Still in there".repeat(100))?;
 
-        // Create metadata
         let metadata = TlockMetadata::new(
-            "document.txt".to_string(),
+            "salvage.txt".to_string(),
             "1d".to_string(),
             Utc::now() + Duration::days(1),
             None,
-            None,
+            Some("encrypted-key".to_string()),
+        );
+        let tlock_path = TlockArchive::create(&source, metadata.clone(), "password")?;
+        let offset = TlockArchive::get_payload_offset(&tlock_path)? as usize;
+
+        // Lose the header and most of the metadata, keep the payload
+        let bytes = fs::read(&tlock_path)?;
+        let damaged = test_dir.join("damaged.7z.tlock");
+        fs::write(&damaged, &bytes[offset - 20..])?;
+        assert!(TlockArchive::read_metadata(&damaged).is_err());
+
+        // The encrypted key is required
+        let mut keyless = metadata.clone();
+        keyless.encrypted_key = None;
+        assert!(TlockArchive::recover(&damaged, keyless).is_err());
+
+        let recovered = TlockArchive::recover(&damaged, metadata)?;
+        assert_eq!(recovered, test_dir.join("damaged.recovered.7z.tlock"));
+        TlockArchive::verify_integrity(&recovered)?;
+        let restored = TlockArchive::read_metadata(&recovered)?.metadata.unwrap();
+        assert_eq!(restored.original_file, "salvage.txt");
+        assert_eq!(
+            fs::read(&recovered)?[TlockArchive::get_payload_offset(&recovered)? as usize..],
+            bytes[offset..]
         );
 
-        // Create .7z.tlock file
-        let password = "extraction_test_pwd";
-        let tlock_path = TlockArchive::create(&source_file, metadata, password)?;
+        // Never overwrites an earlier recovery
+        let again = TlockArchive::read_metadata(&recovered)?.metadata.unwrap();
+        assert!(TlockArchive::recover(&damaged, again).is_err());
 
-        // Extract to new directory
-        let extract_dir = test_dir.join("extracted");
-        TlockArchive::extract(&tlock_path, password, &extract_dir)?;
+        // Nothing to recover without a payload
+        let empty = test_dir.join("empty.7z.tlock");
+        fs::write(&empty, &bytes[..offset])?;
+        let mut meta = restored;
+        meta.encrypted_key = Some("encrypted-key".to_string());
+        assert!(TlockArchive::recover(&empty, meta).is_err());
 
-        // Verify extracted file
-        let extracted_file = extract_dir.join("document.txt");
-        assert!(extracted_file.exists(), "Extracted file should exist");
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
 
-        let extracted_content = fs::read(&extracted_file)?;
-        assert_eq!(extracted_content, content);
+    #[test]
+    fn test_normalize_header_keeps_metadata_and_payload() -> Result<()> {
+        let test_dir = setup_test_dir("normalize_header");
+        let source = test_dir.join("data.txt");
+        fs::write(&source, b"Normalize me".repeat(100))?;
+
+        let metadata = TlockMetadata::new(
+            "data.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            Some("encrypted-key".to_string()),
+        );
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
+
+        // Already canonical: nothing to do
+        assert!(!TlockArchive::normalize_header(&tlock_path)?);
+
+        // Simulate a writer that left junk in the reserved bytes
+        let mut bytes = fs::read(&tlock_path)?;
+        bytes[12..HEADER_SIZE].copy_from_slice(&[0xAB; 12]);
+        fs::write(&tlock_path, &bytes)?;
+
+        assert!(TlockArchive::normalize_header(&tlock_path)?);
+        let normalized = fs::read(&tlock_path)?;
+        assert_eq!(normalized[12..HEADER_SIZE], [0u8; 12]);
+        assert_eq!(normalized[..12], bytes[..12]);
+        assert_eq!(normalized[HEADER_SIZE..], bytes[HEADER_SIZE..]);
+        assert!(!TlockArchive::normalize_header(&tlock_path)?);
+
+        // A corrupt payload is never written back over the original
+        let mut corrupt = bytes.clone();
+        corrupt.truncate(corrupt.len() - 10);
+        fs::write(&tlock_path, &corrupt)?;
+        assert!(TlockArchive::normalize_header(&tlock_path).is_err());
+        assert_eq!(fs::read(&tlock_path)?, corrupt);
 
         cleanup_test_dir(&test_dir);
         Ok(())
     }
 
     #[test]
-    fn test_wrong_password_fails() -> Result<()> {
-        let test_dir = setup_test_dir("wrong_pwd");
+    fn test_payload_checksum_detects_corruption() -> Result<()> {
+        let test_dir = setup_test_dir("checksum");
 
-        // Create a test file
-        let source_file = test_dir.join("secret.txt");
-        fs::write(&source_file, b"Secret!")?;
+        let mut paths = Vec::new();
+        for (name, version) in [("v1", MIN_TLOCK_VERSION), ("v2", TLOCK_VERSION)] {
+            let source = test_dir.join(format!("{}.txt", name));
+            fs::write(&source, format!("Checksummed {}", name).repeat(100))?;
+            let metadata = TlockMetadata::new(
+                format!("{}.txt", name),
+                "1d".to_string(),
+                Utc::now() + Duration::days(1),
+                None,
+                Some("encrypted-key".to_string()),
+            );
+            let options = CreateOptions {
+                format_version: Some(version),
+                ..Default::default()
+            };
+            paths.push(TlockArchive::create_with_options(&source, metadata, "password", &options)?);
+        }
+        let (v1_path, v2_path) = (&paths[0], &paths[1]);
+
+        // Only version 2 carries a checksum, and it matches the payload
+        assert!(TlockArchive::open_payload(v1_path)?.checksum().is_none());
+        let mut payload = TlockArchive::open_payload(v2_path)?;
+        let stored = *payload.checksum().expect("v2 has a checksum");
+        assert_eq!(stored, payload_checksum(&mut payload)?);
+        TlockArchive::extract(v2_path, "password", &test_dir.join("out_v2"))?;
+
+        // Flip a byte inside the encrypted data (between the 7z start and end headers)
+        let offset = TlockArchive::get_payload_offset(v2_path)? as usize;
+        let mut data = fs::read(v2_path)?;
+        data[offset + SEVENZ_START_HEADER_SIZE as usize + 1] ^= 0xFF;
+        fs::write(v2_path, &data)?;
+
+        for result in [
+            TlockArchive::extract(v2_path, "password", &test_dir.join("out_corrupt")),
+            TlockArchive::extract_payload_to_temp(v2_path).map(|_| ()),
+            TlockArchive::verify_integrity(v2_path),
+        ] {
+            assert!(matches!(result, Err(TimeLockerError::ChecksumMismatch { .. })));
+        }
+        assert!(!test_dir.join("out_corrupt").exists());
 
-        // Create .7z.tlock file
+        // Version 1 files still extract without a checksum
+        TlockArchive::extract(v1_path, "password", &test_dir.join("out_v1"))?;
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<()> {
+        let test_dir = setup_test_dir("fingerprint");
+        let source = test_dir.join("copy-me.txt");
+        fs::write(&source, b"Backed up twice")?;
         let metadata = TlockMetadata::new(
-            "secret.txt".to_string(),
+            "copy-me.txt".to_string(),
             "1d".to_string(),
             Utc::now() + Duration::days(1),
             None,
-            None,
+            Some("encrypted-key".to_string()),
         );
-        let tlock_path = TlockArchive::create(&source_file, metadata, "correct_password")?;
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
 
-        // Try to extract with wrong password
-        let extract_dir = test_dir.join("extracted");
-        let result = TlockArchive::extract(&tlock_path, "wrong_password", &extract_dir);
+        let fingerprint = TlockArchive::fingerprint(&tlock_path)?;
+        assert_eq!(fingerprint.len(), 64);
+        let copy = test_dir.join("backup.7z.tlock");
+        fs::copy(&tlock_path, &copy)?;
+        assert_eq!(TlockArchive::fingerprint(&copy)?, fingerprint);
 
-        assert!(result.is_err());
+        // The payload is unchanged, but the metadata isn't
+        let mut metadata = TlockArchive::read_metadata(&copy)?.metadata.unwrap();
+        metadata.note = Some("Second copy".to_string());
+        TlockArchive::rewrite_metadata(&copy, &metadata)?;
+        assert_ne!(TlockArchive::fingerprint(&copy)?, fingerprint);
+
+        // Not a .7z.tlock file
+        assert!(TlockArchive::fingerprint(&source).is_err());
 
         cleanup_test_dir(&test_dir);
         Ok(())
     }
 
     #[test]
-    fn test_validate_tlock_file() -> Result<()> {
-        let test_dir = setup_test_dir("validate");
+    fn test_wrap_large_archive_streams_payload() -> Result<()> {
+        let test_dir = setup_test_dir("wrap_large");
+        let source = test_dir.join("large.bin");
+        {
+            // 64 MB, written in chunks so the test itself stays small in memory
+            let mut writer = BufWriter::new(File::create(&source)?);
+            let chunk: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+            for _ in 0..64 {
+                writer.write_all(&chunk)?;
+            }
+            writer.flush()?;
+        }
 
-        // Create a valid .7z.tlock file
-        let source_file = test_dir.join("test.txt");
-        fs::write(&source_file, b"Test content")?;
+        let compression = CompressionSettings { level: 0, method: CompressionMethod::Store, ..Default::default() };
+        let archive_path = create_encrypted_archive(&source, "password", &compression)?;
+        let archive_len = fs::metadata(&archive_path)?.len();
+        assert!(archive_len > 64 * 1024 * 1024);
 
         let metadata = TlockMetadata::new(
-            "test.txt".to_string(),
+            "large.bin".to_string(),
             "1d".to_string(),
             Utc::now() + Duration::days(1),
             None,
-            None,
+            Some("encrypted-key".to_string()),
         );
-        let tlock_path = TlockArchive::create(&source_file, metadata, "password")?;
+        let tlock_path = TlockArchive::output_path(&[source.as_path()])?;
+        TlockArchive::wrap_archive(&archive_path, &tlock_path, metadata, "password", &CreateOptions::default())?;
 
-        // Valid file should validate
-        assert!(TlockArchive::validate(&tlock_path)?);
+        assert!(!archive_path.exists());
+        let payload = TlockArchive::open_payload(&tlock_path)?;
+        assert_eq!(payload.len(), archive_len);
+        TlockArchive::verify_integrity(&tlock_path)?;
+        assert!(verify_archive_password(TlockArchive::open_payload(&tlock_path)?, "password")?);
 
-        // Non-existent file should return false
-        let fake_path = test_dir.join("nonexistent.7z.tlock");
-        assert!(!TlockArchive::validate(&fake_path)?);
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
 
-        // Create a fake file with wrong magic bytes
-        let bad_file = test_dir.join("bad.7z.tlock");
-        fs::write(&bad_file, b"NOT_A_TLOCK_FILE_AT_ALL")?;
-        assert!(!TlockArchive::validate(&bad_file)?);
+    #[test]
+    fn test_crypto_parameters_recorded() -> Result<()> {
+        let test_dir = setup_test_dir("crypto_parameters");
+        let source = test_dir.join("audited.txt");
+        fs::write(&source, b"protected how, exactly?")?;
+
+        let metadata = TlockMetadata::new(
+            "audited.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            Some("encrypted-key".to_string()),
+        );
+        let options = CreateOptions {
+            compression: CompressionSettings { level: 9, ..Default::default() },
+            ..Default::default()
+        };
+        let tlock_path = TlockArchive::create_with_options(&source, metadata, "password", &options)?;
+
+        let params = TlockArchive::read_metadata(&tlock_path)?
+            .metadata
+            .unwrap()
+            .crypto_parameters
+            .expect("crypto parameters recorded at lock time");
+        assert_eq!(params.cipher, ARCHIVE_CIPHER);
+        assert_eq!(params.kdf_cycles_power, archive_kdf_cycles_power());
+        assert!(params.header_encryption);
+        assert_eq!(params.compression, "lzma2");
+        assert_eq!(params.compression_level, Some(9));
+        assert!(params.precompressed_stored);
+
+        let stored = CryptoParameters::for_compression(&CompressionSettings {
+            method: CompressionMethod::Store,
+            ..Default::default()
+        });
+        assert_eq!(stored.compression_level, None);
+        assert!(!stored.precompressed_stored);
+
+        // Files locked before the parameters were recorded still read
+        let old: TlockMetadata = serde_json::from_str(
+            r#"{"locked":true,"created":"2025-01-01T00:00:00Z","unlocks":"2026-01-01T00:00:00Z",
+               "duration":"1y","original_file":"old.txt"}"#,
+        )
+        .unwrap();
+        assert!(old.crypto_parameters.is_none());
 
         cleanup_test_dir(&test_dir);
         Ok(())
     }
 
     #[test]
-    fn test_invalid_file_errors() -> Result<()> {
-        let test_dir = setup_test_dir("invalid");
+    fn test_beacon_parameters_embedded() {
+        let mut metadata = TlockMetadata::new(
+            "portable.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let quicknet = crypto::BeaconConfig::quicknet();
+        metadata.set_beacon(&quicknet);
+        assert_eq!(metadata.beacon_chain_hash.as_deref(), Some(quicknet.chain_hash.as_str()));
+        assert_eq!(metadata.beacon().unwrap(), quicknet);
+
+        // A chain this build doesn't know resolves from the stored parameters
+        let params = crypto::BeaconParameters {
+            chain_hash: "ab".repeat(32),
+            ..crypto::BeaconParameters::of(&quicknet)
+        };
+        metadata.beacon_chain_hash = Some(params.chain_hash.clone());
+        metadata.beacon_parameters = Some(params.clone());
+        let json = serde_json::to_string(&metadata).unwrap();
+        let restored: TlockMetadata = serde_json::from_str(&json).unwrap();
+        let beacon = restored.beacon().unwrap();
+        assert_eq!(crypto::BeaconParameters::of(&beacon), params);
+        assert!(beacon.endpoints.is_empty());
+
+        // Parameters for a different chain than the one recorded are ignored
+        metadata.beacon_chain_hash = Some("cd".repeat(32));
+        assert!(metadata.beacon().is_err());
+    }
 
-        // Test file too small
-        let small_file = test_dir.join("small.7z.tlock");
-        fs::write(&small_file, b"tiny")?;
-        assert!(TlockArchive::read_metadata(&small_file).is_err());
+    #[test]
+    fn test_create_many_shares_payload() -> Result<()> {
+        let test_dir = setup_test_dir("create_many");
+        let source = test_dir.join("letter.txt");
+        fs::write(&source, b"one payload, two release dates")?;
 
-        // Test wrong magic bytes
-        let wrong_magic = test_dir.join("wrong_magic.7z.tlock");
-        let mut bad_data = vec![0u8; 100];
-        bad_data[0..7].copy_from_slice(b"BADMAGC");
-        fs::write(&wrong_magic, &bad_data)?;
-        let err = TlockArchive::read_metadata(&wrong_magic).unwrap_err();
-        assert!(err.to_string().contains("bad magic bytes"));
+        let metadata = TlockMetadata::new(
+            "letter.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            None,
+        );
+        let outputs = vec![
+            (test_dir.join("letter_a.7z.tlock"), Utc::now() + Duration::days(30)),
+            (test_dir.join("letter_b.7z.tlock"), Utc::now() + Duration::days(365)),
+        ];
+        let created = TlockArchive::create_many(
+            &[source.as_path()],
+            metadata,
+            "password",
+            &outputs,
+            &CreateOptions::default(),
+        )?;
+        assert_eq!(created, vec![outputs[0].0.clone(), outputs[1].0.clone()]);
+
+        let beacon = crypto::BeaconConfig::quicknet();
+        let mut payloads = Vec::new();
+        for (path, unlocks) in &outputs {
+            TlockArchive::verify_integrity(path)?;
+            let metadata = TlockArchive::read_metadata(path)?.metadata.unwrap();
+            assert_eq!(metadata.unlocks, *unlocks);
+            assert_eq!(
+                crypto::ciphertext_round(metadata.encrypted_key.as_ref().unwrap())?,
+                crypto::datetime_to_round(*unlocks, &beacon)
+            );
+            let payload = path.with_extension("payload");
+            TlockArchive::export_payload(path, &payload)?;
+            payloads.push(fs::read(&payload)?);
+        }
+        assert_eq!(payloads[0], payloads[1]);
 
-        // Test unsupported version
-        let bad_version = test_dir.join("bad_version.7z.tlock");
-        let mut bad_ver_data = vec![0u8; 100];
-        bad_ver_data[0..7].copy_from_slice(TLOCK_MAGIC);
-        bad_ver_data[7] = 99; // Unsupported version
-        fs::write(&bad_version, &bad_ver_data)?;
-        let err = TlockArchive::read_metadata(&bad_version).unwrap_err();
-        assert!(err.to_string().contains("Unsupported"));
+        // The shared temp archive is gone once both outputs exist
+        let leftovers: Vec<_> = fs::read_dir(&test_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "7z"))
+            .collect();
+        assert!(leftovers.is_empty());
 
         cleanup_test_dir(&test_dir);
         Ok(())
     }
 
     #[test]
-    fn test_scan_tlock_files() -> Result<()> {
-        let test_dir = setup_test_dir("scan");
-
-        // Create test files
-        for i in 0..3 {
-            let source = test_dir.join(format!("file{}.txt", i));
-            fs::write(&source, format!("Content {}", i))?;
+    fn test_recompress_keeps_contents_and_metadata() -> Result<()> {
+        let test_dir = setup_test_dir("recompress");
+        let source = test_dir.join("notes.txt");
+        let content = b"Compress me well. ".repeat(5000);
+        fs::write(&source, &content)?;
 
-            let metadata = TlockMetadata::new(
-                format!("file{}.txt", i),
-                "1d".to_string(),
-                Utc::now() + Duration::days(1),
-                None,
-                None,
-            );
-            TlockArchive::create(&source, metadata, "password")?;
+        let metadata = TlockMetadata::new(
+            "notes.txt".to_string(),
+            "1d".to_string(),
+            Utc::now() + Duration::days(1),
+            None,
+            Some("encrypted-key".to_string()),
+        );
+        let tlock_path = TlockArchive::create_with_options(&source, metadata, "password", &CreateOptions::default())?;
+        let before = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+
+        let settings = [
+            CompressionSettings { level: 0, method: CompressionMethod::Lzma2, ..Default::default() },
+            CompressionSettings { level: 9, method: CompressionMethod::Lzma2, ..Default::default() },
+            CompressionSettings { level: 0, method: CompressionMethod::Store, ..Default::default() },
+        ];
+        for (i, compression) in settings.iter().enumerate() {
+            TlockArchive::recompress(&tlock_path, "password", compression)?;
+
+            let after = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+            assert_eq!(after.unlocks, before.unlocks);
+            assert_eq!(after.encrypted_key, before.encrypted_key);
+
+            let out = test_dir.join(format!("out_{}", i));
+            TlockArchive::extract(&tlock_path, "password", &out)?;
+            assert_eq!(fs::read(out.join("notes.txt"))?, content);
         }
 
-        // Create a subdirectory with another file
-        let sub_dir = test_dir.join("subdir");
-        fs::create_dir_all(&sub_dir)?;
-        let sub_source = sub_dir.join("nested.txt");
-        fs::write(&sub_source, b"Nested content")?;
-        let nested_metadata = TlockMetadata::new(
-            "nested.txt".to_string(),
+        // The wrong password leaves the file untouched
+        let untouched = fs::read(&tlock_path)?;
+        assert!(TlockArchive::recompress(&tlock_path, "wrong", &CompressionSettings::default()).is_err());
+        assert_eq!(fs::read(&tlock_path)?, untouched);
+
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_summary() -> Result<()> {
+        let test_dir = setup_test_dir("content_summary");
+        let source = test_dir.join("photos");
+        fs::create_dir_all(source.join("2024"))?;
+        fs::write(source.join("a.jpg"), vec![1u8; 1000])?;
+        fs::write(source.join("2024").join("b.jpg"), vec![2u8; 500])?;
+
+        let mut metadata = TlockMetadata::new(
+            "photos".to_string(),
             "1d".to_string(),
             Utc::now() + Duration::days(1),
             None,
             None,
         );
-        TlockArchive::create(&sub_source, nested_metadata, "password")?;
+        metadata.is_directory = true;
+        let tlock_path = TlockArchive::create(&source, metadata, "password")?;
 
-        // Scan directory
-        let archives = scan_tlock_files(&test_dir)?;
+        // Directories don't count, and nothing was recorded at lock time to rely on
+        let summary = TlockArchive::content_summary(&tlock_path, "password")?;
+        assert_eq!(summary, ContentSummary { file_count: 2, total_size: 1500 });
+        assert!(TlockArchive::content_summary(&tlock_path, "wrong").is_err());
 
-        assert_eq!(archives.len(), 4, "Should find 4 .7z.tlock files");
+        cleanup_test_dir(&test_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate_password() -> Result<()> {
+        let test_dir = setup_test_dir("rotate_password");
+        let source = test_dir.join("secret.txt");
+        fs::write(&source, b"rotate me")?;
+
+        let unlocks = Utc::now() - Duration::hours(1);
+        let mut metadata = TlockMetadata::new(
+            "secret.txt".to_string(),
+            "1d".to_string(),
+            unlocks,
+            Some(crypto::datetime_to_round(unlocks, &crypto::BeaconConfig::quicknet())),
+            Some("encrypted-key".to_string()),
+        );
+        let options = CreateOptions { entry_index: true, ..Default::default() };
+        let tlock_path = TlockArchive::create_with_options(&source, metadata.clone(), "password", &options)?;
+
+        TlockArchive::rotate_password(&tlock_path, "password")?;
+
+        // Same unlock time and round, a new time-locked key, and the old password is useless
+        let after = TlockArchive::read_metadata(&tlock_path)?.metadata.unwrap();
+        assert_eq!(after.unlocks, metadata.unlocks);
+        assert_eq!(after.drand_round, metadata.drand_round);
+        assert_ne!(after.encrypted_key, metadata.encrypted_key);
+        assert!(crypto::open_with_password(after.sealed_entry_index.as_ref().unwrap(), "password").is_err());
+        assert!(TlockArchive::extract(&tlock_path, "password", &test_dir.join("out")).is_err());
+        TlockArchive::verify_integrity(&tlock_path)?;
+
+        // A recipient's copy of the old password can't be rotated
+        metadata.recipient_encrypted_key = Some("age-encrypted".to_string());
+        let with_recipient = test_dir.join("recipient.txt");
+        fs::write(&with_recipient, b"shared")?;
+        let tlock_path = TlockArchive::create(&with_recipient, metadata, "password")?;
+        let untouched = fs::read(&tlock_path)?;
+        assert!(matches!(
+            TlockArchive::rotate_password(&tlock_path, "password"),
+            Err(TimeLockerError::Encryption(_))
+        ));
+        assert_eq!(fs::read(&tlock_path)?, untouched);
 
         cleanup_test_dir(&test_dir);
         Ok(())
@@ -814,6 +4360,27 @@ mod tests {
         assert_eq!(7 + 1 + 4 + 12, HEADER_SIZE);
     }
 
+    #[test]
+    fn test_resolve_format_version() {
+        let default = CreateOptions::default();
+        assert_eq!(default.resolve_version().unwrap(), TLOCK_VERSION);
+
+        let oldest = CreateOptions {
+            format_version: Some(MIN_TLOCK_VERSION),
+            entry_index: true,
+            ..Default::default()
+        };
+        assert_eq!(oldest.resolve_version().unwrap(), MIN_TLOCK_VERSION);
+
+        for version in [0, TLOCK_VERSION + 1] {
+            let options = CreateOptions {
+                format_version: Some(version),
+                ..Default::default()
+            };
+            assert!(options.resolve_version().is_err());
+        }
+    }
+
     #[test]
     fn test_directory_archiving() -> Result<()> {
         let test_dir = setup_test_dir("dir_archive");
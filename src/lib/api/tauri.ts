@@ -12,6 +12,15 @@ export interface TlockMetadata {
   originalSize?: number;
   compressedSize?: number;
   fileCount?: number;
+  /** Unencrypted note from whoever locked the file */
+  note?: string;
+  /** Advisory "open before" time; unlocking still works after it */
+  expires?: string;
+  isExpired?: boolean;
+  /** An age recipient can unlock this file before its unlock time */
+  hasRecipientKey?: boolean;
+  /** Custom key/value fields set when locking (unencrypted) */
+  extra?: Record<string, string>;
 }
 
 export interface LockedItem {
@@ -23,14 +32,36 @@ export interface LockedItem {
   zipPath: string;
   created: Date;
   unlocks: Date;
+  /** When the drand round for the key is published - the real earliest unlock */
+  decryptableAt?: Date;
+  drandRound?: number;
   isReady: boolean;
   metadata?: TlockMetadata;
   isLegacyFormat?: boolean; // true if using old .key.md format
   unlockedPath?: string;  // Path to unlocked content if already extracted
 }
 
+export type CompressionMethod = 'lzma2' | 'store';
+
+/** 'bcj_x86' helps compress x86 executables; 'auto' uses it when a .exe/.dll/.so is locked */
+export type CompressionFilter = 'none' | 'bcj_x86' | 'auto';
+
+export interface CompressionSettings {
+  /** LZMA2 level, 0 (fastest) to 9 (smallest); defaults to 6 */
+  level: number;
+  method: CompressionMethod;
+  filter?: CompressionFilter;
+  /** Compress already-compressed files (JPEG, MP4, zip...) too; they are otherwise only encrypted */
+  uniform?: boolean;
+}
+
 export interface LockOptions {
   deleteOriginal?: boolean;
+  compression?: CompressionSettings;
+  /** Stored unencrypted in the file's metadata - readable before unlock, so no secrets */
+  note?: string;
+  /** Archive password instead of a generated one; whoever knows it can open the file at any time */
+  password?: string;
 }
 
 export interface LockResult {
@@ -44,12 +75,73 @@ export interface LockResult {
   originalDeleted?: boolean;
   /** Error message if deletion was requested but failed (archive still created successfully) */
   deletionError?: string;
+  /** Estimated entropy of a user-supplied password */
+  passwordEntropyBits?: number;
+  /** Set when a user-supplied password looks guessable (the file is still locked) */
+  passwordWarning?: string;
 }
 
 export interface UnlockResult {
   success: boolean;
   outputPath?: string;
   error?: string;
+  /** Set when the file is still time-locked, so the UI can show its own countdown */
+  timeLock?: TimeLockActive;
+  /** The file is due but no drand endpoint answered: a network problem, try again later */
+  drandUnavailable?: boolean;
+  /** Password of the written .7z archive, when unlocked with `extractAsArchive` */
+  archivePassword?: string;
+}
+
+/** What the `unlock_tlock_file` command returns */
+export interface UnlockTlockResult {
+  output_path: string;
+  archive_password?: string;
+}
+
+/** Error payload the unlock commands return for a file that is still time-locked */
+export interface TimeLockActive {
+  code: 'time_lock_active';
+  unlocks_at: string;
+  remaining_seconds: number;
+}
+
+/** Error payload for a due file whose drand beacon couldn't be fetched */
+export interface DrandUnavailable {
+  code: 'drand_unavailable';
+  message: string;
+}
+
+function isTimeLockActive(error: unknown): error is TimeLockActive {
+  return typeof error === 'object' && error !== null && (error as TimeLockActive).code === 'time_lock_active';
+}
+
+function isDrandUnavailable(error: unknown): error is DrandUnavailable {
+  return typeof error === 'object' && error !== null && (error as DrandUnavailable).code === 'drand_unavailable';
+}
+
+/** Turn an unlock command error into a failed UnlockResult */
+function unlockFailure(error: unknown): UnlockResult {
+  if (isTimeLockActive(error)) {
+    const hours = Math.floor(error.remaining_seconds / 3600);
+    const minutes = Math.floor(error.remaining_seconds / 60) % 60;
+    return {
+      success: false,
+      error: `Time lock still active. Unlock in ${hours} hours, ${minutes} minutes`,
+      timeLock: error
+    };
+  }
+  if (isDrandUnavailable(error)) {
+    return {
+      success: false,
+      error: error.message,
+      drandUnavailable: true
+    };
+  }
+  return {
+    success: false,
+    error: error instanceof Error ? error.message : String(error)
+  };
 }
 
 export interface MigrationResult {
@@ -58,25 +150,66 @@ export interface MigrationResult {
   error?: string;
 }
 
+export interface MigrateProgressEvent {
+  current: number;
+  total: number;
+  key_path: string;
+  status: 'migrating' | 'migrated' | 'failed';
+  tlock_path?: string;
+  error?: string;
+}
+
+export interface MigrationSummary {
+  total: number;
+  migrated: { tlock_path: string; message: string; old_files_deleted: boolean; old_files_kept: string[] }[];
+  failed: { key_path: string; error: string }[];
+}
+
+export interface DirectoryMigrationResult {
+  success: boolean;
+  tlock_path: string;
+  message: string;
+  old_files_deleted: boolean;
+  old_files_kept: string[];
+  /** Left alone because a .7z.tlock already existed */
+  skipped: boolean;
+}
+
 export interface AppSettings {
   vaults: string[];
+  /** Delete the .7z.tlock file after a verified successful unlock */
+  delete_after_unlock?: boolean;
+  /** Cap archive reads/writes at this many MB/s while locking and unlocking */
+  throttle_mb_per_sec?: number | null;
+  /** drand relays to fetch rounds from instead of the public ones (empty = built-in) */
+  drand_endpoints?: string[];
+  /** Compress already-compressed files (JPEG, MP4, zip...) too; they are otherwise only encrypted */
+  uniform_compression?: boolean;
+  /** Name of the folder files are unlocked into; {name}, {date} and {timestamp} are filled in, and {name} plus some fixed text are required (unset = "unlocked_{name}") */
+  output_name_template?: string | null;
 }
 
 // Progress event types
 export interface LockProgressEvent {
-  stage: 'compressing' | 'encrypting' | 'finalizing';
+  stage: 'compressing' | 'encrypting' | 'writing_metadata' | 'finalizing';
   progress: number;  // 0-100
   currentFile?: string;
   bytesProcessed?: number;
   totalBytes?: number;
+  etaSeconds?: number;
+  /** Seconds since the operation started */
+  elapsedSeconds?: number;
 }
 
 export interface UnlockProgressEvent {
-  stage: 'decrypting' | 'extracting' | 'finalizing';
+  stage: 'decrypting' | 'fetching_beacon' | 'extracting' | 'finalizing';
   progress: number;  // 0-100
   currentFile?: string;
   bytesProcessed?: number;
   totalBytes?: number;
+  etaSeconds?: number;
+  /** Seconds since the operation started */
+  elapsedSeconds?: number;
 }
 
 interface AppStateResponse {
@@ -102,6 +235,44 @@ export async function onUnlockProgress(callback: (event: UnlockProgressEvent) =>
   });
 }
 
+/**
+ * Listen for per-file bulk migration events from the backend
+ */
+export async function onMigrateProgress(callback: (event: MigrateProgressEvent) => void): Promise<UnlistenFn> {
+  return await listen<MigrateProgressEvent>('migrate-progress', (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface ActiveOperation {
+  id: string;
+  /** Stage of the last progress event, null before the first one */
+  phase: string | null;
+  started_at: string;
+  /** Seconds since the operation last made progress */
+  idle_seconds: number;
+  bytes_processed: number;
+  cancelled: boolean;
+}
+
+/**
+ * List the lock/unlock operations the backend is tracking
+ */
+export async function listActiveOperations(): Promise<ActiveOperation[]> {
+  return await invoke<ActiveOperation[]>('list_active_operations');
+}
+
+/**
+ * Cancel and forget operations that made no progress for a while
+ * @param maxIdleSeconds - Idle time before an operation counts as stuck (defaults to 30 minutes)
+ * @returns Ids of the pruned operations
+ */
+export async function pruneStaleOperations(maxIdleSeconds?: number): Promise<string[]> {
+  return await invoke<string[]>('prune_stale_operations', {
+    maxIdleSeconds: maxIdleSeconds ?? null
+  });
+}
+
 /**
  * Open a path in the system file explorer (cross-platform)
  * @param path - Path to file or directory to open
@@ -128,7 +299,49 @@ export async function lockItem(
       filePath: path,
       unlockTime,
       vault: vault || null,
-      deleteOriginal: options?.deleteOriginal || false
+      deleteOriginal: options?.deleteOriginal || false,
+      compression: options?.compression || null,
+      note: options?.note || null,
+      password: options?.password || null
+    });
+    return {
+      success: true,
+      keyPath: result.key_path,
+      tlockPath: result.tlock_path,
+      zipPath: result.archive_path,
+      unlockTime: result.unlocks_at,
+      originalDeleted: result.original_deleted || false,
+      deletionError: result.deletion_error || undefined,
+      passwordEntropyBits: result.password_entropy_bits ?? undefined,
+      passwordWarning: result.password_warning || undefined
+    };
+  } catch (error) {
+    return {
+      success: false,
+      error: error instanceof Error ? error.message : String(error)
+    };
+  }
+}
+
+/**
+ * Lock an unlocked_* directory again until a new time
+ * @param unlockedPath - Directory produced by a previous unlock
+ * @param unlockTime - ISO timestamp when the directory can be unlocked again
+ * @param vault - Optional vault directory to store the locked file
+ * @param deleteUnlocked - Delete the directory once the new .7z.tlock is validated
+ */
+export async function relockItem(
+  unlockedPath: string,
+  unlockTime: string,
+  vault?: string,
+  deleteUnlocked?: boolean
+): Promise<LockResult> {
+  try {
+    const result = await invoke<any>('relock_item', {
+      unlockedPath,
+      unlockTime,
+      vault: vault || null,
+      deleteUnlocked: deleteUnlocked || false
     });
     return {
       success: true,
@@ -147,13 +360,24 @@ export async function lockItem(
   }
 }
 
+/**
+ * Move the unlock time of a .7z.tlock file without re-archiving it.
+ * Only works once the file's current unlock time has passed.
+ * @param tlockPath - Path to .7z.tlock file
+ * @param newUnlockTime - New unlock time (ISO 8601)
+ * @returns The new unlock time
+ */
+export async function changeUnlockTime(tlockPath: string, newUnlockTime: string): Promise<string> {
+  return await invoke<string>('change_unlock_time', { tlockPath, newUnlockTime });
+}
+
 /**
  * Unlock a previously locked item using its key file (legacy format)
  * @param keyPath - Path to .key.md file
  */
 export async function unlockItem(keyPath: string): Promise<UnlockResult> {
   try {
-    const outputPath = await invoke<string>('unlock_item', {
+    const outputPath = await invoke<string>('unlock_item_with_progress', {
       keyPath
     });
     return {
@@ -161,10 +385,7 @@ export async function unlockItem(keyPath: string): Promise<UnlockResult> {
       outputPath
     };
   } catch (error) {
-    return {
-      success: false,
-      error: error instanceof Error ? error.message : String(error)
-    };
+    return unlockFailure(error);
   }
 }
 
@@ -172,25 +393,58 @@ export async function unlockItem(keyPath: string): Promise<UnlockResult> {
  * Unlock a .7z.tlock file (new unified format)
  * @param tlockPath - Path to .7z.tlock file
  * @param outputDir - Optional output directory (defaults to same directory as tlock file)
+ * @param identityPath - Optional age identity file, to open a file locked to its recipient early
+ * @param extractAsArchive - Write the decrypted 7z archive instead of extracting it; its password
+ *   comes back as `archivePassword`, and whoever has both can open it at any time
  */
-export async function unlockTlockFile(tlockPath: string, outputDir?: string): Promise<UnlockResult> {
+export async function unlockTlockFile(
+  tlockPath: string,
+  outputDir?: string,
+  identityPath?: string,
+  extractAsArchive?: boolean
+): Promise<UnlockResult> {
   try {
-    const outputPath = await invoke<string>('unlock_tlock_file', {
+    const result = await invoke<UnlockTlockResult>('unlock_tlock_file', {
       tlockPath,
-      outputDir: outputDir || null
+      outputDir: outputDir || null,
+      identityPath: identityPath || null,
+      extractAsArchive: extractAsArchive ?? null
     });
     return {
       success: true,
-      outputPath
+      outputPath: result.output_path,
+      archivePassword: result.archive_password
     };
   } catch (error) {
-    return {
-      success: false,
-      error: error instanceof Error ? error.message : String(error)
-    };
+    return unlockFailure(error);
   }
 }
 
+export interface EntryPreview {
+  name: string;
+  size: number;
+  /** Entry contents, base64-encoded */
+  data: string;
+}
+
+/**
+ * Decrypt a single entry of an unlockable .7z.tlock file into memory for previewing
+ * @param tlockPath - Path to the .7z.tlock file
+ * @param entryName - Path of the entry inside the archive
+ * @param maxBytes - Largest entry accepted; bigger entries are rejected
+ */
+export async function extractEntryToBytes(
+  tlockPath: string,
+  entryName: string,
+  maxBytes: number
+): Promise<EntryPreview> {
+  return await invoke<EntryPreview>('extract_entry_to_bytes', {
+    tlockPath,
+    entryName,
+    maxBytes
+  });
+}
+
 /**
  * Migrate a legacy .key.md + .7z file pair to the new .7z.tlock format
  * @param keyPath - Path to the legacy .key.md file
@@ -214,6 +468,50 @@ export async function migrateToTlock(keyPath: string, deleteOriginal?: boolean):
   }
 }
 
+/**
+ * Migrate every legacy key file in a vault (or all vaults) to .7z.tlock
+ * Listen with onMigrateProgress() for per-file progress.
+ * @param vault - Optional vault directory (defaults to all known vaults)
+ * @param deleteOriginal - Whether to delete the original files after each migration
+ * @param keepAsMigrated - Rename the original files to *.migrated instead (see cleanupMigrated)
+ */
+export async function migrateAllWithProgress(
+  vault?: string,
+  deleteOriginal?: boolean,
+  keepAsMigrated?: boolean
+): Promise<MigrationSummary> {
+  return await invoke<MigrationSummary>('migrate_all_with_progress', {
+    vault: vault || null,
+    deleteOldFiles: deleteOriginal || false,
+    keepAsMigrated: keepAsMigrated || false
+  });
+}
+
+/**
+ * Migrate every legacy .key.md file in a directory (and below) to .7z.tlock,
+ * skipping ones already migrated. Returns one result per key file found.
+ */
+export async function migrateDirectory(
+  dir: string,
+  deleteOldFiles?: boolean
+): Promise<DirectoryMigrationResult[]> {
+  return await invoke<DirectoryMigrationResult[]>('migrate_directory', {
+    dir,
+    deleteOldFiles: deleteOldFiles || false
+  });
+}
+
+/**
+ * Delete the original files that migrations kept as *.migrated
+ * @param vault - Optional vault directory (defaults to all known vaults)
+ * @returns Paths of the deleted files
+ */
+export async function cleanupMigrated(vault?: string): Promise<string[]> {
+  return await invoke<string[]>('cleanup_migrated', {
+    vault: vault || null
+  });
+}
+
 /**
  * Check if a file is a legacy .key.md file
  */
@@ -228,6 +526,56 @@ export function isTlockFile(path: string): boolean {
   return path.toLowerCase().endsWith('.7z.tlock') || path.toLowerCase().endsWith('.tlock');
 }
 
+/**
+ * Earliest time an item can actually be unlocked: the nominal unlock time,
+ * or later if the drand beacon for its key hasn't been published by then
+ */
+export function effectiveUnlockTime(item: Pick<LockedItem, 'unlocks' | 'decryptableAt'>): Date {
+  if (item.decryptableAt && item.decryptableAt > item.unlocks) {
+    return item.decryptableAt;
+  }
+  return item.unlocks;
+}
+
+/**
+ * Convert a backend locked item into the frontend LockedItem shape
+ */
+function toLockedItem(item: any): LockedItem {
+  // Use backend's is_legacy_format field, fallback to detection
+  const keyPath = item.key_path || '';
+  const tlockPath = item.tlock_path || '';
+  const isLegacy = item.is_legacy_format ?? (keyPath.endsWith('.key.md') && !tlockPath);
+  const unlocks = new Date(item.unlocks_at);
+  const decryptableAt = item.decryptable_at ? new Date(item.decryptable_at) : undefined;
+
+  return {
+    id: item.id,
+    name: item.name,
+    type: 'file' as const,
+    keyPath: keyPath,
+    tlockPath: tlockPath || undefined,
+    zipPath: item.archive_path,
+    created: new Date(item.created_at),
+    unlocks,
+    decryptableAt,
+    drandRound: item.drand_round ?? undefined,
+    isReady: effectiveUnlockTime({ unlocks, decryptableAt }) <= new Date(),
+    isLegacyFormat: isLegacy,
+    unlockedPath: item.unlocked_path || undefined,
+    metadata: item.metadata ? {
+      version: item.metadata.version || '1.0',
+      locked: item.metadata.locked ?? true,
+      created: item.metadata.created || item.created_at,
+      unlocks: item.metadata.unlocks || item.unlocks_at,
+      duration: item.metadata.duration || '',
+      originalFile: item.metadata.original_file || item.name,
+      originalSize: item.metadata.original_size,
+      compressedSize: item.metadata.compressed_size,
+      fileCount: item.metadata.file_count
+    } : undefined
+  };
+}
+
 /**
  * Get complete application state from backend
  * This is the single source of truth for all app state
@@ -237,37 +585,7 @@ export async function getAppState(): Promise<{ settings: AppSettings; lockedItem
     const state = await invoke<AppStateResponse>('get_app_state');
     return {
       settings: state.settings,
-      lockedItems: state.locked_items.map(item => {
-        // Use backend's is_legacy_format field, fallback to detection
-        const keyPath = item.key_path || '';
-        const tlockPath = item.tlock_path || '';
-        const isLegacy = item.is_legacy_format ?? (keyPath.endsWith('.key.md') && !tlockPath);
-
-        return {
-          id: item.id,
-          name: item.name,
-          type: 'file' as const,
-          keyPath: keyPath,
-          tlockPath: tlockPath || undefined,
-          zipPath: item.archive_path,
-          created: new Date(item.created_at),
-          unlocks: new Date(item.unlocks_at),
-          isReady: item.is_unlockable || new Date(item.unlocks_at) <= new Date(),
-          isLegacyFormat: isLegacy,
-          unlockedPath: item.unlocked_path || undefined,
-          metadata: item.metadata ? {
-            version: item.metadata.version || '1.0',
-            locked: item.metadata.locked ?? true,
-            created: item.metadata.created || item.created_at,
-            unlocks: item.metadata.unlocks || item.unlocks_at,
-            duration: item.metadata.duration || '',
-            originalFile: item.metadata.original_file || item.name,
-            originalSize: item.metadata.original_size,
-            compressedSize: item.metadata.compressed_size,
-            fileCount: item.metadata.file_count
-          } : undefined
-        };
-      })
+      lockedItems: state.locked_items.map(toLockedItem)
     };
   } catch (error) {
     console.error('Failed to get app state:', error);
@@ -275,6 +593,167 @@ export async function getAppState(): Promise<{ settings: AppSettings; lockedItem
   }
 }
 
+/**
+ * Re-scan a single vault directory and return only its items
+ * @param path - Vault directory to scan
+ */
+export async function scanSingleVault(path: string): Promise<LockedItem[]> {
+  const items = await invoke<any[]>('scan_single_vault', { path });
+  return items.map(toLockedItem);
+}
+
+/**
+ * Find vaults (folders with locked files or a .timelocker-vault marker) under a root folder
+ * @param root - Folder to search from
+ * @param maxDepth - Levels below the root to search (default 4)
+ * @param register - Add the vaults found to the configured vaults
+ */
+export async function discoverVaults(
+  root: string,
+  maxDepth?: number,
+  register?: boolean
+): Promise<string[]> {
+  return invoke<string[]>('discover_vaults', { root, maxDepth, register });
+}
+
+export interface DeletionFailure {
+  path: string;
+  error: string;
+}
+
+export interface DeleteItemResult {
+  /** Paths that were removed */
+  removed: string[];
+  /** Paths that were left in place, with why */
+  errors: DeletionFailure[];
+}
+
+/**
+ * Permanently delete a locked item (.7z.tlock, or a legacy .key.md with its .7z),
+ * optionally with its unlocked directory. Only items inside known vaults can be deleted.
+ */
+export async function deleteLockedItem(
+  tlockPath: string,
+  alsoDeleteUnlocked?: boolean
+): Promise<DeleteItemResult> {
+  return invoke<DeleteItemResult>('delete_locked_item', {
+    tlockPath,
+    alsoDeleteUnlocked: alsoDeleteUnlocked ?? null,
+  });
+}
+
+export interface DuplicateGroup {
+  /** How the copies were matched: 'checksum' or 'metadata' */
+  matched_by: 'checksum' | 'metadata';
+  original_file: string | null;
+  paths: string[];
+  /** Bytes freed by deleting every copy but the first */
+  reclaimable_bytes: number;
+}
+
+/**
+ * Find locked items that appear more than once across the configured vaults (read-only)
+ */
+export async function findDuplicates(): Promise<DuplicateGroup[]> {
+  return invoke<DuplicateGroup[]>('find_duplicates');
+}
+
+export interface DrandEndpointStatus {
+  url: string;
+  chain_hash: string;
+  /** 'built-in', 'custom' (the drand_endpoints setting), 'command line' or the TIMELOCKER_BEACON config file path */
+  source: string;
+  /** null unless reachability was checked */
+  reachable: boolean | null;
+  latency_ms: number | null;
+  error: string | null;
+}
+
+/**
+ * Get the drand relays in the order they're tried, optionally checking each is reachable
+ */
+export async function getDrandEndpoints(check = false): Promise<DrandEndpointStatus[]> {
+  return invoke<DrandEndpointStatus[]>('get_drand_endpoints', { check });
+}
+
+export interface AuditEntry {
+  path: string;
+  /** Header format version, null when the header couldn't be read */
+  version: number | null;
+  unlocks: string | null;
+  drand_round: number | null;
+  /** When the key's round is published */
+  round_time: string | null;
+  payload_bytes: number | null;
+  /** null for version 1 files, which have no checksum */
+  checksum_ok: boolean | null;
+  /** Every inconsistency found; empty when the file checks out */
+  issues: string[];
+}
+
+export interface AuditReport {
+  vault: string;
+  audited_at: string;
+  entries: AuditEntry[];
+  /** Number of files with at least one issue */
+  flagged: number;
+}
+
+/**
+ * Cross-check unlock times, drand rounds, headers and payloads of every file in a vault (read-only)
+ */
+export async function auditVault(vault: string): Promise<AuditReport> {
+  return invoke<AuditReport>('audit_vault', { vault });
+}
+
+export interface DrandHealth {
+  url: string;
+  chain_hash: string;
+  source: string;
+  /** Reachable, serving the expected chain, and in step with the local clock */
+  healthy: boolean;
+  latest_round: number | null;
+  reported_chain_hash: string | null;
+  /** Local time minus the latest round's scheduled time, in seconds */
+  drift_seconds: number | null;
+  latency_ms: number | null;
+  error: string | null;
+}
+
+/**
+ * Fetch the latest round from every drand relay to confirm locking and unlocking will work
+ */
+export async function checkDrandHealth(): Promise<DrandHealth[]> {
+  return invoke<DrandHealth[]>('check_drand_health');
+}
+
+/** How one drand relay did in `benchmarkEndpoints` */
+export interface EndpointLatency {
+  url: string;
+  chain_hash: string;
+  source: string;
+  /** Median time to fetch the latest round; null if every attempt failed */
+  latency_ms: number | null;
+  samples_ms: number[];
+  latest_round: number | null;
+  error: string | null;
+}
+
+/**
+ * Time fetching the latest round from every drand relay, fastest first
+ * @param samples - Fetches per relay (defaults to 3)
+ * @param reorder - Try the relays fastest first for the rest of the session
+ */
+export async function benchmarkEndpoints(
+  samples?: number,
+  reorder?: boolean
+): Promise<EndpointLatency[]> {
+  return invoke<EndpointLatency[]>('benchmark_endpoints', {
+    samples: samples ?? null,
+    reorder: reorder ?? null
+  });
+}
+
 /**
  * Save application settings to backend
  */
@@ -287,3 +766,30 @@ export async function saveSettings(settings: AppSettings): Promise<boolean> {
     return false;
   }
 }
+
+/**
+ * Build plain-text unlock instructions to send along with a .7z.tlock file
+ * (public metadata only - nothing secret)
+ * @param tlockPath - Path to .7z.tlock file
+ */
+export async function generateInstructions(tlockPath: string): Promise<string> {
+  return await invoke<string>('generate_instructions', { tlockPath });
+}
+
+/**
+ * Re-encrypt an unlockable .7z.tlock file under a fresh archive password,
+ * e.g. after its password was revealed for an external 7z tool
+ * @param tlockPath - Path to .7z.tlock file
+ */
+export async function rotateArchivePassword(tlockPath: string): Promise<void> {
+  await invoke('rotate_archive_password', { tlockPath });
+}
+
+/**
+ * SHA-256 of a whole .7z.tlock file (metadata included), to check two copies are identical.
+ * For dedup/verification, not security.
+ * @param tlockPath - Path to .7z.tlock file
+ */
+export async function getTlockFingerprint(tlockPath: string): Promise<string> {
+  return invoke<string>('get_tlock_fingerprint', { tlockPath });
+}